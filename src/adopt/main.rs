@@ -0,0 +1,123 @@
+use anyhow::{bail, Context, Result};
+use clap::Clap;
+use std::{fs, io::ErrorKind, path::Path};
+
+use crate::{
+    config::{self, Config},
+    install::helper::{adopt::Adoption, mongodb, pm2},
+    log_i18n, tr, Com,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    /// 要接管其配置文件的组件：mongodb（mongod.conf）或 hydro（PM2 的 ecosystem.json）
+    /// Component whose config file to take over: mongodb (mongod.conf) or hydro (ecosystem.json of PM2)
+    component: Com,
+
+    /// 仅显示将导入与丢弃的设置，不作修改
+    /// Prints the settings to import and drop only, without changing anything
+    #[clap(short, long)]
+    dry_run: bool,
+
+    /// 即使有 H2O2 无法管理的设置也接管，这些设置将被丢弃，原文件会备份
+    /// Takes the file over even if it has settings H2O2 does not manage, which are dropped, with the original backed up
+    #[clap(long)]
+    force: bool,
+}
+
+/// Imports the settings of a config file H2O2 did not write into the profile, then rewrites it
+/// from the profile, keeping the original as `<file>.bak`.
+pub async fn main(args: Args) -> Result<()> {
+    let mut config = config::load_config().await?;
+    let com = args.component;
+    let path = match com {
+        Com::MongoDB => mongodb::config_path(),
+        Com::Hydro => pm2::ecosystem_path(),
+        com => bail!(tr!("adopt.unsupported", com)),
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            log_i18n!(info, "adopt.not-found", path.display());
+            return Ok(());
+        }
+        Err(e) => return Err(e).with_context(|| tr!("adopt.unparsable", path.display())),
+    };
+    let generated = match com {
+        Com::MongoDB => mongodb::is_generated(&text),
+        _ => pm2::is_generated(&text),
+    };
+    if generated {
+        log_i18n!(info, "adopt.already-managed", path.display());
+        return Ok(());
+    }
+
+    let adoption = import(&mut config, com, &text, &path)?;
+    for setting in &adoption.imported {
+        log_i18n!(info, "adopt.imported", setting);
+    }
+    for setting in &adoption.dropped {
+        log_i18n!(warn, "adopt.dropped", setting);
+    }
+    if args.dry_run {
+        return Ok(());
+    }
+    if !adoption.dropped.is_empty() && !args.force {
+        bail!(tr!("adopt.unmanaged", adoption.dropped.len()));
+    }
+
+    let mut backup = path.clone().into_os_string();
+    backup.push(".bak");
+    fs::copy(&path, &backup)
+        .with_context(|| tr!("sys.write-failed", Path::new(&backup).display()))?;
+    // the generators refuse to overwrite the original
+    fs::remove_file(&path).with_context(|| tr!("sys.write-failed", path.display()))?;
+    let written = match com {
+        Com::MongoDB => mongodb::write_config(&config.profile.mongodb),
+        _ => pm2::write_ecosystem(
+            &config.components.hydro,
+            &config.profile.hydro,
+            config.profile.kill_after(),
+        ),
+    };
+    written.with_context(|| tr!("sys.write-failed", path.display()))?;
+    config::save_config(&config).await?;
+    log_i18n!(
+        info,
+        "adopt.adopted",
+        path.display(),
+        Path::new(&backup).display(),
+        com
+    );
+    Ok(())
+}
+
+/// Imports the settings of `text`, the file at `path`, into the profile of `config`.
+fn import(config: &mut Config, com: Com, text: &str, path: &Path) -> Result<Adoption> {
+    match com {
+        Com::MongoDB => {
+            let settings = mongodb::parse_config(text)
+                .with_context(|| tr!("adopt.unparsable", path.display()))?;
+            // the data would be left behind rather than adopted
+            let data_path = mongodb::data_path();
+            let elsewhere = settings
+                .iter()
+                .find(|(key, value)| key == "storage.dbPath" && Path::new(value) != data_path);
+            if let Some((_, value)) = elsewhere {
+                bail!(tr!("adopt.data-path", value, data_path.display()));
+            }
+            Ok(mongodb::adopt_config(
+                &settings,
+                &mut config.profile.mongodb,
+            ))
+        }
+        _ => {
+            if !config.components.hydro.is_installed() {
+                bail!(tr!("check.not-installed", Com::Hydro));
+            }
+            pm2::adopt_ecosystem(text, &mut config.profile.hydro)
+                .with_context(|| tr!("adopt.unparsable", path.display()))
+        }
+    }
+}
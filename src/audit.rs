@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::config;
+
+/// A record in the audit log, which keeps one JSON object per line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    /// Seconds since the Unix epoch.
+    pub time: u64,
+    pub action: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub details: serde_json::Value,
+}
+
+impl Entry {
+    pub fn new(action: &str, component: Option<String>, details: serde_json::Value) -> Self {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            time,
+            action: action.to_owned(),
+            component,
+            details,
+        }
+    }
+}
+
+pub fn get_audit_log_path() -> PathBuf {
+    config::get_com_path().join("audit.log")
+}
+
+pub fn append(entry: &Entry) -> io::Result<()> {
+    let path = get_audit_log_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+/// Reads all records, skipping lines that cannot be parsed.
+pub fn read() -> io::Result<Vec<Entry>> {
+    let path = get_audit_log_path();
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
@@ -0,0 +1,237 @@
+use anyhow::{bail, Context, Result};
+use clap::{AppSettings, Clap};
+use futures::{stream::FuturesUnordered, StreamExt};
+use std::{fs, path::PathBuf};
+
+use crate::{
+    config::{self, Config, ConfigError},
+    install::{
+        self, download,
+        helper::{
+            bundle::{Bundle, Meta},
+            hydro,
+            npm::NPMJS_REGISTRY,
+        },
+        Com, OfflineFile, OfflineManifest, Options,
+    },
+    log_i18n, net, platform, progress, tr,
+    utils::{decode_output, run_capped, sha256_file_async},
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Clap, Debug)]
+enum SubCommand {
+    /// 在可联网的机器上下载全部组件及 Hydro 的软件包，制作离线包
+    /// Downloads all the components and the packages of Hydro into a bundle on a machine with network access
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Create(CreateArgs),
+
+    /// 在无法联网的服务器上从离线包安装组件
+    /// Installs the components from a bundle on an air-gapped server
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Install(InstallArgs),
+}
+
+#[derive(Clap, Debug)]
+struct CreateArgs {
+    /// 离线包目录，其中的组件适用于与本机相同的系统与架构
+    /// Directory of the bundle, whose components are for the same OS and architecture as this machine
+    dir: PathBuf,
+}
+
+#[derive(Clap, Debug)]
+struct InstallArgs {
+    /// 离线包目录
+    /// Directory of the bundle
+    dir: PathBuf,
+
+    /// 要安装的组件，默认为全部
+    /// Components to install, defaults to all
+    components: Vec<Com>,
+}
+
+/// Components whose artifacts are downloaded into the bundle, while the rest are installed from
+/// the packages in it.
+const ARTIFACTS: [Com; 4] = [Com::NodeJS, Com::MongoDB, Com::MinIO, Com::Sandbox];
+
+/// Lines of output kept to explain a failure.
+const TAIL_LINES: usize = 20;
+
+impl Args {
+    /// Name of the operation, if the subcommand modifies the deployment.
+    pub fn operation(&self) -> Option<&'static str> {
+        match self.subcmd {
+            SubCommand::Create(_) => None,
+            SubCommand::Install(_) => Some("bundle install"),
+        }
+    }
+}
+
+pub async fn main(args: Args) -> Result<()> {
+    match args.subcmd {
+        SubCommand::Create(args) => create(args).await,
+        SubCommand::Install(args) => {
+            if let Err(e) = Bundle::new(&args.dir).meta() {
+                bail!(tr!("bundle.invalid", args.dir.display(), e));
+            }
+            install::main(install::Args::from_bundle(args.dir, args.components)).await
+        }
+    }
+}
+
+async fn create(args: CreateArgs) -> Result<()> {
+    let config = match config::load_config().await {
+        Ok(config) => config,
+        Err(ConfigError::FileNotExist) => Config::default(),
+        Err(e) => return Err(e.into()),
+    };
+    net::configure(config.profile.proxy.as_deref());
+
+    let bundle = Bundle::new(&args.dir);
+    fs::create_dir_all(bundle.dir()).context(tr!("sys.create-dir-failed"))?;
+    let opts = Options::from_config(&config).context(tr!("sys.temp-dir-failed"))?;
+
+    // Yarn 1 is required to fill the mirror, so check it before downloading anything
+    let nodejs = &config.components.nodejs;
+    let yarn = config.components.yarn.path_or("yarn");
+    let yarn_version = duct::cmd!(yarn, "--version")
+        .stdout_capture()
+        .stderr_null()
+        .run()
+        .ok()
+        .and_then(|output| semver::Version::parse(decode_output(&output.stdout).trim()).ok());
+    if yarn_version.is_none_or(|version| version.major != 1) {
+        bail!(tr!("bundle.requires-yarn"));
+    }
+
+    let manifest = download_artifacts(&bundle, &opts).await?;
+
+    log_i18n!(info, "bundle.fetching-packages");
+    let npm = nodejs.path(&platform::cmd("npm"));
+    let registry = duct::cmd!(&npm, "config", "get", "registry")
+        .stdout_capture()
+        .stderr_null()
+        .unchecked()
+        .run()
+        .map(|output| decode_output(&output.stdout).trim().to_owned())
+        .ok()
+        .filter(|registry| registry.starts_with("http"))
+        .unwrap_or_else(|| NPMJS_REGISTRY.to_owned());
+    // installing into a throwaway prefix fills the cache with the packages and their metadata
+    let prefix = opts.temp.tempdir()?;
+    let output = run_capped(&duct::cmd!(
+        &npm,
+        "install",
+        "--global",
+        "--prefix",
+        prefix.path(),
+        "--cache",
+        bundle.npm_cache(),
+        "--registry",
+        &registry,
+        "yarn",
+        "pm2"
+    ))?;
+    if !output.status.success() {
+        bail!(
+            "{}\n{}",
+            tr!("bundle.packages-failed", "npm install --global yarn pm2"),
+            output.snippet(TAIL_LINES)
+        );
+    }
+
+    let hydro = fill_yarn_mirror(
+        &bundle,
+        yarn,
+        &hydro::package_spec(config.profile.hydro.channel),
+    )?;
+    bundle.save_meta(&Meta { registry, hydro })?;
+    let text = serde_json::to_string_pretty(&manifest)?;
+    fs::write(bundle.manifest(), text)?;
+
+    log_i18n!(info, "bundle.created", bundle.dir().display());
+    Ok(())
+}
+
+/// Downloads the artifacts into the bundle, listing them in the manifest returned.
+async fn download_artifacts(bundle: &Bundle, opts: &Options) -> Result<OfflineManifest> {
+    let mut downloads = ARTIFACTS
+        .iter()
+        .map(|&com| download(com, opts))
+        .collect::<FuturesUnordered<_>>();
+
+    let mut manifest = OfflineManifest::default();
+    let mut failed = Vec::new();
+    while let Some(res) = downloads.next().await {
+        let (com, cached) = match res {
+            Ok(ok) => ok,
+            Err(e) => {
+                log_i18n!(error, "install.download-failed", e.com);
+                progress::phase(e.com.name(), "failed");
+                tracing::error!("{}", e);
+                failed.push(e.com.to_string());
+                continue;
+            }
+        };
+        let name = cached
+            .file_name()
+            .expect("downloads are saved as files")
+            .to_string_lossy()
+            .into_owned();
+        let path = bundle.dir().join(&name);
+        fs::copy(&cached, &path)?;
+        manifest.files.push(OfflineFile {
+            component: com.name().to_owned(),
+            path: name,
+            sha256: Some(sha256_file_async(path).await?),
+        });
+        progress::phase(com.name(), "done");
+    }
+    if !failed.is_empty() {
+        bail!(tr!("bundle.download-failed", failed.join(", ")));
+    }
+    manifest.files.sort_by_key(|file| {
+        ARTIFACTS
+            .iter()
+            .position(|com| com.name() == file.component)
+    });
+    Ok(manifest)
+}
+
+/// Resolves `spec` of Hydro into the lockfile of the bundle, saving the packages into its
+/// mirror, and returns Hydro as resolved, e.g. `hydrooj@^4.5.0`.
+fn fill_yarn_mirror(bundle: &Bundle, yarn: &str, spec: &str) -> Result<String> {
+    let dir = bundle.hydro_dir();
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join("package.json"), "{\n  \"private\": true\n}\n")?;
+    // resolve again rather than keeping what an earlier bundle resolved
+    let _ = fs::remove_file(dir.join("yarn.lock"));
+    let output = run_capped(
+        &duct::cmd!(yarn, "add", spec, "--ignore-scripts", "--non-interactive")
+            .dir(&dir)
+            .env("YARN_YARN_OFFLINE_MIRROR", bundle.yarn_mirror())
+            .env("YARN_YARN_OFFLINE_MIRROR_PRUNING", "true"),
+    )?;
+    // the packages are in the mirror, and installed again on the server
+    let _ = fs::remove_dir_all(dir.join("node_modules"));
+    if !output.status.success() {
+        bail!(
+            "{}\n{}",
+            tr!("bundle.packages-failed", format!("yarn add {}", spec)),
+            output.snippet(TAIL_LINES)
+        );
+    }
+
+    let package =
+        serde_json::from_str::<serde_json::Value>(&fs::read_to_string(dir.join("package.json"))?)?;
+    match package["dependencies"][hydro::PACKAGE].as_str() {
+        Some(range) => Ok(format!("{}@{}", hydro::PACKAGE, range)),
+        None => bail!(tr!("bundle.packages-failed", format!("yarn add {}", spec))),
+    }
+}
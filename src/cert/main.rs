@@ -0,0 +1,282 @@
+use anyhow::{bail, Context, Result};
+use clap::{AppSettings, Clap};
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use super::preflight;
+use crate::{
+    config::{self, CaddyProfile, Config},
+    health,
+    install::helper::caddy::{self, CertInfo},
+    log_i18n, notify, tr,
+    utils::format_timestamp,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Clap, Debug)]
+enum SubCommand {
+    /// 显示当前证书的信息
+    /// Shows the certificate currently served
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Show,
+
+    /// 通过 Let's Encrypt 为域名申请证书
+    /// Obtains a certificate for the domain from Let's Encrypt
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Issue(IssueArgs),
+
+    /// 使用自定义证书
+    /// Uses a custom certificate
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Load(LoadArgs),
+
+    /// 检查域名解析与 80、443 端口是否满足签发证书的要求
+    /// Checks whether the DNS and ports 80 and 443 are ready for issuing certificates
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Preflight(PreflightArgs),
+
+    /// 检查证书是否即将过期，并发送通知，适合在 cron 中运行
+    /// Checks whether the certificate expires soon and sends notifications, for use in cron
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Check,
+}
+
+#[derive(Clap, Debug)]
+struct IssueArgs {
+    /// 域名，需已解析到本机
+    /// Domain name, which must resolve to this host
+    #[clap(long)]
+    domain: Option<String>,
+
+    /// ACME 账户的邮箱
+    /// Email of the ACME account
+    #[clap(long)]
+    email: Option<String>,
+
+    /// 跳过域名解析与端口检查
+    /// Skips checking the DNS and ports
+    #[clap(long)]
+    skip_preflight: bool,
+}
+
+#[derive(Clap, Debug)]
+struct PreflightArgs {
+    /// 域名，默认为配置中的域名
+    /// Domain name, defaults to the one in the profile
+    #[clap(long)]
+    domain: Option<String>,
+}
+
+#[derive(Clap, Debug)]
+struct LoadArgs {
+    /// 证书链文件 (PEM)
+    /// Certificate chain file (PEM)
+    #[clap(long)]
+    cert: PathBuf,
+
+    /// 私钥文件 (PEM)
+    /// Private key file (PEM)
+    #[clap(long)]
+    key: PathBuf,
+
+    /// 域名
+    /// Domain name
+    #[clap(long)]
+    domain: Option<String>,
+}
+
+impl Args {
+    /// Name of the operation, if the subcommand modifies the deployment.
+    pub fn operation(&self) -> Option<&'static str> {
+        match self.subcmd {
+            SubCommand::Issue(_) => Some("cert issue"),
+            SubCommand::Load(_) => Some("cert load"),
+            _ => None,
+        }
+    }
+}
+
+/// How long to wait for Caddy to obtain a certificate.
+const ISSUE_TIMEOUT: Duration = Duration::from_secs(120);
+
+pub async fn main(args: Args) -> Result<()> {
+    let mut config = config::load_config().await?;
+    let caddy_path = match config.components.extras.get("caddy") {
+        Some(info) if info.is_installed() => info.path_or("caddy").to_owned(),
+        _ => bail!(tr!("cert.no-proxy")),
+    };
+    match args.subcmd {
+        SubCommand::Show => show(&config.profile.caddy),
+        SubCommand::Issue(args) => issue(&mut config, &caddy_path, args).await,
+        SubCommand::Load(args) => load(&mut config, &caddy_path, args).await,
+        SubCommand::Preflight(args) => {
+            let domain = match args.domain.or(config.profile.caddy.domain) {
+                Some(domain) => domain,
+                None => bail!(tr!("cert.no-domain")),
+            };
+            if preflight::check(&domain).await {
+                log_i18n!(info, "cert.preflight-passed", &domain);
+            }
+            Ok(())
+        }
+        SubCommand::Check => check(&config).await,
+    }
+}
+
+fn show(profile: &CaddyProfile) -> Result<()> {
+    let domain = match &profile.domain {
+        Some(domain) => domain,
+        None => bail!(tr!("cert.no-domain")),
+    };
+    println!(" domain:     {}", domain);
+    match (&profile.tls_cert_file, &profile.tls_key_file) {
+        (Some(cert), Some(key)) => {
+            println!(" source:     custom");
+            println!(" cert file:  {}", cert);
+            println!(" key file:   {}", key);
+        }
+        _ => println!(" source:     Let's Encrypt"),
+    }
+    let cert = caddy::served_cert(domain).with_context(|| tr!("cert.fetch-failed", domain))?;
+    println!(" issuer:     {}", cert.issuer);
+    println!(" expires:    {}", format_timestamp(cert.not_after));
+    println!(" days left:  {}", days_left(&cert));
+    Ok(())
+}
+
+async fn issue(config: &mut Config, caddy_path: &str, args: IssueArgs) -> Result<()> {
+    let profile = &mut config.profile.caddy;
+    if let Some(domain) = args.domain {
+        profile.domain = Some(domain);
+    }
+    if let Some(email) = args.email {
+        profile.email = Some(email);
+    }
+    let domain = match &profile.domain {
+        Some(domain) => domain.clone(),
+        None => bail!(tr!("cert.no-domain")),
+    };
+    if !args.skip_preflight && !preflight::check(&domain).await {
+        bail!(tr!("cert.preflight-failed"));
+    }
+    // leave the certificate to Caddy's ACME client
+    profile.tls_cert_file = None;
+    profile.tls_key_file = None;
+    apply(config, caddy_path).await?;
+
+    log_i18n!(info, "cert.issuing", &domain);
+    let deadline = SystemTime::now() + ISSUE_TIMEOUT;
+    loop {
+        match caddy::served_cert(&domain) {
+            // Caddy serves a self-signed certificate from its internal CA until it gets one
+            Ok(cert) if !cert.issuer.contains("Caddy Local Authority") => {
+                log_i18n!(
+                    info,
+                    "cert.issued",
+                    &domain,
+                    format_timestamp(cert.not_after)
+                );
+                return Ok(());
+            }
+            Ok(_) => {}
+            Err(e) => tracing::debug!("Failed to fetch the certificate: {}", e),
+        }
+        if SystemTime::now() > deadline {
+            bail!(tr!("cert.issue-timeout", &domain));
+        }
+        tokio::time::sleep(Duration::from_secs(3)).await;
+    }
+}
+
+async fn load(config: &mut Config, caddy_path: &str, args: LoadArgs) -> Result<()> {
+    let cert = caddy::file_cert(&args.cert)
+        .with_context(|| tr!("cert.invalid-cert", args.cert.display()))?;
+    if !caddy::key_matches(&args.cert, &args.key).unwrap_or(false) {
+        bail!(tr!(
+            "cert.key-mismatch",
+            args.key.display(),
+            args.cert.display()
+        ));
+    }
+    if days_left(&cert) < 0 {
+        bail!(tr!("cert.expired", format_timestamp(cert.not_after)));
+    }
+
+    // copy the files, so that renewing them in place does not break Caddy halfway
+    let dir = caddy::tls_path();
+    fs::create_dir_all(&dir)?;
+    let cert_file = dir.join("cert.pem");
+    let key_file = dir.join("key.pem");
+    fs::copy(&args.cert, &cert_file)?;
+    fs::copy(&args.key, &key_file)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&key_file, fs::Permissions::from_mode(0o600))?;
+    }
+
+    let profile = &mut config.profile.caddy;
+    if let Some(domain) = args.domain {
+        profile.domain = Some(domain);
+    }
+    if profile.domain.is_none() {
+        bail!(tr!("cert.no-domain"));
+    }
+    profile.tls_cert_file = Some(cert_file.to_string_lossy().into_owned());
+    profile.tls_key_file = Some(key_file.to_string_lossy().into_owned());
+    apply(config, caddy_path).await?;
+    log_i18n!(info, "cert.loaded", format_timestamp(cert.not_after));
+    Ok(())
+}
+
+/// Saves the profile and makes Caddy serve with it.
+async fn apply(config: &Config, caddy_path: &str) -> Result<()> {
+    config::save_config(config).await?;
+    let path = caddy::write_caddyfile(&config.profile.caddy)?;
+    tracing::debug!("Caddyfile written to {}", path.display());
+    caddy::reload(caddy_path).with_context(|| tr!("cert.reload-failed"))?;
+    health::wait_healthy(config, &health::Service::caddy(config)).await
+}
+
+async fn check(config: &Config) -> Result<()> {
+    let profile = &config.profile.caddy;
+    let domain = match &profile.domain {
+        Some(domain) => domain,
+        None => bail!(tr!("cert.no-domain")),
+    };
+    let message = match caddy::served_cert(domain) {
+        Ok(cert) if days_left(&cert) > i64::from(profile.expiry_notice_days) => {
+            log_i18n!(info, "cert.valid", domain, days_left(&cert));
+            return Ok(());
+        }
+        Ok(cert) => tr!(
+            "cert.expiring",
+            domain,
+            days_left(&cert),
+            format_timestamp(cert.not_after)
+        ),
+        Err(e) => format!("{}\n{}", tr!("cert.fetch-failed", domain), e),
+    };
+    tracing::warn!("{}", message);
+    if !notify::send(&config.profile.notify, "cert-expiring", &message).await {
+        log_i18n!(warn, "cert.no-notify");
+    }
+    Ok(())
+}
+
+/// Whole days until the certificate expires, negative once it has expired.
+pub fn days_left(cert: &CertInfo) -> i64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    (cert.not_after as i64 - now as i64).div_euclid(86400)
+}
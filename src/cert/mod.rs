@@ -0,0 +1,5 @@
+mod main;
+mod preflight;
+
+pub use main::*;
+pub use preflight::check as preflight;
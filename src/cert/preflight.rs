@@ -0,0 +1,99 @@
+use std::{
+    collections::BTreeSet,
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+use tokio::{net::TcpStream, time::timeout};
+
+use crate::{log_i18n, profiling};
+
+/// Services replying with the public IP of the requester in plain text.
+const IP_SOURCES: &[&str] = &["https://api.ipify.org/", "https://4.ipw.cn/"];
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Checks that `domain` resolves to the public IP of this host, and that ports 80 and 443 are
+/// reachable through it, as Let's Encrypt requires. Logs the problems found and returns whether
+/// all passed.
+pub async fn check(domain: &str) -> bool {
+    let resolved = match tokio::net::lookup_host((domain, 0)).await {
+        Ok(addrs) => addrs.map(|addr| addr.ip()).collect::<BTreeSet<_>>(),
+        Err(e) => {
+            tracing::debug!("Failed to resolve {}: {}", domain, e);
+            log_i18n!(warn, "cert.dns-unresolved", domain);
+            return false;
+        }
+    };
+    let public_ip = match public_ip().await {
+        Some(ip) => ip,
+        None => {
+            log_i18n!(warn, "cert.public-ip-unknown");
+            return false;
+        }
+    };
+    tracing::debug!(
+        "{} resolves to {:?}, public IP is {}",
+        domain,
+        resolved,
+        public_ip
+    );
+    if !resolved.contains(&public_ip) {
+        let resolved = resolved
+            .iter()
+            .map(IpAddr::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        log_i18n!(warn, "cert.dns-mismatch", domain, resolved, public_ip);
+        return false;
+    }
+
+    // check every port, even if an earlier one fails
+    let mut ok = true;
+    for port in [80, 443] {
+        if reachable(SocketAddr::new(public_ip, port)).await {
+            continue;
+        }
+        ok = false;
+        // tell a missing listener from a blocked port
+        if reachable(SocketAddr::new([127, 0, 0, 1].into(), port)).await {
+            log_i18n!(warn, "cert.port-blocked", port, public_ip);
+        } else {
+            log_i18n!(warn, "cert.port-not-listening", port);
+        }
+    }
+    ok
+}
+
+async fn public_ip() -> Option<IpAddr> {
+    let client = reqwest::Client::builder()
+        .timeout(CONNECT_TIMEOUT)
+        .build()
+        .ok()?;
+    for url in IP_SOURCES {
+        let _span = profiling::span("main", *url);
+        let res = client
+            .get(*url)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status());
+        let text = match res {
+            Ok(res) => res.text().await,
+            Err(e) => Err(e),
+        };
+        match text {
+            Ok(text) => match text.trim().parse() {
+                Ok(ip) => return Some(ip),
+                Err(_) => tracing::debug!("Unexpected reply from {}: {}", url, text.trim()),
+            },
+            Err(e) => tracing::debug!("Failed to fetch {}: {}", url, e),
+        }
+    }
+    None
+}
+
+async fn reachable(addr: SocketAddr) -> bool {
+    matches!(
+        timeout(CONNECT_TIMEOUT, TcpStream::connect(addr)).await,
+        Ok(Ok(_))
+    )
+}
@@ -0,0 +1,204 @@
+use semver::VersionReq;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::Manifest;
+use crate::{
+    config::{Config, Version},
+    registry, tr,
+    utils::{decode_output, parse_date},
+};
+
+/// Days before the end of life to start reminding admins.
+const EOL_NOTICE_DAYS: u64 = 90;
+
+/// A known problem of some versions of a component: their end of life, or a vulnerability.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Advisory {
+    /// name of the component in the registry, e.g. `mongodb`
+    pub component: String,
+
+    /// versions affected, as a semver requirement like `>=4.4, <5.0`, or `<RELEASE.<date>` for
+    /// components versioned by release tags like MinIO
+    pub affected: String,
+
+    /// date like `2024-02-29` when the versions reach their end of life
+    #[serde(default)]
+    pub eol: Option<String>,
+
+    /// CVE ID of the vulnerability
+    #[serde(default)]
+    pub cve: Option<String>,
+
+    /// whether the vulnerability is critical
+    #[serde(default)]
+    pub critical: bool,
+
+    #[serde(default)]
+    pub summary: String,
+}
+
+/// Advisories shipped with H2O2, used unless a manifest carries newer ones.
+pub fn builtin() -> Vec<Advisory> {
+    const EOL: &[(&str, &str, &str)] = &[
+        ("nodejs", "<12", "2021-04-30"),
+        ("nodejs", ">=12, <13", "2022-04-30"),
+        ("nodejs", ">=14, <15", "2023-04-30"),
+        ("nodejs", ">=16, <17", "2023-09-11"),
+        ("nodejs", ">=18, <19", "2025-04-30"),
+        ("nodejs", ">=20, <21", "2026-04-30"),
+        ("nodejs", ">=22, <23", "2027-04-30"),
+        ("mongodb", "<4.2", "2022-04-30"),
+        ("mongodb", ">=4.2, <4.4", "2023-04-30"),
+        ("mongodb", ">=4.4, <5.0", "2024-02-29"),
+        ("mongodb", ">=5.0, <6.0", "2024-10-31"),
+        ("mongodb", ">=6.0, <7.0", "2025-07-31"),
+    ];
+    let mut advisories = EOL
+        .iter()
+        .map(|(component, affected, eol)| Advisory {
+            component: (*component).to_owned(),
+            affected: (*affected).to_owned(),
+            eol: Some((*eol).to_owned()),
+            cve: None,
+            critical: false,
+            summary: String::new(),
+        })
+        .collect::<Vec<_>>();
+    advisories.push(Advisory {
+        component: "minio".to_owned(),
+        affected: "<RELEASE.2023-03-20T20-16-18Z".to_owned(),
+        eol: None,
+        cve: Some("CVE-2023-28432".to_owned()),
+        critical: true,
+        summary: "environment variables, including the root credentials, are disclosed in \
+            cluster deployments"
+            .to_owned(),
+    });
+    advisories
+}
+
+/// The advisories of the cached manifest, or those shipped with H2O2 if there is none.
+pub fn known() -> Vec<Advisory> {
+    match Manifest::cached() {
+        Ok(Some(manifest)) => manifest.advisories,
+        _ => builtin(),
+    }
+}
+
+/// A version of a component as recorded or reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Release {
+    Semver(semver::Version),
+    /// release tag like `RELEASE.2021-04-06T23-11-00Z`, which sorts by date
+    Tag(String),
+}
+
+impl std::fmt::Display for Release {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Semver(version) => write!(f, "{}", version),
+            Self::Tag(tag) => f.write_str(tag),
+        }
+    }
+}
+
+impl Advisory {
+    pub fn affects(&self, release: &Release) -> bool {
+        match (release, self.affected.strip_prefix('<')) {
+            (Release::Tag(tag), Some(before)) if before.starts_with("RELEASE.") => {
+                tag.as_str() < before
+            }
+            (Release::Semver(version), _) => VersionReq::parse(&self.affected)
+                .map(|req| req.matches(version))
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+}
+
+/// How an advisory applies to an installed version.
+#[derive(Debug, Clone)]
+pub enum Finding {
+    /// the version has reached its end of life on the date
+    Eol(String),
+    /// the version reaches its end of life on the date, in the number of days
+    EolSoon(String, u64),
+    /// the version has the vulnerability
+    Vulnerable(Advisory),
+}
+
+impl Finding {
+    /// Whether admins should act now rather than plan for it.
+    pub fn is_urgent(&self) -> bool {
+        match self {
+            Self::Eol(_) => true,
+            Self::EolSoon(..) => false,
+            Self::Vulnerable(advisory) => advisory.critical,
+        }
+    }
+
+    /// Tells the finding about `release` of the component `name`.
+    pub fn message(&self, name: &str, release: &Release) -> String {
+        let display = registry::find(name).map_or(name, |desc| desc.display);
+        match self {
+            Self::Eol(date) => tr!("check.eol", display, release, date),
+            Self::EolSoon(date, days) => tr!("check.eol-soon", display, release, date, days),
+            Self::Vulnerable(advisory) => tr!(
+                "check.vulnerable",
+                display,
+                release,
+                advisory.cve.as_deref().unwrap_or("-"),
+                &advisory.summary
+            ),
+        }
+    }
+}
+
+/// Evaluates `advisories` against the installed components, returning the findings of each.
+pub fn evaluate(config: &Config, advisories: &[Advisory]) -> Vec<(String, Release, Finding)> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let mut findings = Vec::new();
+    for advisory in advisories {
+        let release = match release_of(config, &advisory.component) {
+            Some(release) if advisory.affects(&release) => release,
+            _ => continue,
+        };
+        let finding = match advisory.eol.as_deref().and_then(parse_date) {
+            Some(eol) if eol <= now => Finding::Eol(advisory.eol.clone().unwrap()),
+            Some(eol) if eol - now <= EOL_NOTICE_DAYS * 86400 => {
+                Finding::EolSoon(advisory.eol.clone().unwrap(), (eol - now) / 86400)
+            }
+            Some(_) => continue,
+            None => Finding::Vulnerable(advisory.clone()),
+        };
+        findings.push((advisory.component.clone(), release, finding));
+    }
+    findings
+}
+
+/// The installed release of the component `name`. MinIO records no version, so its release tag
+/// is asked for.
+fn release_of(config: &Config, name: &str) -> Option<Release> {
+    let info = config.components.get(name)?;
+    match &info.version {
+        Version::Valid(version) => Some(Release::Semver(version.clone())),
+        Version::Installed if name == "minio" => {
+            // example: minio version RELEASE.2021-04-06T23-11-00Z
+            let output = duct::cmd!(info.path_or("minio"), "--version")
+                .stdout_capture()
+                .stderr_null()
+                .unchecked()
+                .run()
+                .ok()?;
+            let stdout = decode_output(&output.stdout);
+            let tag = stdout
+                .split_whitespace()
+                .find(|word| word.starts_with("RELEASE."))?;
+            Some(Release::Tag(tag.to_owned()))
+        }
+        _ => None,
+    }
+}
@@ -1,5 +1,290 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use clap::Clap;
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+};
 
-pub async fn main() -> Result<()> {
-    todo!();
+use super::{
+    advisory::{self, Advisory},
+    Manifest,
+};
+use crate::{
+    config::{self, Config, HydroChannel, Version},
+    install::helper::{corepack, hydro, mongodb, nodejs, sandbox},
+    log_i18n, output, tr,
+    utils::format_timestamp,
+    Com,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    /// 从文件（或解压后的离线包目录）读取版本清单，而不访问网络
+    /// Reads the release manifest from a file (or an unpacked bundle directory) instead of the network
+    #[clap(long)]
+    manifest_file: Option<PathBuf>,
+}
+
+pub async fn main(args: Args) -> Result<()> {
+    let config = config::load_config().await?;
+    check_yarn_pin(&config);
+    let manifest = match &args.manifest_file {
+        Some(path) => from_file(path)?,
+        None => fetch(config.profile.nodejs.lts.as_deref()).await?,
+    };
+    let findings = check_advisories(&config, &manifest.advisories);
+    check_hydro(&config, &manifest);
+
+    let report = REPORTED
+        .iter()
+        .filter(|&&com| config.profile.runs(com))
+        .map(|&com| {
+            let installed = config.components.borrow_by_com(com);
+            let latest = latest_of(&manifest, com, config.profile.hydro.channel);
+            let status = match (&installed.version, &latest) {
+                _ if !installed.is_installed() => "missing",
+                (Version::Valid(version), Some(latest)) if version >= latest => "up-to-date",
+                (Version::Valid(_), Some(_)) => "update-available",
+                _ => "unknown",
+            };
+            (com, installed, latest, status)
+        })
+        .collect::<Vec<_>>();
+
+    if output::is_json() {
+        let components = report
+            .iter()
+            .map(|(com, installed, latest, status)| {
+                json!({
+                    "name": com.name(),
+                    "version": installed.version().map(ToString::to_string),
+                    "latest": latest.as_ref().map(ToString::to_string),
+                    "status": status,
+                })
+            })
+            .collect::<Vec<_>>();
+        output::print_json(&json!({
+            "components": components,
+            "hydro_channel": config.profile.hydro.channel.to_string(),
+            "yarn_pinned": config.profile.yarn.pinned,
+            "fetched_at": manifest.fetched_at,
+            "findings": output::findings(&findings),
+        }));
+        return Ok(());
+    }
+
+    println!();
+    println!(" {:<8} {:<14} latest", "", "installed");
+    for (com, installed, latest, status) in &report {
+        let version = if installed.is_installed() {
+            installed.version.to_string()
+        } else {
+            "-".to_owned()
+        };
+        let latest = latest
+            .as_ref()
+            .map_or_else(|| "-".to_owned(), ToString::to_string);
+        let status = match *status {
+            "missing" => tr!("check.status-missing"),
+            "up-to-date" => tr!("check.status-up-to-date"),
+            "update-available" => tr!("check.status-update-available"),
+            _ => tr!("check.status-unknown"),
+        };
+        println!(
+            " {:<8} {:<14} {:<14} {}",
+            com.to_string(),
+            version,
+            latest,
+            status
+        );
+    }
+    Ok(())
+}
+
+/// Components compared with the latest releases, in the order of the report.
+const REPORTED: [Com; 6] = [
+    Com::NodeJS,
+    Com::MongoDB,
+    Com::Sandbox,
+    Com::Yarn,
+    Com::PM2,
+    Com::Hydro,
+];
+
+/// The latest release of `com` in the manifest: that in the channel of Hydro, the `latest`
+/// dist-tag of the other npm packages, or the release recorded, e.g. the latest LTS of Node.js.
+fn latest_of(manifest: &Manifest, com: Com, channel: HydroChannel) -> Option<semver::Version> {
+    let version = match com {
+        Com::Hydro => {
+            return manifest
+                .dist_tags
+                .get(hydro::PACKAGE)
+                .and_then(|tags| hydro::latest_in(tags, channel))
+        }
+        Com::Yarn | Com::PM2 => manifest.dist_tags.get(com.name())?.get("latest")?,
+        _ => manifest.releases.get(com.name())?,
+    };
+    semver::Version::parse(version).ok()
+}
+
+/// Tells whether a newer release of Hydro is in its channel and how to install it.
+fn check_hydro(config: &Config, manifest: &Manifest) {
+    let installed = &config.components.hydro;
+    if !installed.is_installed() {
+        log_i18n!(warn, "check.not-installed", Com::Hydro);
+        return;
+    }
+    let channel = config.profile.hydro.channel;
+    let latest = match latest_of(manifest, Com::Hydro, channel) {
+        Some(latest) => latest,
+        None => {
+            log_i18n!(warn, "check.no-release", Com::Hydro, channel);
+            return;
+        }
+    };
+    log_i18n!(info, "check.latest", Com::Hydro, channel, &latest);
+
+    match &installed.version {
+        Version::Valid(version) if *version >= latest => {
+            log_i18n!(info, "check.up-to-date", Com::Hydro);
+        }
+        _ => {
+            log_i18n!(
+                info,
+                "check.hydro-update-available",
+                &latest,
+                hydro::package_spec(channel)
+            );
+        }
+    }
+}
+
+/// Findings of the advisories, see [`advisory::evaluate`].
+type Findings = Vec<(String, advisory::Release, advisory::Finding)>;
+
+/// Logs the end of life dates and vulnerabilities of the installed versions.
+fn check_advisories(config: &Config, advisories: &[Advisory]) -> Findings {
+    let findings = advisory::evaluate(config, advisories);
+    for (name, release, finding) in &findings {
+        let message = finding.message(name, release);
+        if finding.is_urgent() {
+            tracing::warn!("{}", message);
+        } else {
+            tracing::info!("{}", message);
+        }
+    }
+    findings
+}
+
+/// Tells whether the Yarn pinned by corepack is the one recommended for the installed Node.js.
+fn check_yarn_pin(config: &Config) {
+    let pinned = match &config.profile.yarn.pinned {
+        Some(pinned) => pinned,
+        None => return,
+    };
+    let nodejs = &config.components.nodejs;
+    match nodejs.version().and_then(corepack::yarn_version) {
+        Some(recommended) if recommended == pinned => {
+            log_i18n!(info, "check.yarn-pinned", pinned)
+        }
+        Some(recommended) => log_i18n!(info, "check.yarn-pin-outdated", pinned, recommended),
+        None => log_i18n!(warn, "check.yarn-pin-unsupported", pinned, &nodejs.version),
+    }
+}
+
+/// Fetches the manifest from the npm registry, nodejs.org, the MongoDB release feed and GitHub,
+/// falling back to the cached one when offline. Only the npm registry is required, the releases
+/// from the other sources are left out if they are unreachable. Node.js is the newest release of
+/// the LTS line `nodejs_lts`.
+async fn fetch(nodejs_lts: Option<&str>) -> Result<Manifest> {
+    let (hydro, pm2, yarn, nodejs, mongodb, sandbox) = tokio::join!(
+        hydro::dist_tags(hydro::PACKAGE),
+        hydro::dist_tags(Com::PM2.name()),
+        hydro::dist_tags(Com::Yarn.name()),
+        nodejs::latest_lts(nodejs_lts),
+        latest_mongodb(),
+        sandbox::latest_release(),
+    );
+    let err = match hydro {
+        Ok(tags) => {
+            let mut manifest = Manifest::new(BTreeMap::from([(hydro::PACKAGE.to_owned(), tags)]));
+            for (com, tags) in [(Com::PM2, pm2), (Com::Yarn, yarn)] {
+                match tags {
+                    Ok(tags) => {
+                        manifest.dist_tags.insert(com.name().to_owned(), tags);
+                    }
+                    Err(e) => log_i18n!(warn, "check.release-unavailable", com, e),
+                }
+            }
+            for (com, version) in [
+                (Com::NodeJS, nodejs.map(Some)),
+                (Com::MongoDB, mongodb),
+                (Com::Sandbox, sandbox.map(Some)),
+            ] {
+                match version {
+                    Ok(Some(version)) => {
+                        manifest
+                            .releases
+                            .insert(com.name().to_owned(), version.to_string());
+                    }
+                    Ok(None) => {}
+                    Err(e) => log_i18n!(warn, "check.release-unavailable", com, e),
+                }
+            }
+            if let Err(e) = manifest.save_cache() {
+                tracing::debug!("Failed to cache the manifest: {}", e);
+            }
+            return Ok(manifest);
+        }
+        Err(e) => e,
+    };
+    match Manifest::cached() {
+        Ok(Some(manifest)) => {
+            tracing::debug!("Failed to fetch the manifest: {}", err);
+            log_i18n!(
+                warn,
+                "check.manifest-cached",
+                format_timestamp(manifest.fetched_at)
+            );
+            Ok(manifest)
+        }
+        _ => Err(err).with_context(|| tr!("check.fetch-failed", Com::Hydro)),
+    }
+}
+
+/// The latest production release of MongoDB built for this platform.
+async fn latest_mongodb() -> io::Result<Option<semver::Version>> {
+    match mongodb::download_target() {
+        Some(target) => Ok(mongodb::find_release(&target, None)
+            .await?
+            .map(|release| release.version)),
+        None => Ok(None),
+    }
+}
+
+/// Reads the manifest carried in, and caches it unless the cached one is newer.
+fn from_file(path: &Path) -> Result<Manifest> {
+    let manifest =
+        Manifest::load(path).with_context(|| tr!("check.manifest-invalid", path.display()))?;
+    if let Ok(Some(cached)) = Manifest::cached() {
+        if cached.fetched_at > manifest.fetched_at {
+            log_i18n!(
+                warn,
+                "check.manifest-outdated",
+                format_timestamp(manifest.fetched_at),
+                format_timestamp(cached.fetched_at)
+            );
+            return Ok(cached);
+        }
+    }
+    manifest.save_cache()?;
+    log_i18n!(
+        info,
+        "check.manifest-loaded",
+        format_timestamp(manifest.fetched_at)
+    );
+    Ok(manifest)
 }
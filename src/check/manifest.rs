@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::advisory::{self, Advisory};
+use crate::config;
+
+/// Release data the installed versions are compared with.
+///
+/// Every online check caches it as `~/.h2o2/cache/manifest.json`, which is also the format read
+/// by `--manifest-file`, so an admin can carry the file from an online machine to an offline one.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Manifest {
+    /// seconds since the Unix epoch when the data was fetched
+    pub fetched_at: u64,
+
+    /// dist-tags of npm packages, by package name
+    pub dist_tags: BTreeMap<String, HashMap<String, String>>,
+
+    /// end of life dates and vulnerabilities of component versions, which manifests written
+    /// before they were added lack
+    #[serde(default = "advisory::builtin")]
+    pub advisories: Vec<Advisory>,
+
+    /// SHA-256 of artifacts by file name, which `install --file` verifies files without a
+    /// built-in checksum against
+    #[serde(default)]
+    pub checksums: BTreeMap<String, String>,
+
+    /// latest releases of the components not published to npm by component name, e.g. the
+    /// latest LTS of Node.js
+    #[serde(default)]
+    pub releases: BTreeMap<String, String>,
+
+    /// hosts mirrors may redirect downloads to besides the built-in ones, e.g. a new CDN
+    #[serde(default)]
+    pub redirect_hosts: Vec<String>,
+}
+
+impl Manifest {
+    pub fn new(dist_tags: BTreeMap<String, HashMap<String, String>>) -> Self {
+        let fetched_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Self {
+            fetched_at,
+            dist_tags,
+            advisories: advisory::builtin(),
+            checksums: BTreeMap::new(),
+            releases: BTreeMap::new(),
+            redirect_hosts: Vec::new(),
+        }
+    }
+
+    /// Reads a manifest file, or `manifest.json` in an unpacked bundle directory.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let path = if path.is_dir() {
+            path.join("manifest.json")
+        } else {
+            path.to_owned()
+        };
+        serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// The cached manifest, if any.
+    pub fn cached() -> io::Result<Option<Self>> {
+        match Self::load(&cache_path()) {
+            Ok(manifest) => Ok(Some(manifest)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save_cache(&self) -> io::Result<()> {
+        let path = cache_path();
+        fs::create_dir_all(path.parent().unwrap())?;
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+}
+
+pub fn cache_path() -> PathBuf {
+    config::get_cache_path().join("manifest.json")
+}
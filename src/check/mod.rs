@@ -1,3 +1,6 @@
+pub mod advisory;
 mod main;
+mod manifest;
 
 pub use main::*;
+pub use manifest::Manifest;
@@ -0,0 +1,25 @@
+use anyhow::{Context, Result};
+use clap::Clap;
+use std::fs;
+
+use crate::{
+    install::cache,
+    log_i18n, tr,
+    utils::{format_size, summarize_dir},
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {}
+
+pub async fn main(_args: Args) -> Result<()> {
+    let path = cache::downloads_path();
+    if !path.is_dir() {
+        log_i18n!(info, "clean.empty");
+        return Ok(());
+    }
+    let (files, size) = summarize_dir(&path).unwrap_or_default();
+    fs::remove_dir_all(&path).with_context(|| tr!("clean.failed", path.display()))?;
+    log_i18n!(info, "clean.done", files, format_size(size));
+    Ok(())
+}
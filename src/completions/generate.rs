@@ -0,0 +1,248 @@
+use clap::App;
+use std::fmt::Write;
+
+/// A command and what may follow it, collected from the clap definition.
+struct Command {
+    /// Names from the binary to this command, such as `["h2o2", "minio", "ls"]`.
+    path: Vec<String>,
+    about: String,
+    aliases: Vec<String>,
+    subcommands: Vec<String>,
+    /// Flags and options, such as `--tmp-dir` and `-h`.
+    flags: Vec<String>,
+    /// Possible values of positional arguments.
+    values: Vec<String>,
+}
+
+impl Command {
+    fn id(&self) -> String {
+        self.path.join("__")
+    }
+
+    fn words(&self) -> Vec<&str> {
+        self.subcommands
+            .iter()
+            .chain(self.flags.iter())
+            .chain(self.values.iter())
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+fn collect(app: &App, parent: &[String], out: &mut Vec<Command>) {
+    let mut path = parent.to_vec();
+    path.push(app.get_name().to_owned());
+
+    let mut flags = Vec::new();
+    let mut values = Vec::new();
+    for arg in app.get_arguments() {
+        if arg.get_long().is_none() && arg.get_short().is_none() {
+            if let Some(possible) = arg.get_possible_values() {
+                values.extend(possible.iter().map(|value| (*value).to_owned()));
+            }
+            continue;
+        }
+        if let Some(long) = arg.get_long() {
+            flags.push(format!("--{}", long));
+        }
+        if let Some(short) = arg.get_short() {
+            flags.push(format!("-{}", short));
+        }
+    }
+    // added by clap when building
+    flags.push("--help".to_owned());
+    flags.push("--version".to_owned());
+
+    out.push(Command {
+        path: path.clone(),
+        about: app.get_about().map(english_part).unwrap_or_default(),
+        aliases: app.get_visible_aliases().map(str::to_owned).collect(),
+        subcommands: app
+            .get_subcommands()
+            .map(|sub| sub.get_name().to_owned())
+            .collect(),
+        flags,
+        values,
+    });
+    for sub in app.get_subcommands() {
+        collect(sub, &path, out);
+    }
+}
+
+/// Abouts are written in Chinese followed by English, only the English part is kept.
+fn english_part(about: &str) -> String {
+    let start = about
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !c.is_ascii())
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let english = &about[start..];
+    // the Chinese part may end with a word like `mc`
+    let start = english.find(|c: char| c.is_ascii_uppercase()).unwrap_or(0);
+    english[start..].trim().to_owned()
+}
+
+fn commands(app: &App, bin: &str) -> Vec<Command> {
+    let mut out = Vec::new();
+    collect(app, &[], &mut out);
+    // the app is named `H2O2`, but the binary is `h2o2`
+    for command in out.iter_mut() {
+        command.path[0] = bin.to_owned();
+    }
+    out
+}
+
+/// Writes the `case` branches moving `cmd` into a subcommand, which are the same in bash and zsh.
+fn write_transitions(out: &mut String, commands: &[Command]) {
+    for command in commands.iter().filter(|command| command.path.len() > 1) {
+        let (name, parent) = command.path.split_last().unwrap();
+        let parent = parent.join("__");
+        let patterns = std::iter::once(name)
+            .chain(command.aliases.iter())
+            .map(|name| format!("{}__{}", parent, name))
+            .collect::<Vec<_>>();
+        let _ = writeln!(
+            out,
+            "            {}) cmd=\"{}\" ;;",
+            patterns.join("|"),
+            command.id()
+        );
+    }
+}
+
+pub fn bash(app: &App, bin: &str) -> String {
+    let commands = commands(app, bin);
+    let mut out = String::new();
+    let _ = writeln!(out, "_{}() {{", bin);
+    let _ = writeln!(out, "    local i cur cmd opts");
+    let _ = writeln!(out, "    COMPREPLY=()");
+    let _ = writeln!(out, "    cur=\"${{COMP_WORDS[COMP_CWORD]}}\"");
+    let _ = writeln!(out, "    cmd=\"{}\"", bin);
+    let _ = writeln!(out);
+    let _ = writeln!(
+        out,
+        "    for i in \"${{COMP_WORDS[@]:1:COMP_CWORD-1}}\"; do"
+    );
+    let _ = writeln!(out, "        case \"${{cmd}}__${{i}}\" in");
+    write_transitions(&mut out, &commands);
+    let _ = writeln!(out, "        esac");
+    let _ = writeln!(out, "    done");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    case \"${{cmd}}\" in");
+    for command in &commands {
+        let _ = writeln!(
+            out,
+            "        {}) opts=\"{}\" ;;",
+            command.id(),
+            command.words().join(" ")
+        );
+    }
+    let _ = writeln!(out, "        *) opts=\"\" ;;");
+    let _ = writeln!(out, "    esac");
+    let _ = writeln!(out);
+    let _ = writeln!(
+        out,
+        "    COMPREPLY=( $(compgen -W \"${{opts}}\" -- \"${{cur}}\") )"
+    );
+    let _ = writeln!(out, "}}");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "complete -F _{0} -o bashdefault -o default {0}", bin);
+    out
+}
+
+pub fn zsh(app: &App, bin: &str) -> String {
+    let commands = commands(app, bin);
+    let mut out = String::new();
+    let _ = writeln!(out, "#compdef {}", bin);
+    let _ = writeln!(out);
+    let _ = writeln!(out, "_{}() {{", bin);
+    let _ = writeln!(out, "    local cmd=\"{}\" word", bin);
+    let _ = writeln!(out, "    local -a opts");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    for word in \"${{(@)words[2,CURRENT-1]}}\"; do");
+    let _ = writeln!(out, "        case \"${{cmd}}__${{word}}\" in");
+    write_transitions(&mut out, &commands);
+    let _ = writeln!(out, "        esac");
+    let _ = writeln!(out, "    done");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    case \"${{cmd}}\" in");
+    for command in &commands {
+        let _ = writeln!(
+            out,
+            "        {}) opts=({}) ;;",
+            command.id(),
+            command.words().join(" ")
+        );
+    }
+    let _ = writeln!(out, "    esac");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    compadd -- \"${{opts[@]}}\"");
+    let _ = writeln!(out, "}}");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "_{} \"$@\"", bin);
+    out
+}
+
+pub fn fish(app: &App, bin: &str) -> String {
+    let commands = commands(app, bin);
+    let mut out = String::new();
+    for command in &commands {
+        // the subcommand names seen after the binary
+        let seen = &command.path[1..];
+        let condition = if seen.is_empty() {
+            "__fish_use_subcommand".to_owned()
+        } else {
+            seen.iter()
+                .map(|name| format!("__fish_seen_subcommand_from {}", name))
+                .collect::<Vec<_>>()
+                .join("; and ")
+        };
+        let condition = if command.subcommands.is_empty() || seen.is_empty() {
+            condition
+        } else {
+            format!(
+                "{}; and not __fish_seen_subcommand_from {}",
+                condition,
+                command.subcommands.join(" ")
+            )
+        };
+
+        for sub in commands
+            .iter()
+            .filter(|c| c.path.len() == command.path.len() + 1)
+        {
+            if sub.path[..command.path.len()] != command.path[..] {
+                continue;
+            }
+            let _ = writeln!(
+                out,
+                "complete -c {} -n '{}' -f -a '{}' -d '{}'",
+                bin,
+                condition,
+                sub.path.last().unwrap(),
+                escape_fish(&sub.about)
+            );
+        }
+        for flag in &command.flags {
+            let option = match flag.strip_prefix("--") {
+                Some(long) => format!("-l {}", long),
+                None => format!("-s {}", &flag[1..]),
+            };
+            let _ = writeln!(out, "complete -c {} -n '{}' {}", bin, condition, option);
+        }
+        if !command.values.is_empty() {
+            let _ = writeln!(
+                out,
+                "complete -c {} -n '{}' -f -a '{}'",
+                bin,
+                condition,
+                command.values.join(" ")
+            );
+        }
+    }
+    out
+}
+
+fn escape_fish(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\'', "\\'")
+}
@@ -0,0 +1,141 @@
+use anyhow::{bail, Context, Result};
+use clap::{App, AppSettings, ArgEnum, Clap};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::generate;
+use crate::{
+    log_i18n,
+    platform::{self, Shell},
+};
+
+const BIN: &str = "h2o2";
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    /// 目标 shell，默认根据 $SHELL 检测
+    /// Target shell, detected from $SHELL by default
+    #[clap(long, arg_enum)]
+    shell: Option<ShellKind>,
+
+    #[clap(subcommand)]
+    action: Option<Action>,
+}
+
+#[derive(Clap, Debug)]
+enum Action {
+    /// 将补全脚本安装到 shell 的补全目录
+    /// Installs the completion script into the completion directory of the shell
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Install(InstallArgs),
+}
+
+#[derive(Clap, Debug)]
+struct InstallArgs {
+    /// 目标 shell，默认根据 $SHELL 检测
+    /// Target shell, detected from $SHELL by default
+    #[clap(long, arg_enum)]
+    shell: Option<ShellKind>,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy)]
+enum ShellKind {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Prints the completion script, or installs it with `h2o2 completions install`.
+///
+/// `app` is the definition of the whole command line, which lives in the binary.
+pub async fn main(args: Args, app: App<'_>) -> Result<()> {
+    let shell = match &args.action {
+        Some(Action::Install(install)) => install.shell.or(args.shell),
+        None => args.shell,
+    };
+    let shell = match shell {
+        Some(shell) => shell,
+        None => detect_shell()?,
+    };
+    let script = match shell {
+        ShellKind::Bash => generate::bash(&app, BIN),
+        ShellKind::Zsh => generate::zsh(&app, BIN),
+        ShellKind::Fish => generate::fish(&app, BIN),
+    };
+
+    match args.action {
+        None => print!("{}", script),
+        Some(Action::Install(_)) => {
+            let path = completion_path(shell)?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).context("Failed to create completion directory")?;
+            }
+            fs::write(&path, script).context("Failed to write completion script")?;
+            log_i18n!(info, "completions.installed", path.display());
+            if let ShellKind::Zsh = shell {
+                ensure_zsh_fpath(path.parent().expect("completion path should have a parent"))?;
+            }
+            log_i18n!(info, "completions.reopen-terminal");
+        }
+    }
+    Ok(())
+}
+
+fn detect_shell() -> Result<ShellKind> {
+    match platform::current().shell {
+        Shell::Bash => Ok(ShellKind::Bash),
+        Shell::Zsh => Ok(ShellKind::Zsh),
+        Shell::Fish => Ok(ShellKind::Fish),
+        shell => bail!(
+            "不支持的 shell：{0}，请使用 --shell 指定。 Unsupported shell: {0}, please specify it with --shell.",
+            shell
+        ),
+    }
+}
+
+fn home_dir() -> Result<PathBuf> {
+    dirs::home_dir().context("Failed to get home dir")
+}
+
+fn completion_path(shell: ShellKind) -> Result<PathBuf> {
+    Ok(match shell {
+        // loaded on demand by bash-completion
+        ShellKind::Bash => dirs::data_dir()
+            .map_or_else(|| home_dir().map(|home| home.join(".local/share")), Ok)?
+            .join("bash-completion")
+            .join("completions")
+            .join(BIN),
+        ShellKind::Zsh => home_dir()?.join(".zfunc").join(format!("_{}", BIN)),
+        ShellKind::Fish => dirs::config_dir()
+            .map_or_else(|| home_dir().map(|home| home.join(".config")), Ok)?
+            .join("fish")
+            .join("completions")
+            .join(format!("{}.fish", BIN)),
+    })
+}
+
+/// Adds `dir` to `fpath` in `.zshrc` unless it is already there.
+fn ensure_zsh_fpath(dir: &Path) -> Result<()> {
+    let zdotdir = std::env::var_os("ZDOTDIR").map(PathBuf::from);
+    let zshrc = match zdotdir {
+        Some(dir) => dir,
+        None => home_dir()?,
+    }
+    .join(".zshrc");
+    let content = fs::read_to_string(&zshrc).unwrap_or_default();
+    let dir = dir.to_string_lossy();
+    if content.contains(dir.as_ref()) {
+        return Ok(());
+    }
+    // `compinit` reads `fpath`, so it is called again after the change
+    let snippet = format!(
+        "\n# H2O2 completions\nfpath=({} $fpath)\nautoload -Uz compinit && compinit\n",
+        dir
+    );
+    fs::write(&zshrc, content + &snippet).context("Failed to update .zshrc")?;
+    log_i18n!(info, "completions.fpath-added", zshrc.display());
+    Ok(())
+}
@@ -0,0 +1,4 @@
+mod generate;
+mod main;
+
+pub use main::*;
@@ -0,0 +1,78 @@
+use anyhow::{bail, Context, Result};
+use clap::{AppSettings, Clap};
+use std::{fs, path::Path};
+
+use super::{config_path_of, get_config_path, load_config, make_private, ConfigFormat};
+use crate::{log_i18n, tr};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Clap, Debug)]
+enum SubCommand {
+    /// 将配置文件转换为另一种格式，原文件备份为 <文件>.bak
+    /// Converts the config file into another format, keeping the original as <file>.bak
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Convert(ConvertArgs),
+}
+
+#[derive(Clap, Debug)]
+struct ConvertArgs {
+    /// 目标格式
+    /// Format to convert into
+    #[clap(long, arg_enum)]
+    to: ConfigFormat,
+}
+
+impl Args {
+    /// Name of the operation, if the subcommand modifies the config.
+    pub fn operation(&self) -> Option<&'static str> {
+        match self.subcmd {
+            SubCommand::Convert(_) => Some("config"),
+        }
+    }
+}
+
+pub async fn main(args: Args) -> Result<()> {
+    match args.subcmd {
+        SubCommand::Convert(args) => convert(args.to).await,
+    }
+}
+
+/// Rewrites the config file as `~/.h2o2config.<ext>` of `format`, moving the original aside so
+/// that the new one is found.
+async fn convert(format: ConfigFormat) -> Result<()> {
+    let config = load_config().await?;
+    let from = get_config_path();
+    if ConfigFormat::of(&from) == format {
+        log_i18n!(info, "config.already-format", from.display(), format);
+        return Ok(());
+    }
+    let to = config_path_of(format);
+    if to.exists() {
+        bail!(tr!("config.convert-exists", to.display()));
+    }
+
+    let text = format.serialize(&config)?;
+    fs::write(&to, text)
+        .and_then(|()| make_private(&to))
+        .with_context(|| tr!("sys.write-failed", to.display()))?;
+    let mut backup = from.clone().into_os_string();
+    backup.push(".bak");
+    // the backup holds the same secrets, though it may have been written before they were guarded
+    fs::rename(&from, &backup)
+        .and_then(|()| make_private(Path::new(&backup)))
+        .with_context(|| tr!("sys.write-failed", Path::new(&backup).display()))?;
+    log_i18n!(
+        info,
+        "config.converted",
+        to.display(),
+        format,
+        Path::new(&backup).display()
+    );
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use std::{any::Any, backtrace::Backtrace, panic, thread};
+
+use crate::{lock, log, tr};
+
+/// Where crashes are to be reported.
+const ISSUES_URL: &str = "https://github.com/wuxianucw/h2o2/issues";
+
+/// Replaces the raw panic output with a short notice telling where the details are and how to
+/// report them.
+///
+/// The panic message, the operation being run, the spans the panic happens in (which tell the
+/// component being installed) and a backtrace are written to the log file. They are printed
+/// instead only if the log file is not available.
+pub fn install_hook() {
+    panic::set_hook(Box::new(|info| {
+        let message = payload_message(info.payload());
+        let location = info
+            .location()
+            .map_or_else(|| "<unknown>".to_owned(), ToString::to_string);
+        let operation = lock::held_operation();
+        let details = format!(
+            "H2O2 {} panicked at {}: {}\n  thread: {}\n  operation: {}\n  platform: {}-{}\n  backtrace:\n{}",
+            env!("CARGO_PKG_VERSION"),
+            location,
+            message,
+            thread::current().name().unwrap_or("<unnamed>"),
+            operation.as_deref().unwrap_or("-"),
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            Backtrace::force_capture()
+        );
+        let written = log::write_error(format_args!("{}", details));
+
+        eprintln!();
+        eprintln!("{}", tr!("crash.panicked", message));
+        if let Some(operation) = operation {
+            eprintln!("{}", tr!("crash.interrupted", operation));
+        }
+        if written {
+            eprintln!(
+                "{}",
+                tr!("crash.log-written", log::get_log_path().display())
+            );
+        } else {
+            eprintln!("{}", tr!("crash.log-unavailable"));
+            eprintln!("{}", details);
+        }
+        eprintln!("{}", tr!("crash.report", ISSUES_URL));
+    }));
+}
+
+fn payload_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    }
+}
@@ -0,0 +1,237 @@
+use anyhow::{Context, Result};
+use clap::Clap;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use crate::{config::Config, install::helper::mongodb, log_i18n, tr};
+
+#[derive(Clap, Debug)]
+pub struct CheckIndexesArgs {
+    /// 创建缺失的索引，建议在维护时段内进行
+    /// Creates the missing indexes, preferably during a maintenance window
+    #[clap(long)]
+    pub rebuild: bool,
+
+    /// 报告运行超过此秒数的查询
+    /// Reports queries running longer than this many seconds
+    #[clap(long, default_value = "10")]
+    long_running: u64,
+}
+
+/// An index Hydro creates on startup.
+struct ExpectedIndex {
+    collection: &'static str,
+    name: &'static str,
+    key: &'static [(&'static str, i32)],
+    unique: bool,
+}
+
+/// Indexes the queries of Hydro rely on. Indexes are compared by their keys, as the names may
+/// differ between versions.
+const EXPECTED: &[ExpectedIndex] = &[
+    ExpectedIndex {
+        collection: "document",
+        name: "basic",
+        key: &[("domainId", 1), ("docType", 1), ("docId", 1)],
+        unique: true,
+    },
+    ExpectedIndex {
+        collection: "document",
+        name: "owner",
+        key: &[("domainId", 1), ("docType", 1), ("owner", 1), ("docId", -1)],
+        unique: false,
+    },
+    ExpectedIndex {
+        collection: "document.status",
+        name: "basic",
+        key: &[("domainId", 1), ("docType", 1), ("uid", 1), ("docId", 1)],
+        unique: true,
+    },
+    ExpectedIndex {
+        collection: "domain.user",
+        name: "uid",
+        key: &[("domainId", 1), ("uid", 1)],
+        unique: true,
+    },
+    ExpectedIndex {
+        collection: "record",
+        name: "basic",
+        key: &[("domainId", 1), ("contest", 1), ("_id", -1)],
+        unique: false,
+    },
+    ExpectedIndex {
+        collection: "record",
+        name: "withUser",
+        key: &[("domainId", 1), ("contest", 1), ("uid", 1), ("_id", -1)],
+        unique: false,
+    },
+    ExpectedIndex {
+        collection: "record",
+        name: "withProblem",
+        key: &[("domainId", 1), ("contest", 1), ("pid", 1), ("_id", -1)],
+        unique: false,
+    },
+    ExpectedIndex {
+        collection: "user",
+        name: "uname",
+        key: &[("unameLower", 1)],
+        unique: true,
+    },
+    ExpectedIndex {
+        collection: "user",
+        name: "mail",
+        key: &[("mailLower", 1)],
+        unique: true,
+    },
+];
+
+/// Index as listed by the script, with the key as `[field, direction]` pairs to keep the order.
+#[derive(Deserialize, Debug)]
+struct Index {
+    name: String,
+    key: Vec<(String, String)>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Operation {
+    opid: serde_json::Value,
+    ns: String,
+    op: String,
+    secs_running: u64,
+    plan_summary: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Report {
+    indexes: BTreeMap<String, Vec<Index>>,
+    /// `None` if `currentOp` is not available, e.g. lacking privileges
+    operations: Option<Vec<Operation>>,
+}
+
+impl ExpectedIndex {
+    fn matches(&self, index: &Index) -> bool {
+        index.key.len() == self.key.len()
+            && self
+                .key
+                .iter()
+                .zip(&index.key)
+                .all(|((field, dir), (f, d))| field == f && dir.to_string() == *d)
+    }
+
+    /// The key as a JavaScript object literal.
+    fn key_literal(&self) -> String {
+        let fields = self
+            .key
+            .iter()
+            .map(|(field, dir)| format!("{:?}: {}", field, dir))
+            .collect::<Vec<_>>();
+        format!("{{ {} }}", fields.join(", "))
+    }
+}
+
+pub fn check_indexes(config: &Config, args: CheckIndexesArgs) -> Result<()> {
+    let mut collections = EXPECTED.iter().map(|e| e.collection).collect::<Vec<_>>();
+    collections.dedup();
+    let script = format!(
+        r#"
+const n = x => (x && x.toNumber) ? x.toNumber() : Number(x || 0);
+const indexes = {{}};
+for (const name of {collections:?}) {{
+    indexes[name] = db.getCollection(name).getIndexes().map(i => ({{
+        name: i.name,
+        key: Object.keys(i.key).map(k => [k, typeof i.key[k] === 'string' ? i.key[k] : String(n(i.key[k]))]),
+    }}));
+}}
+let operations = null;
+try {{
+    operations = db.currentOp({{ active: true, secs_running: {{ $gte: {secs} }} }}).inprog.map(o => ({{
+        opid: o.opid,
+        ns: o.ns || '',
+        op: o.op || '',
+        secsRunning: n(o.secs_running),
+        planSummary: o.planSummary || null,
+    }}));
+}} catch (e) {{}}
+print(JSON.stringify({{ indexes, operations }}));
+"#,
+        collections = collections,
+        secs = args.long_running
+    );
+    let output = mongodb::eval(&config.components.mongodb, &config.profile.mongodb, &script)
+        .context(tr!("db.indexes-failed"))?;
+    let report: Report = serde_json::from_str(&output)
+        .with_context(|| format!("{}\n{}", tr!("db.indexes-failed"), output))?;
+
+    let missing = EXPECTED
+        .iter()
+        .filter(|expected| {
+            !report.indexes[expected.collection]
+                .iter()
+                .any(|index| expected.matches(index))
+        })
+        .collect::<Vec<_>>();
+    for expected in &missing {
+        log_i18n!(
+            warn,
+            "db.index-missing",
+            expected.collection,
+            expected.key_literal()
+        );
+    }
+    for (collection, indexes) in &report.indexes {
+        for index in indexes {
+            let known = index.name == "_id_"
+                || EXPECTED
+                    .iter()
+                    .any(|e| e.collection == collection && e.matches(index));
+            if !known {
+                log_i18n!(info, "db.index-extra", collection, &index.name);
+            }
+        }
+    }
+    match &report.operations {
+        Some(operations) => {
+            for op in operations {
+                log_i18n!(
+                    warn,
+                    "db.long-running",
+                    &op.opid,
+                    &op.op,
+                    &op.ns,
+                    op.secs_running,
+                    op.plan_summary.as_deref().unwrap_or("-")
+                );
+            }
+        }
+        None => log_i18n!(warn, "db.current-op-unavailable"),
+    }
+
+    if missing.is_empty() {
+        log_i18n!(info, "db.indexes-ok");
+        return Ok(());
+    }
+    if !args.rebuild {
+        log_i18n!(info, "db.indexes-rebuild-hint");
+        return Ok(());
+    }
+    for expected in missing {
+        log_i18n!(
+            info,
+            "db.index-creating",
+            expected.collection,
+            expected.name
+        );
+        let script = format!(
+            "print(db.getCollection({:?}).createIndex({}, {{ name: {:?}, unique: {}, background: true }}))",
+            expected.collection,
+            expected.key_literal(),
+            expected.name,
+            expected.unique
+        );
+        mongodb::eval(&config.components.mongodb, &config.profile.mongodb, &script)
+            .with_context(|| tr!("db.index-create-failed", expected.collection, expected.name))?;
+    }
+    log_i18n!(info, "db.indexes-rebuilt");
+    Ok(())
+}
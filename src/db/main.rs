@@ -0,0 +1,211 @@
+use anyhow::{bail, Context, Result};
+use clap::{AppSettings, Clap};
+use serde::Deserialize;
+
+use super::indexes::{self, CheckIndexesArgs};
+use crate::{
+    config::{self, Config},
+    install::helper::mongodb,
+    log_i18n, tr,
+    utils::format_size,
+    Com,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Clap, Debug)]
+enum SubCommand {
+    /// 使用 Hydro 的连接配置打开 MongoDB shell
+    /// Opens the MongoDB shell connected the way Hydro connects
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(setting = AppSettings::TrailingVarArg)]
+    #[clap(setting = AppSettings::AllowLeadingHyphen)]
+    Shell(ShellArgs),
+
+    /// 统计 Hydro 数据库的大小、文档数和索引使用情况
+    /// Prints sizes, document counts and index usage of the Hydro database
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Stats,
+
+    /// 检查 Hydro 所需的索引与长时间运行的查询，并可重建缺失的索引
+    /// Checks the indexes Hydro needs and long-running queries, and can rebuild missing indexes
+    #[clap(setting = AppSettings::ColoredHelp)]
+    CheckIndexes(CheckIndexesArgs),
+}
+
+impl Args {
+    /// Name of the operation, if the subcommand modifies the database.
+    pub fn operation(&self) -> Option<&'static str> {
+        match &self.subcmd {
+            SubCommand::CheckIndexes(args) if args.rebuild => Some("db check-indexes"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clap, Debug)]
+struct ShellArgs {
+    /// 传递给 mongosh（或 mongo）的参数
+    /// Arguments passed to mongosh (or mongo)
+    args: Vec<String>,
+}
+
+/// Collects the statistics as a single line of JSON. Numbers may be `NumberLong`s in the legacy
+/// shell, hence `n()`.
+const STATS_SCRIPT: &str = r#"
+const n = x => (x && x.toNumber) ? x.toNumber() : Number(x || 0);
+const s = db.stats();
+const collections = db.getCollectionNames().sort().map(name => {
+    const c = db.getCollection(name);
+    const cs = c.stats();
+    let unused = null;
+    try {
+        unused = c.aggregate([{ $indexStats: {} }]).toArray()
+            .filter(i => i.name !== '_id_' && n(i.accesses.ops) === 0)
+            .map(i => i.name);
+    } catch (e) {}
+    return {
+        name,
+        count: n(cs.count),
+        size: n(cs.size),
+        storageSize: n(cs.storageSize),
+        nindexes: n(cs.nindexes),
+        totalIndexSize: n(cs.totalIndexSize),
+        unusedIndexes: unused,
+    };
+});
+print(JSON.stringify({
+    db: s.db,
+    objects: n(s.objects),
+    dataSize: n(s.dataSize),
+    storageSize: n(s.storageSize),
+    indexSize: n(s.indexSize),
+    collections,
+}));
+"#;
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct DbStats {
+    db: String,
+    objects: u64,
+    data_size: u64,
+    storage_size: u64,
+    index_size: u64,
+    collections: Vec<CollectionStats>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct CollectionStats {
+    name: String,
+    count: u64,
+    size: u64,
+    storage_size: u64,
+    nindexes: u64,
+    total_index_size: u64,
+    /// `None` if `$indexStats` is not available, e.g. lacking privileges
+    unused_indexes: Option<Vec<String>>,
+}
+
+pub async fn main(args: Args) -> Result<()> {
+    let config = config::load_config().await?;
+    if !config.components.mongodb.is_installed() {
+        bail!(tr!("check.not-installed", Com::MongoDB));
+    }
+
+    match args.subcmd {
+        SubCommand::Shell(args) => shell(&config, args.args),
+        SubCommand::Stats => stats(&config),
+        SubCommand::CheckIndexes(args) => indexes::check_indexes(&config, args),
+    }
+}
+
+fn shell(config: &Config, args: Vec<String>) -> Result<()> {
+    let shell = match mongodb::shell_path(&config.components.mongodb) {
+        Some(shell) => shell,
+        None => bail!(tr!("db.shell-not-found")),
+    };
+    let mut shell_args = vec![mongodb::connection_uri(&config.profile.mongodb)];
+    shell_args.extend(args);
+
+    let status = duct::cmd(&shell, shell_args)
+        .unchecked()
+        .run()
+        .with_context(|| tr!("db.shell-failed", shell.display()))?
+        .status;
+    if !status.success() {
+        bail!(tr!("db.shell-exited", shell.display(), status));
+    }
+    Ok(())
+}
+
+fn stats(config: &Config) -> Result<()> {
+    let output = mongodb::eval(
+        &config.components.mongodb,
+        &config.profile.mongodb,
+        STATS_SCRIPT,
+    )
+    .context(tr!("db.stats-failed"))?;
+    let stats: DbStats = serde_json::from_str(&output)
+        .with_context(|| format!("{}\n{}", tr!("db.stats-failed"), output))?;
+
+    log_i18n!(info, "db.stats", &stats.db);
+    tracing::info!(
+        "  objects: {}, data: {}, storage: {}, indexes: {}",
+        stats.objects,
+        format_size(stats.data_size),
+        format_size(stats.storage_size),
+        format_size(stats.index_size)
+    );
+
+    let width = stats
+        .collections
+        .iter()
+        .map(|c| c.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("collection".len());
+    tracing::info!(
+        "  {:<width$}  {:>10}  {:>11}  {:>11}  {:>7}  {:>11}",
+        "collection",
+        "count",
+        "size",
+        "storage",
+        "indexes",
+        "index size",
+        width = width
+    );
+    for c in &stats.collections {
+        tracing::info!(
+            "  {:<width$}  {:>10}  {:>11}  {:>11}  {:>7}  {:>11}",
+            c.name,
+            c.count,
+            format_size(c.size),
+            format_size(c.storage_size),
+            c.nindexes,
+            format_size(c.total_index_size),
+            width = width
+        );
+    }
+
+    let mut unknown = false;
+    for c in &stats.collections {
+        match &c.unused_indexes {
+            Some(unused) if !unused.is_empty() => {
+                log_i18n!(warn, "db.unused-indexes", &c.name, unused.join(", "));
+            }
+            Some(_) => {}
+            None => unknown = true,
+        }
+    }
+    if unknown {
+        log_i18n!(warn, "db.index-stats-unavailable");
+    }
+    Ok(())
+}
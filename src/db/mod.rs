@@ -0,0 +1,4 @@
+mod indexes;
+mod main;
+
+pub use main::*;
@@ -0,0 +1,54 @@
+use duct::cmd;
+use std::collections::BTreeMap;
+
+use super::runtimes::extract_version;
+use crate::{
+    config::{ComponentInfo, Version},
+    log_i18n, registry,
+    utils::decode_output,
+};
+
+/// Probes the optional components described in the registry.
+///
+/// A recorded path is preferred to searching `PATH`, and components no longer found are removed.
+pub fn detect(extras: &mut BTreeMap<String, ComponentInfo>) {
+    for desc in registry::extras() {
+        let (executable, args) = match desc.probe {
+            Some(probe) => probe,
+            None => continue,
+        };
+        let executable = extras
+            .get(desc.name)
+            .and_then(|info| info.path.clone())
+            .unwrap_or_else(|| executable.to_owned());
+
+        log_i18n!(info, "detect.detecting", desc.display);
+        let output = match cmd(&executable, args)
+            .stdout_capture()
+            .stderr_capture()
+            .unchecked()
+            .run()
+        {
+            Ok(output) if output.status.success() => output,
+            Ok(output) => {
+                tracing::debug!("{} exited abnormally ({})", executable, &output.status);
+                extras.remove(desc.name);
+                continue;
+            }
+            Err(e) => {
+                tracing::debug!("{:#?}", e);
+                extras.remove(desc.name);
+                continue;
+            }
+        };
+
+        let version = extract_version(&decode_output(&output.stdout))
+            .and_then(|version| semver::Version::parse(&version).ok())
+            .map_or(Version::Installed, Version::Valid);
+        tracing::info!("Found: {} {}", desc.display, &version);
+        extras.insert(
+            desc.name.to_owned(),
+            ComponentInfo::new(version, Some(executable)),
+        );
+    }
+}
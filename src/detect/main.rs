@@ -2,18 +2,26 @@ use anyhow::{Context, Result};
 use clap::Clap;
 use duct::cmd;
 use semver::Version;
-use std::{io::ErrorKind, path::Path};
+use serde_json::json;
+use std::{fs, io::ErrorKind, path::Path};
 
+use super::{extras, runtimes};
 use crate::{
     check_version,
     config::{self, Config, ConfigError},
-    maybe_cmd, show,
-    utils::debug_output,
+    install::helper::jspm,
+    langs, log_i18n, output, platform, show,
+    utils::{debug_output, decode_output},
+    Com,
 };
 
 #[derive(Clap, Debug)]
 #[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
 pub struct Args {
+    /// 要探测的组件，默认为全部；依赖于它们的组件也会被重新探测
+    /// Components to detect, defaults to all; components depending on them are detected as well
+    components: Vec<Com>,
+
     /// 仅显示探测到的组件及版本，不更新配置文件
     /// Prints the components detected only without updating .h2o2config
     #[clap(short, long)]
@@ -23,16 +31,22 @@ pub struct Args {
     /// Runs without loading config
     #[clap(long)]
     no_config: bool,
+
+    /// 同时探测评测可用的编译器与解释器
+    /// Detects compilers and interpreters available to the judge as well
+    #[clap(long)]
+    runtimes: bool,
 }
 
 pub async fn main(args: Args) -> Result<()> {
     let mut config = if args.no_config {
-        log::info!("当前模式将不加载配置文件。 Skipped config loading.");
-        // load config actually, because if not, sandbox config will lose
+        log_i18n!(info, "config.skip-loading");
+        // load config actually, because if not, sandbox config and profile will lose
         match config::load_config().await {
             Ok(cfg) => {
                 let mut config = Config::default();
                 config.components.sandbox = cfg.components.sandbox;
+                config.profile = cfg.profile;
                 config
             }
             Err(_) => Config::default(),
@@ -40,17 +54,17 @@ pub async fn main(args: Args) -> Result<()> {
     } else {
         match config::load_config().await {
             Ok(config) => {
-                log::info!("已成功加载配置。 Config loaded successfully.");
+                log_i18n!(info, "config.loaded");
                 config
             }
             Err(e) => {
                 match e {
                     ConfigError::FileNotExist => {
-                        log::info!("配置文件不存在，开始初始化。 Config file does not exist, start initialization.");
+                        log_i18n!(info, "config.initializing");
                     }
                     e => {
-                        log::error!("加载配置失败！准备尝试重新初始化。 Failed to load config! Try to reinitialize.");
-                        log::debug!("{:#?}", e);
+                        log_i18n!(error, "config.load-failed");
+                        tracing::debug!("{:#?}", e);
                     }
                 };
                 Config::default()
@@ -58,399 +72,421 @@ pub async fn main(args: Args) -> Result<()> {
         }
     };
 
+    let targets = if args.components.is_empty() {
+        config.profile.components()
+    } else {
+        let mut targets = args.components.clone();
+        for com in &args.components {
+            targets.extend(com.dependents());
+        }
+        targets
+    };
+
+    let package_manager = config.profile.hydro.package_manager;
     let mut com = &mut config.components;
-    let (mut nodejs_ok, mut yarn_ok) = (false, false);
+    // components not being detected are trusted as recorded in config
+    let mut nodejs_ok = !targets.contains(&Com::NodeJS) && com.nodejs.is_installed();
+    let mut yarn_ok = !targets.contains(&Com::Yarn) && com.yarn.is_installed();
 
     // detect Node.js
-    log::info!("探测 Node.js... Detecting Node.js...");
-    let executable = com.nodejs.path("node");
-    let executable = &executable[..];
-    // try to execute `node -v`
-    match cmd!(executable, "-v")
-        .stdout_capture()
-        .stderr_capture()
-        .unchecked()
-        .run()
-    {
-        Ok(output) => {
-            let stdout =
-                String::from_utf8(output.stdout.clone()).context("Failed to convert stdout")?;
-            if output.status.success() {
-                // try to parse version
-                // stdout: v{version}
-                let stdout = stdout.trim();
-                if stdout.len() < "v?".len() {
-                    log::error!(
-                        "Node.js 的输出太短，疑似运行异常。 \
-                        The output of Node.js is too short, and it seems to be running abnormally."
-                    );
-                    debug_output(&output);
-                } else {
-                    // skip the leading "v" and parse
-                    match Version::parse(&stdout["v".len()..]) {
-                        Ok(version) => {
-                            log::info!("Found: Node.js {}", &version);
-                            check_version!(nodejs, &version, warn);
-                            com.nodejs.version = config::Version::Valid(version);
-                            // leave `nodejs.path` untouched
-                            // com.nodejs.path = path;
-                            nodejs_ok = true;
-                        }
-                        Err(e) => {
-                            log::error!("解析版本号失败。 Failed to parse version.");
-                            log::debug!("{:#?}", e);
-                            debug_output(&output);
+    if targets.contains(&Com::NodeJS) {
+        log_i18n!(info, "detect.detecting", Com::NodeJS);
+        let executable = com.nodejs.path("node");
+        let executable = &executable[..];
+        // try to execute `node -v`
+        match cmd!(executable, "-v")
+            .stdout_capture()
+            .stderr_capture()
+            .unchecked()
+            .run()
+        {
+            Ok(output) => {
+                let stdout = decode_output(&output.stdout);
+                if output.status.success() {
+                    // try to parse version
+                    // stdout: v{version}
+                    let stdout = stdout.trim();
+                    if stdout.len() < "v?".len() {
+                        log_i18n!(error, "detect.output-too-short", Com::NodeJS);
+                        debug_output(&output);
+                    } else {
+                        // skip the leading "v" and parse
+                        match Version::parse(&stdout["v".len()..]) {
+                            Ok(version) => {
+                                tracing::info!("Found: Node.js {}", &version);
+                                check_version!(nodejs, &version, warn);
+                                com.nodejs.version = config::Version::Valid(version);
+                                // leave `nodejs.path` untouched
+                                // com.nodejs.path = path;
+                                nodejs_ok = true;
+                            }
+                            Err(e) => {
+                                log_i18n!(error, "detect.parse-version-failed");
+                                tracing::debug!("{:#?}", e);
+                                debug_output(&output);
+                            }
                         }
                     }
-                }
-            } else {
-                log::error!(
-                    "Node.js 异常退出（{0}），无法识别版本。 \
-                    Node.js exited abnormally and the version could not be recognized. ({0})",
-                    &output.status,
-                );
-                debug_output(&output);
-            }
-        }
-        Err(e) => {
-            if let ErrorKind::NotFound = e.kind() {
-                log::error!("未找到 Node.js。 Node.js is not found.");
-            } else {
-                log::error!(
-                    "命令 `{0} -v` 执行异常。 Failed to execute `{0} -v`.",
-                    executable,
-                );
-                log::debug!("{:#?}", e);
-            }
-        }
-    }
-
-    // detect MongoDB
-    log::info!("探测 MongoDB... Detecting MongoDB...");
-    let executable = com.mongodb.path_or("mongod");
-    // try to execute `mongod --version`
-    match cmd!(executable, "--version")
-        .stdout_capture()
-        .stderr_capture()
-        .unchecked()
-        .run()
-    {
-        Ok(output) => {
-            let stdout =
-                String::from_utf8(output.stdout.clone()).context("Failed to convert stdout")?;
-            if output.status.success() {
-                // try to parse version
-                // stdout(first line): db version v{<version>}
-                let stdout = stdout.lines().next().unwrap_or("").trim();
-                if stdout.len() < "db version v?".len() {
-                    log::error!(
-                        "MongoDB 的输出太短，疑似运行异常。 \
-                        The output of MongoDB is too short, and it seems to be running abnormally."
+                } else {
+                    log_i18n!(
+                        error,
+                        "detect.version-unrecognized",
+                        Com::NodeJS,
+                        &output.status
                     );
                     debug_output(&output);
-                } else {
-                    // skip the leading "db version v" and parse
-                    match Version::parse(&stdout["db version v".len()..]) {
-                        Ok(version) => {
-                            log::info!("Found: MongoDB {}", &version);
-                            check_version!(mongodb, &version, warn);
-                            com.mongodb.path = Some(executable.to_owned());
-                            com.mongodb.version = config::Version::Valid(version);
-                        }
-                        Err(e) => {
-                            log::error!("解析版本号失败。 Failed to parse version.");
-                            log::debug!("{:#?}", e);
-                            debug_output(&output);
-                        }
-                    }
                 }
-            } else {
-                log::error!(
-                    "MongoDB 异常退出（{0}），无法识别版本。 \
-                    MongoDB exited abnormally and the version could not be recognized. ({0})",
-                    &output.status,
-                );
-                debug_output(&output);
             }
-        }
-        Err(e) => {
-            if let ErrorKind::NotFound = e.kind() {
-                log::error!("未找到 MongoDB。 MongoDB is not found.");
-            } else {
-                log::error!(
-                    "命令 `{0} --version` 执行异常。 Failed to execute `{0} --version`.",
-                    executable,
-                );
-                log::debug!("{:#?}", e);
-            }
-        }
-    }
-
-    // detect MinIO
-    log::info!("探测 MinIO... Detecting MinIO...");
-    let executable = com.minio.path_or("minio");
-    // try to execute `minio -v`
-    match cmd!(executable, "-v")
-        .stdout_capture()
-        .stderr_capture()
-        .unchecked()
-        .run()
-    {
-        Ok(output) => {
-            let stdout =
-                String::from_utf8(output.stdout.clone()).context("Failed to convert stdout")?;
-            if output.status.success() {
-                // simply check prefix
-                // stdout: minio version {not a semver}
-                // example: minio version RELEASE.2021-04-06T23-11-00Z
-                let stdout = stdout.trim();
-                if stdout.starts_with("minio version ") {
-                    log::info!("Found: MinIO installed");
-                    com.minio.path = Some(executable.to_owned());
-                    com.minio.version = config::Version::Installed;
+            Err(e) => {
+                if let ErrorKind::NotFound = e.kind() {
+                    log_i18n!(error, "detect.not-found", Com::NodeJS);
                 } else {
-                    log::error!(
-                        "MinIO 的输出格式不正确，疑似运行异常。 \
-                        The output format of MinIO is incorrect, and it seems to be running abnormally."
-                    );
-                    debug_output(&output);
+                    log_i18n!(error, "detect.exec-failed", executable, "-v");
+                    tracing::debug!("{:#?}", e);
                 }
-            } else {
-                log::error!(
-                    "MinIO 异常退出。 MinIO exited abnormally. ({})",
-                    &output.status,
-                );
-                debug_output(&output);
-            }
-        }
-        Err(e) => {
-            if let ErrorKind::NotFound = e.kind() {
-                log::error!("未找到 MinIO。 MinIO is not found.");
-            } else {
-                log::error!(
-                    "命令 `{0} -v` 执行异常。 Failed to execute `{0} -v`.",
-                    executable,
-                );
-                log::debug!("{:#?}", e);
             }
         }
     }
 
-    // detect sandbox
-    log::info!("sandbox 无法探测，跳过。 Cannot detect sandbox, skipped.");
-
-    // detect Yarn
-    if nodejs_ok {
-        log::info!("探测 Yarn... Detecting Yarn...");
-        let executable = com.yarn.path_or(maybe_cmd!("yarn"));
-        // try to execute `yarn -v`
-        match cmd!(executable, "-v")
+    // detect MongoDB
+    if targets.contains(&Com::MongoDB) {
+        log_i18n!(info, "detect.detecting", Com::MongoDB);
+        let executable = com.mongodb.path_or("mongod");
+        // try to execute `mongod --version`
+        match cmd!(executable, "--version")
             .stdout_capture()
             .stderr_capture()
             .unchecked()
             .run()
         {
             Ok(output) => {
-                let stdout =
-                    String::from_utf8(output.stdout.clone()).context("Failed to convert stdout")?;
+                let stdout = decode_output(&output.stdout);
                 if output.status.success() {
                     // try to parse version
-                    // stdout: {version}
-                    let stdout = stdout.trim();
-                    match Version::parse(stdout) {
-                        Ok(version) => {
-                            log::info!("Found: Yarn {}", &version);
-                            com.yarn.path = Some(executable.to_owned());
-                            com.yarn.version = config::Version::Valid(version);
-                            yarn_ok = true;
-                        }
-                        Err(e) => {
-                            log::error!("解析版本号失败。 Failed to parse version.");
-                            log::debug!("{:#?}", e);
-                            debug_output(&output);
+                    // stdout(first line): db version v{<version>}
+                    let stdout = stdout.lines().next().unwrap_or("").trim();
+                    if stdout.len() < "db version v?".len() {
+                        log_i18n!(error, "detect.output-too-short", Com::MongoDB);
+                        debug_output(&output);
+                    } else {
+                        // skip the leading "db version v" and parse
+                        match Version::parse(&stdout["db version v".len()..]) {
+                            Ok(version) => {
+                                tracing::info!("Found: MongoDB {}", &version);
+                                check_version!(mongodb, &version, warn);
+                                com.mongodb.path = Some(executable.to_owned());
+                                com.mongodb.version = config::Version::Valid(version);
+                            }
+                            Err(e) => {
+                                log_i18n!(error, "detect.parse-version-failed");
+                                tracing::debug!("{:#?}", e);
+                                debug_output(&output);
+                            }
                         }
                     }
                 } else {
-                    log::error!(
-                        "Yarn 异常退出（{0}），无法识别版本。 \
-                        Yarn exited abnormally and the version could not be recognized. ({0})",
-                        &output.status,
+                    log_i18n!(
+                        error,
+                        "detect.version-unrecognized",
+                        Com::MongoDB,
+                        &output.status
                     );
                     debug_output(&output);
                 }
             }
             Err(e) => {
                 if let ErrorKind::NotFound = e.kind() {
-                    log::error!("未找到 Yarn。 Yarn is not found.");
+                    log_i18n!(error, "detect.not-found", Com::MongoDB);
                 } else {
-                    log::error!(
-                        "命令 `{0} -v` 执行异常。 Failed to execute `{0} -v`.",
-                        executable,
-                    );
-                    log::debug!("{:#?}", e);
+                    log_i18n!(error, "detect.exec-failed", executable, "--version");
+                    tracing::debug!("{:#?}", e);
                 }
             }
         }
-    } else {
-        log::warn!(
-            "未找到 Node.js，跳过 Yarn（依赖 Node.js）。 \
-            Skip Yarn (which depends on Node.js) due to Node.js not found."
-        );
     }
 
-    // detect PM2
-    if nodejs_ok {
-        log::info!("探测 PM2... Detecting PM2...");
-        let executable = com.pm2.path_or(maybe_cmd!("pm2"));
-        // try to execute `pm2 -v -s --no-daemon`
-        match cmd!(executable, "-v", "-s", "--no-daemon")
+    // detect MinIO
+    if targets.contains(&Com::MinIO) {
+        log_i18n!(info, "detect.detecting", Com::MinIO);
+        let executable = com.minio.path_or("minio");
+        // try to execute `minio -v`
+        match cmd!(executable, "-v")
             .stdout_capture()
             .stderr_capture()
             .unchecked()
             .run()
         {
             Ok(output) => {
-                let stdout =
-                    String::from_utf8(output.stdout.clone()).context("Failed to convert stdout")?;
+                let stdout = decode_output(&output.stdout);
                 if output.status.success() {
-                    // try to parse version
-                    // stdout: {version}
+                    // simply check prefix
+                    // stdout: minio version {not a semver}
+                    // example: minio version RELEASE.2021-04-06T23-11-00Z
                     let stdout = stdout.trim();
-                    match Version::parse(stdout) {
-                        Ok(version) => {
-                            log::info!("Found: PM2 {}", &version);
-                            com.pm2.path = Some(executable.to_owned());
-                            com.pm2.version = config::Version::Valid(version);
-                        }
-                        Err(e) => {
-                            log::error!("解析版本号失败。 Failed to parse version.");
-                            log::debug!("{:#?}", e);
-                            debug_output(&output);
-                        }
+                    if stdout.starts_with("minio version ") {
+                        tracing::info!("Found: MinIO installed");
+                        com.minio.path = Some(executable.to_owned());
+                        com.minio.version = config::Version::Installed;
+                    } else {
+                        log_i18n!(error, "detect.output-malformed", Com::MinIO);
+                        debug_output(&output);
                     }
                 } else {
-                    log::error!(
-                        "PM2 异常退出（{0}），无法识别版本。 \
-                        PM2 exited abnormally and the version could not be recognized. ({0})",
-                        &output.status,
+                    log_i18n!(
+                        error,
+                        "detect.exited-abnormally",
+                        Com::MinIO,
+                        &output.status
                     );
                     debug_output(&output);
                 }
             }
             Err(e) => {
                 if let ErrorKind::NotFound = e.kind() {
-                    log::error!("未找到 PM2。 PM2 is not found.");
+                    log_i18n!(error, "detect.not-found", Com::MinIO);
                 } else {
-                    log::error!(
-                        "命令 `{0} -v -s --no-daemon` 执行异常。 Failed to execute `{0} -v -s --no-daemon`.",
-                        executable,
-                    );
-                    log::debug!("{:#?}", e);
+                    log_i18n!(error, "detect.exec-failed", executable, "-v");
+                    tracing::debug!("{:#?}", e);
                 }
             }
         }
-    } else {
-        log::warn!(
-            "未找到 Node.js，跳过 PM2（依赖 Node.js）。 \
-            Skip PM2 (which depends on Node.js) due to Node.js not found."
-        );
     }
 
-    // detect Hydro
-    if yarn_ok {
-        log::info!("探测 Hydro... Detecting Hydro...");
-        let yarn_global_dir;
-        let path = match com.hydro.path.as_deref() {
-            Some(path) => path,
-            None => {
-                let yarn = com
-                    .yarn
-                    .path
-                    .as_ref()
-                    .expect("Yarn should be OK, but its `path` is `None`");
-                yarn_global_dir = cmd!(yarn, "global", "dir")
-                    .unchecked()
-                    .read()
-                    .with_context(|| {
-                        format!("Failed to get the result of `{} global dir`", yarn)
-                    })?;
-                &yarn_global_dir
-            }
-        };
-        // Note: `path` may not exist
-        if Path::new(path).is_dir() {
-            // try to execute some magic command
-            let node = com
-                .nodejs
-                .path
-                .as_deref()
-                .expect("Node.js should be OK, but its `path` is `None`");
-            match cmd!(
-                node,
-                "-e",
-                "console.log(require('hydrooj/package.json').version)"
-            )
-            .dir(path)
-            .stdout_capture()
-            .stderr_capture()
-            .unchecked()
-            .run()
+    // detect sandbox
+    if targets.contains(&Com::Sandbox) {
+        log_i18n!(info, "detect.sandbox-skipped");
+    }
+
+    // detect Yarn
+    if targets.contains(&Com::Yarn) {
+        if nodejs_ok {
+            log_i18n!(info, "detect.detecting", Com::Yarn);
+            let yarn = platform::cmd("yarn");
+            let executable = com.yarn.path_or(&yarn);
+            // try to execute `yarn -v`
+            match cmd!(executable, "-v")
+                .stdout_capture()
+                .stderr_capture()
+                .unchecked()
+                .run()
             {
                 Ok(output) => {
-                    let stdout = String::from_utf8(output.stdout.clone())
-                        .context("Failed to convert stdout")?;
+                    let stdout = decode_output(&output.stdout);
                     if output.status.success() {
                         // try to parse version
                         // stdout: {version}
                         let stdout = stdout.trim();
                         match Version::parse(stdout) {
                             Ok(version) => {
-                                log::info!("Found: Hydro {}", &version);
-                                com.hydro.version = config::Version::Valid(version);
-                                com.hydro.path = Some(path.to_owned());
+                                tracing::info!("Found: Yarn {}", &version);
+                                com.yarn.path = Some(executable.to_owned());
+                                com.yarn.version = config::Version::Valid(version);
+                                // `corepack prepare` may have activated another one
+                                if config.profile.yarn.pinned.is_some() {
+                                    config.profile.yarn.pinned = Some(com.yarn.version.to_string());
+                                }
+                                if jspm::is_berry(&com.yarn.version) {
+                                    log_i18n!(warn, "detect.yarn-berry", &com.yarn.version);
+                                }
+                                yarn_ok = true;
                             }
                             Err(e) => {
-                                log::error!("解析版本号失败。 Failed to parse version.");
-                                log::debug!("{:#?}", e);
+                                log_i18n!(error, "detect.parse-version-failed");
+                                tracing::debug!("{:#?}", e);
                                 debug_output(&output);
                             }
                         }
                     } else {
-                        log::error!("未找到 Hydro。 Hydro is not found.");
+                        log_i18n!(
+                            error,
+                            "detect.version-unrecognized",
+                            Com::Yarn,
+                            &output.status
+                        );
                         debug_output(&output);
                     }
                 }
                 Err(e) => {
                     if let ErrorKind::NotFound = e.kind() {
-                        log::error!("未找到 Hydro。 Hydro is not found.");
+                        log_i18n!(error, "detect.not-found", Com::Yarn);
+                    } else {
+                        log_i18n!(error, "detect.exec-failed", executable, "-v");
+                        tracing::debug!("{:#?}", e);
+                    }
+                }
+            }
+        } else {
+            log_i18n!(warn, "detect.dependency-missing", Com::Yarn, Com::NodeJS);
+        }
+    }
+
+    // detect PM2
+    if targets.contains(&Com::PM2) {
+        if nodejs_ok {
+            log_i18n!(info, "detect.detecting", Com::PM2);
+            let pm2 = platform::cmd("pm2");
+            let executable = com.pm2.path_or(&pm2);
+            // try to execute `pm2 -v -s --no-daemon`
+            match cmd!(executable, "-v", "-s", "--no-daemon")
+                .stdout_capture()
+                .stderr_capture()
+                .unchecked()
+                .run()
+            {
+                Ok(output) => {
+                    let stdout = decode_output(&output.stdout);
+                    if output.status.success() {
+                        // try to parse version
+                        // stdout: {version}
+                        let stdout = stdout.trim();
+                        match Version::parse(stdout) {
+                            Ok(version) => {
+                                tracing::info!("Found: PM2 {}", &version);
+                                com.pm2.path = Some(executable.to_owned());
+                                com.pm2.version = config::Version::Valid(version);
+                            }
+                            Err(e) => {
+                                log_i18n!(error, "detect.parse-version-failed");
+                                tracing::debug!("{:#?}", e);
+                                debug_output(&output);
+                            }
+                        }
                     } else {
-                        log::error!(
-                            "命令 `{0} -e <...>` 执行异常。 Failed to execute `{0} -e <...>`.",
-                            node,
+                        log_i18n!(
+                            error,
+                            "detect.version-unrecognized",
+                            Com::PM2,
+                            &output.status
                         );
-                        log::debug!("{:#?}", e);
+                        debug_output(&output);
+                    }
+                }
+                Err(e) => {
+                    if let ErrorKind::NotFound = e.kind() {
+                        log_i18n!(error, "detect.not-found", Com::PM2);
+                    } else {
+                        log_i18n!(error, "detect.exec-failed", executable, "-v -s --no-daemon");
+                        tracing::debug!("{:#?}", e);
                     }
                 }
             }
         } else {
-            log::error!("未找到 Hydro。 Hydro is not found.");
+            log_i18n!(warn, "detect.dependency-missing", Com::PM2, Com::NodeJS);
         }
-    } else {
-        log::warn!(
-            "未找到 Yarn，跳过 Hydro（依赖 Yarn）。 \
-            Skip Hydro (which depends on Yarn) due to Yarn not found."
-        );
     }
 
-    log::info!("结果如下： Result:");
-    show::show_components(com);
+    // detect Hydro
+    if targets.contains(&Com::Hydro) {
+        if yarn_ok {
+            log_i18n!(info, "detect.detecting", Com::Hydro);
+            let global_dir;
+            let path = match com.hydro.path.as_deref() {
+                Some(path) => path,
+                None => {
+                    let pm = jspm::select(package_manager, &com.nodejs, &com.yarn);
+                    global_dir = pm.global_dir().with_context(|| {
+                        format!("Failed to get the global dir of {}", pm.name())
+                    })?;
+                    &global_dir
+                }
+            };
+            // Note: `path` may not exist
+            if Path::new(path).is_dir() {
+                // try to execute some magic command
+                let node = com
+                    .nodejs
+                    .path
+                    .as_deref()
+                    .expect("Node.js should be OK, but its `path` is `None`");
+                match cmd!(
+                    node,
+                    "-e",
+                    "console.log(require('hydrooj/package.json').version)"
+                )
+                .dir(path)
+                .stdout_capture()
+                .stderr_capture()
+                .unchecked()
+                .run()
+                {
+                    Ok(output) => {
+                        let stdout = decode_output(&output.stdout);
+                        if output.status.success() {
+                            // try to parse version
+                            // stdout: {version}
+                            let stdout = stdout.trim();
+                            match Version::parse(stdout) {
+                                Ok(version) => {
+                                    tracing::info!("Found: Hydro {}", &version);
+                                    com.hydro.version = config::Version::Valid(version);
+                                    com.hydro.path = Some(path.to_owned());
+                                }
+                                Err(e) => {
+                                    log_i18n!(error, "detect.parse-version-failed");
+                                    tracing::debug!("{:#?}", e);
+                                    debug_output(&output);
+                                }
+                            }
+                        } else {
+                            log_i18n!(error, "detect.not-found", Com::Hydro);
+                            debug_output(&output);
+                        }
+                    }
+                    Err(e) => {
+                        if let ErrorKind::NotFound = e.kind() {
+                            log_i18n!(error, "detect.not-found", Com::Hydro);
+                        } else {
+                            log_i18n!(error, "detect.exec-failed", node, "-e <...>");
+                            tracing::debug!("{:#?}", e);
+                        }
+                    }
+                }
+            } else {
+                log_i18n!(error, "detect.not-found", Com::Hydro);
+            }
+        } else {
+            log_i18n!(warn, "detect.dependency-missing", Com::Hydro, Com::Yarn);
+        }
+    }
+
+    // optional components cannot be selected yet, so they are detected with all components
+    if args.components.is_empty() {
+        extras::detect(&mut config.components.extras);
+    }
+
+    if args.runtimes {
+        config.runtimes = runtimes::detect();
+    }
+
+    if output::is_json() {
+        output::print_json(&json!({
+            "components": output::components(&config.components),
+            "runtimes": args.runtimes.then(|| output::runtimes(&config.runtimes)),
+            "dry_run": args.dry_run,
+        }));
+    } else {
+        log_i18n!(info, "detect.result");
+        show::show_components(&config.components);
+        if args.runtimes {
+            show::show_runtimes(&config.runtimes);
+        }
+    }
     if args.dry_run {
         return Ok(());
     }
 
-    log::info!("将写入配置文件... Saving config...");
+    if args.runtimes {
+        let path = config::get_com_path().join("hydro").join("langs.yaml");
+        let langs = langs::generate(&config.runtimes);
+        fs::create_dir_all(path.parent().unwrap())
+            .and_then(|_| fs::write(&path, langs::to_yaml(&langs)))
+            .context("Failed to write language settings")?;
+        log_i18n!(info, "detect.langs-generated", path.display());
+    }
+
+    log_i18n!(info, "config.saving");
     config::save_config(&config).await?;
-    log::info!("配置已成功保存。 Config saved successfully.");
+    log_i18n!(info, "config.saved");
 
     Ok(())
 }
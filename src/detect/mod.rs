@@ -1,3 +1,5 @@
+mod extras;
 mod main;
+mod runtimes;
 
 pub use main::*;
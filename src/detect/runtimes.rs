@@ -0,0 +1,81 @@
+use duct::cmd;
+use std::collections::BTreeMap;
+
+use crate::{config::RuntimeInfo, log_i18n, utils::decode_output};
+
+/// (runtime name, executable, arguments printing the version)
+const PROBES: &[(&str, &str, &[&str])] = &[
+    ("gcc", "gcc", &["--version"]),
+    ("g++", "g++", &["--version"]),
+    ("java", "javac", &["-version"]),
+    ("python2", "python2", &["--version"]),
+    ("python3", "python3", &["--version"]),
+    ("rustc", "rustc", &["--version"]),
+    ("go", "go", &["version"]),
+    ("fpc", "fpc", &["-iV"]),
+];
+
+/// Probes the runtimes commonly used by judges.
+pub fn detect() -> BTreeMap<String, RuntimeInfo> {
+    let mut runtimes = BTreeMap::new();
+
+    for (name, executable, args) in PROBES {
+        log_i18n!(info, "detect.detecting", name);
+        let output = match cmd(*executable, *args)
+            .stdout_capture()
+            .stderr_capture()
+            .unchecked()
+            .run()
+        {
+            Ok(output) if output.status.success() => output,
+            Ok(output) => {
+                tracing::debug!("{} exited abnormally ({})", executable, &output.status);
+                continue;
+            }
+            Err(e) => {
+                tracing::debug!("{:#?}", e);
+                continue;
+            }
+        };
+
+        // some runtimes (old javac, python2) print the version to stderr
+        let text = format!(
+            "{}{}",
+            decode_output(&output.stdout),
+            decode_output(&output.stderr),
+        );
+        match extract_version(&text) {
+            Some(version) => {
+                tracing::info!("Found: {} {}", name, &version);
+                runtimes.insert(
+                    (*name).to_owned(),
+                    RuntimeInfo::new(version, (*executable).to_owned()),
+                );
+            }
+            None => tracing::debug!("Failed to parse version of {}:\n{}", name, &text),
+        }
+    }
+
+    runtimes
+}
+
+/// Extracts the version from outputs like `gcc (Ubuntu 11.4.0-1ubuntu1~22.04) 11.4.0`,
+/// `go version go1.21.0 linux/amd64` or `Redis server v=6.2.5 sha=00000000:0`.
+pub(super) fn extract_version(text: &str) -> Option<String> {
+    let line = text.lines().find(|line| !line.trim().is_empty())?;
+    line.split_whitespace()
+        .map(|token| {
+            token
+                .trim_start_matches("go")
+                .trim_start_matches('v')
+                .trim_start_matches('=')
+        })
+        .filter(|token| token.starts_with(|c: char| c.is_ascii_digit()))
+        .map(|token| {
+            token
+                .chars()
+                .take_while(|c| c.is_ascii_digit() || *c == '.')
+                .collect::<String>()
+        })
+        .rfind(|version| version.contains('.'))
+}
@@ -0,0 +1,374 @@
+use anyhow::{Context, Result};
+use clap::{AppSettings, Clap};
+use std::{fmt::Write, fs, path::Path};
+
+use crate::{
+    audit::{self, Entry},
+    config::{self, Config},
+    install::{
+        helper::{hydro, mongodb, pm2, sandbox},
+        managed_binary,
+    },
+    log_i18n,
+    registry::COMPONENTS,
+    service,
+    utils::format_timestamp,
+    Com,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Clap, Debug)]
+enum SubCommand {
+    /// 生成部署的离线运维手册（HTML），包括组件版本、端口、服务定义、定时任务与恢复步骤
+    /// Generates an offline runbook (HTML) of the deployment, with the component versions, ports, service definitions, schedules and recovery steps
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Offline(OfflineArgs),
+}
+
+#[derive(Clap, Debug)]
+struct OfflineArgs {
+    /// 输出文件，默认输出到标准输出
+    /// Output file, defaults to stdout
+    #[clap(short, long)]
+    output: Option<String>,
+}
+
+/// Entries of the audit log listed in the runbook, the latest ones.
+const HISTORY: usize = 20;
+
+const STYLE: &str = "body{font-family:sans-serif;max-width:960px;margin:2em auto;padding:0 1em;\
+line-height:1.5}table{border-collapse:collapse}th,td{border:1px solid #ccc;padding:4px 8px;\
+text-align:left}pre{background:#f5f5f5;padding:8px;overflow-x:auto}code{background:#f5f5f5}";
+
+pub async fn main(args: Args) -> Result<()> {
+    let config = config::load_config().await?;
+    match args.subcmd {
+        SubCommand::Offline(args) => {
+            // the runbook is still useful without the history
+            let history = audit::read().unwrap_or_default();
+            let html = runbook(&config, &history);
+            match args.output {
+                Some(path) => {
+                    fs::write(&path, html).with_context(|| format!("Failed to write {}", &path))?;
+                    log_i18n!(info, "docs.exported", &path);
+                }
+                None => print!("{}", html),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Escapes `text` for HTML.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn code(text: impl AsRef<str>) -> String {
+    format!("<code>{}</code>", escape(text.as_ref()))
+}
+
+/// Appends a table whose cells are HTML already.
+fn table(html: &mut String, headers: &[&str], rows: &[Vec<String>]) {
+    html.push_str("<table>\n<tr>");
+    for header in headers {
+        let _ = write!(html, "<th>{}</th>", header);
+    }
+    html.push_str("</tr>\n");
+    for row in rows {
+        html.push_str("<tr>");
+        for cell in row {
+            let _ = write!(html, "<td>{}</td>", cell);
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+}
+
+/// Renders the runbook from the config, the files H2O2 wrote and the audit log. Secrets such
+/// as passwords and tokens are left out, as it is meant to be handed over.
+fn runbook(config: &Config, history: &[Entry]) -> String {
+    let generated_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+        <title>Hydro 运维手册 Runbook</title>\n<style>{}</style>\n</head>\n<body>\n\
+        <h1>Hydro 运维手册 Runbook</h1>\n\
+        <p>由 H2O2 {} 生成于 Generated by H2O2 {} at {}</p>\n\
+        <p>服务器角色 Role of the server: {}</p>\n",
+        STYLE,
+        env!("CARGO_PKG_VERSION"),
+        env!("CARGO_PKG_VERSION"),
+        format_timestamp(generated_at),
+        code(config.profile.role.to_string())
+    );
+
+    components(&mut html, config);
+    ports(&mut html, config);
+    services(&mut html);
+    backups(&mut html, config);
+    recovery(&mut html, config);
+    self::history(&mut html, history);
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn components(html: &mut String, config: &Config) {
+    html.push_str("<h2>组件 Components</h2>\n");
+    let rows = COMPONENTS
+        .iter()
+        .filter_map(|desc| {
+            let info = config.components.get(desc.name)?;
+            if !info.is_installed() {
+                return None;
+            }
+            Some(vec![
+                escape(desc.display),
+                escape(&info.version.to_string()),
+                info.path.as_ref().map_or_else(String::new, code),
+            ])
+        })
+        .collect::<Vec<_>>();
+    table(
+        html,
+        &["组件 Component", "版本 Version", "路径 Path"],
+        &rows,
+    );
+}
+
+fn ports(html: &mut String, config: &Config) {
+    html.push_str("<h2>端口 Ports</h2>\n");
+    let profile = &config.profile;
+    let components = &config.components;
+    let mut rows = Vec::new();
+    if components.mongodb.is_installed() {
+        rows.push(vec![
+            "MongoDB".to_owned(),
+            code(format!(
+                "{}:{}",
+                profile.mongodb.bind_ip, profile.mongodb.port
+            )),
+        ]);
+    }
+    if components.minio.is_installed() {
+        rows.push(vec!["MinIO API".to_owned(), code(&profile.minio.address)]);
+        if profile.minio.browser {
+            rows.push(vec![
+                "MinIO Console".to_owned(),
+                code(&profile.minio.console_address),
+            ]);
+        }
+    }
+    // the URI of MongoDB holds the credentials, so only its hosts are listed
+    if profile.is_external(Com::MongoDB) {
+        rows.push(vec![
+            "MongoDB (external)".to_owned(),
+            code(profile.mongodb.addresses().join(", ")),
+        ]);
+    }
+    if profile.is_external(Com::MinIO) {
+        rows.push(vec![
+            "S3 (external)".to_owned(),
+            code(profile.minio.api_url()),
+        ]);
+    }
+    if components.sandbox.is_installed() {
+        rows.push(vec!["Sandbox".to_owned(), code(sandbox::HOST)]);
+    }
+    if components.hydro.is_installed() {
+        rows.push(vec!["Hydro".to_owned(), code(&profile.caddy.upstream)]);
+    }
+    if let Some(domain) = &profile.caddy.domain {
+        rows.push(vec![
+            "Caddy".to_owned(),
+            format!("{} {}", code(":80, :443"), escape(domain)),
+        ]);
+    }
+    table(html, &["服务 Service", "监听地址 Address"], &rows);
+}
+
+fn services(html: &mut String) {
+    html.push_str("<h2>服务定义 Service definitions</h2>\n");
+    let mut found = false;
+    for &com in service::SERVICES {
+        let path = service::unit_path(com);
+        if let Ok(content) = fs::read_to_string(&path) {
+            unit(html, &path, &content);
+            found = true;
+        }
+    }
+    if !found {
+        html.push_str(
+            "<p>未以 systemd 服务运行组件。 No component runs as a systemd service, \
+            see <code>h2o2 service install</code>.</p>\n",
+        );
+    }
+}
+
+fn unit(html: &mut String, path: &Path, content: &str) {
+    let _ = write!(
+        html,
+        "<h3>{}</h3>\n<pre>{}</pre>\n",
+        code(path.display().to_string()),
+        escape(content)
+    );
+}
+
+fn backups(html: &mut String, config: &Config) {
+    html.push_str("<h2>定时任务与备份 Schedules and backups</h2>\n");
+    let timers = service::timer_paths();
+    if timers.is_empty() {
+        html.push_str("<p>H2O2 未设置任何定时任务。 H2O2 runs nothing on schedule.</p>\n");
+    }
+    for timer in timers {
+        let content = fs::read_to_string(&timer).unwrap_or_default();
+        let calendar = content
+            .lines()
+            .find_map(|line| line.strip_prefix("OnCalendar="))
+            .unwrap_or("-");
+        let _ = writeln!(
+            html,
+            "<p>{} OnCalendar={}</p>",
+            code(timer.display().to_string()),
+            code(calendar)
+        );
+        if let Ok(content) = fs::read_to_string(timer.with_extension("service")) {
+            let _ = writeln!(html, "<pre>{}</pre>", escape(&content));
+        }
+    }
+
+    html.push_str("<h3>需要备份的数据 Data to back up</h3>\n<ul>\n");
+    for (what, path) in backup_paths(config) {
+        let _ = writeln!(html, "<li>{}: {}</li>", what, code(path));
+    }
+    html.push_str("</ul>\n");
+}
+
+/// What to back up, and where it is.
+fn backup_paths(config: &Config) -> Vec<(&'static str, String)> {
+    let components = &config.components;
+    let mut paths = vec![(
+        "H2O2 配置 H2O2 config",
+        config::get_config_path().display().to_string(),
+    )];
+    if components.mongodb.is_installed() {
+        let path = match managed_binary(Com::MongoDB, &components.mongodb) {
+            Some(_) => mongodb::data_path().display().to_string(),
+            // installed by the system, whose config tells the dbpath
+            None => "storage.dbPath in mongod.conf".to_owned(),
+        };
+        paths.push(("MongoDB 数据 MongoDB data", path));
+    }
+    if components.minio.is_installed() {
+        paths.push((
+            "MinIO 数据 MinIO data",
+            config.profile.minio.data_dir().display().to_string(),
+        ));
+    }
+    if components.hydro.is_installed() {
+        paths.push((
+            "Hydro 配置 Hydro config",
+            hydro::get_hydro_path().display().to_string(),
+        ));
+    }
+    paths
+}
+
+fn recovery(html: &mut String, config: &Config) {
+    html.push_str("<h2>恢复步骤 Recovery steps</h2>\n<ol>\n");
+    let components = &config.components;
+    let mut steps = vec![
+        format!(
+            "将备份的 H2O2 配置复制到 Copy the H2O2 config backed up to {}",
+            code(config::get_config_path().display().to_string())
+        ),
+        format!(
+            "运行 Run {}，或在无法联网的服务器上运行 or on air-gapped servers {}",
+            code("h2o2 install"),
+            code("h2o2 bundle install <DIR>")
+        ),
+    ];
+    let mut stopped = Vec::new();
+    if components.mongodb.is_installed() {
+        stopped.push(Com::MongoDB);
+    }
+    if components.minio.is_installed() {
+        stopped.push(Com::MinIO);
+    }
+    if !stopped.is_empty() {
+        let units = stopped
+            .iter()
+            .map(|&com| service::unit_name(com))
+            .collect::<Vec<_>>()
+            .join(" ");
+        steps.push(format!(
+            "停止服务 Stop the services: {}",
+            code(format!("systemctl stop {}", units))
+        ));
+    }
+    for (what, path) in backup_paths(config).into_iter().skip(1) {
+        steps.push(format!("恢复 Restore {}: {}", what, code(path)));
+    }
+    steps.push(format!(
+        "运行 Run {} 重新写入并启动服务 to write and start the services again",
+        code("h2o2 service install")
+    ));
+    if components.hydro.is_installed() {
+        steps.push(format!(
+            "以 PM2 启动 Hydro Start Hydro with PM2: {}",
+            code(format!("pm2 start {} && pm2 save", pm2::HYDRO_APP))
+        ));
+    }
+    steps.push(format!(
+        "运行 Run {} 与 and {} 确认部署正常 to confirm the deployment",
+        code("h2o2 verify"),
+        code("h2o2 status")
+    ));
+    for step in steps {
+        let _ = writeln!(html, "<li>{}</li>", step);
+    }
+    html.push_str("</ol>\n");
+}
+
+fn history(html: &mut String, history: &[Entry]) {
+    html.push_str("<h2>操作记录 History</h2>\n");
+    let rows = history
+        .iter()
+        .rev()
+        .filter(|entry| entry.action != "install-metrics")
+        .take(HISTORY)
+        .map(|entry| {
+            vec![
+                escape(&format_timestamp(entry.time)),
+                escape(&entry.action),
+                escape(entry.component.as_deref().unwrap_or("-")),
+                escape(entry.details["version"].as_str().unwrap_or("")),
+            ]
+        })
+        .collect::<Vec<_>>();
+    table(
+        html,
+        &["时间 Time", "操作 Action", "组件 Component", "版本 Version"],
+        &rows,
+    );
+}
@@ -0,0 +1,260 @@
+use anyhow::Result;
+use clap::Clap;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+    time::Duration,
+};
+use tokio::{net::TcpStream, time::timeout};
+
+use crate::{
+    check_version,
+    config::{self, Config},
+    install::helper::{jspm, mongodb, pm2, sandbox},
+    log_i18n, platform, sys,
+    utils::format_size,
+    Com,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {}
+
+/// Free space below which a file system is reported, in bytes.
+const LOW_DISK: u64 = 2 << 30;
+
+/// Controllers the sandbox limits programs with.
+const SANDBOX_CONTROLLERS: &[&str] = &["cpu", "memory", "pids"];
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub async fn main(_args: Args) -> Result<()> {
+    // the checks work without H2O2 set up, but can be more accurate with it
+    let config = config::load_config().await.ok();
+    let config = config.as_ref();
+
+    // run every check, even if an earlier one fails
+    let mut problems = 0;
+    log_i18n!(info, "doctor.checking", "PATH");
+    problems += !crate::env::doctor(config) as usize;
+    log_i18n!(info, "doctor.checking", "system");
+    problems += !sys::check(config).await as usize;
+    log_i18n!(info, "doctor.checking", "cgroup");
+    problems += !check_cgroup() as usize;
+    log_i18n!(info, "doctor.checking", "disk");
+    problems += !check_dirs(config) as usize;
+    log_i18n!(info, "doctor.checking", "ports");
+    problems += !check_ports(config).await as usize;
+    if let Some(config) = config {
+        log_i18n!(info, "doctor.checking", "versions");
+        problems += !check_versions(config) as usize;
+    }
+
+    if problems == 0 {
+        log_i18n!(info, "doctor.passed");
+    } else {
+        log_i18n!(warn, "doctor.problems-found", problems);
+    }
+    Ok(())
+}
+
+/// Checks that the controllers the sandbox relies on are available.
+fn check_cgroup() -> bool {
+    let controllers = match platform::cgroup_controllers() {
+        Some(controllers) => controllers,
+        None if platform::current().os == platform::Os::Linux => {
+            log_i18n!(warn, "doctor.cgroup-unavailable");
+            return false;
+        }
+        None => return true,
+    };
+    let missing = SANDBOX_CONTROLLERS
+        .iter()
+        .filter(|name| !controllers.iter().any(|c| c == *name))
+        .copied()
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        return true;
+    }
+    log_i18n!(warn, "doctor.cgroup-missing", missing.join(", "));
+    false
+}
+
+/// Checks the free space and permissions of the directories H2O2 and the components write to.
+fn check_dirs(config: Option<&Config>) -> bool {
+    let mut dirs = vec![
+        config::get_com_path(),
+        config
+            .and_then(|config| config.profile.tmp_dir.as_ref())
+            .map_or_else(env::temp_dir, PathBuf::from),
+    ];
+    // data dirs only matter once the components are installed
+    let mut data_dirs = Vec::new();
+    if let Some(config) = config {
+        if config.components.mongodb.is_installed() {
+            data_dirs.push((Com::MongoDB, mongodb::data_path()));
+        }
+        if config.components.minio.is_installed() {
+            data_dirs.push((Com::MinIO, config.profile.minio.data_dir()));
+        }
+    }
+    dirs.extend(data_dirs.iter().map(|(_, dir)| dir.clone()));
+
+    let mut ok = true;
+    let mut reported = Vec::new();
+    for dir in &dirs {
+        let existing = match nearest_existing(dir) {
+            Some(existing) => existing,
+            None => continue,
+        };
+        match platform::free_space(existing) {
+            // report each file system once
+            Some(free) if free < LOW_DISK && !reported.contains(&free) => {
+                reported.push(free);
+                ok = false;
+                log_i18n!(warn, "doctor.low-disk", dir.display(), format_size(free));
+            }
+            _ => {}
+        }
+    }
+    for (com, dir) in &data_dirs {
+        if let Some(existing) = nearest_existing(dir) {
+            if !is_writable(existing) {
+                ok = false;
+                log_i18n!(warn, "doctor.dir-not-writable", com, existing.display());
+            }
+        }
+    }
+    ok
+}
+
+fn nearest_existing(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|path| path.exists())
+}
+
+/// Whether a file can be created in `dir`, which is more reliable than reading the mode, as ACLs
+/// and read-only mounts are taken into account.
+fn is_writable(dir: &Path) -> bool {
+    let probe = dir.join(format!(".h2o2-doctor-{}", process::id()));
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// A port a component listens on.
+struct Port {
+    address: String,
+    com: &'static str,
+    /// whether the component is running, so that it is the one listening
+    running: bool,
+}
+
+/// Checks that the ports of the components are not taken by other processes.
+async fn check_ports(config: Option<&Config>) -> bool {
+    if platform::find_processes("").is_none() {
+        return true;
+    }
+    let default = Config::default();
+    let config = config.unwrap_or(&default);
+    let profile = &config.profile;
+    let running = |name: &str| platform::find_processes(name).is_some_and(|p| !p.is_empty());
+
+    let mut ports = vec![
+        Port {
+            address: format!("127.0.0.1:{}", profile.mongodb.port),
+            com: "MongoDB",
+            running: running("mongod"),
+        },
+        Port {
+            address: config::to_local_address(&profile.minio.address),
+            com: "MinIO",
+            running: running("minio"),
+        },
+        Port {
+            address: sandbox::HOST.trim_start_matches("http://").to_owned(),
+            com: "sandbox",
+            running: running("sandbox"),
+        },
+        Port {
+            address: config::to_local_address(&profile.caddy.upstream),
+            com: "Hydro",
+            running: hydro_running(config),
+        },
+    ];
+    if profile.minio.browser {
+        ports.push(Port {
+            address: config::to_local_address(&profile.minio.console_address),
+            com: "MinIO",
+            running: running("minio"),
+        });
+    }
+    // external endpoints listen elsewhere, leaving their ports here to others
+    for (com, name) in [(Com::MongoDB, "MongoDB"), (Com::MinIO, "MinIO")] {
+        if profile.is_external(com) {
+            ports.retain(|port| port.com != name);
+        }
+    }
+    if profile.caddy.domain.is_some() {
+        for port in [80, 443] {
+            ports.push(Port {
+                address: format!("127.0.0.1:{}", port),
+                com: "Caddy",
+                running: running("caddy"),
+            });
+        }
+    }
+
+    let mut ok = true;
+    for port in ports.iter().filter(|port| !port.running) {
+        let taken = matches!(
+            timeout(CONNECT_TIMEOUT, TcpStream::connect(port.address.as_str())).await,
+            Ok(Ok(_))
+        );
+        if taken {
+            ok = false;
+            let number = port.address.rsplit(':').next().unwrap_or_default();
+            log_i18n!(
+                warn,
+                "doctor.port-conflict",
+                &port.address,
+                port.com,
+                number
+            );
+        }
+    }
+    ok
+}
+
+fn hydro_running(config: &Config) -> bool {
+    if pm2::daemon_pid().is_none() {
+        return false;
+    }
+    let cmd = platform::cmd("pm2");
+    pm2::list(config.components.pm2.path_or(&cmd)).is_ok_and(|processes| {
+        processes
+            .iter()
+            .any(|p| p.name == pm2::HYDRO_APP && p.status() == "online")
+    })
+}
+
+/// Checks that the versions recorded work with Hydro.
+fn check_versions(config: &Config) -> bool {
+    let com = &config.components;
+    let mut ok = true;
+    if let Some(version) = com.nodejs.version() {
+        ok &= check_version!(nodejs, version, warn);
+    }
+    if let Some(version) = com.mongodb.version() {
+        ok &= check_version!(mongodb, version, warn);
+    }
+    if jspm::is_berry(&com.yarn.version) {
+        ok = false;
+        log_i18n!(warn, "detect.yarn-berry", &com.yarn.version);
+    }
+    ok
+}
@@ -0,0 +1,241 @@
+use derive_more::Display;
+use std::{env, ffi::OsString, fs, path::Path};
+
+use crate::{
+    config::Config,
+    install::helper::pm2,
+    log_i18n,
+    platform::{self, ServiceManager, Shell},
+    tr,
+    utils::decode_output,
+};
+
+/// Commands which commonly work in a shell but not for services.
+const COMMANDS: &[&str] = &["node", "npm", "yarn", "pm2"];
+
+/// Printed before `PATH` by the shells, to find it among the output of profile scripts.
+const MARKER: &str = "__H2O2_PATH__=";
+
+/// `PATH` given by `sshd`, and by systemd to services when its manager environment lacks one.
+const DEFAULT_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+/// Where commands may be run, each with its own `PATH`.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+enum Context {
+    #[display(fmt = "current shell")]
+    Current,
+    #[display(fmt = "login shell")]
+    Login,
+    #[display(fmt = "non-login shell")]
+    NonLogin,
+    #[display(fmt = "PM2")]
+    Pm2,
+    #[display(fmt = "systemd")]
+    Systemd,
+}
+
+impl Context {
+    const ALL: [Context; 5] = [
+        Context::Current,
+        Context::Login,
+        Context::NonLogin,
+        Context::Pm2,
+        Context::Systemd,
+    ];
+}
+
+/// `PATH` of a context, or why it is not known.
+type ContextPath = Result<OsString, String>;
+
+/// Compares `PATH` of the current shell, a login shell, a non-login shell, PM2 and systemd,
+/// prints the file each of [`COMMANDS`] resolves to in them, and logs suggestions for the
+/// differences found. Returns whether they agree.
+pub fn check(config: Option<&Config>) -> bool {
+    let paths = Context::ALL
+        .iter()
+        .filter_map(|&context| Some((context, path_of(context, config)?)))
+        .collect::<Vec<_>>();
+    for (context, path) in &paths {
+        match path {
+            Ok(path) => tracing::debug!("PATH of {}: {}", context, path.to_string_lossy()),
+            Err(reason) => log_i18n!(warn, "env.context-unavailable", context, reason),
+        }
+    }
+
+    let mut ok = true;
+    let mut pm2_missing = false;
+    for command in COMMANDS {
+        let resolved = paths
+            .iter()
+            .filter_map(|(context, path)| {
+                let path = path.as_ref().ok()?;
+                Some((*context, platform::find_in(path, command)))
+            })
+            .collect::<Vec<_>>();
+        let lines = resolved
+            .iter()
+            .map(|(context, found)| {
+                let found = match found {
+                    Some(found) => found.display().to_string(),
+                    None => tr!("env.not-found"),
+                };
+                format!("  {:<16} {}", context.to_string(), found)
+            })
+            .collect::<Vec<_>>();
+        tracing::info!("{}\n{}", command, lines.join("\n"));
+
+        // what the user runs is taken as the intended one
+        let reference = resolved
+            .iter()
+            .filter(|(context, _)| matches!(context, Context::Current | Context::Login))
+            .find_map(|(_, found)| found.clone());
+        let reference = match reference {
+            Some(reference) => reference,
+            None => continue,
+        };
+        for (context, found) in &resolved {
+            match found {
+                Some(found) if same_file(found, &reference) => {}
+                Some(found) => {
+                    ok = false;
+                    log_i18n!(
+                        warn,
+                        "env.mismatch",
+                        command,
+                        context,
+                        found.display(),
+                        reference.display()
+                    );
+                }
+                None => {
+                    ok = false;
+                    let dir = reference.parent().unwrap_or(&reference).display();
+                    match context {
+                        Context::Current => {}
+                        Context::Login => log_i18n!(warn, "env.missing-login", command, dir),
+                        Context::NonLogin => {
+                            log_i18n!(warn, "env.missing-non-login", command, reference.display())
+                        }
+                        Context::Pm2 | Context::Systemd => {
+                            pm2_missing |= *context == Context::Pm2;
+                            log_i18n!(
+                                warn,
+                                "env.missing-service",
+                                command,
+                                context,
+                                reference.display()
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+    if pm2_missing {
+        log_i18n!(info, "env.pm2-update-env");
+    }
+    ok
+}
+
+/// Reads `PATH` of `context`, or `None` if the context does not exist here.
+fn path_of(context: Context, config: Option<&Config>) -> Option<ContextPath> {
+    let current = platform::current();
+    match context {
+        Context::Current => Some(env::var_os("PATH").ok_or_else(|| "PATH is not set".to_owned())),
+        Context::Login | Context::NonLogin if current.is_windows() => None,
+        Context::Login => Some(shell_path(true)),
+        Context::NonLogin => Some(shell_path(false)),
+        Context::Pm2 => pm2_path(config),
+        Context::Systemd if current.service_manager == ServiceManager::Systemd => {
+            Some(systemd_path())
+        }
+        Context::Systemd => None,
+    }
+}
+
+/// Starts the shell of the user with a minimal environment, as `sshd` would, and reads `PATH` set
+/// up by its startup files. The non-login one runs a command like `ssh host command`, scripts and
+/// cron jobs do, and usually reads no startup files at all.
+fn shell_path(login: bool) -> ContextPath {
+    let current = platform::current();
+    let shell = match current.shell {
+        Shell::Bash | Shell::Zsh | Shell::Fish => env::var("SHELL").unwrap_or_default(),
+        _ => "sh".to_owned(),
+    };
+    let print = if current.shell == Shell::Fish {
+        format!("printf '\\n{}%s\\n' (string join : $PATH)", MARKER)
+    } else {
+        format!("printf '\\n{}%s\\n' \"$PATH\"", MARKER)
+    };
+    let mut args = if login { vec!["-l"] } else { Vec::new() };
+    args.extend(&["-c", &print]);
+
+    let mut vars = ["HOME", "USER", "LOGNAME", "SHELL", "LANG"]
+        .iter()
+        .filter_map(|name| Some((name.to_string(), env::var(name).ok()?)))
+        .collect::<Vec<_>>();
+    vars.push(("PATH".to_owned(), DEFAULT_PATH.to_owned()));
+    let output = duct::cmd(&shell, &args)
+        .full_env(vars)
+        .stdin_null()
+        .stdout_capture()
+        .stderr_capture()
+        .unchecked()
+        .run()
+        .map_err(|e| format!("{}: {}", shell, e))?;
+    decode_output(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix(MARKER))
+        .map(OsString::from)
+        .ok_or_else(|| format!("{} exited with {}", shell, output.status))
+}
+
+/// Reads `PATH` of the Hydro process managed by PM2, or of the first one if Hydro is not
+/// managed by it. `None` if the daemon of PM2 is not running, as querying it would start one
+/// with the environment of this shell.
+fn pm2_path(config: Option<&Config>) -> Option<ContextPath> {
+    pm2::daemon_pid()?;
+
+    let cmd = platform::cmd("pm2");
+    let program = config.map_or(cmd.as_str(), |config| config.components.pm2.path_or(&cmd));
+    let processes = match pm2::list(program) {
+        Ok(processes) => processes,
+        Err(reason) => return Some(Err(reason)),
+    };
+    let process = processes
+        .iter()
+        .find(|p| p.name == pm2::HYDRO_APP)
+        .or_else(|| processes.first())?;
+    Some(
+        process.pm2_env["PATH"]
+            .as_str()
+            .map(OsString::from)
+            .ok_or_else(|| format!("PATH of {} is unknown", process.name)),
+    )
+}
+
+fn systemd_path() -> ContextPath {
+    let output = duct::cmd!("systemctl", "show-environment")
+        .stdout_capture()
+        .stderr_capture()
+        .unchecked()
+        .run()
+        .map_err(|e| format!("systemctl: {}", e))?;
+    if !output.status.success() {
+        return Err(decode_output(&output.stderr).trim().to_owned());
+    }
+    let path = decode_output(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("PATH=").map(ToOwned::to_owned))
+        .unwrap_or_else(|| DEFAULT_PATH.to_owned());
+    Ok(path.into())
+}
+
+/// Whether the paths lead to the same file, e.g. `/bin/node` and `/usr/bin/node` on merged
+/// `/usr` systems.
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
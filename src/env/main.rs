@@ -0,0 +1,33 @@
+use anyhow::Result;
+use clap::{AppSettings, Clap};
+
+use super::doctor;
+use crate::{config, log_i18n};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Clap, Debug)]
+enum SubCommand {
+    /// 比较登录 shell、非登录 shell、PM2 与 systemd 中的 PATH，找出命令在服务中不可用的原因
+    /// Compares PATH in login and non-login shells, PM2 and systemd, to find out why commands are missing for services
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Doctor,
+}
+
+pub async fn main(args: Args) -> Result<()> {
+    // works without H2O2 set up, but PM2 is found more reliably with it
+    let config = config::load_config().await.ok();
+    match args.subcmd {
+        SubCommand::Doctor => {
+            if doctor(config.as_ref()) {
+                log_i18n!(info, "env.doctor-passed");
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,5 @@
+mod doctor;
+mod main;
+
+pub use doctor::check as doctor;
+pub use main::*;
@@ -0,0 +1,91 @@
+use anyhow::{Context, Result};
+use clap::{ArgEnum, Clap};
+use tokio::fs;
+
+use crate::{
+    config::{self, Config},
+    langs, log_i18n,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    /// 导出评测环境说明
+    /// Exports the description of the judge environment
+    #[clap(long, arg_enum)]
+    judge_env: Format,
+
+    /// 输出文件，默认输出到标准输出
+    /// Output file, defaults to stdout
+    #[clap(short, long)]
+    output: Option<String>,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy)]
+enum Format {
+    Markdown,
+}
+
+pub async fn main(args: Args) -> Result<()> {
+    let config = config::load_config().await?;
+    if config.runtimes.is_empty() {
+        log_i18n!(warn, "export.no-runtime");
+    }
+
+    let text = match args.judge_env {
+        Format::Markdown => judge_env_markdown(&config),
+    };
+
+    match args.output {
+        Some(path) => {
+            fs::write(&path, text)
+                .await
+                .with_context(|| format!("Failed to write {}", &path))?;
+            log_i18n!(info, "export.exported", &path);
+        }
+        None => print!("{}", text),
+    }
+
+    Ok(())
+}
+
+fn judge_env_markdown(config: &Config) -> String {
+    let mut md = String::from("# 评测环境 Judge Environment\n\n");
+
+    md.push_str("## 语言 Languages\n\n");
+    md.push_str(
+        "| 语言 Language | 版本 Version | 编译命令 Compile Command | 运行命令 Execute Command |\n",
+    );
+    md.push_str("| --- | --- | --- | --- |\n");
+    for lang in langs::generate(&config.runtimes) {
+        let version = &config.runtimes[lang.runtime].version;
+        md.push_str(&format!(
+            "| {} | {} {} | {} | `{}` |\n",
+            lang.name,
+            lang.runtime,
+            version,
+            lang.compile
+                .as_ref()
+                .map_or_else(|| "-".to_owned(), |c| format!("`{}`", c)),
+            &lang.execute,
+        ));
+    }
+
+    md.push_str("\n## 限制 Limits\n\n");
+    let sandbox = &config.profile.sandbox;
+    md.push_str(&format!(
+        "- 同时评测的提交数 Concurrent submissions: {}\n",
+        sandbox
+            .parallelism
+            .map_or_else(|| "CPU 核数 number of CPUs".to_owned(), |p| p.to_string())
+    ));
+    md.push_str(&format!(
+        "- 输出限制 Output limit: {}\n",
+        sandbox
+            .output_limit
+            .map_or_else(|| "256 MiB".to_owned(), |l| format!("{} MiB", l))
+    ));
+    md.push_str("- 时间与内存限制以各题目设置为准 Time and memory limits are set per problem\n");
+
+    md
+}
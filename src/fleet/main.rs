@@ -0,0 +1,232 @@
+use anyhow::{bail, Context, Result};
+use clap::{AppSettings, Clap};
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    config::{self, Config, JudgeToken},
+    install::helper::hydro,
+    log_i18n, platform, tr,
+    utils::{format_timestamp, random_token},
+    Com,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Clap, Debug)]
+enum SubCommand {
+    /// 在 Hydro 所在服务器上管理评测节点连接所用的令牌
+    /// Manages the tokens judge nodes connect with, on the server running Hydro
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Token(TokenArgs),
+
+    /// 在评测节点上写入令牌，使独立评测机连接 Hydro
+    /// Writes a token on a judge node, so that its standalone judge connects to Hydro
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Join(JoinArgs),
+}
+
+#[derive(Clap, Debug)]
+struct TokenArgs {
+    #[clap(subcommand)]
+    action: TokenAction,
+}
+
+#[derive(Clap, Debug)]
+enum TokenAction {
+    /// 为评测节点创建评测账号，其密码即为令牌
+    /// Creates a judge account for a judge node, whose password is the token
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Create(CreateArgs),
+
+    /// 吊销评测节点的令牌，撤销其评测账号的权限
+    /// Revokes the token of a judge node, taking the privileges of its judge account away
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Revoke(RevokeArgs),
+
+    /// 列出已创建的令牌
+    /// Lists the tokens created
+    #[clap(setting = AppSettings::ColoredHelp)]
+    List,
+}
+
+#[derive(Clap, Debug)]
+struct CreateArgs {
+    /// 评测节点名称，仅可包含字母、数字、`-` 与 `_`
+    /// Name of the judge node, of letters, digits, `-` and `_` only
+    name: String,
+
+    /// 评测节点访问 Hydro 的地址，默认由 profile.caddy.domain 得出
+    /// URL the judge node reaches Hydro at, defaults to the one of profile.caddy.domain
+    #[clap(long)]
+    url: Option<String>,
+}
+
+#[derive(Clap, Debug)]
+struct RevokeArgs {
+    /// 评测节点名称
+    /// Name of the judge node
+    name: String,
+}
+
+#[derive(Clap, Debug)]
+struct JoinArgs {
+    /// Hydro 的地址，如 `https://oj.example.com/`
+    /// URL of Hydro, e.g. `https://oj.example.com/`
+    url: String,
+
+    /// 创建令牌时使用的评测节点名称
+    /// Name of the judge node the token is created for
+    name: String,
+
+    /// 令牌
+    /// The token
+    token: String,
+}
+
+/// Privilege of a user without any, taking every privilege of the judge account away.
+const PRIV_NONE: &str = "0";
+
+impl Args {
+    /// Name of the operation, if the subcommand modifies the deployment.
+    pub fn operation(&self) -> Option<&'static str> {
+        match &self.subcmd {
+            SubCommand::Token(args) => match args.action {
+                TokenAction::Create(_) | TokenAction::Revoke(_) => Some("fleet token"),
+                TokenAction::List => None,
+            },
+            SubCommand::Join(_) => Some("fleet join"),
+        }
+    }
+}
+
+pub async fn main(args: Args) -> Result<()> {
+    match args.subcmd {
+        SubCommand::Token(args) => {
+            let mut config = config::load_config().await?;
+            match args.action {
+                TokenAction::Create(args) => create(&mut config, args).await,
+                TokenAction::Revoke(args) => revoke(&mut config, args).await,
+                TokenAction::List => {
+                    list(&config);
+                    Ok(())
+                }
+            }
+        }
+        SubCommand::Join(args) => join(args),
+    }
+}
+
+/// Runs `hydrooj cli` of the Hydro installed.
+fn hydro_cli(config: &Config, args: &[&str]) -> Result<String> {
+    let global_dir = match config.components.hydro.path.as_deref() {
+        Some(path) if config.components.hydro.is_installed() => path,
+        _ => bail!(tr!("check.not-installed", Com::Hydro)),
+    };
+    let node = config.components.nodejs.path(&platform::cmd("node"));
+    hydro::cli(&node, Path::new(global_dir), args).map_err(|e| anyhow::anyhow!(e))
+}
+
+async fn create(config: &mut Config, args: CreateArgs) -> Result<()> {
+    let valid = !args.name.is_empty()
+        && args
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        bail!(tr!("fleet.invalid-name", &args.name));
+    }
+    if config.fleet.contains_key(&args.name) {
+        bail!(tr!("fleet.token-exists", &args.name));
+    }
+
+    let uname = format!("judge-{}", args.name);
+    let mail = format!("{}@judge.h2o2", uname);
+    let token = random_token(16);
+    let output = hydro_cli(config, &["user", "create", &mail, &uname, &token])?;
+    // the CLI prints what the call returns, which is the user ID
+    let uid = output
+        .lines()
+        .rev()
+        .find_map(|line| line.trim().parse::<i64>().ok())
+        .with_context(|| tr!("fleet.unexpected-output", output.trim()))?;
+    hydro_cli(config, &["user", "setJudge", &uid.to_string()])?;
+
+    let created = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    config.fleet.insert(
+        args.name.clone(),
+        JudgeToken {
+            uid,
+            uname,
+            created,
+        },
+    );
+    config::save_config(config).await?;
+
+    let url = args
+        .url
+        .or_else(|| {
+            let domain = config.profile.caddy.domain.as_ref()?;
+            Some(format!("https://{}/", domain))
+        })
+        .unwrap_or_else(|| "<URL>".to_owned());
+    log_i18n!(info, "fleet.token-created", &args.name, uid);
+    println!("h2o2 fleet join {} {} {}", url, args.name, token);
+    Ok(())
+}
+
+async fn revoke(config: &mut Config, args: RevokeArgs) -> Result<()> {
+    let token = match config.fleet.get(&args.name) {
+        Some(token) => token,
+        None => bail!(tr!("fleet.token-not-found", &args.name)),
+    };
+    hydro_cli(
+        config,
+        &["user", "setPriv", &token.uid.to_string(), PRIV_NONE],
+    )?;
+    config.fleet.remove(&args.name);
+    config::save_config(config).await?;
+    log_i18n!(info, "fleet.token-revoked", &args.name);
+    Ok(())
+}
+
+fn list(config: &Config) {
+    if config.fleet.is_empty() {
+        log_i18n!(info, "fleet.no-tokens");
+        return;
+    }
+    for (name, token) in &config.fleet {
+        println!(
+            " {:<16} {:<24} uid {:<6} {}",
+            name,
+            token.uname,
+            token.uid,
+            format_timestamp(token.created)
+        );
+    }
+}
+
+/// Points the standalone judge of this node at Hydro with the token.
+fn join(args: JoinArgs) -> Result<()> {
+    let url = url::Url::parse(&args.url).with_context(|| tr!("fleet.invalid-url", &args.url))?;
+    let host = url.host_str().unwrap_or("hydro");
+    let uname = format!("judge-{}", args.name);
+    hydro::set_judge_host(host, url.as_str(), &uname, &args.token)
+        .context("Failed to write Hydro judge config")?;
+    log_i18n!(
+        info,
+        "fleet.joined",
+        hydro::judge_config_path().display(),
+        url.as_str()
+    );
+    Ok(())
+}
@@ -0,0 +1,264 @@
+use anyhow::{bail, Result};
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{BufRead, BufReader},
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    net::TcpStream,
+    time::{self, Instant},
+};
+
+use crate::{
+    config::{self, Config},
+    install::helper::{pm2, sandbox},
+    log_i18n, platform, tr,
+    utils::decode_output,
+    Com,
+};
+
+/// Time a service may take to become healthy after (re)starting.
+pub const WINDOW: Duration = Duration::from_secs(30);
+
+const INTERVAL: Duration = Duration::from_secs(1);
+
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Time a PM2 app must stay online, as PM2 restarts a crashing one again and again.
+const MIN_UPTIME: Duration = Duration::from_secs(3);
+
+/// Lines of logs attached to a failure.
+const LOG_LINES: usize = 20;
+
+/// Something telling that a service works, rather than that its process is merely spawned.
+#[derive(Debug, Clone)]
+pub enum Probe {
+    /// the address accepts connections
+    Tcp(String),
+    /// the URL replies without a server error
+    Http(String),
+    /// the app managed by PM2 is online, and stays so
+    Pm2App(&'static str),
+}
+
+/// Where the logs of a service are, to explain a failure.
+#[derive(Debug, Clone)]
+pub enum Logs {
+    /// the logs of the PM2 app
+    Pm2App(&'static str),
+    /// the journal of the systemd unit
+    Journal(String),
+}
+
+/// The probes of a service started by H2O2.
+#[derive(Debug, Clone)]
+pub struct Service {
+    pub name: String,
+    pub probes: Vec<Probe>,
+    pub logs: Option<Logs>,
+}
+
+impl Service {
+    /// The probes of the built-in component `com`, or `None` if it is not a service.
+    pub fn of(config: &Config, com: Com) -> Option<Self> {
+        let profile = &config.profile;
+        let (probes, logs) = match com {
+            Com::MongoDB => (
+                profile
+                    .mongodb
+                    .addresses()
+                    .into_iter()
+                    .map(Probe::Tcp)
+                    .collect(),
+                None,
+            ),
+            Com::MinIO => (
+                vec![Probe::Http(format!(
+                    "{}/minio/health/live",
+                    profile.minio.api_url()
+                ))],
+                None,
+            ),
+            Com::Sandbox => (
+                vec![Probe::Tcp(
+                    sandbox::HOST.trim_start_matches("http://").to_owned(),
+                )],
+                None,
+            ),
+            Com::Hydro => (
+                vec![
+                    Probe::Pm2App(pm2::HYDRO_APP),
+                    Probe::Http(format!(
+                        "http://{}/",
+                        config::to_local_address(&profile.caddy.upstream)
+                    )),
+                ],
+                Some(Logs::Pm2App(pm2::HYDRO_APP)),
+            ),
+            _ => return None,
+        };
+        Some(Self {
+            name: com.to_string(),
+            probes,
+            logs,
+        })
+    }
+
+    /// The standalone judge managed by PM2.
+    pub fn judge() -> Self {
+        Self {
+            name: "HydroJudge".to_owned(),
+            probes: vec![Probe::Pm2App(pm2::JUDGE_APP)],
+            logs: Some(Logs::Pm2App(pm2::JUDGE_APP)),
+        }
+    }
+
+    /// Caddy serving Hydro.
+    pub fn caddy(config: &Config) -> Self {
+        let port = if config.profile.caddy.domain.is_some() {
+            443
+        } else {
+            80
+        };
+        Self {
+            name: "Caddy".to_owned(),
+            probes: vec![Probe::Tcp(format!("127.0.0.1:{}", port))],
+            logs: Some(Logs::Journal("caddy".to_owned())),
+        }
+    }
+}
+
+/// Waits up to [`WINDOW`] for every probe of `service` to pass after it is (re)started. Fails
+/// with the last problem found and the last lines of its logs otherwise.
+pub async fn wait_healthy(config: &Config, service: &Service) -> Result<()> {
+    log_i18n!(info, "health.waiting", &service.name);
+    let deadline = Instant::now() + WINDOW;
+    let problem = loop {
+        let problem = check(config, service).await;
+        match problem {
+            None => {
+                log_i18n!(info, "health.healthy", &service.name);
+                return Ok(());
+            }
+            Some(problem) if Instant::now() >= deadline => break problem,
+            Some(problem) => tracing::debug!("{} is not healthy yet: {}", service.name, problem),
+        }
+        time::sleep(INTERVAL).await;
+    };
+
+    let mut message = tr!("health.unhealthy", &service.name, WINDOW.as_secs(), problem);
+    if let Some(logs) = &service.logs {
+        match last_logs(config, logs) {
+            Some(lines) if !lines.is_empty() => {
+                message.push('\n');
+                message.push_str(&tr!("health.last-logs"));
+                message.push('\n');
+                message.push_str(&lines);
+            }
+            _ => {}
+        }
+    }
+    bail!(message)
+}
+
+/// Runs every probe of `service`, returning the first problem found.
+async fn check(config: &Config, service: &Service) -> Option<String> {
+    for probe in &service.probes {
+        let problem = match probe {
+            Probe::Tcp(address) => tcp(address).await,
+            Probe::Http(url) => http(url).await,
+            Probe::Pm2App(app) => pm2_app(config, app),
+        };
+        if problem.is_some() {
+            return problem;
+        }
+    }
+    None
+}
+
+async fn tcp(address: &str) -> Option<String> {
+    match time::timeout(TIMEOUT, TcpStream::connect(address)).await {
+        Ok(Ok(_)) => None,
+        Ok(Err(e)) => Some(format!("{}: {}", address, e)),
+        Err(_) => Some(format!("{}: timed out", address)),
+    }
+}
+
+async fn http(url: &str) -> Option<String> {
+    let client = match reqwest::Client::builder().timeout(TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => return Some(e.to_string()),
+    };
+    match client.get(url).send().await {
+        Ok(res) if res.status().is_server_error() => Some(format!("{}: {}", url, res.status())),
+        Ok(_) => None,
+        Err(e) => Some(format!("{}: {}", url, e)),
+    }
+}
+
+fn pm2_app(config: &Config, app: &str) -> Option<String> {
+    let cmd = platform::cmd("pm2");
+    let processes = match pm2::list(config.components.pm2.path_or(&cmd)) {
+        Ok(processes) => processes,
+        Err(reason) => return Some(reason),
+    };
+    let instances = processes
+        .iter()
+        .filter(|p| p.name == app)
+        .collect::<Vec<_>>();
+    if instances.is_empty() {
+        return Some(format!("{} is not managed by PM2", app));
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_millis() as u64);
+    instances.iter().find_map(|p| {
+        if p.status() != "online" {
+            return Some(format!("{} is {}", app, p.status()));
+        }
+        let since = p.pm2_env["pm_uptime"].as_u64().unwrap_or_default();
+        let uptime = Duration::from_millis(now.saturating_sub(since));
+        if uptime < MIN_UPTIME {
+            Some(format!("{} has been online for {:?} only", app, uptime))
+        } else {
+            None
+        }
+    })
+}
+
+/// The last lines of `logs`, or `None` if they cannot be read.
+fn last_logs(config: &Config, logs: &Logs) -> Option<String> {
+    match logs {
+        Logs::Pm2App(app) => {
+            let cmd = platform::cmd("pm2");
+            let processes = pm2::list(config.components.pm2.path_or(&cmd)).ok()?;
+            let process = processes.iter().find(|p| p.name == *app)?;
+            let path = process.pm2_env["pm_err_log_path"].as_str()?;
+            tail(Path::new(path))
+        }
+        Logs::Journal(unit) => {
+            let lines = LOG_LINES.to_string();
+            let output = duct::cmd!("journalctl", "-u", unit, "-n", lines, "--no-pager")
+                .stdout_capture()
+                .stderr_null()
+                .unchecked()
+                .run()
+                .ok()?;
+            Some(decode_output(&output.stdout).trim_end().to_owned())
+        }
+    }
+}
+
+fn tail(path: &Path) -> Option<String> {
+    let file = fs::File::open(path).ok()?;
+    let mut lines = VecDeque::with_capacity(LOG_LINES);
+    for line in BufReader::new(file).lines() {
+        if lines.len() == LOG_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line.ok()?);
+    }
+    Some(Vec::from(lines).join("\n"))
+}
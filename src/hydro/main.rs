@@ -0,0 +1,59 @@
+use anyhow::Result;
+use clap::{AppSettings, ArgEnum, Clap};
+
+use super::maintenance;
+use crate::{config, log_i18n};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Clap, Debug)]
+enum SubCommand {
+    /// 开启或关闭维护模式，开启时反向代理返回维护页面而不是 Hydro
+    /// Turns maintenance mode on or off, in which the reverse proxy serves a maintenance page instead of Hydro
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Maintenance(MaintenanceArgs),
+}
+
+#[derive(Clap, Debug)]
+struct MaintenanceArgs {
+    #[clap(arg_enum)]
+    switch: Switch,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Switch {
+    On,
+    Off,
+}
+
+impl Args {
+    /// Name of the operation, if the subcommand modifies the deployment.
+    pub fn operation(&self) -> Option<&'static str> {
+        match self.subcmd {
+            SubCommand::Maintenance(_) => Some("hydro maintenance"),
+        }
+    }
+}
+
+pub async fn main(args: Args) -> Result<()> {
+    let mut config = config::load_config().await?;
+    match args.subcmd {
+        SubCommand::Maintenance(args) => {
+            let on = args.switch == Switch::On;
+            if config.profile.caddy.maintenance == on {
+                log_i18n!(
+                    info,
+                    "hydro.maintenance-unchanged",
+                    if on { "on" } else { "off" }
+                );
+                return Ok(());
+            }
+            maintenance::set(&mut config, on).await
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    config::{self, Config},
+    health,
+    install::helper::caddy,
+    log_i18n, tr,
+};
+
+/// The Caddy serving Hydro, which the maintenance page is served by, or `None` if Hydro is not
+/// served through it.
+pub fn caddy_program(config: &Config) -> Option<String> {
+    config.profile.caddy.domain.as_ref()?;
+    match config.components.extras.get("caddy") {
+        Some(info) if info.is_installed() => Some(info.path_or("caddy").to_owned()),
+        _ => None,
+    }
+}
+
+/// Turns maintenance mode on or off, saving it in the profile so that the Caddyfile keeps it
+/// when written again.
+pub async fn set(config: &mut Config, on: bool) -> Result<()> {
+    let caddy_program = match caddy_program(config) {
+        Some(program) => program,
+        None => bail!(tr!("hydro.maintenance-no-proxy")),
+    };
+    config.profile.caddy.maintenance = on;
+    config::save_config(config).await?;
+    caddy::write_caddyfile(&config.profile.caddy)
+        .and_then(|_| caddy::reload(&caddy_program))
+        .context(tr!("hydro.maintenance-failed"))?;
+    health::wait_healthy(config, &health::Service::caddy(config)).await?;
+    if on {
+        log_i18n!(info, "hydro.maintenance-on");
+    } else {
+        log_i18n!(info, "hydro.maintenance-off");
+    }
+    Ok(())
+}
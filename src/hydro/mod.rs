@@ -0,0 +1,4 @@
+mod main;
+pub mod maintenance;
+
+pub use main::*;
@@ -0,0 +1,1738 @@
+use once_cell::sync::Lazy;
+use std::{cell::Cell, env, fmt::Display};
+
+/// The language of user-facing messages, selected by `H2O2_LANG` (`zh`, `en` or `both`).
+///
+/// Messages are bilingual by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    Zh,
+    En,
+    Both,
+}
+
+impl Lang {
+    fn from_env() -> Self {
+        match env::var("H2O2_LANG") {
+            Ok(lang) => match lang.to_lowercase().as_str() {
+                "zh" | "zh-cn" | "zh_cn" | "cn" => Lang::Zh,
+                "en" | "en-us" | "en_us" => Lang::En,
+                _ => Lang::Both,
+            },
+            Err(_) => Lang::Both,
+        }
+    }
+}
+
+static LANG: Lazy<Lang> = Lazy::new(Lang::from_env);
+
+thread_local! {
+    /// Set while rendering one half of a bilingual message, so that localized arguments
+    /// (e.g. the cause of an error) are rendered in the same language.
+    static RENDERING: Cell<Option<Lang>> = const { Cell::new(None) };
+}
+
+/// The selected language.
+pub fn lang() -> Lang {
+    *LANG
+}
+
+/// (key, 中文, English)
+static MESSAGES: &[(&str, &str, &str)] = &[
+    // adopt
+    (
+        "adopt.adopted",
+        "{0} 已由 H2O2 接管，原文件备份为 {1}。请重启 {2} 使其生效。",
+        "{0} is now managed by H2O2, with the original kept as {1}. Restart {2} to apply it.",
+    ),
+    (
+        "adopt.already-managed",
+        "{0} 已由 H2O2 管理，无需接管。",
+        "{0} is already managed by H2O2.",
+    ),
+    (
+        "adopt.data-path",
+        "数据目录 {0} 不同于 H2O2 使用的 {1}，接管后数据将被留在原处。请先迁移数据并修改 storage.dbPath。",
+        "The data dir {0} differs from {1} used by H2O2, where the data would be left behind. Move the data and change storage.dbPath first.",
+    ),
+    ("adopt.dropped", "将丢弃：{0}", "To drop: {0}"),
+    ("adopt.imported", "将导入：{0}", "To import: {0}"),
+    ("adopt.not-found", "{0} 不存在，无需接管。", "{0} does not exist, there is nothing to adopt."),
+    (
+        "adopt.unmanaged",
+        "有 {0} 项设置 H2O2 无法管理，接管后将被丢弃。确认后请使用 --force 接管。",
+        "{0} settings are not managed by H2O2 and would be dropped. Use --force to take the file over anyway.",
+    ),
+    (
+        "adopt.unparsable",
+        "无法解析 {0}，请手动将其设置写入配置后删除该文件。",
+        "Failed to parse {0}, please copy its settings into the profile by hand and remove it.",
+    ),
+    (
+        "adopt.unsupported",
+        "H2O2 不会为 {0} 生成可接管的配置文件，仅支持 mongodb 与 hydro。",
+        "H2O2 generates no config file of {0} to adopt. Only mongodb and hydro are supported.",
+    ),
+    // bundle
+    (
+        "bundle.created",
+        "离线包已创建于 {0}，将其复制到服务器后运行 `h2o2 bundle install <DIR>`。",
+        "The bundle is created in {0}. Copy it to the server and run `h2o2 bundle install <DIR>` there.",
+    ),
+    (
+        "bundle.download-failed",
+        "下载 {0} 失败，离线包不完整。",
+        "Failed to download {0}, the bundle is incomplete.",
+    ),
+    (
+        "bundle.fetching-packages",
+        "正在获取 Yarn、PM2 与 Hydro 的软件包……",
+        "Fetching the packages of Yarn, PM2 and Hydro...",
+    ),
+    (
+        "bundle.invalid",
+        "{0} 不是由 `h2o2 bundle create` 创建的离线包：{1}",
+        "{0} is not a bundle created by `h2o2 bundle create`: {1}",
+    ),
+    (
+        "bundle.packages-failed",
+        "获取软件包失败：`{0}`",
+        "Failed to fetch the packages: `{0}`",
+    ),
+    (
+        "bundle.requires-yarn",
+        "制作离线包需要 Node.js 与 Yarn 1，请先运行 `h2o2 install yarn`。",
+        "Creating a bundle requires Node.js and Yarn 1, run `h2o2 install yarn` first.",
+    ),
+    // cert
+    (
+        "cert.dns-mismatch",
+        "{0} 解析到 {1}，而本机的公网 IP 为 {2}，请将域名的 A 记录指向 {2}。",
+        "{0} resolves to {1}, but the public IP of this host is {2}, please point the A record of the domain to {2}.",
+    ),
+    (
+        "cert.dns-unresolved",
+        "无法解析 {0}，请添加指向本机公网 IP 的 A 记录。",
+        "{0} does not resolve, please add an A record pointing to the public IP of this host.",
+    ),
+    ("cert.expired", "证书已于 {0} 过期。", "The certificate expired at {0}."),
+    (
+        "cert.expiring",
+        "{0} 的证书将在 {1} 天后（{2}）过期。",
+        "The certificate of {0} expires in {1} days ({2}).",
+    ),
+    (
+        "cert.fetch-failed",
+        "无法获取 {0} 当前使用的证书。",
+        "Failed to fetch the certificate served for {0}.",
+    ),
+    ("cert.invalid-cert", "无法读取证书 {0}。", "Failed to read the certificate {0}."),
+    (
+        "cert.issue-timeout",
+        "等待 {0} 的证书签发超时，请确认域名已解析到本机且 80 和 443 端口可从外部访问，并查看 Caddy 的日志。",
+        "Timed out waiting for the certificate of {0}, please make sure the domain resolves to this host and ports 80 and 443 are reachable from outside, and check the logs of Caddy.",
+    ),
+    (
+        "cert.issued",
+        "已取得 {0} 的证书，有效期至 {1}。",
+        "The certificate of {0} is obtained, valid until {1}.",
+    ),
+    (
+        "cert.issuing",
+        "正在通过 Let's Encrypt 申请 {0} 的证书...",
+        "Requesting the certificate of {0} from Let's Encrypt...",
+    ),
+    (
+        "cert.key-mismatch",
+        "私钥 {0} 与证书 {1} 不匹配。",
+        "The private key {0} does not match the certificate {1}.",
+    ),
+    (
+        "cert.loaded",
+        "已启用自定义证书，有效期至 {0}。",
+        "The custom certificate is in use, valid until {0}.",
+    ),
+    (
+        "cert.no-domain",
+        "未设置域名，请使用 `--domain` 指定。",
+        "No domain is set, please specify it with `--domain`.",
+    ),
+    (
+        "cert.no-notify",
+        "未配置通知，请在配置文件的 `profile.notify` 中设置 `command` 或 `webhook`。",
+        "No notification is configured, please set `command` or `webhook` in `profile.notify` of the config.",
+    ),
+    (
+        "cert.no-proxy",
+        "未检测到反向代理 (Caddy)，请安装 Caddy 后运行 `h2o2 detect`。",
+        "The reverse proxy (Caddy) is not detected, please install Caddy and run `h2o2 detect`.",
+    ),
+    (
+        "cert.port-blocked",
+        "无法通过 {1} 连接 {0} 端口，但本机上有服务在监听，请在防火墙或云服务商的安全组中放行该端口。如果本机位于 NAT 之后，此检查可能误报。",
+        "Port {0} is not reachable via {1} although something listens on it locally, please allow the port in the firewall or the security group of the cloud provider. This may be a false alarm if the host is behind NAT.",
+    ),
+    (
+        "cert.port-not-listening",
+        "{0} 端口上没有服务在监听，请确认 Caddy 正在运行。",
+        "Nothing listens on port {0}, please make sure Caddy is running.",
+    ),
+    (
+        "cert.preflight-failed",
+        "域名解析或端口检查未通过，请按上述提示修复，或使用 `--skip-preflight` 跳过检查。",
+        "The DNS or port check failed, please fix the problems above, or skip the check with `--skip-preflight`.",
+    ),
+    ("cert.preflight-passed", "{0} 已可签发证书。", "{0} is ready for issuing certificates."),
+    (
+        "cert.public-ip-unknown",
+        "无法获取本机的公网 IP。",
+        "Failed to get the public IP of this host.",
+    ),
+    ("cert.reload-failed", "Caddy 重新加载配置失败。", "Caddy failed to reload the config."),
+    ("cert.valid", "{0} 的证书还有 {1} 天过期。", "The certificate of {0} expires in {1} days."),
+    // check
+    (
+        "check.fetch-failed",
+        "获取 {0} 的最新版本失败！",
+        "Failed to fetch the latest version of {0}!",
+    ),
+    (
+        "check.hydro-update-available",
+        "Hydro 可更新至 {0}，请运行 `yarn global add {1}`。",
+        "Hydro can be updated to {0}, please run `yarn global add {1}`.",
+    ),
+    (
+        "check.latest",
+        "{0} 在 {1} 频道的最新版本为 {2}。",
+        "The latest version of {0} on the {1} channel is {2}.",
+    ),
+    (
+        "check.manifest-cached",
+        "无法获取最新的版本清单，使用 {0} 缓存的版本清单。",
+        "Failed to fetch the latest release manifest, using the one cached at {0}.",
+    ),
+    (
+        "check.manifest-invalid",
+        "无法读取版本清单 {0}。",
+        "Failed to read the release manifest {0}.",
+    ),
+    (
+        "check.manifest-loaded",
+        "已从文件更新版本清单，其数据获取于 {0}。",
+        "The release manifest is refreshed from the file, whose data was fetched at {0}.",
+    ),
+    (
+        "check.manifest-outdated",
+        "文件中的版本清单（{0}）早于缓存的版本清单（{1}），将使用缓存的版本清单。",
+        "The release manifest in the file ({0}) is older than the cached one ({1}), using the cached one.",
+    ),
+    (
+        "check.no-release",
+        "{0} 在 {1} 频道暂无发布版本。",
+        "No release of {0} is published on the {1} channel.",
+    ),
+    ("check.not-installed", "{0} 未安装。", "{0} is not installed."),
+    (
+        "check.release-unavailable",
+        "无法获取 {0} 的最新版本：{1}",
+        "Failed to get the latest release of {0}: {1}",
+    ),
+    ("check.status-missing", "未安装", "not installed"),
+    ("check.status-unknown", "无法比较", "unknown"),
+    ("check.status-up-to-date", "已是最新", "up to date"),
+    ("check.status-update-available", "可更新", "update available"),
+    ("check.up-to-date", "{0} 已是最新版本。", "{0} is up to date."),
+    (
+        "check.yarn-pin-outdated",
+        "corepack 固定的 Yarn 为 {0}，而当前 Node.js 推荐 {1}，可运行 `corepack prepare yarn@{1} --activate` 后再运行 `h2o2 detect yarn` 切换。",
+        "Yarn {0} is pinned by corepack, while {1} is recommended for the installed Node.js. Run `corepack prepare yarn@{1} --activate` and then `h2o2 detect yarn` to switch.",
+    ),
+    (
+        "check.yarn-pin-unsupported",
+        "corepack 固定的 Yarn 为 {0}，但 Node.js {1} 未附带可用的 corepack，Yarn 可能无法使用。",
+        "Yarn {0} is pinned by corepack, but Node.js {1} ships no usable corepack, so Yarn may be unavailable.",
+    ),
+    (
+        "check.yarn-pinned",
+        "corepack 固定的 Yarn {0} 与当前 Node.js 推荐的版本一致。",
+        "Yarn {0} pinned by corepack is the one recommended for the installed Node.js.",
+    ),
+    (
+        "check.eol",
+        "{0} {1} 已于 {2} 停止维护，不再获得安全更新，请尽快升级。",
+        "{0} {1} reached its end of life on {2} and gets no more security fixes. Please update it soon.",
+    ),
+    (
+        "check.eol-soon",
+        "{0} {1} 将于 {2}（{3} 天后）停止维护，请计划升级。",
+        "{0} {1} reaches its end of life on {2}, in {3} days. Please plan to update it.",
+    ),
+    (
+        "check.vulnerable",
+        "{0} {1} 存在已知漏洞 {2}：{3}。请尽快升级。",
+        "{0} {1} has the known vulnerability {2}: {3}. Please update it soon.",
+    ),
+    // clean
+    (
+        "clean.done",
+        "已清空下载缓存，删除了 {0} 个文件，共 {1}。",
+        "The download cache is purged, {0} files of {1} are deleted.",
+    ),
+    ("clean.empty", "下载缓存为空。", "The download cache is empty."),
+    ("clean.failed", "无法清空下载缓存 {0}！", "Failed to purge the download cache {0}!"),
+    // completions
+    (
+        "completions.fpath-added",
+        "已将补全目录添加到 {0} 的 fpath 中。",
+        "The completion directory is added to fpath in {0}.",
+    ),
+    ("completions.installed", "补全脚本已安装至 {0}。", "Completion script is installed to {0}."),
+    (
+        "completions.reopen-terminal",
+        "重新打开终端后生效。",
+        "It takes effect after the terminal is reopened.",
+    ),
+    // component
+    (
+        "component.optional",
+        "`{}` 是可选组件，此处不支持。",
+        "`{}` is an optional component, which is not supported here.",
+    ),
+    ("component.unknown", "未知组件 `{}`。", "Unknown component `{}`."),
+    (
+        "component.unknown-suggest",
+        "未知组件 `{}`，是否要使用 `{}`？",
+        "Unknown component `{}`, did you mean `{}`?",
+    ),
+    // config
+    (
+        "config.already-format",
+        "配置文件 {0} 已是 {1} 格式。",
+        "The config file {0} is already in {1}.",
+    ),
+    (
+        "config.convert-exists",
+        "{0} 已存在，请先移走它。",
+        "{0} already exists, please move it away first.",
+    ),
+    (
+        "config.converted",
+        "配置已以 {1} 格式写入 {0}，原文件备份为 {2}。",
+        "The config has been written to {0} in {1}, with the original kept as {2}.",
+    ),
+    (
+        "config.deserialize-failed",
+        "解析配置文件失败，请尝试运行 `h2o2 detect`。",
+        "Failed to deserialize config file, consider running `h2o2 detect` to fix.",
+    ),
+    (
+        "config.initializing",
+        "配置文件不存在，开始初始化。",
+        "Config file does not exist, start initialization.",
+    ),
+    (
+        "config.invalid-proxy",
+        "代理地址 {0} 无效，仅支持 http、https、socks5 与 socks5h，将不使用代理。",
+        "Proxy {0} is invalid, as only http, https, socks5 and socks5h are supported. No proxy is used.",
+    ),
+    (
+        "config.load-failed",
+        "加载配置失败！准备尝试重新初始化。",
+        "Failed to load config! Try to reinitialize.",
+    ),
+    ("config.loaded", "已成功加载配置。", "Config loaded successfully."),
+    (
+        "config.not-exist",
+        "配置文件不存在，请先运行 `h2o2 detect` 或 `h2o2 install`。",
+        "Config file does not exist, please run `h2o2 detect` or `h2o2 install` first.",
+    ),
+    (
+        "config.read-failed",
+        "读取配置文件失败，请尝试运行 `h2o2 detect`。",
+        "Failed to read config file, consider running `h2o2 detect` to fix.",
+    ),
+    ("config.saved", "配置已成功保存。", "Config saved successfully."),
+    ("config.saving", "将写入配置文件...", "Saving config..."),
+    (
+        "config.serialize-failed",
+        "序列化配置失败，请联系开发者。",
+        "Failed to serialize config, please contact the developer.",
+    ),
+    ("config.skip-loading", "当前模式将不加载配置文件。", "Skipped config loading."),
+    ("config.write-failed", "写入配置文件失败。", "Failed to write config file."),
+    // crash
+    (
+        "crash.interrupted",
+        "`{}` 操作已中断，部分更改可能已经生效，可运行 `h2o2 show` 检查部署状态。",
+        "The `{}` operation was interrupted and may be partially applied, run `h2o2 show` to check the deployment.",
+    ),
+    (
+        "crash.log-unavailable",
+        "无法写入日志文件，崩溃详情如下：",
+        "Failed to write the log file, details of the crash follow:",
+    ),
+    (
+        "crash.log-written",
+        "崩溃详情已写入 {}。",
+        "Details of the crash are written to {}.",
+    ),
+    (
+        "crash.panicked",
+        "H2O2 意外崩溃：{}",
+        "H2O2 crashed unexpectedly: {}",
+    ),
+    (
+        "crash.report",
+        "请在 {} 提交 issue 并附上该日志，以帮助我们修复此问题。",
+        "Please file an issue at {} with the log attached to help us fix it.",
+    ),
+    // db
+    (
+        "db.current-op-unavailable",
+        "无法获取正在运行的操作，可能是权限不足。",
+        "Failed to get the operations in progress, possibly lacking privileges.",
+    ),
+    (
+        "db.index-create-failed",
+        "在 {0} 上创建索引 {1} 失败！",
+        "Failed to create the index {1} on {0}!",
+    ),
+    ("db.index-creating", "正在 {0} 上创建索引 {1}...", "Creating the index {1} on {0}..."),
+    (
+        "db.index-extra",
+        "{0} 上有 Hydro 未使用的索引 {1}。",
+        "{0} has the index {1} not used by Hydro.",
+    ),
+    ("db.index-missing", "{0} 缺少索引 {1}。", "{0} lacks the index {1}."),
+    (
+        "db.index-stats-unavailable",
+        "无法获取部分集合的索引使用情况。",
+        "Index usage of some collections is unavailable.",
+    ),
+    ("db.indexes-failed", "检查索引失败！", "Failed to check the indexes!"),
+    ("db.indexes-ok", "Hydro 所需的索引均已存在。", "All indexes Hydro needs exist."),
+    (
+        "db.indexes-rebuild-hint",
+        "请在维护时段内运行 `h2o2 db check-indexes --rebuild` 创建缺失的索引。",
+        "Please run `h2o2 db check-indexes --rebuild` during a maintenance window to create the missing indexes.",
+    ),
+    ("db.indexes-rebuilt", "缺失的索引已创建。", "The missing indexes are created."),
+    (
+        "db.long-running",
+        "操作 {0}（{1} {2}）已运行 {3} 秒，执行计划：{4}",
+        "Operation {0} ({1} {2}) has been running for {3} seconds, plan: {4}",
+    ),
+    ("db.shell-exited", "{0} 异常退出。（{1}）", "{0} exited abnormally. ({1})"),
+    ("db.shell-failed", "执行 {0} 失败！", "Failed to execute {0}!"),
+    (
+        "db.shell-not-found",
+        "未找到 mongosh 或 mongo，请先安装 MongoDB Shell。",
+        "Neither mongosh nor mongo is found, please install MongoDB Shell first.",
+    ),
+    ("db.stats", "数据库 {0} 的统计信息：", "Statistics of database {0}:"),
+    ("db.stats-failed", "获取数据库统计信息失败！", "Failed to get database statistics!"),
+    (
+        "db.unused-indexes",
+        "集合 {0} 中有未使用过的索引：{1}",
+        "Indexes never used in collection {0}: {1}",
+    ),
+    // detect
+    (
+        "detect.dependency-missing",
+        "未找到 {1}，跳过 {0}（依赖 {1}）。",
+        "Skip {0} (which depends on {1}) due to {1} not found.",
+    ),
+    ("detect.detecting", "探测 {0}...", "Detecting {0}..."),
+    ("detect.exec-failed", "命令 `{0} {1}` 执行异常。", "Failed to execute `{0} {1}`."),
+    ("detect.exited-abnormally", "{0} 异常退出（{1}）。", "{0} exited abnormally. ({1})"),
+    (
+        "detect.langs-generated",
+        "语言设置已生成至 {0}，可将其填入 Hydro 的 `langs` 设置。",
+        "Language settings are generated to {0}, which can be filled into the `langs` setting of Hydro.",
+    ),
+    ("detect.not-found", "未找到 {0}。", "{0} is not found."),
+    (
+        "detect.output-malformed",
+        "{0} 的输出格式不正确，疑似运行异常。",
+        "The output format of {0} is incorrect, and it seems to be running abnormally.",
+    ),
+    (
+        "detect.output-too-short",
+        "{0} 的输出太短，疑似运行异常。",
+        "The output of {0} is too short, and it seems to be running abnormally.",
+    ),
+    ("detect.parse-version-failed", "解析版本号失败。", "Failed to parse version."),
+    ("detect.result", "结果如下：", "Result:"),
+    ("detect.sandbox-skipped", "sandbox 无法探测，跳过。", "Cannot detect sandbox, skipped."),
+    (
+        "detect.version-unrecognized",
+        "{0} 异常退出（{1}），无法识别版本。",
+        "{0} exited abnormally and the version could not be recognized. ({1})",
+    ),
+    (
+        "detect.version-unsupported",
+        "Hydro 需要 `{0} {1}`，当前版本可能无法正常工作。",
+        "Hydro requires `{0} {1}`, the current version may not work properly.",
+    ),
+    (
+        "detect.yarn-berry",
+        "Yarn {0} 为 Yarn 2 及以上版本（Berry），不支持 `yarn global`，Hydro 将改用 npm 或通过 corepack 运行的 Yarn 1 管理，可在配置文件的 `profile.hydro` 中设置 `package_manager`。",
+        "Yarn {0} is Yarn 2 or later (Berry), which has no `yarn global`. Hydro will be managed with npm or Yarn 1 run through corepack instead, as set by `package_manager` in `profile.hydro` of the config.",
+    ),
+    // doctor
+    (
+        "doctor.cgroup-missing",
+        "内核未启用 cgroup 控制器 {0}，沙箱无法限制评测程序的资源。可在内核启动参数中加入 `cgroup_enable=memory swapaccount=1` 后重启。",
+        "The cgroup controllers {0} are not enabled in the kernel, so the sandbox cannot limit the resources of programs judged. Add `cgroup_enable=memory swapaccount=1` to the kernel command line and reboot.",
+    ),
+    (
+        "doctor.cgroup-unavailable",
+        "未挂载 cgroup 文件系统，沙箱无法运行。请挂载 /sys/fs/cgroup，在容器中运行时请以 `--privileged` 启动容器。",
+        "No cgroup file system is mounted, so the sandbox cannot run. Mount /sys/fs/cgroup, or start the container with `--privileged` if running in one.",
+    ),
+    (
+        "doctor.checking",
+        "正在检查 {0}...",
+        "Checking {0}...",
+    ),
+    (
+        "doctor.dir-not-writable",
+        "无法写入 {0} 的数据目录 {1}。请以运行 {0} 的用户执行 `chown -R <用户> {1}`。",
+        "The data dir {1} of {0} is not writable. Run `chown -R <user> {1}` with the user running {0}.",
+    ),
+    (
+        "doctor.low-disk",
+        "{0} 所在文件系统仅剩 {1} 可用空间。请清理磁盘，例如运行 `h2o2 prune`。",
+        "Only {1} is free on the file system of {0}. Free up some space, e.g. by running `h2o2 prune`.",
+    ),
+    (
+        "doctor.passed",
+        "未发现问题。",
+        "No problems found.",
+    ),
+    (
+        "doctor.port-conflict",
+        "{0} 已被其他进程占用，{1} 将无法监听。可运行 `ss -ltnp 'sport = :{2}'` 找到该进程并停止它，或在配置文件中修改 {1} 的端口。",
+        "{0} is taken by another process, so {1} cannot listen on it. Find the process with `ss -ltnp 'sport = :{2}'` and stop it, or change the port of {1} in the config.",
+    ),
+    (
+        "doctor.problems-found",
+        "发现 {0} 类问题，请按上述建议修复。",
+        "Found {0} kinds of problems. Fix them as suggested above.",
+    ),
+    // docs
+    ("docs.exported", "运维手册已导出至 {0}。", "The runbook is exported to {0}."),
+    // env
+    (
+        "env.context-unavailable",
+        "无法获取 {0} 中的 PATH：{1}",
+        "Failed to get PATH of {0}: {1}",
+    ),
+    (
+        "env.doctor-passed",
+        "各环境中的命令均指向相同文件。",
+        "Commands resolve to the same files in every environment.",
+    ),
+    (
+        "env.mismatch",
+        "`{0}` 在 {1} 中指向 {2}，而不是 {3}，请统一 PATH 或在其中使用绝对路径。",
+        "`{0}` resolves to {2} in {1} instead of {3}, please make PATH consistent or use the absolute path there.",
+    ),
+    (
+        "env.missing-login",
+        "`{0}` 在登录 shell 中不可用，请将 `export PATH={1}:$PATH` 加入 ~/.profile，而非仅加入 ~/.bashrc 等交互式 shell 的配置。",
+        "`{0}` is missing in login shells, please add `export PATH={1}:$PATH` to ~/.profile rather than only to the config of interactive shells such as ~/.bashrc.",
+    ),
+    (
+        "env.missing-non-login",
+        "`{0}` 在非登录 shell 中不可用（如 `ssh host command`、脚本与 cron 任务），请在其中使用绝对路径 {1}，或将其链接到 /usr/local/bin。",
+        "`{0}` is missing in non-login shells (such as `ssh host command`, scripts and cron jobs), please use the absolute path {1} there, or link it into /usr/local/bin.",
+    ),
+    (
+        "env.missing-service",
+        "`{0}` 在 {1} 中不可用，请在服务定义中使用绝对路径 {2}。",
+        "`{0}` is missing in {1}, please use the absolute path {2} in the service definition.",
+    ),
+    ("env.not-found", "（未找到）", "(not found)"),
+    (
+        "env.pm2-update-env",
+        "PM2 沿用其启动时的 PATH，修正 PATH 后请运行 `pm2 update` 与 `pm2 restart all --update-env` 使其生效。",
+        "PM2 keeps the PATH it is started with, run `pm2 update` and `pm2 restart all --update-env` to apply a fixed PATH.",
+    ),
+    // export
+    ("export.exported", "已导出至 {0}。", "Exported to {0}."),
+    (
+        "export.no-runtime",
+        "配置文件中未记录任何编译器，请先运行 `h2o2 detect --runtimes`。",
+        "No runtime is recorded in config, please run `h2o2 detect --runtimes` first.",
+    ),
+    // fleet
+    ("fleet.invalid-name", "无效的评测节点名称：{0}。", "Invalid name of judge node: {0}."),
+    ("fleet.invalid-url", "无效的地址：{0}", "Invalid URL: {0}"),
+    (
+        "fleet.joined",
+        "已写入 {0}，独立评测机将连接 {1}，请启用独立评测机（`h2o2 scale judge <N>`）或重启它。",
+        "Written to {0}, the standalone judge will connect to {1}, please enable it (`h2o2 scale judge <N>`) or restart it.",
+    ),
+    ("fleet.no-tokens", "尚未创建任何令牌。", "No token is created yet."),
+    (
+        "fleet.token-created",
+        "已为评测节点 {0} 创建评测账号（uid {1}），请在该节点上运行以下命令，令牌仅显示一次：",
+        "Created the judge account (uid {1}) for judge node {0}, please run the command below on the node, the token is shown only once:",
+    ),
+    (
+        "fleet.token-exists",
+        "评测节点 {0} 的令牌已存在，请先吊销。",
+        "A token of judge node {0} exists, please revoke it first.",
+    ),
+    ("fleet.token-not-found", "没有评测节点 {0} 的令牌。", "No token of judge node {0} is found."),
+    ("fleet.token-revoked", "已吊销评测节点 {0} 的令牌。", "Revoked the token of judge node {0}."),
+    (
+        "fleet.unexpected-output",
+        "无法从 Hydro CLI 的输出中读取用户 ID：{0}",
+        "Failed to read the user ID from the output of Hydro CLI: {0}",
+    ),
+    // health
+    ("health.healthy", "{0} 运行正常。", "{0} is healthy."),
+    ("health.last-logs", "最后的日志：", "Last logs:"),
+    (
+        "health.unhealthy",
+        "{0} 在 {1} 秒内未能正常运行：{2}",
+        "{0} is not healthy within {1} seconds: {2}",
+    ),
+    ("health.waiting", "等待 {0} 正常运行...", "Waiting for {0} to become healthy..."),
+    // hydro
+    (
+        "hydro.maintenance-failed",
+        "切换维护模式失败！",
+        "Failed to switch maintenance mode!",
+    ),
+    (
+        "hydro.maintenance-no-proxy",
+        "维护页面由 Caddy 提供，请先运行 `h2o2 cert issue` 通过 Caddy 在域名上提供 Hydro。",
+        "The maintenance page is served by Caddy. Serve Hydro on a domain through Caddy with `h2o2 cert issue` first.",
+    ),
+    (
+        "hydro.maintenance-off",
+        "维护模式已关闭，Hydro 恢复访问。",
+        "Maintenance mode is off, Hydro is served again.",
+    ),
+    (
+        "hydro.maintenance-on",
+        "维护模式已开启，访问者将看到维护页面。",
+        "Maintenance mode is on, visitors see the maintenance page.",
+    ),
+    (
+        "hydro.maintenance-unchanged",
+        "维护模式已经是 {0}。",
+        "Maintenance mode is already {0}.",
+    ),
+    // install
+    (
+        "install.already-installed",
+        "{0} 已安装，不执行任何操作。",
+        "{0} is already installed, skip.",
+    ),
+    (
+        "install.bundle-lockfile-kept",
+        "Yarn 的全局目录 {0} 中已有 yarn.lock，将保留该文件，Hydro 可能无法离线安装。",
+        "Keeping the yarn.lock existing in {0}, the global dir of Yarn, with which Hydro may fail to install offline.",
+    ),
+    (
+        "install.by-package-manager",
+        "{0} 由包管理器安装，跳过下载。",
+        "{0} is installed by the package manager, skip downloading.",
+    ),
+    (
+        "install.check-hydro-update",
+        "若需要检查更新 Hydro，请运行 `h2o2 check`。",
+        "If you need to check and update Hydro, please run `h2o2 check`.",
+    ),
+    (
+        "install.cache-hit",
+        "使用已缓存的下载文件。",
+        "Using the cached download.",
+    ),
+    (
+        "install.checksum-ambiguous",
+        "`--checksum` 仅可与一个 `--file` 一起使用。",
+        "`--checksum` can only be used with a single `--file`.",
+    ),
+    (
+        "install.external",
+        "profile 中已为 {0} 配置外部地址，仍将按要求在本机安装。",
+        "An external endpoint of {0} is configured in the profile, installing it here as asked.",
+    ),
+    (
+        "install.external-no-credentials",
+        "profile 中未配置外部 {0} 的凭据（root_user 与 root_password），Hydro 可能无法访问。",
+        "No credentials of the external {0} are configured in the profile (root_user and root_password), which Hydro may fail to access.",
+    ),
+    (
+        "install.file-duplicate",
+        "为 {0} 指定了多个文件。",
+        "Multiple files are given for {0}.",
+    ),
+    (
+        "install.file-no-checksum",
+        "{0} 没有可用于校验的 SHA-256，请通过 `--checksum` 指定，Node.js 也可将 SHASUMS256.txt 放在同一目录下。",
+        "No SHA-256 is known to verify {0} against, please give it by `--checksum`, or put SHASUMS256.txt next to it for Node.js.",
+    ),
+    (
+        "install.file-not-found",
+        "文件 {0} 不存在。",
+        "File {0} does not exist.",
+    ),
+    (
+        "install.file-not-supported",
+        "{0} 由包管理器安装，不支持从文件安装。",
+        "{0} is installed by the package manager, and cannot be installed from a file.",
+    ),
+    (
+        "install.file-unknown-component",
+        "无法从文件名推断 {0} 对应的组件，请以 `组件=路径` 的形式指定。",
+        "Failed to tell the component of {0} by its name, please give it as `component=path`.",
+    ),
+    (
+        "install.file-unknown-version",
+        "无法从文件名 {0} 得知版本，请保留下载时的文件名。",
+        "Failed to tell the version by the file name {0}, please keep the name it was downloaded as.",
+    ),
+    (
+        "install.using-file",
+        "使用文件 {0}。",
+        "Using file {0}.",
+    ),
+    (
+        "install.version-not-found",
+        "下载源上没有 {0} {1} 的安装包。",
+        "No package of {0} {1} is found on the mirror.",
+    ),
+    ("install.checksum-failed", "文件校验失败！", "File checksum mismatch!"),
+    ("install.checksum-mismatch", "文件校验和不匹配", "file checksum mismatch"),
+    ("install.command-failed", "`{}` 执行失败，最后的输出：", "`{}` failed, last output:"),
+    ("install.configure-failed", "配置 {0} 失败！", "Failed to configure {0}!"),
+    (
+        "install.container-detected",
+        "检测到当前运行在容器（{0}）中，将跳过服务管理相关的步骤。",
+        "Running in a container ({0}), service manager steps will be skipped.",
+    ),
+    (
+        "install.container-path",
+        "当前运行在容器中，请将 {0} 添加到镜像的 PATH 中。",
+        "Running in a container, please add {0} to PATH of the image.",
+    ),
+    (
+        "install.corepack-pinning",
+        "使用 corepack 固定并启用 Yarn {0}...",
+        "Pinning and activating Yarn {0} with corepack...",
+    ),
+    (
+        "install.corepack-unavailable",
+        "Node.js {0} 未附带可用的 corepack，改用 npm 安装 Yarn。",
+        "Node.js {0} ships no usable corepack, installing Yarn with npm instead.",
+    ),
+    ("install.download-failed", "下载 {0} 失败！", "Failed to download {0}!"),
+    (
+        "install.download-interrupted",
+        "下载中断：{0}，已下载 {1}，稍后继续（第 {2}/{3} 次）。",
+        "Download interrupted: {0}. {1} is downloaded, resuming soon (attempt {2}/{3}).",
+    ),
+    (
+        "install.download-restarted",
+        "服务器不支持断点续传，重新开始下载。",
+        "The server does not support resuming, downloading from the start again.",
+    ),
+    (
+        "install.download-throttled",
+        "下载源繁忙（{0}），{1} 秒后重试（第 {2}/{3} 次）。",
+        "The mirror is busy ({0}), retrying in {1} seconds (attempt {2}/{3}).",
+    ),
+    ("install.downloaded", "下载完毕。", "Download completed."),
+    ("install.downloading", "开始下载...", "Downloading..."),
+    (
+        "install.downloads-saved",
+        "下载完毕，文件已保存至 {0}。",
+        "Download completed, files are saved to {0}.",
+    ),
+    ("install.downloads-failed", "部分组件下载失败。", "Some components failed to download."),
+    ("install.failed", "安装 {} 失败：{}。", "Failed to install {}: {}."),
+    ("install.finding-source", "寻找最快的下载源...", "Finding the fastest download source..."),
+    (
+        "install.full-output-logged",
+        "完整输出已写入日志文件 {0}。",
+        "The full output is written to the log file {0}.",
+    ),
+    (
+        "install.getting-path",
+        "安装已完成，获取安装目录...",
+        "Installation finished. Getting path...",
+    ),
+    ("install.getting-version", "获取版本...", "Getting version..."),
+    ("install.install-failed", "安装 {0} 失败！", "Failed to install {0}!"),
+    (
+        "install.invalid-pin",
+        "配置中固定的版本 {0} 无效，将使用内置版本。",
+        "The version {0} pinned in the config is invalid, using the built-in one.",
+    ),
+    (
+        "install.invalid-version",
+        "无效的版本：{0}，应形如 `16.20.2`。",
+        "Invalid version: {0}, which should look like `16.20.2`.",
+    ),
+    (
+        "install.judge-local-mongodb",
+        "judge 角色的服务器通常连接 web 服务器上的 MongoDB，但 profile.mongodb.host 为 {0}。",
+        "Servers of the judge role usually connect to MongoDB on the web server, but profile.mongodb.host is {0}.",
+    ),
+    (
+        "install.manifest-invalid",
+        "无法读取离线清单 {0}：{1}",
+        "Failed to read the offline manifest {0}: {1}",
+    ),
+    ("install.metrics", "各阶段耗时：", "Time spent in each stage:"),
+    (
+        "install.mirror-switched",
+        "下载源 {0} 无法提供下载：{1}，改用 {2}。",
+        "Mirror {0} failed to serve the download: {1}, switching to {2}.",
+    ),
+    (
+        "install.mongodb-insecure-bind",
+        "MongoDB 将监听非本地地址 {0} 且未启用 TLS，请确认防火墙已正确配置。",
+        "MongoDB will listen on non-local address {0} without TLS, make sure the firewall is configured properly.",
+    ),
+    (
+        "install.mongodb-no-build",
+        "未找到适用于 {1} ({2}) 的 MongoDB {0} 版本。",
+        "No release of MongoDB {0} is built for {1} ({2}).",
+    ),
+    (
+        "install.mongodb-unknown-distro",
+        "无法识别当前 Linux 发行版，MongoDB 仅为 Ubuntu、Debian、RHEL 系列与 Amazon Linux 提供构建，请手动安装后运行 `h2o2 detect`。",
+        "Unknown Linux distribution, MongoDB is only built for Ubuntu, Debian, RHEL-compatible distributions and Amazon Linux, please install it manually and run `h2o2 detect`.",
+    ),
+    (
+        "install.no-available-source",
+        "没有可用的下载源，探测结果：",
+        "no available source, probing results:",
+    ),
+    (
+        "install.nodejs-index-failed",
+        "无法从下载源获取 Node.js 的版本列表 index.json：{0}",
+        "Failed to fetch the list of Node.js releases, index.json, from the mirror: {0}",
+    ),
+    (
+        "install.nodejs-lts",
+        "将安装 LTS 系列 {1} 的最新版本 {0}。",
+        "Installing {0}, the newest release of the LTS line {1}.",
+    ),
+    (
+        "install.nodejs-lts-not-found",
+        "下载源未列出 LTS 系列 {0} 的版本，请检查 profile.nodejs.lts。",
+        "No release of the LTS line {0} is listed by the mirror, please check profile.nodejs.lts.",
+    ),
+    (
+        "install.nodejs-recommended",
+        "若需要 H2O2 安装一个推荐版本的 Node.js，请删除系统中已存在的版本并重新运行 H2O2。",
+        "If you need H2O2 to install a recommended version of Node.js, please delete the existing version in the system and run H2O2 again.",
+    ),
+    (
+        "install.not-in-role",
+        "{0} 通常不在 {1} 角色的服务器上运行，仍将按要求安装。",
+        "{0} does not usually run on servers of the {1} role, installing it as asked.",
+    ),
+    (
+        "install.npm-attempt-failed",
+        "`{0} {1}` 失败（第 {2} 次）：{3}。",
+        "`{0} {1}` failed (attempt {2}): {3}.",
+    ),
+    (
+        "install.npm-switch-registry",
+        "清理缓存并切换到 {0} 重试。",
+        "Cleaning the cache and retrying with {0}.",
+    ),
+    (
+        "install.offline-skipped",
+        "离线模式下未提供 {0} 的文件，跳过。",
+        "No file of {0} is given in offline mode, skipped.",
+    ),
+    (
+        "install.partially-failed",
+        "部分组件安装失败：{}。已安装的组件已记录到配置文件，修复问题后重新运行 `h2o2 install` 即可继续。",
+        "Some components failed to install: {}. The installed ones are recorded in the config, run `h2o2 install` again to continue after fixing the problems.",
+    ),
+    ("install.platform-not-supported", "暂不支持当前平台", "your platform is not supported yet"),
+    (
+        "install.redirect-rejected",
+        "下载被重定向至不受信任的地址 {0}",
+        "The download is redirected to {0}, which is not trusted",
+    ),
+    ("install.redirected", "下载被重定向至 {0}。", "The download is redirected to {0}."),
+    ("install.require", "需要 {}", "require {}"),
+    (
+        "install.sandbox-latest",
+        "将安装 GitHub 上的最新版本 {0}。",
+        "Installing {0}, the latest release on GitHub.",
+    ),
+    (
+        "install.sandbox-latest-failed",
+        "无法从 GitHub 获取最新版本：{0}，将安装 {1}。可设置环境变量 {2} 以提高 GitHub API 的请求频率限制，或在 profile.sandbox.version 中固定版本。",
+        "Failed to fetch the latest release from GitHub: {0}, installing {1} instead. Set {2} in the environment to raise the rate limit of the GitHub API, or pin a release in profile.sandbox.version.",
+    ),
+    (
+        "install.sandbox-privileged",
+        "sandbox 需要创建命名空间和 cgroup，请确保容器以 `--privileged` 运行。",
+        "sandbox needs to create namespaces and cgroups, please make sure the container runs with `--privileged`.",
+    ),
+    (
+        "install.sandbox-token-failed",
+        "配置 sandbox 访问令牌失败！",
+        "Failed to set up the sandbox auth token!",
+    ),
+    (
+        "install.shasums-failed",
+        "获取 {0} 的校验值失败：{1}",
+        "Failed to fetch the checksums of {0}: {1}",
+    ),
+    (
+        "install.smaller-archive",
+        "使用体积更小的 {0}。",
+        "Using the smaller archive {0}.",
+    ),
+    (
+        "install.source-profile",
+        "请手动执行 `source ~/.profile` 来应用更改。",
+        "Please execute `source ~/.profile` manually to apply changes.",
+    ),
+    ("install.start", "开始安装 {0}...", "Start to install {0}..."),
+    ("install.summary", "安装结果：", "Installation summary:"),
+    ("install.summary-failed", "失败", "failed"),
+    ("install.summary-installed", "已安装 {}", "installed {}"),
+    ("install.summary-not-installed", "未安装", "not installed"),
+    ("install.summary-skipped", "已存在，跳过", "already installed, skipped"),
+    (
+        "install.with-dependencies",
+        "一并安装所依赖的组件：{0}。",
+        "Installing the components depended on as well: {0}.",
+    ),
+    ("install.x86-not-supported", "x86 架构不受支持。", "The x86 architecture is not supported."),
+    (
+        "install.yarn-berry",
+        "Yarn {0} 为 Yarn 2 及以上版本（Berry），不支持 `yarn global`，将改用 {1} 安装 Hydro，可在配置文件的 `profile.hydro` 中设置 `package_manager`。",
+        "Yarn {0} is Yarn 2 or later (Berry), which has no `yarn global`. Hydro will be installed with {1} instead, which can be changed by `package_manager` in `profile.hydro` of the config.",
+    ),
+    // lock
+    (
+        "lock.held",
+        "另一个 H2O2 进程（PID {1}）正在执行 `{0}`，请稍后重试，或使用 `--wait` 等待其完成。",
+        "Another H2O2 process (PID {1}) is running `{0}`, please try again later or use `--wait` to wait for it.",
+    ),
+    (
+        "lock.io-failed",
+        "读写操作锁失败。",
+        "Failed to read or write the operation lock.",
+    ),
+    (
+        "lock.stale-removed",
+        "已移除过期的操作锁（PID {0}）。",
+        "Removed the stale operation lock (PID {0}).",
+    ),
+    (
+        "lock.waiting",
+        "正在等待另一个 H2O2 进程（PID {1}）完成 `{0}`...",
+        "Waiting for another H2O2 process (PID {1}) to finish `{0}`...",
+    ),
+    // minio
+    (
+        "minio.cannot-check-running",
+        "无法检查 MinIO 是否正在运行，请确认其已停止。",
+        "Cannot check whether MinIO is running, please make sure it is stopped.",
+    ),
+    ("minio.copying", "不在同一设备上，正在复制数据...", "Copying data across devices..."),
+    (
+        "minio.data-dir-changed",
+        "MinIO 数据目录已从 {0} 改为 {1}，请先运行 `h2o2 minio migrate` 迁移已有文件。",
+        "MinIO data directory is changed from {0} to {1}, please run `h2o2 minio migrate` to move the existing files first.",
+    ),
+    ("minio.data-dir-missing", "原数据目录 {0} 不存在。", "Old data directory {0} does not exist."),
+    (
+        "minio.data-dir-nested",
+        "新数据目录不能位于原数据目录中。",
+        "The new data directory cannot be inside the old one.",
+    ),
+    (
+        "minio.data-dir-not-empty",
+        "新数据目录 {0} 非空，请清空或更换目录。",
+        "New data directory {0} is not empty, please empty it or choose another one.",
+    ),
+    (
+        "minio.data-dir-unchanged",
+        "MinIO 已在使用数据目录 {0}，无需迁移。",
+        "MinIO is already using data directory {0}, nothing to migrate.",
+    ),
+    ("minio.env-read-failed", "读取 MinIO 环境文件失败！", "Failed to read MinIO env file!"),
+    ("minio.env-write-failed", "写入 MinIO 环境文件失败！", "Failed to write MinIO env file!"),
+    ("minio.mc-failed", "mc 异常退出。（{0}）", "mc exited abnormally. ({0})"),
+    ("minio.mc-not-found", "未找到 mc，开始安装。", "mc is not found, start to install."),
+    ("minio.migrate-failed", "迁移 MinIO 数据失败！", "Failed to migrate MinIO data!"),
+    (
+        "minio.migrated",
+        "MinIO 数据已迁移至 {0}（{1} 个文件，{2}）。",
+        "MinIO data is migrated to {0} ({1} files, {2}).",
+    ),
+    ("minio.moving", "正在将数据从 {0} 移动到 {1}...", "Moving data from {0} to {1}..."),
+    ("minio.not-installed", "MinIO 未安装。", "MinIO is not installed."),
+    (
+        "minio.not-set-up",
+        "MinIO 尚未配置，请使用 `--from` 指定原数据目录。",
+        "MinIO is not set up yet, please specify the old data directory with `--from`.",
+    ),
+    (
+        "minio.old-kept",
+        "原数据保留在 {0}，确认无误后可手动删除。",
+        "The old data is kept in {0}, which can be removed once verified.",
+    ),
+    (
+        "minio.restarted",
+        "MinIO 已使用新的数据目录重新启动（pid {0}），输出写入 {1}。",
+        "MinIO is started again with the new data directory (pid {0}), writing its output to {1}.",
+    ),
+    (
+        "minio.restarted-unit",
+        "已使用新的数据目录重新启动 {0}。",
+        "{0} is started again with the new data directory.",
+    ),
+    (
+        "minio.start-again",
+        "MinIO 此前未在运行，可使用环境文件 {0} 启动：",
+        "MinIO was not running, start it with env file {0}:",
+    ),
+    (
+        "minio.start-failed",
+        "无法重新启动 MinIO，请查看 {0}。",
+        "Failed to start MinIO again, see {0}.",
+    ),
+    ("minio.stop-failed", "无法停止 MinIO（pid {0}）！", "Failed to stop MinIO (pid {0})!"),
+    ("minio.stopping", "正在停止 MinIO（pid {0}）...", "Stopping MinIO (pid {0})..."),
+    (
+        "minio.supervised",
+        "MinIO 停止后又被重新启动，请先停用管理它的服务。",
+        "MinIO is started again after stopping, please disable the service managing it first.",
+    ),
+    (
+        "minio.verify-failed",
+        "校验失败：原目录有 {0} 个文件（{1}），新目录有 {2} 个文件（{3}）。原数据已保留。",
+        "Verification failed: {0} files ({1}) in the old directory, but {2} files ({3}) in the new one. The old data is kept.",
+    ),
+    // mirror
+    ("mirror.invalid-url", "无效的地址：{0}", "Invalid URL: {0}"),
+    (
+        "mirror.not-pinned",
+        "{0} 没有固定的下载源。",
+        "No mirror of {0} is pinned.",
+    ),
+    (
+        "mirror.pinned",
+        "已固定 {0} 的下载源为 {1}，安装时将不再测速。",
+        "{1} is pinned as the mirror of {0}, which installs use without probing.",
+    ),
+    (
+        "mirror.pinned-note",
+        "{0} 的下载源已固定为 {1}（标记为 *），可通过 `h2o2 mirror unset {0}` 取消。",
+        "The mirror of {0} is pinned to {1} (marked with *), see `h2o2 mirror unset {0}`.",
+    ),
+    (
+        "mirror.unknown-list",
+        "未知的下载源列表 {0}，可选：{1}",
+        "Unknown mirror list {0}, expected one of: {1}",
+    ),
+    (
+        "mirror.unpinned",
+        "已取消固定 {0} 的下载源。",
+        "The mirror of {0} is no longer pinned.",
+    ),
+    // notify
+    ("notify.command-failed", "通知命令执行失败：{0}", "The notification command failed: {0}"),
+    (
+        "notify.webhook-failed",
+        "发送通知到 webhook 失败：{0}",
+        "Failed to send the notification to the webhook: {0}",
+    ),
+    // platform
+    (
+        "platform.x86-warning",
+        "x86 架构不受支持，Hydro 将无法正常工作，请考虑使用 x86_64。",
+        "The x86 architecture is not supported, Hydro will not work properly, please consider using x86_64.",
+    ),
+    // profiling
+    (
+        "profiling.write-failed",
+        "写入性能记录 {0} 失败：{1}",
+        "Failed to write the profile {0}: {1}",
+    ),
+    ("profiling.written", "性能记录已写入 {0}。", "The profile is written to {0}."),
+    // prune
+    ("prune.cache", "测试数据缓存 {0}：{1} 个文件，{2}。", "Test data cache {0}: {1} files, {2}."),
+    (
+        "prune.cache-failed",
+        "清理测试数据缓存 {0} 失败！",
+        "Failed to prune the test data cache {0}!",
+    ),
+    (
+        "prune.compact-hint",
+        "MongoDB 不会立即将删除记录占用的磁盘空间归还给系统，可在维护时段内通过 `h2o2 db shell` 执行 `db.runCommand({ compact: 'record' })`。",
+        "MongoDB does not return the disk space of deleted records to the system at once, run `db.runCommand({ compact: 'record' })` via `h2o2 db shell` during a maintenance window to do so.",
+    ),
+    ("prune.done", "清理完成，共释放 {0}。", "Pruning is done, {0} is reclaimed in total."),
+    ("prune.dry-run", "预演模式，不会删除任何数据。", "Dry run, no data will be deleted."),
+    (
+        "prune.dry-run-done",
+        "预演完成，共可释放 {0}。确认无误后，请在 24 小时内加上 `--execute` 以相同参数重新运行。",
+        "Dry run is done, {0} can be reclaimed in total. If it looks right, please run again with the same options plus `--execute` within 24 hours.",
+    ),
+    (
+        "prune.dry-run-required",
+        "未找到以相同参数进行的预演，请先去掉 `--execute` 运行一次。",
+        "No dry run with the same options is found, please run without `--execute` first.",
+    ),
+    ("prune.files", "提交文件：{0} 个，{1}。", "Submission files: {0}, {1}."),
+    (
+        "prune.files-failed",
+        "清理 MinIO 中的提交文件失败！",
+        "Failed to prune the submission files in MinIO!",
+    ),
+    (
+        "prune.files-kept",
+        "保留通过的记录时，不清理提交文件。",
+        "Submission files are kept along with the accepted records.",
+    ),
+    (
+        "prune.invalid-date",
+        "无效的日期：{0}，格式应为 YYYY-MM-DD。",
+        "Invalid date: {0}, the format should be YYYY-MM-DD.",
+    ),
+    (
+        "prune.nothing",
+        "请指定要清理的内容，如 `--records-before 2023-01-01` 或 `--testdata-cache`。",
+        "Please specify what to prune, such as `--records-before 2023-01-01` or `--testdata-cache`.",
+    ),
+    ("prune.records", "提交记录：{0} 条，{1}。", "Submission records: {0}, {1}."),
+    ("prune.records-deleted", "已删除 {0} 条提交记录。", "{0} submission records are deleted."),
+    ("prune.records-failed", "清理提交记录失败！", "Failed to prune the submission records!"),
+    // sandbox
+    (
+        "sandbox.cache-cleaned",
+        "已删除沙箱中缓存的 {0}/{1} 个文件。",
+        "{0} of {1} files cached in the sandbox are deleted.",
+    ),
+    ("sandbox.cache-empty", "沙箱中没有缓存的文件。", "No file is cached in the sandbox."),
+    (
+        "sandbox.clean-not-scheduled",
+        "沙箱缓存未设置定时清理。",
+        "Cleaning the sandbox cache is not scheduled.",
+    ),
+    (
+        "sandbox.clean-scheduled",
+        "已设置定时清理沙箱缓存（{0}），可通过 `systemctl list-timers {1}` 查看。",
+        "Cleaning the sandbox cache is scheduled ({0}), see `systemctl list-timers {1}`.",
+    ),
+    (
+        "sandbox.clean-unscheduled",
+        "已取消定时清理沙箱缓存。",
+        "Cleaning the sandbox cache is no longer scheduled.",
+    ),
+    (
+        "sandbox.lang-failed",
+        "{0}（{1}）未通过：{2}",
+        "{0} ({1}) failed: {2}",
+    ),
+    (
+        "sandbox.lang-no-output",
+        "编译成功，但未生成 {0}",
+        "compiled, but {0} is not written",
+    ),
+    ("sandbox.lang-passed", "{0}（{1}）通过。", "{0} ({1}) passed."),
+    (
+        "sandbox.lang-skipped",
+        "{0}（{1}）没有可用的测试程序，已跳过。",
+        "{0} ({1}) is skipped, as there is no program to test it with.",
+    ),
+    (
+        "sandbox.lang-unknown",
+        "未配置语言 {0}，请运行 `h2o2 detect --runtimes` 检测运行时。",
+        "Language {0} is not set up, run `h2o2 detect --runtimes` to detect the runtimes.",
+    ),
+    (
+        "sandbox.lang-wrong-output",
+        "输出不正确：{0}",
+        "wrong output: {0}",
+    ),
+    (
+        "sandbox.langs-failed",
+        "{0}/{1} 种语言未通过测试。",
+        "{0} of {1} languages failed the test.",
+    ),
+    (
+        "sandbox.langs-passed",
+        "全部 {0} 种语言均通过测试。",
+        "All {0} languages passed the test.",
+    ),
+    (
+        "sandbox.no-langs",
+        "未检测到任何语言的运行时，请先运行 `h2o2 detect --runtimes`。",
+        "No runtime of any language is detected, run `h2o2 detect --runtimes` first.",
+    ),
+    ("sandbox.not-installed", "沙箱未安装。", "The sandbox is not installed."),
+    (
+        "sandbox.request-failed",
+        "沙箱请求 `{0}` 失败：{1}",
+        "Sandbox request `{0}` failed: {1}",
+    ),
+    (
+        "sandbox.unreachable",
+        "无法连接沙箱 {0}，请确认其正在运行。",
+        "Failed to connect to the sandbox at {0}, please make sure it is running.",
+    ),
+    // scale
+    ("scale.not-installed", "Hydro 或 PM2 未安装。", "Hydro or PM2 is not installed."),
+    ("scale.scaled", "{0} 进程数已调整为 {1}。", "The number of {0} processes is set to {1}."),
+    (
+        "scale.zero-processes",
+        "Hydro 进程数不能为 0。",
+        "The number of Hydro processes cannot be 0.",
+    ),
+    // self-update
+    (
+        "self-update.available",
+        "H2O2 有新版本 {0}（当前为 {1}）。",
+        "H2O2 {0} is available (currently {1}).",
+    ),
+    (
+        "self-update.bad-release",
+        "无法解析 GitHub 返回的发布信息。",
+        "Failed to parse the release returned by GitHub.",
+    ),
+    ("self-update.bad-tag", "无法解析发布版本 {0}。", "Failed to parse the release version {0}."),
+    (
+        "self-update.checksum-mismatch",
+        "{0} 的校验和不符（应为 {1}，实为 {2}），已放弃更新。",
+        "Checksum mismatch of {0} (expected {1}, got {2}), the update is abandoned.",
+    ),
+    (
+        "self-update.no-asset",
+        "发布 {1} 中没有本平台的二进制文件 {0}。",
+        "Release {1} has no binary {0} for this platform.",
+    ),
+    (
+        "self-update.no-checksums",
+        "发布 {0} 中没有其二进制文件的校验和，无法校验下载的文件。",
+        "Release {0} has no checksums of its binaries to verify the download with.",
+    ),
+    (
+        "self-update.no-exe",
+        "无法找到正在运行的 H2O2 可执行文件。",
+        "Failed to locate the running executable of H2O2.",
+    ),
+    ("self-update.request-failed", "请求 {0} 失败。", "Failed to request {0}."),
+    ("self-update.up-to-date", "H2O2 {0} 已是最新版本。", "H2O2 {0} is up to date."),
+    ("self-update.updated", "H2O2 已从 {0} 更新到 {1}。", "H2O2 has been updated from {0} to {1}."),
+    // service
+    (
+        "service.cannot-stop",
+        "{0} 不以服务运行，仅可停止 Hydro、sandbox、MinIO 与 MongoDB。",
+        "{0} does not run as a service. Only Hydro, sandbox, MinIO and MongoDB can be stopped.",
+    ),
+    (
+        "service.external",
+        "profile 中已为 {0} 配置外部地址，跳过。",
+        "An external endpoint of {0} is configured in the profile, skipped.",
+    ),
+    (
+        "service.failed",
+        "管理 {0} 的 systemd 服务失败！",
+        "Failed to manage the systemd service of {0}!",
+    ),
+    (
+        "service.installed",
+        "{0} 已由 systemd 管理，开机时自动启动：{1}",
+        "{0} is managed by systemd and started on boot: {1}",
+    ),
+    (
+        "service.kill-timeout",
+        "{0} 启动时设置的 PM2 将在 {1} 秒后强制结束它，而非 {2} 秒。重启 Hydro 后 profile.stop_timeout 才会生效，--timeout 对其无效。",
+        "PM2 kills {0} after {1} seconds as set when it started, rather than {2} seconds. profile.stop_timeout applies once Hydro restarts, while --timeout does not apply to it.",
+    ),
+    (
+        "service.killed",
+        "{0} 未在 {1} 秒内退出，已强制结束。",
+        "{0} did not exit within {1} seconds, killed.",
+    ),
+    (
+        "service.not-found",
+        "{0} 没有 H2O2 写入的 systemd 单元，跳过。",
+        "{0} has no systemd unit written by H2O2, skipped.",
+    ),
+    (
+        "service.not-in-role",
+        "{0} 不在 {1} 角色的服务器上运行，跳过。",
+        "{0} does not run on servers of the {1} role, skipped.",
+    ),
+    (
+        "service.not-installed",
+        "{0} 未安装，跳过。",
+        "{0} is not installed, skipped.",
+    ),
+    (
+        "service.not-running",
+        "{0} 未在运行。",
+        "{0} is not running.",
+    ),
+    (
+        "service.not-service",
+        "{0} 不以服务运行，仅支持 MongoDB、MinIO、sandbox 与 Hydro。",
+        "{0} does not run as a service. Only MongoDB, MinIO, sandbox and Hydro are supported.",
+    ),
+    (
+        "service.removed",
+        "已停止 {0} 并删除其 systemd 单元：{1}",
+        "Stopped {0} and removed its systemd unit: {1}",
+    ),
+    (
+        "service.running-unmanaged",
+        "{0} 正在运行（PID {1}），但不受 systemd 管理，已仅设为开机启动。请停止该进程后运行 `systemctl start {2}`。",
+        "{0} is running (PID {1}) outside systemd, so it is only enabled on boot. Stop the process, then run `systemctl start {2}`.",
+    ),
+    (
+        "service.stop-unsupported",
+        "无法在此系统上查找 {0} 的进程，请手动停止。",
+        "Processes of {0} cannot be found on this system, please stop it manually.",
+    ),
+    ("service.stopped", "{0} 已停止。", "{0} is stopped."),
+    ("service.stopping", "正在停止 {0}……", "Stopping {0}..."),
+    (
+        "service.systemd-required",
+        "服务由 systemd 管理，而当前的服务管理器为 {0}。",
+        "Services are managed with systemd, while the service manager here is {0}.",
+    ),
+    // status
+    ("status.all-up", "所有组件均在运行。", "All components are running."),
+    (
+        "status.pm2-unavailable",
+        "无法获取 PM2 管理的进程：{0}",
+        "Failed to list the processes managed by PM2: {0}",
+    ),
+    (
+        "status.port-closed",
+        "{0} 正在运行，但无法连接 {1}，请检查其监听地址。",
+        "{0} is running, but {1} cannot be connected, please check the address it listens on.",
+    ),
+    ("status.some-down", "以下组件未在运行：{0}", "Some components are not running: {0}"),
+    // sys
+    ("sys.check-passed", "系统检查通过。", "All system checks passed."),
+    (
+        "sys.clock-drift",
+        "系统时钟偏差 {0} 秒，比赛计时可能不准确，请运行 `h2o2 sys enable-timesync`。",
+        "The system clock is off by {0}s, which affects contest timing. Consider running `h2o2 sys enable-timesync`.",
+    ),
+    (
+        "sys.clock-drift-unknown",
+        "无法获取参考时间，跳过时钟偏差检查。",
+        "Cannot get the reference time, skipped checking clock drift.",
+    ),
+    ("sys.create-dir-failed", "创建目录失败！", "Failed to create directory!"),
+    ("sys.fstab-failed", "写入 /etc/fstab 失败！", "Failed to write /etc/fstab!"),
+    ("sys.invalid-size", "无效的大小：{0}", "Invalid size: {0}"),
+    ("sys.linux-only", "该功能仅支持 Linux。", "This is only supported on Linux."),
+    (
+        "sys.low-memory",
+        "内存较小（{0}），swap 仅有 {1}，构建 Hydro 前端可能失败，建议运行 `h2o2 sys add-swap --size 2G`。",
+        "Memory is low ({0}) with only {1} of swap, building the Hydro UI may fail. Consider running `h2o2 sys add-swap --size 2G`.",
+    ),
+    (
+        "sys.no-package-manager",
+        "未找到包管理器，请手动安装 {0}。",
+        "No package manager is found, please install {0} manually.",
+    ),
+    (
+        "sys.no-service-manager",
+        "未找到支持的服务管理器（systemd 或 OpenRC）。",
+        "No supported service manager (systemd or OpenRC) is found.",
+    ),
+    ("sys.package-install-failed", "安装软件包失败！", "Failed to install the package!"),
+    ("sys.package-installing", "正在使用 {1} 安装 {0}...", "Installing {0} with {1}..."),
+    ("sys.root-required", "请以 root 身份运行。", "Please run as root."),
+    ("sys.swap-creating", "正在创建 swap 文件 {0}（{1}）...", "Creating swap file {0} ({1})..."),
+    (
+        "sys.swap-enabled",
+        "已启用 swap 文件 {0}，当前 swap 共 {1}。",
+        "Swap file {0} is enabled, {1} of swap in total.",
+    ),
+    ("sys.swap-exists", "{0} 已存在。", "{0} already exists."),
+    ("sys.swap-failed", "创建 swap 文件失败！", "Failed to create the swap file!"),
+    (
+        "sys.swap-in-container",
+        "无法在容器中启用 swap，请在宿主机上操作。",
+        "Swap cannot be enabled inside a container, please do it on the host.",
+    ),
+    (
+        "sys.swap-not-persistent",
+        "该 swap 文件重启后不会自动启用，可使用 `--fstab` 写入 /etc/fstab。",
+        "The swap file is not enabled on boot, use `--fstab` to add it to /etc/fstab.",
+    ),
+    ("sys.temp-dir-failed", "创建临时目录失败！", "Failed to create temp directory!"),
+    (
+        "sys.timesync-active",
+        "时间同步服务 {0} 已在运行。",
+        "Time sync service {0} is already running.",
+    ),
+    ("sys.timesync-enabled", "已启用时间同步服务 {0}。", "Time sync service {0} is enabled."),
+    ("sys.timesync-failed", "启用时间同步服务失败！", "Failed to enable time sync service!"),
+    (
+        "sys.timesync-in-container",
+        "容器使用宿主机的时钟，请在宿主机上启用时间同步。",
+        "Containers use the clock of the host, please enable time sync on the host.",
+    ),
+    (
+        "sys.timesync-inactive",
+        "未检测到运行中的时间同步服务（chrony、ntpd 或 systemd-timesyncd），请运行 `h2o2 sys enable-timesync`。",
+        "No time sync service (chrony, ntpd or systemd-timesyncd) is running. Consider running `h2o2 sys enable-timesync`.",
+    ),
+    (
+        "sys.tuning-applied",
+        "已写入 {0} 和 {1}，资源限制将在重新登录或重启服务后生效。",
+        "{0} and {1} are written, the resource limits take effect after logging in again or restarting the services.",
+    ),
+    (
+        "sys.tuning-apply-hint",
+        "运行 `h2o2 sys tune --apply` 以应用推荐值。",
+        "Run `h2o2 sys tune --apply` to apply the recommended values.",
+    ),
+    ("sys.tuning-failed", "应用内核参数失败！", "Failed to apply the kernel parameters!"),
+    (
+        "sys.tuning-in-container",
+        "容器共享宿主机的内核参数，请在宿主机上调整。",
+        "Containers share the kernel parameters of the host, please tune them on the host.",
+    ),
+    (
+        "sys.tuning-not-recommended",
+        "{0} 当前为 {1}，推荐值为 {2}。",
+        "{0} is {1}, while {2} is recommended.",
+    ),
+    (
+        "sys.tuning-not-effective",
+        "以上设置未生效，可能被其他工具覆盖，请手动检查。",
+        "The settings above do not take effect, maybe overridden by other tools. Please check them manually.",
+    ),
+    (
+        "sys.tuning-not-persistent",
+        "未找到 systemd 或 tuned，THP 和 readahead 设置重启后将失效。",
+        "Neither systemd nor tuned is found, the THP and readahead settings will be lost after a reboot.",
+    ),
+    (
+        "sys.tuning-suggest",
+        "运行 `h2o2 sys tune` 查看详情。",
+        "Run `h2o2 sys tune` for details.",
+    ),
+    ("sys.write-failed", "写入 {0} 失败！", "Failed to write {0}!"),
+    // telemetry
+    (
+        "telemetry.disabled",
+        "匿名统计已关闭，不会发送任何数据。",
+        "Anonymous statistics are off, nothing is sent.",
+    ),
+    (
+        "telemetry.enabled",
+        "匿名统计已开启，每次安装后发送至 {0}。",
+        "Anonymous statistics are on, sent to {0} after each install.",
+    ),
+    (
+        "telemetry.no-endpoint",
+        "请通过 `--endpoint` 指定接收统计的 URL。",
+        "Please specify the URL receiving the statistics with `--endpoint`.",
+    ),
+    (
+        "telemetry.sample",
+        "发送的内容示例如下，不包含路径、地址、域名等可识别信息：",
+        "A sample of what is sent follows, which has nothing identifying such as paths, addresses or domains:",
+    ),
+    // uninstall
+    (
+        "uninstall.data-kept",
+        "{0} 的数据保留在 {1}，使用 `--purge` 可一并删除。",
+        "Data of {0} is kept in {1}, use `--purge` to remove it as well.",
+    ),
+    ("uninstall.done", "{0} 已卸载。", "{0} is uninstalled."),
+    ("uninstall.failed", "卸载 {0} 失败。", "Failed to uninstall {0}."),
+    ("uninstall.not-installed", "{0} 未安装，跳过。", "{0} is not installed, skipped."),
+    (
+        "uninstall.not-managed",
+        "{0} 不是由 H2O2 安装的（{1}），请以安装它的方式卸载。",
+        "{0} is not installed by H2O2 ({1}), please remove it the way it was installed.",
+    ),
+    (
+        "uninstall.profile-updated",
+        "已从 ~/.profile 的 PATH 中移除 {0}，重新登录后生效。",
+        "Removed {0} from PATH in ~/.profile, which takes effect after logging in again.",
+    ),
+    ("uninstall.remove-failed", "删除 {0} 失败。", "Failed to remove {0}."),
+    ("uninstall.removed", "已删除 {0}。", "Removed {0}."),
+    (
+        "uninstall.required-by",
+        "{1} 依赖于 {0}，请一并卸载。",
+        "{0} is required by {1}, please uninstall them together.",
+    ),
+    (
+        "uninstall.running",
+        "{0} 正在运行（PID {1}），请先停止。",
+        "{0} is running (PID {1}), please stop it first.",
+    ),
+    // update
+    (
+        "update.already-latest",
+        "{0} {1} 已是 H2O2 安装的最新版本。",
+        "{0} {1} is already the latest release H2O2 installs.",
+    ),
+    (
+        "update.fcv-failed",
+        "设置 featureCompatibilityVersion 为 {0} 失败！",
+        "Failed to set featureCompatibilityVersion to {0}!",
+    ),
+    (
+        "update.fcv-mismatch",
+        "featureCompatibilityVersion 为 {0}，需要先设置为 {1}。",
+        "featureCompatibilityVersion is {0}, it must be set to {1} first.",
+    ),
+    ("update.finished", "更新已完成。", "Update finished."),
+    ("update.hydro-install-failed", "安装 {0} 失败！", "Failed to install {0}!"),
+    ("update.hydro-installed", "已安装 Hydro {0}。", "Hydro {0} is installed."),
+    (
+        "update.invalid-target",
+        "无法从 {0} 升级至 {1}，可用的版本系列：{2}。",
+        "Cannot upgrade from {0} to {1}, available release series: {2}.",
+    ),
+    ("update.mongodb-unhealthy", "MongoDB 状态异常：{0}。", "MongoDB is unhealthy: {0}."),
+    (
+        "update.mongodb-unreachable",
+        "无法连接到 MongoDB，请确认其正在运行。",
+        "Failed to connect to MongoDB, please make sure it is running.",
+    ),
+    (
+        "update.maintenance-kept",
+        "维护模式仍处于开启状态，修复后运行 `h2o2 update --continue` 继续，或运行 `h2o2 hydro maintenance off` 关闭。",
+        "Maintenance mode is kept on. Run `h2o2 update --continue` after fixing the problem, or `h2o2 hydro maintenance off` to turn it off.",
+    ),
+    (
+        "update.invalid-registry",
+        "无效的 npm 源：{0}，请使用 npmjs、npmmirror 或其地址。",
+        "Invalid npm registry: {0}, use npmjs, npmmirror or a URL.",
+    ),
+    ("update.no-plan", "没有未完成的更新计划。", "There is no unfinished update plan."),
+    ("update.nothing", "没有需要更新的组件。", "Nothing to update."),
+    ("update.not-supported", "暂不支持更新 {0}。", "Updating {0} is not supported yet."),
+    ("update.plan", "更新计划：", "Update plan:"),
+    ("update.plan-aborted", "已放弃未完成的更新计划。", "The unfinished update plan is discarded."),
+    ("update.plan-io-failed", "读写更新计划失败！", "Failed to read or write the update plan!"),
+    (
+        "update.plan-pending",
+        "存在未完成的更新计划，请运行 `h2o2 update --continue` 继续，或运行 `h2o2 update --abort` 放弃。",
+        "An unfinished update plan exists, please run `h2o2 update --continue` to resume it or `h2o2 update --abort` to discard it.",
+    ),
+    (
+        "update.reload-failed",
+        "通过 PM2 重启 Hydro 失败！",
+        "Failed to restart Hydro through PM2!",
+    ),
+    (
+        "update.reloading",
+        "正在通过 PM2 {1} {0}...",
+        "Running PM2 {1} on {0}...",
+    ),
+    (
+        "update.restart-required",
+        "请重启 {0}（或重启系统），然后运行 `h2o2 update --continue` 继续更新。",
+        "Please restart {0} (or reboot), then run `h2o2 update --continue` to resume the update.",
+    ),
+    (
+        "update.series-skipped",
+        "不能从 {0} 直接升级至 {1}，MongoDB 必须逐个版本系列升级。",
+        "Cannot upgrade from {0} to {1} directly, MongoDB must be upgraded one release series at a time.",
+    ),
+    (
+        "update.step-failed",
+        "更新步骤失败，请在修复问题后运行 `h2o2 update --continue` 重试。",
+        "The update step failed, please fix the problem and run `h2o2 update --continue` to retry.",
+    ),
+    (
+        "update.to-single",
+        "--to 仅可用于单个组件。",
+        "--to can only be used with a single component.",
+    ),
+    ("update.ui-build-failed", "构建 Hydro 前端失败！", "Failed to build the Hydro UI!"),
+    (
+        "update.ui-build-oom",
+        "构建过程内存不足，请运行 `h2o2 sys add-swap` 添加 swap 后运行 `h2o2 update --continue` 重试。",
+        "The build ran out of memory, please add swap with `h2o2 sys add-swap`, then retry with `h2o2 update --continue`.",
+    ),
+    ("update.ui-building", "正在构建前端...", "Building the UI..."),
+    ("update.ui-built", "前端构建完成。", "The UI is built."),
+    (
+        "update.ui-not-found",
+        "未找到 Hydro 前端或其构建脚本（{0}）。",
+        "The Hydro UI or its build script is not found ({0}).",
+    ),
+    ("update.upgraded", "已更新 {0} 至 {1}。", "{0} is upgraded to {1}."),
+    ("update.up-to-date", "{0} 已是 {1} 系列。", "{0} is already on the {1} series."),
+    (
+        "update.version-unknown",
+        "无法识别已安装的 {0} 的版本，请先运行 `h2o2 detect`。",
+        "The version of the installed {0} is unknown, please run `h2o2 detect` first.",
+    ),
+    // verify
+    (
+        "verify.checksum-differs",
+        "校验值为 {0}，安装时记录为 {1}",
+        "checksum {0}, recorded {1} when installed",
+    ),
+    (
+        "verify.config-differs",
+        "与当前配置生成的内容不同，可运行 `h2o2 service install` 重新生成",
+        "differs from what the profile generates, run `h2o2 service install` to write it again",
+    ),
+    ("verify.passed", "所有文件均未被改动。", "Nothing has drifted."),
+    (
+        "verify.problems-found",
+        "改动或缺失的文件：{0} 个。",
+        "Files drifted or missing: {0}.",
+    ),
+    ("verify.status-drifted", "已改动", "drifted"),
+    ("verify.status-missing", "缺失", "missing"),
+    ("verify.status-ok", "正常", "ok"),
+    ("verify.status-unrecorded", "无记录", "unrecorded"),
+    (
+        "verify.unit-differs",
+        "与 `h2o2 service install` 写入的内容不同",
+        "differs from what `h2o2 service install` writes",
+    ),
+    ("verify.unit-missing", "引用的 {0} 不存在", "{0} referred to does not exist"),
+    // watch
+    (
+        "watch.db-growth",
+        "{0} 数据目录在 {2} 分钟内增长了 {1}，当前为 {3}。",
+        "The data dir of {0} grew by {1} in {2} minutes, and is {3} now.",
+    ),
+    (
+        "watch.disk-low",
+        "{0} 所在磁盘仅剩 {1} 可用空间。",
+        "Only {1} is free on the disk of {0}.",
+    ),
+    (
+        "watch.memory-low",
+        "可用内存仅剩 {0}（共 {1}）。",
+        "Only {0} of {1} memory is available.",
+    ),
+    (
+        "watch.memory-pressure",
+        "最近一分钟内有 {0}% 的时间因等待内存而停顿。",
+        "Tasks stalled waiting for memory for {0}% of the last minute.",
+    ),
+    (
+        "watch.mirrors-ranked",
+        "已重新测试下载源并保存排名。",
+        "The mirrors are probed again, and the ranking is saved.",
+    ),
+    (
+        "watch.mirrors-unreachable",
+        "{0} 的下载源均不可用：{1}",
+        "No mirror of {0} is reachable: {1}",
+    ),
+    (
+        "watch.no-hooks",
+        "未配置通知（.h2o2config 中的 notify.command 或 notify.webhook），告警仅会写入日志。",
+        "No notification is configured (notify.command or notify.webhook in .h2o2config), alerts are only logged.",
+    ),
+    ("watch.resolved", "告警已恢复：{0}", "Alert resolved: {0}"),
+    (
+        "watch.started",
+        "开始监控资源用量，每 {0} 秒检查一次。",
+        "Watching resource usage, checking every {0} seconds.",
+    ),
+];
+
+/// Looks up `key` in the message table and renders it in the selected language, replacing
+/// each `{}` with the next argument and each `{n}` with the n-th one.
+///
+/// A bilingual message is rendered as `中文 English`. Unknown keys are rendered as is.
+pub fn tr(key: &str, args: &[&dyn Display]) -> String {
+    let (zh, en) = match MESSAGES.iter().find(|(k, _, _)| *k == key) {
+        Some((_, zh, en)) => (*zh, *en),
+        None => {
+            tracing::debug!("Missing message `{}`", key);
+            (key, key)
+        }
+    };
+    match RENDERING.with(Cell::get).unwrap_or_else(lang) {
+        Lang::Zh => render(zh, args),
+        Lang::En => render(en, args),
+        Lang::Both => format!(
+            "{} {}",
+            render_in(Lang::Zh, zh, args),
+            render_in(Lang::En, en, args)
+        ),
+    }
+}
+
+fn render_in(lang: Lang, template: &str, args: &[&dyn Display]) -> String {
+    let prev = RENDERING.with(|r| r.replace(Some(lang)));
+    let text = render(template, args);
+    RENDERING.with(|r| r.set(prev));
+    text
+}
+
+fn render(template: &str, args: &[&dyn Display]) -> String {
+    let mut text = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = match rest.find('}') {
+            Some(end) => end,
+            None => break,
+        };
+        // `{}` takes the next argument, `{n}` takes the n-th one
+        let index = match &rest[1..end] {
+            "" => {
+                next += 1;
+                Some(next - 1)
+            }
+            n => n.parse::<usize>().ok(),
+        };
+        match index.and_then(|i| args.get(i)) {
+            Some(arg) => text.push_str(&arg.to_string()),
+            None => text.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    text.push_str(rest);
+    text
+}
+
+/// Renders a message from the message table, e.g. `tr!("install.require", com)`.
+#[macro_export]
+macro_rules! tr {
+    ($key:expr $(, $arg:expr)* $(,)?) => {
+        $crate::i18n::tr($key, &[$(&$arg as &dyn ::std::fmt::Display),*])
+    };
+}
+
+/// Logs a message from the message table, optionally tagged with a component, e.g.
+/// `log_i18n!(info, [com], "install.downloading")` logs `[Node.js] 开始下载... Downloading...`.
+#[macro_export]
+macro_rules! log_i18n {
+    ($level:ident, [$tag:expr], $key:expr $(, $arg:expr)* $(,)?) => {
+        ::tracing::$level!("[{}] {}", $tag, $crate::tr!($key $(, $arg)*))
+    };
+    ($level:ident, $key:expr $(, $arg:expr)* $(,)?) => {
+        ::tracing::$level!("{}", $crate::tr!($key $(, $arg)*))
+    };
+}
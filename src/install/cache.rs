@@ -0,0 +1,45 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{config, utils::sha256_file};
+
+/// Directory of the downloads kept for later installs.
+pub fn downloads_path() -> PathBuf {
+    config::get_cache_path().join("downloads")
+}
+
+/// Path of the cached download `filename` with the SHA-256 `checksum`. Keyed by the checksum
+/// rather than the URL, the same file is found whichever mirror it was downloaded from.
+fn entry(checksum: &str, filename: &str) -> PathBuf {
+    downloads_path().join(checksum).join(filename)
+}
+
+/// Copies the cached download `filename` to `target` if its content still has `checksum`.
+/// A corrupted entry is removed. Returns whether it is copied.
+pub fn restore(checksum: &str, filename: &str, target: &Path) -> io::Result<bool> {
+    let path = entry(checksum, filename);
+    if !path.is_file() {
+        return Ok(false);
+    }
+    if sha256_file(&path)? != checksum {
+        tracing::debug!("Removing corrupted cache entry {}", path.display());
+        fs::remove_file(&path)?;
+        return Ok(false);
+    }
+    fs::copy(&path, target)?;
+    Ok(true)
+}
+
+/// Keeps a copy of `path`, verified to have `checksum`, as the download `filename`.
+pub fn store(path: &Path, checksum: &str, filename: &str) -> io::Result<()> {
+    let target = entry(checksum, filename);
+    let dir = target.parent().unwrap();
+    fs::create_dir_all(dir)?;
+    // a partial copy must never be taken as an entry
+    let partial = dir.join(format!(".{}.{}", filename, process::id()));
+    fs::copy(path, &partial)?;
+    fs::rename(&partial, &target)
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use tokio::sync::watch;
+
+use super::Com;
+use crate::config::ComponentInfo;
+
+/// Whether a component others depend on is installed.
+#[derive(Debug, Clone)]
+pub enum Signal {
+    Pending,
+    Ready(ComponentInfo),
+    Failed,
+}
+
+/// The components of an installation, each with a channel telling those depending on it, as in
+/// [`Com::dependencies`], whether it is installed. The channels keep the last signal, so a
+/// component may start waiting before or after its dependencies are done.
+pub struct TaskGraph {
+    channels: HashMap<Com, (watch::Sender<Signal>, watch::Receiver<Signal>)>,
+}
+
+impl TaskGraph {
+    pub fn new() -> Self {
+        Self {
+            channels: Com::ALL
+                .iter()
+                .map(|&com| (com, watch::channel(Signal::Pending)))
+                .collect(),
+        }
+    }
+
+    /// Tells the components depending on `com` that it is installed as `info`.
+    pub fn ready(&self, com: Com, info: ComponentInfo) {
+        self.send(com, Signal::Ready(info));
+    }
+
+    /// Tells the components depending on `com` that it cannot be installed.
+    pub fn failed(&self, com: Com) {
+        self.send(com, Signal::Failed);
+    }
+
+    fn send(&self, com: Com, signal: Signal) {
+        // never fails, as the graph keeps a receiver
+        let _ = self.channels[&com].0.send(signal);
+    }
+
+    /// Fails the components neither ready nor `scheduled`, so that nothing waits for them.
+    pub fn seal(&self, scheduled: &[Com]) {
+        for (&com, (_, rx)) in &self.channels {
+            let pending = matches!(*rx.borrow(), Signal::Pending);
+            if pending && !scheduled.contains(&com) {
+                self.failed(com);
+            }
+        }
+    }
+
+    /// What `com` waits for before being installed.
+    pub fn dependencies(&self, com: Com) -> Dependencies {
+        Dependencies(
+            com.dependencies()
+                .iter()
+                .map(|dep| (*dep, self.channels[dep].1.clone()))
+                .collect(),
+        )
+    }
+
+    /// Sorts `coms` so that each comes after those it depends on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the dependencies form a cycle.
+    pub fn order(coms: &[Com]) -> Vec<Com> {
+        let mut rest = coms.to_vec();
+        let mut sorted = Vec::with_capacity(rest.len());
+        while !rest.is_empty() {
+            let (free, blocked): (Vec<_>, Vec<_>) = rest
+                .iter()
+                .partition(|com| com.dependencies().iter().all(|dep| !rest.contains(dep)));
+            assert!(!free.is_empty(), "Dependency cycle among {:?}", rest);
+            sorted.extend(free);
+            rest = blocked;
+        }
+        sorted
+    }
+}
+
+impl Default for TaskGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The channels of the components one depends on.
+pub struct Dependencies(Vec<(Com, watch::Receiver<Signal>)>);
+
+impl Dependencies {
+    /// Waits for every dependency to be installed, returning their info in the order of
+    /// [`Com::dependencies`], or the first one failed.
+    pub async fn wait(self) -> Result<Vec<ComponentInfo>, Com> {
+        let mut infos = Vec::with_capacity(self.0.len());
+        for (com, mut rx) in self.0 {
+            loop {
+                let signal = rx.borrow().clone();
+                match signal {
+                    Signal::Ready(info) => {
+                        infos.push(info);
+                        break;
+                    }
+                    Signal::Failed => return Err(com),
+                    Signal::Pending => {
+                        if rx.changed().await.is_err() {
+                            return Err(com);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(infos)
+    }
+}
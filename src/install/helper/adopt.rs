@@ -0,0 +1,31 @@
+use std::{fs, io, path::Path};
+
+/// Settings of a file written by hand, sorted out when H2O2 takes it over.
+#[derive(Debug, Default)]
+pub struct Adoption {
+    /// settings imported into the profile, e.g. `net.port = 27018`
+    pub imported: Vec<String>,
+    /// settings H2O2 does not manage, which are dropped once it rewrites the file
+    pub dropped: Vec<String>,
+}
+
+/// Fails if the file at `path` exists but is not written by H2O2, as told by `is_generated`, so
+/// that a hand-crafted one is adopted by `h2o2 adopt <name>` rather than overwritten.
+pub fn ensure_generated(path: &Path, name: &str, is_generated: fn(&str) -> bool) -> io::Result<()> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if is_generated(&text) {
+        return Ok(());
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!(
+            "{} is not written by H2O2, run `h2o2 adopt {}` to import its settings first",
+            path.display(),
+            name
+        ),
+    ))
+}
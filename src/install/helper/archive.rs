@@ -0,0 +1,127 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, Read},
+    path::{Component, Path, PathBuf},
+};
+
+/// Compression of a tarball, told by its file name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    pub fn from_name(name: &str) -> Option<Self> {
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Compression::Gzip)
+        } else if name.ends_with(".tar.xz") {
+            Some(Compression::Xz)
+        } else if name.ends_with(".tar.zst") {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    fn decoder<'a>(self, reader: impl Read + 'a) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            Compression::Gzip => Box::new(flate2::read::GzDecoder::new(reader)),
+            Compression::Xz => Box::new(xz2::read::XzDecoder::new(reader)),
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+        })
+    }
+}
+
+/// Extracts the tarball `path` into `target` in process, dropping the first `strip` components
+/// of the paths like `tar --strip-components` does.
+///
+/// Entries leaving `target`, e.g. by `..` or through a symlink extracted before, are rejected
+/// rather than extracted.
+pub fn extract(path: impl AsRef<Path>, target: impl AsRef<Path>, strip: usize) -> io::Result<()> {
+    let path = path.as_ref();
+    let target = target.as_ref();
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let compression = Compression::from_name(&name).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a supported tarball", name),
+        )
+    })?;
+    let reader = compression.decoder(BufReader::new(File::open(path)?))?;
+    let mut archive = tar::Archive::new(reader);
+    archive.set_preserve_permissions(true);
+    archive.set_preserve_mtime(true);
+    fs::create_dir_all(target)?;
+    let target_real = target.canonicalize()?;
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let rel = match stripped(&entry.path()?, strip)? {
+            Some(rel) => rel,
+            None => continue,
+        };
+        let dest = target.join(&rel);
+        ensure_inside(&target_real, &dest)?;
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // hard links name another entry of the archive, which is stripped likewise
+        if entry.header().entry_type().is_hard_link() {
+            let link = match entry.link_name()? {
+                Some(link) => link.into_owned(),
+                None => continue,
+            };
+            if let Some(link) = stripped(&link, strip)? {
+                ensure_inside(&target_real, &target.join(&link))?;
+                let _ = fs::remove_file(&dest);
+                fs::hard_link(target.join(link), &dest)?;
+            }
+            continue;
+        }
+        entry.unpack(&dest)?;
+    }
+    Ok(())
+}
+
+/// Fails if the parent of `dest` resolves outside `target`, which is canonical, like
+/// `tar::Entry::unpack_in` checks. The nearest ancestor existing is resolved, so that no dir is
+/// created through a symlink leaving `target` either.
+fn ensure_inside(target: &Path, dest: &Path) -> io::Result<()> {
+    let mut dir = dest.parent();
+    while let Some(path) = dir {
+        match path.canonicalize() {
+            Ok(real) if real.starts_with(target) => return Ok(()),
+            Ok(_) => break,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => dir = path.parent(),
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} leaves the target dir", dest.display()),
+    ))
+}
+
+/// `path` without the first `strip` components, or `None` if nothing is left.
+fn stripped(path: &Path, strip: usize) -> io::Result<Option<PathBuf>> {
+    let mut rel = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => rel.push(part),
+            Component::CurDir => {}
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} leaves the target dir", path.display()),
+                ))
+            }
+        }
+    }
+    let rel = rel.components().skip(strip).collect::<PathBuf>();
+    Ok(Some(rel).filter(|rel| !rel.as_os_str().is_empty()))
+}
@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::utils::{run_capped, CappedOutput};
+
+/// The offline manifest listing the artifacts of a bundle.
+pub const MANIFEST: &str = "manifest.json";
+
+const META: &str = "bundle.json";
+
+/// What a bundle records about the packages it holds.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Meta {
+    /// registry the npm cache was filled from, by which npm looks the packages up
+    pub registry: String,
+
+    /// Hydro resolved by the lockfile of the bundle, e.g. `hydrooj@^4.5.0`
+    pub hydro: String,
+}
+
+/// A bundle made by `h2o2 bundle create` for air-gapped servers, laid out as
+///
+/// - `manifest.json`: the offline manifest of the artifacts next to it
+/// - `bundle.json`: the [`Meta`] of the packages
+/// - `npm-cache/`: cache of npm holding Yarn and PM2
+/// - `hydro/`: `package.json` and `yarn.lock` resolving Hydro
+/// - `yarn-mirror/`: offline mirror of Yarn holding the packages in the lockfile
+#[derive(Debug, Clone)]
+pub struct Bundle {
+    dir: PathBuf,
+}
+
+impl Bundle {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        // absolute, as Yarn resolves the mirror against the dir it runs in
+        let dir = std::path::absolute(&dir).unwrap_or_else(|_| dir.as_ref().to_owned());
+        Self { dir }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn manifest(&self) -> PathBuf {
+        self.dir.join(MANIFEST)
+    }
+
+    pub fn npm_cache(&self) -> PathBuf {
+        self.dir.join("npm-cache")
+    }
+
+    pub fn hydro_dir(&self) -> PathBuf {
+        self.dir.join("hydro")
+    }
+
+    pub fn yarn_mirror(&self) -> PathBuf {
+        self.dir.join("yarn-mirror")
+    }
+
+    pub fn meta(&self) -> io::Result<Meta> {
+        let text = fs::read_to_string(self.dir.join(META))?;
+        serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save_meta(&self, meta: &Meta) -> io::Result<()> {
+        let text = serde_json::to_string_pretty(meta)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(self.dir.join(META), text)
+    }
+
+    /// Installs the global `package` with `npm` from the cache of the bundle only.
+    pub fn npm_install(&self, npm: &str, package: &str) -> io::Result<CappedOutput> {
+        let meta = self.meta()?;
+        let cache = self.npm_cache();
+        run_capped(&duct::cmd!(
+            npm,
+            "install",
+            "--global",
+            package,
+            "--offline",
+            "--cache",
+            cache,
+            "--registry",
+            meta.registry
+        ))
+    }
+
+    /// Runs `args` of Yarn 1 by `program` offline, fetching the packages from the mirror of
+    /// the bundle.
+    pub fn yarn_offline(&self, program: &str, args: &[String]) -> io::Result<CappedOutput> {
+        // Yarn 1 reads `YARN_*` variables as its config, e.g. `yarn-offline-mirror`
+        run_capped(
+            &duct::cmd(
+                program,
+                args.iter().map(String::as_str).chain(["--offline"]),
+            )
+            .env("YARN_YARN_OFFLINE_MIRROR", self.yarn_mirror()),
+        )
+    }
+
+    /// Puts the lockfile resolving Hydro into `global_dir` of Yarn, so that `yarn global add`
+    /// finds the packages in the mirror. An existing lockfile is kept, and `false` is returned.
+    pub fn seed_global_dir(&self, global_dir: &Path) -> io::Result<bool> {
+        if global_dir.join("yarn.lock").exists() {
+            return Ok(false);
+        }
+        fs::create_dir_all(global_dir)?;
+        for file in ["package.json", "yarn.lock"] {
+            fs::copy(self.hydro_dir().join(file), global_dir.join(file))?;
+        }
+        Ok(true)
+    }
+}
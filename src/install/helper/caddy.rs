@@ -0,0 +1,186 @@
+use duct::cmd;
+use std::{
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::{self, CaddyProfile},
+    utils::{decode_output, parse_http_date},
+};
+
+/// Served with status 503 in maintenance mode, in one line as Caddyfile strings have no escapes
+/// other than `\"`.
+const MAINTENANCE_PAGE: &str =
+    "系统维护中，请稍后再试。 Hydro is under maintenance, please come back later.";
+
+/// Seconds clients are told to wait in maintenance mode.
+const MAINTENANCE_RETRY_AFTER: u32 = 300;
+
+pub fn get_caddy_path() -> PathBuf {
+    config::get_com_path().join("caddy")
+}
+
+pub fn caddyfile_path() -> PathBuf {
+    get_caddy_path().join("Caddyfile")
+}
+
+/// Where custom certificates are copied to.
+pub fn tls_path() -> PathBuf {
+    get_caddy_path().join("tls")
+}
+
+/// Writes the Caddyfile serving Hydro on the domain and returns its path.
+///
+/// Without a custom certificate, Caddy obtains one from Let's Encrypt by itself, answering the
+/// HTTP-01 challenge on port 80. In maintenance mode, a page telling so is served instead.
+pub fn write_caddyfile(profile: &CaddyProfile) -> io::Result<PathBuf> {
+    let domain = profile
+        .domain
+        .as_deref()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "`domain` is not set"))?;
+
+    let mut content = String::from("# Written by H2O2 (`h2o2 cert`)\n");
+    if let Some(email) = &profile.email {
+        writeln!(content, "{{\n\temail {}\n}}\n", email).unwrap();
+    }
+    writeln!(content, "{} {{", domain).unwrap();
+    if let (Some(cert), Some(key)) = (&profile.tls_cert_file, &profile.tls_key_file) {
+        writeln!(content, "\ttls {} {}", cert, key).unwrap();
+    }
+    if profile.maintenance {
+        writeln!(
+            content,
+            "\theader Retry-After {}\n\trespond \"{}\" 503\n}}",
+            MAINTENANCE_RETRY_AFTER, MAINTENANCE_PAGE
+        )
+        .unwrap();
+    } else {
+        writeln!(content, "\treverse_proxy {}\n}}", &profile.upstream).unwrap();
+    }
+
+    let path = caddyfile_path();
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(&path, content)?;
+    Ok(path)
+}
+
+/// Makes the running Caddy load the Caddyfile through its admin API.
+pub fn reload(caddy: &str) -> io::Result<()> {
+    let output = cmd!(
+        caddy,
+        "reload",
+        "--config",
+        caddyfile_path(),
+        "--adapter",
+        "caddyfile"
+    )
+    .stdout_capture()
+    .stderr_capture()
+    .unchecked()
+    .run()?;
+    if !output.status.success() {
+        return Err(io::Error::other(
+            decode_output(&output.stderr).trim().to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+/// Details of a certificate read by `openssl`.
+#[derive(Debug, Clone)]
+pub struct CertInfo {
+    pub issuer: String,
+    /// seconds since the Unix epoch
+    pub not_after: u64,
+}
+
+/// Reads the certificate Caddy is serving for `domain` on this host.
+pub fn served_cert(domain: &str) -> io::Result<CertInfo> {
+    let output = cmd!(
+        "openssl",
+        "s_client",
+        "-connect",
+        "localhost:443",
+        "-servername",
+        domain
+    )
+    .stdin_null()
+    .stderr_null()
+    .pipe(cmd!("openssl", "x509", "-noout", "-enddate", "-issuer"))
+    .stdout_capture()
+    .stderr_capture()
+    .unchecked()
+    .run()?;
+    parse_cert_info(&output)
+}
+
+/// Reads the (first) certificate in a PEM file.
+pub fn file_cert(path: impl AsRef<Path>) -> io::Result<CertInfo> {
+    let output = cmd!(
+        "openssl",
+        "x509",
+        "-noout",
+        "-enddate",
+        "-issuer",
+        "-in",
+        path.as_ref()
+    )
+    .stdout_capture()
+    .stderr_capture()
+    .unchecked()
+    .run()?;
+    parse_cert_info(&output)
+}
+
+/// Whether the private key belongs to the certificate, by comparing their public keys.
+pub fn key_matches(cert: impl AsRef<Path>, key: impl AsRef<Path>) -> io::Result<bool> {
+    let cert_pub = cmd!("openssl", "x509", "-noout", "-pubkey", "-in", cert.as_ref())
+        .stdout_capture()
+        .stderr_null()
+        .run()?;
+    let key_pub = cmd!("openssl", "pkey", "-pubout", "-in", key.as_ref())
+        .stdout_capture()
+        .stderr_null()
+        .run()?;
+    Ok(cert_pub.stdout == key_pub.stdout)
+}
+
+/// Parses the output of `openssl x509 -noout -enddate -issuer`, which looks like
+/// `notAfter=Jan  1 00:00:00 2027 GMT` and `issuer=C = US, O = Let's Encrypt, CN = R3`.
+fn parse_cert_info(output: &std::process::Output) -> io::Result<CertInfo> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "failed to read the certificate: {}",
+                decode_output(&output.stderr).trim()
+            ),
+        )
+    };
+    if !output.status.success() {
+        return Err(invalid());
+    }
+    let stdout = decode_output(&output.stdout);
+    let field = |name: &str| {
+        stdout
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+            .map(str::trim)
+    };
+    let issuer = field("issuer").ok_or_else(invalid)?.to_owned();
+    let not_after = field("notAfter")
+        .and_then(|date| {
+            // reorder into an HTTP date like `Fri, 01 Jan 2027 00:00:00 GMT`
+            let parts = date.split_whitespace().collect::<Vec<_>>();
+            match parts.as_slice() {
+                [month, day, time, year, ..] => {
+                    parse_http_date(&format!("_, {} {} {} {} GMT", day, month, year, time))
+                }
+                _ => None,
+            }
+        })
+        .ok_or_else(invalid)?;
+    Ok(CertInfo { issuer, not_after })
+}
@@ -0,0 +1,65 @@
+use semver::{Version, VersionReq};
+use std::io;
+
+use crate::{
+    config::ComponentInfo,
+    platform,
+    utils::{debug_output, decode_output},
+};
+
+/// Yarn pinned for each range of Node.js, as Hydro requires Yarn 1. Corepack is shipped with
+/// Node.js from 14.19 and 16.9 on, and no longer since 25.
+const YARN_MATRIX: &[(&str, &str)] = &[(">=16.9, <25", "1.22.22"), (">=14.19, <15", "1.22.19")];
+
+/// The version of Yarn to pin with corepack of Node.js `node`, or `None` if it ships no
+/// corepack.
+pub fn yarn_version(node: &Version) -> Option<&'static str> {
+    YARN_MATRIX
+        .iter()
+        .find(|(req, _)| {
+            VersionReq::parse(req)
+                .expect("Incorrect version requirement pattern")
+                .matches(node)
+        })
+        .map(|(_, yarn)| *yarn)
+}
+
+/// Enables the `yarn` shim of corepack in the bin dir of Node.js and activates Yarn `version`
+/// globally. Returns the path of the shim.
+pub fn enable_yarn(nodejs: &ComponentInfo, version: &str) -> io::Result<String> {
+    let corepack = nodejs.path(&platform::cmd("corepack"));
+    run(&corepack, &["enable", "yarn"])?;
+    run(
+        &corepack,
+        &["prepare", &format!("yarn@{}", version), "--activate"],
+    )?;
+    Ok(nodejs.path(&platform::cmd("yarn")))
+}
+
+/// Removes the `yarn` shim of corepack.
+pub fn disable_yarn(nodejs: &ComponentInfo) -> io::Result<()> {
+    run(
+        &nodejs.path(&platform::cmd("corepack")),
+        &["disable", "yarn"],
+    )
+}
+
+fn run(corepack: &str, args: &[&str]) -> io::Result<()> {
+    let output = duct::cmd(corepack, args)
+        // download without asking, as nobody is there to answer
+        .env("COREPACK_ENABLE_DOWNLOAD_PROMPT", "0")
+        .stdout_capture()
+        .stderr_capture()
+        .unchecked()
+        .run()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        debug_output(&output);
+        Err(io::Error::other(format!(
+            "`corepack {}` failed: {}",
+            args.join(" "),
+            decode_output(&output.stderr).trim()
+        )))
+    }
+}
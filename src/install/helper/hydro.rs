@@ -0,0 +1,223 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    jspm,
+    npm::{NPMJS_REGISTRY, NPMMIRROR_REGISTRY},
+};
+use crate::{config::HydroChannel, net, profiling, utils::decode_output};
+
+pub const PACKAGE: &str = "hydrooj";
+
+pub fn get_hydro_path() -> PathBuf {
+    dirs::home_dir()
+        .expect("Failed to get home dir")
+        .join(".hydro")
+}
+
+pub fn judge_config_path() -> PathBuf {
+    get_hydro_path().join("judge.yaml")
+}
+
+pub fn config_path() -> PathBuf {
+    get_hydro_path().join("config.json")
+}
+
+/// Sets top-level `key: value` entries in `~/.hydro/judge.yaml`, keeping other lines untouched.
+pub fn update_judge_config(entries: &[(&str, &str)]) -> io::Result<()> {
+    fs::create_dir_all(get_hydro_path())?;
+    let path = judge_config_path();
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+
+    let mut lines = text.lines().map(ToOwned::to_owned).collect::<Vec<_>>();
+    for (key, value) in entries {
+        let prefix = format!("{}:", key);
+        let line = format!("{} {}", prefix, value);
+        match lines.iter().position(|l| l.starts_with(&prefix)) {
+            Some(pos) => lines[pos] = line,
+            None => lines.push(line),
+        }
+    }
+
+    fs::write(&path, lines.join("\n") + "\n")?;
+    // it holds the passwords of the judge accounts
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+/// Sets the host `name` under `hosts` in `~/.hydro/judge.yaml`, which a standalone judge fetches
+/// submissions from as the judge account `uname`, keeping other lines untouched.
+pub fn set_judge_host(name: &str, server_url: &str, uname: &str, password: &str) -> io::Result<()> {
+    fs::create_dir_all(get_hydro_path())?;
+    let path = judge_config_path();
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+
+    let mut lines = text.lines().map(ToOwned::to_owned).collect::<Vec<_>>();
+    let entry = vec![
+        format!("  {}:", name),
+        "    type: hydro".to_owned(),
+        format!("    server_url: {}", server_url),
+        format!("    uname: {}", uname),
+        format!("    password: {}", password),
+        "    detail: true".to_owned(),
+    ];
+    let hosts = match lines.iter().position(|l| l.trim_end() == "hosts:") {
+        Some(pos) => pos,
+        None => {
+            lines.push("hosts:".to_owned());
+            lines.len() - 1
+        }
+    };
+    // the block of `hosts` ends at the next top-level line
+    let end = lines[hosts + 1..]
+        .iter()
+        .position(|l| !l.is_empty() && !l.starts_with(' '))
+        .map_or(lines.len(), |i| hosts + 1 + i);
+    let key = format!("  {}:", name);
+    match lines[hosts + 1..end].iter().position(|l| *l == key) {
+        Some(i) => {
+            let start = hosts + 1 + i;
+            let stop = lines[start + 1..end]
+                .iter()
+                .position(|l| !l.starts_with("    "))
+                .map_or(end, |j| start + 1 + j);
+            lines.splice(start..stop, entry);
+        }
+        None => {
+            lines.splice(end..end, entry);
+        }
+    }
+
+    fs::write(&path, lines.join("\n") + "\n")
+}
+
+/// Runs `hydrooj cli` of Hydro installed in `global_dir` with `node`, e.g. `user setJudge 2`,
+/// returning what it prints.
+pub fn cli(node: &str, global_dir: &Path, args: &[&str]) -> io::Result<String> {
+    let script = global_dir
+        .join("node_modules")
+        .join(PACKAGE)
+        .join("bin")
+        .join("hydrooj.js");
+    let mut cmd_args = vec![script.into_os_string(), "cli".into()];
+    cmd_args.extend(args.iter().map(Into::into));
+    let output = duct::cmd(node, cmd_args)
+        .stdout_capture()
+        .stderr_capture()
+        .unchecked()
+        .run()?;
+    if !output.status.success() {
+        let stderr = decode_output(&output.stderr);
+        let reason = stderr.lines().last().unwrap_or_default().trim().to_owned();
+        return Err(io::Error::other(format!(
+            "`hydrooj cli {}` failed: {}",
+            args.join(" "),
+            reason
+        )));
+    }
+    Ok(decode_output(&output.stdout))
+}
+
+/// Sets `key` in `~/.hydro/config.json`, keeping other keys untouched.
+pub fn update_config(key: &str, value: serde_json::Value) -> io::Result<()> {
+    fs::create_dir_all(get_hydro_path())?;
+    let path = config_path();
+    let mut config = match fs::read_to_string(&path) {
+        Ok(text) => serde_json::from_str::<serde_json::Map<_, _>>(&text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => serde_json::Map::new(),
+        Err(e) => return Err(e),
+    };
+    config.insert(key.to_owned(), value);
+    let text = serde_json::to_string_pretty(&config)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    fs::write(&path, text)
+}
+
+/// The version of Hydro installed in the global dir `global_dir`.
+pub fn installed_version(global_dir: &Path) -> io::Result<semver::Version> {
+    jspm::package_version(global_dir, PACKAGE)
+}
+
+/// The directory of the default UI package, which is built after Hydro or its addons change.
+pub fn ui_dir(global_dir: &Path) -> PathBuf {
+    global_dir
+        .join("node_modules")
+        .join("@hydrooj")
+        .join("ui-default")
+}
+
+/// The script of the UI package that builds it for production.
+pub fn ui_build_script(ui_dir: &Path) -> io::Result<&'static str> {
+    let package = serde_json::from_str::<serde_json::Value>(&fs::read_to_string(
+        ui_dir.join("package.json"),
+    )?)
+    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    ["build:production", "build"]
+        .iter()
+        .copied()
+        .find(|script| package["scripts"][script].is_string())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no build script is found"))
+}
+
+/// The package to install for `channel`, e.g. `hydrooj@beta`.
+pub fn package_spec(channel: HydroChannel) -> String {
+    format!("{}@{}", PACKAGE, channel.dist_tag())
+}
+
+/// Queries the dist-tags of the npm `package`, e.g. Hydro, trying npmmirror if npmjs is
+/// unreachable.
+pub async fn dist_tags(package: &str) -> io::Result<HashMap<String, String>> {
+    let mut last_err = None;
+    for registry in [NPMJS_REGISTRY, NPMMIRROR_REGISTRY].iter() {
+        let url = format!("{}-/package/{}/dist-tags", registry, package);
+        let text = match fetch_text(&url).await {
+            Ok(text) => text,
+            Err(e) => {
+                tracing::debug!("Failed to fetch {}: {}", url, e);
+                last_err = Some(e);
+                continue;
+            }
+        };
+        return serde_json::from_str(&text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+    }
+    Err(io::Error::other(last_err.unwrap()))
+}
+
+/// The version `channel` points to in `dist_tags`.
+pub fn latest_in(
+    dist_tags: &HashMap<String, String>,
+    channel: HydroChannel,
+) -> Option<semver::Version> {
+    dist_tags
+        .get(channel.dist_tag())
+        .and_then(|version| semver::Version::parse(version).ok())
+}
+
+async fn fetch_text(url: &str) -> reqwest::Result<String> {
+    let _span = profiling::span("main", url);
+    net::client()
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await
+}
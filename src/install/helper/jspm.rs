@@ -0,0 +1,244 @@
+use std::{fmt::Debug, fs, io, path::Path};
+
+use super::npm;
+use crate::{
+    config::{ComponentInfo, Config, PackageManager, Version},
+    platform,
+    utils::{decode_output, CappedOutput},
+    Com,
+};
+
+/// Yarn 1 run through corepack when the installed Yarn is a later one.
+pub const YARN_CLASSIC: &str = "yarn@1.22.22";
+
+/// Whether `version` of Yarn is Yarn 2 or later (Berry), which has no `yarn global`.
+pub fn is_berry(version: &Version) -> bool {
+    matches!(version, Version::Valid(version) if version.major >= 2)
+}
+
+/// A JavaScript package manager, which Hydro and its addons are installed globally with.
+///
+/// Only the arguments differ among them. Commands are run by [`run`], which retries with
+/// another registry on failure.
+pub trait JsPm: Debug + Send + Sync {
+    /// Name shown in messages, e.g. `yarn`.
+    fn name(&self) -> &'static str;
+
+    /// The program run.
+    fn program(&self) -> &str;
+
+    /// Arguments installing `spec` (`package@version`) globally.
+    fn install_global(&self, spec: &str) -> Vec<String>;
+
+    /// Arguments upgrading the global `package` to the dist-tag or version `to`.
+    fn upgrade_global(&self, package: &str, to: &str) -> Vec<String> {
+        self.install_global(&format!("{}@{}", package, to))
+    }
+
+    /// Arguments removing the global `package`.
+    fn remove_global(&self, package: &str) -> Vec<String>;
+
+    /// Arguments running `script` of the package in the working directory.
+    fn run_script(&self, script: &str) -> Vec<String>;
+
+    /// The directory containing `node_modules` of global packages.
+    fn global_dir(&self) -> io::Result<String>;
+}
+
+/// Yarn 1, or Yarn 1 run through corepack if the installed Yarn is Berry.
+#[derive(Debug)]
+pub struct Yarn {
+    program: String,
+    corepack: bool,
+}
+
+impl Yarn {
+    fn args(&self, args: &[&str]) -> Vec<String> {
+        let prefix = if self.corepack {
+            Some(YARN_CLASSIC)
+        } else {
+            None
+        };
+        prefix
+            .into_iter()
+            .chain(args.iter().copied())
+            .map(ToOwned::to_owned)
+            .collect()
+    }
+}
+
+impl JsPm for Yarn {
+    fn name(&self) -> &'static str {
+        if self.corepack {
+            "corepack"
+        } else {
+            "yarn"
+        }
+    }
+
+    fn program(&self) -> &str {
+        &self.program
+    }
+
+    fn install_global(&self, spec: &str) -> Vec<String> {
+        self.args(&["global", "add", spec])
+    }
+
+    fn remove_global(&self, package: &str) -> Vec<String> {
+        self.args(&["global", "remove", package])
+    }
+
+    fn run_script(&self, script: &str) -> Vec<String> {
+        self.args(&["run", script])
+    }
+
+    fn global_dir(&self) -> io::Result<String> {
+        read_line(&self.program, &self.args(&["global", "dir"]))
+    }
+}
+
+#[derive(Debug)]
+pub struct Npm {
+    program: String,
+}
+
+impl JsPm for Npm {
+    fn name(&self) -> &'static str {
+        "npm"
+    }
+
+    fn program(&self) -> &str {
+        &self.program
+    }
+
+    fn install_global(&self, spec: &str) -> Vec<String> {
+        to_args(&["install", "--global", spec])
+    }
+
+    fn remove_global(&self, package: &str) -> Vec<String> {
+        to_args(&["uninstall", "--global", package])
+    }
+
+    fn run_script(&self, script: &str) -> Vec<String> {
+        to_args(&["run", script])
+    }
+
+    fn global_dir(&self) -> io::Result<String> {
+        root_parent(&self.program)
+    }
+}
+
+#[derive(Debug)]
+pub struct Pnpm {
+    program: String,
+}
+
+impl JsPm for Pnpm {
+    fn name(&self) -> &'static str {
+        "pnpm"
+    }
+
+    fn program(&self) -> &str {
+        &self.program
+    }
+
+    fn install_global(&self, spec: &str) -> Vec<String> {
+        to_args(&["add", "--global", spec])
+    }
+
+    fn remove_global(&self, package: &str) -> Vec<String> {
+        to_args(&["remove", "--global", package])
+    }
+
+    fn run_script(&self, script: &str) -> Vec<String> {
+        to_args(&["run", script])
+    }
+
+    fn global_dir(&self) -> io::Result<String> {
+        root_parent(&self.program)
+    }
+}
+
+/// Picks the package manager by `preferred` in the profile. Yarn is used by default unless it
+/// is Berry, in which case npm is used, or Yarn 1 through corepack if Yarn is preferred.
+pub fn select(
+    preferred: PackageManager,
+    nodejs: &ComponentInfo,
+    yarn: &ComponentInfo,
+) -> Box<dyn JsPm> {
+    let berry = is_berry(&yarn.version);
+    match preferred {
+        PackageManager::Auto | PackageManager::Yarn if !berry => Box::new(Yarn {
+            program: yarn.path_or("yarn").to_owned(),
+            corepack: false,
+        }),
+        PackageManager::Yarn => Box::new(Yarn {
+            program: nodejs.path(&platform::cmd("corepack")),
+            corepack: true,
+        }),
+        PackageManager::Auto | PackageManager::Npm => Box::new(Npm {
+            program: nodejs.path(&platform::cmd("npm")),
+        }),
+        PackageManager::Pnpm => Box::new(Pnpm {
+            program: platform::cmd("pnpm"),
+        }),
+    }
+}
+
+/// The package manager managing Hydro of `config`.
+pub fn from_config(config: &Config) -> Box<dyn JsPm> {
+    select(
+        config.profile.hydro.package_manager,
+        &config.components.nodejs,
+        &config.components.yarn,
+    )
+}
+
+/// Runs `args` with `pm`, retrying on failure, against `registry` first if it is given.
+pub async fn run(
+    pm: &dyn JsPm,
+    com: Com,
+    args: &[String],
+    registry: Option<&str>,
+) -> io::Result<CappedOutput> {
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    npm::run_with_registry(com, pm.program(), &args, registry).await
+}
+
+/// The command line of `args`, for messages.
+pub fn display(pm: &dyn JsPm, args: &[String]) -> String {
+    format!("{} {}", pm.name(), args.join(" "))
+}
+
+/// The version of `package` installed in `global_dir` by any of the package managers.
+pub fn package_version(global_dir: &Path, package: &str) -> io::Result<semver::Version> {
+    let path = global_dir
+        .join("node_modules")
+        .join(package)
+        .join("package.json");
+    let package = serde_json::from_str::<serde_json::Value>(&fs::read_to_string(path)?)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let version = package["version"].as_str().unwrap_or_default();
+    semver::Version::parse(version).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn to_args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|&arg| arg.to_owned()).collect()
+}
+
+fn read_line(program: &str, args: &[String]) -> io::Result<String> {
+    let output = duct::cmd(program, args)
+        .stdout_capture()
+        .stderr_null()
+        .run()?;
+    Ok(decode_output(&output.stdout).trim().to_owned())
+}
+
+/// The parent of `{program} root --global`, which prints the `node_modules` of global packages.
+fn root_parent(program: &str) -> io::Result<String> {
+    let root = read_line(program, &to_args(&["root", "--global"]))?;
+    Path::new(&root)
+        .parent()
+        .map(|dir| dir.to_string_lossy().into_owned())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, root))
+}
@@ -1,11 +1,38 @@
-use std::{fs, io, path::Path};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
 
 use super::utils;
-use crate::{config, Com};
+use crate::{
+    config::{self, MinIOProfile},
+    platform, Com,
+};
 
 #[cfg(all(windows, target_arch = "x86"))]
 pub(crate) const BIN_INFO: &str = "";
 
+#[cfg(all(windows, target_arch = "x86"))]
+pub(crate) const MC_BIN_INFO: &str = "";
+
+#[cfg(all(windows, target_arch = "x86_64"))]
+pub(crate) const MC_BIN_INFO: &str = "windows-amd64/mc.exe";
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+pub(crate) const MC_BIN_INFO: &str = "linux-amd64/mc";
+
+#[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+pub(crate) const MC_BIN_INFO: &str = "linux-arm64/mc";
+
+#[cfg(all(target_os = "macos", target_arch = "x86_64"))]
+pub(crate) const MC_BIN_INFO: &str = "darwin-amd64/mc";
+
+#[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+pub(crate) const MC_BIN_INFO: &str = "darwin-arm64/mc";
+
+/// Alias of the local MinIO instance in `mc`.
+pub const MC_ALIAS: &str = "h2o2";
+
 #[cfg(all(windows, target_arch = "x86_64"))]
 pub(crate) const BIN_INFO: &str = "windows-amd64/minio.exe";
 
@@ -21,19 +48,86 @@ pub(crate) const BIN_INFO: &str = "darwin-amd64/minio";
 #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
 pub(crate) const BIN_INFO: &str = "darwin-arm64/minio";
 
-pub async fn determine_mirror() -> Option<String> {
-    let mirrors = vec![
+pub const MIRRORS: utils::MirrorSource = utils::MirrorSource {
+    com: Com::MinIO,
+    key: "minio",
+    builtin: &[
         "http://dl.min.io/server/minio/release/",
         "http://dl.minio.org.cn/server/minio/release/",
-    ];
+    ],
+    testfile: None,
+};
+
+pub const MC_MIRRORS: utils::MirrorSource = utils::MirrorSource {
+    com: Com::MinIO,
+    key: "mc",
+    builtin: &[
+        "http://dl.min.io/client/mc/release/",
+        "http://dl.minio.org.cn/client/mc/release/",
+    ],
+    testfile: None,
+};
 
-    utils::determine_mirror(Com::MinIO, mirrors, None).await
+pub async fn determine_mirror(opts: &utils::ProbeOptions) -> Result<String, utils::ProbeReport> {
+    utils::determine_mirror(&MIRRORS, opts).await
+}
+
+pub async fn determine_mc_mirror(opts: &utils::ProbeOptions) -> Result<String, utils::ProbeReport> {
+    utils::determine_mirror(&MC_MIRRORS, opts).await
+}
+
+pub fn mc_path() -> PathBuf {
+    config::get_com_path()
+        .join("minio")
+        .join(platform::exe("mc"))
+}
+
+pub fn do_install_mc(path: impl AsRef<Path>) -> io::Result<String> {
+    let target_path = mc_path();
+    fs::create_dir_all(target_path.parent().unwrap())?;
+    fs::copy(&path, &target_path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut perms = fs::metadata(&target_path)?.permissions();
+        let mode = perms.mode() | 0o111;
+        perms.set_mode(mode);
+        fs::set_permissions(&target_path, perms)?;
+    }
+    Ok(target_path.to_string_lossy().into_owned())
+}
+
+/// Points the `mc` alias at the local MinIO instance.
+pub fn set_mc_alias(profile: &MinIOProfile) -> io::Result<()> {
+    let (user, password) = match (&profile.root_user, &profile.root_password) {
+        (Some(user), Some(password)) => (user, password),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "MinIO credentials are not recorded in config",
+            ))
+        }
+    };
+    duct::cmd!(
+        mc_path(),
+        "alias",
+        "set",
+        MC_ALIAS,
+        profile.api_url(),
+        user,
+        password
+    )
+    .stdout_capture()
+    .stderr_capture()
+    .run()?;
+    Ok(())
 }
 
 pub fn do_install(path: impl AsRef<Path>) -> io::Result<String> {
     let target_path = config::get_com_path().join("minio");
     fs::create_dir_all(&target_path)?;
-    let target_path = target_path.join(if cfg!(windows) { "minio.exe" } else { "minio" });
+    let target_path = target_path.join(platform::exe("minio"));
     fs::copy(&path, &target_path)?;
     #[cfg(unix)]
     {
@@ -46,3 +140,105 @@ pub fn do_install(path: impl AsRef<Path>) -> io::Result<String> {
     }
     Ok(target_path.to_string_lossy().into_owned())
 }
+
+pub fn env_path() -> PathBuf {
+    config::get_com_path().join("minio").join("minio.env")
+}
+
+/// Where MinIO started by H2O2 outside of systemd writes its output.
+pub fn log_path() -> PathBuf {
+    env_path().with_file_name("minio.log")
+}
+
+/// The variables in the environment file, empty if it is not written yet.
+pub fn read_env() -> io::Result<Vec<(String, String)>> {
+    let content = match fs::read_to_string(env_path()) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(content
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .collect())
+}
+
+/// Writes the environment file read by the MinIO service.
+pub fn write_env(profile: &MinIOProfile) -> io::Result<PathBuf> {
+    let target_path = env_path();
+    fs::create_dir_all(target_path.parent().unwrap())?;
+    fs::write(&target_path, render_env(profile))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::set_permissions(&target_path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(target_path)
+}
+
+/// The content of the environment file according to the profile.
+pub fn render_env(profile: &MinIOProfile) -> String {
+    let mut content = format!(
+        "MINIO_VOLUMES={}\nMINIO_BROWSER={}\n",
+        profile.data_dir().display(),
+        if profile.browser { "on" } else { "off" }
+    );
+    if let Some(region) = &profile.region {
+        content.push_str(&format!("MINIO_REGION={}\n", region));
+    }
+    if let (Some(user), Some(password)) = (&profile.root_user, &profile.root_password) {
+        content.push_str(&format!(
+            "MINIO_ROOT_USER={}\nMINIO_ROOT_PASSWORD={}\n",
+            user, password
+        ));
+    }
+    content
+}
+
+/// The `file` entry of the Hydro config, pointing the storage of Hydro at the S3 API of the
+/// profile.
+pub fn hydro_file_config(profile: &MinIOProfile) -> serde_json::Value {
+    let mut file = serde_json::json!({
+        "type": "s3",
+        "endPoint": profile.api_url(),
+        "accessKey": profile.root_user,
+        "secretKey": profile.root_password,
+        "pathStyle": true,
+    });
+    if let Some(region) = &profile.region {
+        file["region"] = serde_json::Value::String(region.clone());
+    }
+    file
+}
+
+/// The data directory the MinIO service is currently set up with, read from the environment file.
+///
+/// Returns `None` if MinIO has not been set up. Env files written before `MINIO_VOLUMES` was
+/// recorded always used the default directory.
+pub fn applied_data_dir() -> io::Result<Option<PathBuf>> {
+    let content = match fs::read_to_string(env_path()) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let dir = content
+        .lines()
+        .find_map(|line| line.strip_prefix("MINIO_VOLUMES="))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| MinIOProfile::default().data_dir());
+    Ok(Some(dir))
+}
+
+/// Arguments for starting the MinIO server.
+pub fn server_args(profile: &MinIOProfile) -> Vec<String> {
+    vec![
+        "server".to_owned(),
+        profile.data_dir().to_string_lossy().into_owned(),
+        "--address".to_owned(),
+        profile.address.clone(),
+        "--console-address".to_owned(),
+        profile.console_address.clone(),
+    ]
+}
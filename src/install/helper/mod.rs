@@ -1,4 +1,15 @@
+pub mod adopt;
+pub mod archive;
+pub mod bundle;
+pub mod caddy;
+pub mod corepack;
+pub mod hydro;
+pub mod jspm;
 pub mod minio;
+pub mod mongodb;
 pub mod nodejs;
+pub mod npm;
+pub mod pm2;
+pub mod ranking;
 pub mod sandbox;
 pub mod utils;
@@ -0,0 +1,552 @@
+use duct::cmd;
+use serde::Deserialize;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::adopt::{self, Adoption};
+use crate::{
+    config::{self, ComponentInfo, MongoDBProfile, TlsMode},
+    net,
+    platform::{self, Arch, Os},
+    profiling,
+    utils::decode_output,
+    Com,
+};
+
+/// Release series in upgrade order. MongoDB only supports upgrading one series at a time.
+pub const RELEASE_SERIES: &[&str] = &["4.0", "4.2", "4.4", "5.0", "6.0", "7.0", "8.0"];
+
+/// Every release of MongoDB with the download links and checksums of each build.
+const RELEASES_URL: &str = "https://downloads.mongodb.org/full.json";
+
+pub fn get_mongodb_path() -> PathBuf {
+    config::get_com_path().join("mongodb")
+}
+
+#[derive(Deserialize, Debug)]
+struct Releases {
+    versions: Vec<ReleaseEntry>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ReleaseEntry {
+    version: String,
+    production_release: bool,
+    downloads: Vec<Download>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Download {
+    target: String,
+    arch: String,
+    edition: String,
+    archive: Archive,
+}
+
+#[derive(Deserialize, Debug)]
+struct Archive {
+    url: String,
+    sha256: String,
+}
+
+/// A build of MongoDB server for this platform.
+#[derive(Debug, Clone)]
+pub struct Release {
+    pub version: semver::Version,
+    pub url: String,
+    pub sha256: String,
+}
+
+/// The build target of this platform as named by MongoDB, e.g. `ubuntu2204` or `rhel90`.
+///
+/// Linux builds are made for each distribution, so it is read from `/etc/os-release`.
+pub fn download_target() -> Option<String> {
+    match platform::current().os {
+        Os::Windows => Some("windows".to_owned()),
+        Os::Linux => linux_target(&fs::read_to_string("/etc/os-release").ok()?),
+        _ => None,
+    }
+}
+
+fn linux_target(os_release: &str) -> Option<String> {
+    let field = |key: &str| {
+        os_release.lines().find_map(|line| {
+            let value = line.strip_prefix(key)?.strip_prefix('=')?;
+            Some(value.trim().trim_matches('"').to_owned())
+        })
+    };
+    let id = field("ID")?;
+    let id_like = field("ID_LIKE").unwrap_or_default();
+    let version = field("VERSION_ID")?;
+    let major = version.split('.').next()?.to_owned();
+    let target = match id.as_str() {
+        "ubuntu" => format!("ubuntu{}", version.replace('.', "")),
+        "debian" => format!("debian{}", major),
+        "amzn" => format!("amazon{}", major),
+        // Rocky Linux, AlmaLinux, CentOS and others use the builds for RHEL
+        _ if id == "rhel" || id_like.split_whitespace().any(|like| like == "rhel") => {
+            format!("rhel{}0", major)
+        }
+        _ => return None,
+    };
+    Some(target)
+}
+
+/// Finds the latest production release of the `series` (e.g. `6.0`), or of any supported series
+/// if `None`, which has a build for `target`.
+pub async fn find_release(target: &str, series: Option<&str>) -> io::Result<Option<Release>> {
+    let arch = match platform::current().arch {
+        Arch::X86_64 => "x86_64",
+        Arch::Aarch64 => "aarch64",
+        _ => return Ok(None),
+    };
+    let text = {
+        let _span = profiling::span(Com::MongoDB.to_string(), RELEASES_URL);
+        let res = net::client()
+            .get(RELEASES_URL)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(io::Error::other)?;
+        res.text().await.map_err(io::Error::other)?
+    };
+    let releases = serde_json::from_str::<Releases>(&text)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let release = releases
+        .versions
+        .into_iter()
+        .filter(|entry| entry.production_release)
+        .filter_map(|entry| {
+            let version = semver::Version::parse(&entry.version).ok()?;
+            let wanted = match series {
+                Some(series) => series_of(&version) == series,
+                None => RELEASE_SERIES.contains(&series_of(&version).as_str()),
+            };
+            if !wanted {
+                return None;
+            }
+            // Linux builds are "targeted" at a distribution, the others are "base"
+            let download = entry.downloads.into_iter().find(|d| {
+                d.target == target
+                    && d.arch == arch
+                    && (d.edition == "targeted" || d.edition == "base")
+            })?;
+            Some(Release {
+                version,
+                url: download.archive.url,
+                sha256: download.archive.sha256,
+            })
+        })
+        .max_by(|a, b| a.version.cmp(&b.version));
+    Ok(release)
+}
+
+/// Extracts the downloaded archive into the MongoDB dir and returns the path of `mongod`.
+///
+/// Files of the previous installation are overwritten, while the data and the config are kept.
+pub fn do_install(path: impl AsRef<Path>) -> io::Result<String> {
+    let target_path = get_mongodb_path();
+    fs::create_dir_all(&target_path)?;
+    // `tar` shipped with Windows 10 extracts zip archives as well
+    cmd!(
+        "tar",
+        "-xf",
+        path.as_ref(),
+        "-C",
+        &target_path,
+        "--strip-components=1"
+    )
+    .stdout_capture()
+    .stderr_capture()
+    .run()?;
+
+    let mongod = target_path.join("bin").join(platform::exe("mongod"));
+    if !mongod.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not found in the archive", mongod.display()),
+        ));
+    }
+    Ok(mongod.to_string_lossy().into_owned())
+}
+
+/// Generates a self-signed certificate for `host` with `openssl`.
+///
+/// Returns the paths of the PEM file containing both the certificate and the key,
+/// and of the certificate alone (used as CA file by clients).
+pub fn generate_cert(host: &str) -> io::Result<(PathBuf, PathBuf)> {
+    let dir = get_mongodb_path().join("tls");
+    fs::create_dir_all(&dir)?;
+    let key = dir.join("mongodb.key");
+    let cert = dir.join("mongodb.crt");
+    let pem = dir.join("mongodb.pem");
+
+    let san = if host.parse::<std::net::IpAddr>().is_ok() {
+        format!("subjectAltName=IP:{},DNS:localhost", host)
+    } else {
+        format!("subjectAltName=DNS:{},DNS:localhost", host)
+    };
+    cmd!(
+        "openssl",
+        "req",
+        "-x509",
+        "-newkey",
+        "rsa:4096",
+        "-nodes",
+        "-days",
+        "3650",
+        "-subj",
+        format!("/CN={}", host),
+        "-addext",
+        san,
+        "-keyout",
+        &key,
+        "-out",
+        &cert
+    )
+    .stdout_capture()
+    .stderr_capture()
+    .run()?;
+
+    let mut content = fs::read_to_string(&cert)?;
+    content.push_str(&fs::read_to_string(&key)?);
+    fs::write(&pem, content)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::set_permissions(&key, fs::Permissions::from_mode(0o600))?;
+        fs::set_permissions(&pem, fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok((pem, cert))
+}
+
+pub fn config_path() -> PathBuf {
+    get_mongodb_path().join("mongod.conf")
+}
+
+pub fn data_path() -> PathBuf {
+    get_mongodb_path().join("data")
+}
+
+/// Writes `mongod.conf` according to the profile and returns its path. Fails if there is one
+/// not written by H2O2, which must be adopted first.
+pub fn write_config(profile: &MongoDBProfile) -> io::Result<PathBuf> {
+    fs::create_dir_all(data_path())?;
+    let content = render_config(profile)?;
+    let path = config_path();
+    adopt::ensure_generated(&path, "mongodb", is_generated)?;
+    fs::write(&path, content)?;
+    Ok(path)
+}
+
+/// Settings of `mongod.conf` written by [`render_config`], as dotted paths.
+const MANAGED_SETTINGS: &[&str] = &[
+    "storage.dbPath",
+    "net.port",
+    "net.bindIp",
+    "net.tls.mode",
+    "net.tls.certificateKeyFile",
+    "net.tls.CAFile",
+    "net.tls.allowConnectionsWithoutCertificates",
+];
+
+/// Parses `mongod.conf` in the block style, which H2O2 writes and most hand-written ones use,
+/// into its settings as dotted paths like `net.port` and their values. Returns `None` for other
+/// styles, such as flow mappings and lists.
+pub fn parse_config(text: &str) -> Option<Vec<(String, String)>> {
+    let mut settings = Vec::new();
+    // keys of the mappings the line is in, with their indentation
+    let mut parents: Vec<(usize, &str)> = Vec::new();
+    for line in text.lines() {
+        let line = match line.find(" #") {
+            Some(pos) => &line[..pos],
+            None => line,
+        };
+        let content = line.trim();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        let (key, value) = content.split_once(':')?;
+        let (key, value) = (key.trim(), value.trim());
+        if key.is_empty() || key.contains(|c: char| c.is_whitespace() || "-[{\"'".contains(c)) {
+            return None;
+        }
+        while parents.last().is_some_and(|&(i, _)| i >= indent) {
+            parents.pop();
+        }
+        let path = parents
+            .iter()
+            .map(|&(_, key)| key)
+            .chain(Some(key))
+            .collect::<Vec<_>>()
+            .join(".");
+        match value.chars().next() {
+            None => parents.push((indent, key)),
+            Some('[' | '{' | '|' | '>' | '&' | '*') => return None,
+            Some(_) => {
+                let value = value.trim_matches(|c| c == '"' || c == '\'');
+                settings.push((path, value.to_owned()));
+            }
+        }
+    }
+    Some(settings)
+}
+
+/// Whether `mongod.conf` holds nothing but what [`render_config`] writes, with the data dir of
+/// H2O2, so that rewriting it loses nothing.
+pub fn is_generated(text: &str) -> bool {
+    let settings = match parse_config(text) {
+        Some(settings) => settings,
+        None => return false,
+    };
+    settings.iter().all(|(key, value)| match key.as_str() {
+        "storage.dbPath" => Path::new(value) == data_path(),
+        key => MANAGED_SETTINGS.contains(&key),
+    })
+}
+
+/// Imports the `settings` of a hand-written `mongod.conf` into `profile`. The data dir is left
+/// to the caller, as H2O2 keeps the data in its own.
+pub fn adopt_config(settings: &[(String, String)], profile: &mut MongoDBProfile) -> Adoption {
+    let mut adoption = Adoption::default();
+    for (key, value) in settings {
+        let imported = match key.as_str() {
+            "storage.dbPath" => Path::new(value) == data_path(),
+            "net.port" => match value.parse() {
+                Ok(port) => {
+                    profile.port = port;
+                    true
+                }
+                Err(_) => false,
+            },
+            "net.bindIp" => {
+                profile.bind_ip = value.clone();
+                true
+            }
+            "net.tls.mode" => match value.as_str() {
+                "disabled" => {
+                    profile.tls = TlsMode::Disabled;
+                    true
+                }
+                // the only mode written
+                "requireTLS" => true,
+                _ => false,
+            },
+            "net.tls.certificateKeyFile" => {
+                profile.tls = TlsMode::Custom;
+                profile.tls_cert_file = Some(value.clone());
+                true
+            }
+            "net.tls.CAFile" => {
+                profile.tls_ca_file = Some(value.clone());
+                true
+            }
+            // written along with the CA file
+            "net.tls.allowConnectionsWithoutCertificates" => value == "true",
+            _ => false,
+        };
+        let setting = format!("{} = {}", key, value);
+        match imported {
+            true => adoption.imported.push(setting),
+            false => adoption.dropped.push(setting),
+        }
+    }
+    adoption
+}
+
+/// The content of `mongod.conf` according to the profile.
+pub fn render_config(profile: &MongoDBProfile) -> io::Result<String> {
+    let db_path = data_path();
+    let mut content = format!(
+        "storage:\n  dbPath: {}\nnet:\n  port: {}\n  bindIp: {}\n",
+        db_path.display(),
+        profile.port,
+        &profile.bind_ip,
+    );
+    if !profile.tls.is_disabled() {
+        let cert = profile.tls_cert_file.as_deref().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "`tls_cert_file` is not set")
+        })?;
+        content.push_str("  tls:\n    mode: requireTLS\n");
+        content.push_str(&format!("    certificateKeyFile: {}\n", cert));
+        if let Some(ca) = &profile.tls_ca_file {
+            content.push_str(&format!("    CAFile: {}\n", ca));
+            content.push_str("    allowConnectionsWithoutCertificates: true\n");
+        }
+    }
+    Ok(content)
+}
+
+/// Builds the connection string Hydro uses, the external one if given.
+pub fn connection_uri(profile: &MongoDBProfile) -> String {
+    if let Some(uri) = &profile.uri {
+        return uri.clone();
+    }
+    let mut uri = format!("mongodb://{}:{}/hydro", &profile.host, profile.port);
+    if !profile.tls.is_disabled() {
+        uri.push_str("?tls=true");
+        if let Some(ca) = &profile.tls_ca_file {
+            uri.push_str(&format!("&tlsCAFile={}", encode(ca)));
+        }
+    }
+    uri
+}
+
+/// Fills in the certificate paths when `tls = "generate"`, generating them if missing.
+pub fn ensure_cert(profile: &mut MongoDBProfile) -> io::Result<()> {
+    if profile.tls != TlsMode::Generate {
+        return Ok(());
+    }
+    let exists =
+        |path: &Option<String>| matches!(path.as_deref(), Some(p) if Path::new(p).is_file());
+    if exists(&profile.tls_cert_file) && exists(&profile.tls_ca_file) {
+        return Ok(());
+    }
+
+    let (pem, cert) = generate_cert(&profile.host)?;
+    profile.tls_cert_file = Some(pem.to_string_lossy().into_owned());
+    profile.tls_ca_file = Some(cert.to_string_lossy().into_owned());
+    Ok(())
+}
+
+fn encode(s: &str) -> String {
+    url::form_urlencoded::byte_serialize(s.as_bytes()).collect()
+}
+
+/// The release series of `version`, e.g. `4.4` for `4.4.6`.
+pub fn series_of(version: &semver::Version) -> String {
+    format!("{}.{}", version.major, version.minor)
+}
+
+/// The release series to go through to upgrade from `from` to the `to` series, in order.
+///
+/// Returns `None` if either series is unknown or `to` is older than `from`.
+pub fn upgrade_path(from: &semver::Version, to: &str) -> Option<&'static [&'static str]> {
+    let from = RELEASE_SERIES
+        .iter()
+        .position(|series| *series == series_of(from))?;
+    let to = RELEASE_SERIES.iter().position(|series| *series == to)?;
+    if to < from {
+        return None;
+    }
+    Some(&RELEASE_SERIES[from + 1..=to])
+}
+
+/// Finds `mongosh`, or the legacy `mongo` shell shipped with MongoDB before 6.0, preferring the
+/// ones next to the recorded `mongod`.
+pub fn shell_path(mongodb: &ComponentInfo) -> Option<PathBuf> {
+    let dir = mongodb
+        .path
+        .as_deref()
+        .and_then(|path| Path::new(path).parent());
+    ["mongosh", "mongo"].iter().find_map(|name| {
+        dir.map(|dir| dir.join(platform::exe(name)))
+            .filter(|path| path.is_file())
+            .or_else(|| platform::find_in_path(name))
+    })
+}
+
+/// Evaluates `script` on the server with the MongoDB shell and returns the last line printed.
+pub fn eval(mongodb: &ComponentInfo, profile: &MongoDBProfile, script: &str) -> io::Result<String> {
+    let shell = shell_path(mongodb).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "neither mongosh nor mongo is found",
+        )
+    })?;
+    let output = cmd!(&shell, "--quiet", connection_uri(profile), "--eval", script)
+        .stdout_capture()
+        .stderr_capture()
+        .unchecked()
+        .run()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{} exited with {}: {}",
+            shell.display(),
+            output.status,
+            decode_output(&output.stderr).trim()
+        )));
+    }
+    Ok(decode_output(&output.stdout)
+        .lines()
+        .last()
+        .unwrap_or("")
+        .trim()
+        .to_owned())
+}
+
+/// Reads the featureCompatibilityVersion of the server, e.g. `4.4`.
+pub fn feature_compatibility_version(
+    mongodb: &ComponentInfo,
+    profile: &MongoDBProfile,
+) -> io::Result<String> {
+    eval(
+        mongodb,
+        profile,
+        "print(db.adminCommand({ getParameter: 1, featureCompatibilityVersion: 1 })\
+            .featureCompatibilityVersion.version)",
+    )
+}
+
+/// Sets the featureCompatibilityVersion of the server to the `series`.
+pub fn set_feature_compatibility_version(
+    mongodb: &ComponentInfo,
+    profile: &MongoDBProfile,
+    series: &str,
+) -> io::Result<()> {
+    // MongoDB 7.0 and later require confirming the change, as it cannot be downgraded
+    let major = series
+        .split('.')
+        .next()
+        .and_then(|major| major.parse::<u64>().ok());
+    let confirm = if matches!(major, Some(major) if major >= 7) {
+        ", confirm: true"
+    } else {
+        ""
+    };
+    let script = format!(
+        "print(JSON.stringify(db.adminCommand({{ setFeatureCompatibilityVersion: \"{}\"{} }})))",
+        series, confirm
+    );
+    let result = eval(mongodb, profile, &script)?;
+    match serde_json::from_str::<serde_json::Value>(&result) {
+        Ok(result) if result["ok"].as_f64() == Some(1.0) => Ok(()),
+        _ => Err(io::Error::other(format!(
+            "setFeatureCompatibilityVersion failed: {}",
+            result
+        ))),
+    }
+}
+
+/// Checks that the server responds, and that every member is healthy with a primary elected if
+/// it is a replica set. Returns the problem found otherwise.
+pub fn check_health(
+    mongodb: &ComponentInfo,
+    profile: &MongoDBProfile,
+) -> io::Result<Option<String>> {
+    // mongosh throws on a standalone server, with `NoReplicationEnabled` (76), while the legacy
+    // shell returns `ok: 0`
+    let script = "let s; \
+        try { s = db.adminCommand({ replSetGetStatus: 1 }); } \
+        catch (e) { if (e.code === 76) s = { ok: 0 }; else throw e; } \
+        print(JSON.stringify(s.ok ? s.members.map(m => ({ name: m.name, health: m.health, state: m.stateStr })) : null))";
+    let result = eval(mongodb, profile, script)?;
+    let members = match serde_json::from_str::<serde_json::Value>(&result) {
+        Ok(serde_json::Value::Null) => return Ok(None),
+        Ok(serde_json::Value::Array(members)) => members,
+        _ => return Ok(Some(format!("unexpected output: {}", result))),
+    };
+    if let Some(member) = members.iter().find(|m| m["health"].as_f64() != Some(1.0)) {
+        return Ok(Some(format!("member {} is unhealthy", member["name"])));
+    }
+    if !members.iter().any(|m| m["state"] == "PRIMARY") {
+        return Ok(Some("no primary is elected".to_owned()));
+    }
+    Ok(None)
+}
@@ -0,0 +1,145 @@
+use backoff::{backoff::Backoff, ExponentialBackoff};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::time;
+
+use crate::{
+    log_i18n, platform,
+    utils::{debug_capped, debug_output, decode_output, run_capped, CappedOutput},
+    Com,
+};
+
+pub const NPMJS_REGISTRY: &str = "https://registry.npmjs.org/";
+pub const NPMMIRROR_REGISTRY: &str = "https://registry.npmmirror.com/";
+
+/// Attempts made against each registry before switching to the next one.
+const ATTEMPTS_PER_REGISTRY: usize = 2;
+
+/// Directory of the shims of global packages, e.g. `yarn`, from `npm prefix --global`, as
+/// `npm bin` is removed in npm 9.
+pub fn global_bin(npm: &str) -> io::Result<PathBuf> {
+    let output = duct::cmd!(npm, "prefix", "--global")
+        .stdout_capture()
+        .stderr_null()
+        .run()?;
+    let prefix = PathBuf::from(decode_output(&output.stdout).trim());
+    // the shims are put right in the prefix on Windows
+    Ok(match platform::current().is_windows() {
+        true => prefix,
+        false => prefix.join("bin"),
+    })
+}
+
+/// Runs a `npm` or `yarn` command, retrying on failure.
+///
+/// The configured registry is tried first. If it keeps failing, the package cache is cleaned
+/// and the command is retried against the alternate registry. The output of the last attempt
+/// is returned whether it succeeded or not, so the caller can report it.
+pub async fn run_with_retry(com: Com, program: &str, args: &[&str]) -> io::Result<CappedOutput> {
+    run_with_registry(com, program, args, None).await
+}
+
+/// Runs a `npm` or `yarn` command like [`run_with_retry`], trying `registry` first instead of the
+/// configured one if it is given.
+pub async fn run_with_registry(
+    com: Com,
+    program: &str,
+    args: &[&str],
+    registry: Option<&str>,
+) -> io::Result<CappedOutput> {
+    let alternate = match registry {
+        Some(registry) => alternate_of(registry),
+        None => alternate_registry(program),
+    };
+    let registries = [registry, Some(alternate)];
+
+    let mut backoff = ExponentialBackoff {
+        initial_interval: Duration::from_secs(2),
+        max_interval: Duration::from_secs(30),
+        max_elapsed_time: None,
+        ..Default::default()
+    };
+    let mut last = None;
+    for (i, registry) in registries.iter().enumerate() {
+        if let (1, Some(registry)) = (i, registry) {
+            log_i18n!(warn, [com], "install.npm-switch-registry", registry);
+            clean_cache(program);
+        }
+        for attempt in 1..=ATTEMPTS_PER_REGISTRY {
+            let mut cmd_args = args.iter().map(|&arg| arg.to_owned()).collect::<Vec<_>>();
+            if let Some(registry) = registry {
+                cmd_args.push("--registry".to_owned());
+                cmd_args.push((*registry).to_owned());
+            }
+            let output = run_capped(&duct::cmd(program, &cmd_args))?;
+            if output.status.success() {
+                return Ok(output);
+            }
+
+            log_i18n!(
+                warn,
+                [com],
+                "install.npm-attempt-failed",
+                command_name(program),
+                args.join(" "),
+                attempt,
+                &output.status
+            );
+            debug_capped(&output);
+            last = Some(output);
+            if let Some(delay) = backoff.next_backoff() {
+                time::sleep(delay).await;
+            }
+        }
+    }
+
+    Ok(last.expect("at least one attempt is made"))
+}
+
+fn command_name(program: &str) -> &str {
+    Path::new(program)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(program)
+}
+
+/// Picks the registry not currently configured, so that failover really changes something.
+fn alternate_registry(program: &str) -> &'static str {
+    let current = duct::cmd!(program, "config", "get", "registry")
+        .stdout_capture()
+        .stderr_null()
+        .unchecked()
+        .run()
+        .map(|output| decode_output(&output.stdout))
+        .unwrap_or_default();
+    alternate_of(&current)
+}
+
+/// The registry to fail over to from `current`.
+fn alternate_of(current: &str) -> &'static str {
+    if current.contains("npmmirror") || current.contains("taobao") {
+        NPMJS_REGISTRY
+    } else {
+        NPMMIRROR_REGISTRY
+    }
+}
+
+fn clean_cache(program: &str) {
+    let res = if command_name(program) == "yarn" {
+        duct::cmd!(program, "cache", "clean")
+    } else {
+        duct::cmd!(program, "cache", "clean", "--force")
+    }
+    .stdout_capture()
+    .stderr_capture()
+    .unchecked()
+    .run();
+    match res {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => debug_output(&output),
+        Err(e) => tracing::debug!("Failed to clean the cache of {}: {}", program, e),
+    }
+}
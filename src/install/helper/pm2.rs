@@ -0,0 +1,275 @@
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use super::adopt::{self, Adoption};
+use crate::{
+    config::{self, ComponentInfo, HydroProfile, RestartMode},
+    platform,
+    utils::{debug_output, decode_output},
+};
+
+pub const HYDRO_APP: &str = "hydrooj";
+pub const JUDGE_APP: &str = "hydrojudge";
+
+/// A process managed by PM2, as listed by `pm2 jlist`.
+#[derive(Deserialize, Debug)]
+pub struct Process {
+    pub name: String,
+    /// `None` or `0` if it is not running
+    #[serde(default)]
+    pub pid: Option<u32>,
+    #[serde(default)]
+    pub pm2_env: serde_json::Value,
+}
+
+impl Process {
+    /// Status of the process, such as `online` or `stopped`.
+    pub fn status(&self) -> &str {
+        self.pm2_env["status"].as_str().unwrap_or("unknown")
+    }
+}
+
+pub fn ecosystem_path() -> PathBuf {
+    config::get_com_path().join("hydro").join("ecosystem.json")
+}
+
+/// Writes the PM2 ecosystem file describing the Hydro processes and returns its path. Fails if
+/// there is one not written by H2O2, which must be adopted first.
+///
+/// `hydro` must be installed, its `path` being the global dir which contains `node_modules`. PM2
+/// kills the processes once they fail to exit within `kill_after` of being stopped.
+pub fn write_ecosystem(
+    hydro: &ComponentInfo,
+    profile: &HydroProfile,
+    kill_after: Duration,
+) -> io::Result<PathBuf> {
+    let global_dir = hydro.path.as_deref().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "Hydro path is not recorded in config",
+        )
+    })?;
+    let modules = Path::new(global_dir).join("node_modules");
+
+    let mut apps = vec![app(
+        HYDRO_APP,
+        &modules.join("hydrooj").join("bin").join("hydrooj.js"),
+        profile.workers,
+        // reloading replaces processes one by one only in cluster mode
+        profile.restart_mode.is_reload(),
+        kill_after,
+    )];
+    if profile.judge_workers > 0 {
+        apps.push(app(
+            JUDGE_APP,
+            &modules
+                .join("@hydrooj")
+                .join("hydrojudge")
+                .join("bin")
+                .join("hydrojudge.js"),
+            profile.judge_workers,
+            false,
+            kill_after,
+        ));
+    }
+
+    let path = ecosystem_path();
+    adopt::ensure_generated(&path, "hydro", is_generated)?;
+    fs::create_dir_all(path.parent().unwrap())?;
+    let text = serde_json::to_string_pretty(&json!({ "apps": apps }))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    fs::write(&path, text)?;
+    Ok(path)
+}
+
+fn app(
+    name: &str,
+    script: &Path,
+    instances: u32,
+    cluster: bool,
+    kill_after: Duration,
+) -> serde_json::Value {
+    json!({
+        "name": name,
+        "script": script,
+        "instances": instances.max(1),
+        "exec_mode": if cluster || instances > 1 { "cluster" } else { "fork" },
+        // in milliseconds
+        "kill_timeout": kill_after.as_millis() as u64,
+    })
+}
+
+/// Keys of an app written by [`write_ecosystem`].
+const APP_KEYS: &[&str] = &["name", "script", "instances", "exec_mode", "kill_timeout"];
+
+/// Whether the ecosystem file holds nothing but the apps of Hydro as [`write_ecosystem`] writes
+/// them, so that rewriting it loses nothing.
+pub fn is_generated(text: &str) -> bool {
+    let ecosystem = match serde_json::from_str::<serde_json::Value>(text) {
+        Ok(serde_json::Value::Object(ecosystem)) => ecosystem,
+        _ => return false,
+    };
+    let apps = match ecosystem.get("apps").and_then(|apps| apps.as_array()) {
+        Some(apps) if ecosystem.len() == 1 => apps,
+        _ => return false,
+    };
+    apps.iter().all(|app| {
+        let name = app["name"].as_str().unwrap_or_default();
+        let mut keys = app.as_object().into_iter().flat_map(|app| app.keys());
+        (name == HYDRO_APP || name == JUDGE_APP) && keys.all(|key| APP_KEYS.contains(&key.as_str()))
+    })
+}
+
+/// Imports the instances and exec mode of the Hydro apps in a hand-written ecosystem file into
+/// `profile`. Scripts are not imported, as they are those of the Hydro installed.
+pub fn adopt_ecosystem(text: &str, profile: &mut HydroProfile) -> io::Result<Adoption> {
+    let ecosystem = serde_json::from_str::<serde_json::Value>(text)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut adoption = Adoption::default();
+    if let Some(ecosystem) = ecosystem.as_object() {
+        for key in ecosystem.keys().filter(|key| *key != "apps") {
+            adoption.dropped.push(key.clone());
+        }
+    }
+    let apps = ecosystem["apps"].as_array().cloned().unwrap_or_default();
+    // the standalone judge is not run unless listed
+    profile.judge_workers = 0;
+    for app in &apps {
+        let name = app["name"].as_str().unwrap_or_default();
+        if name != HYDRO_APP && name != JUDGE_APP {
+            adoption.dropped.push(format!("apps.{}", name));
+            continue;
+        }
+        let entries = app.as_object().into_iter().flatten();
+        for (key, value) in entries {
+            let imported = match key.as_str() {
+                "name" | "script" => true,
+                "instances" => match value.as_u64() {
+                    Some(instances) if name == HYDRO_APP => {
+                        profile.workers = instances as u32;
+                        true
+                    }
+                    Some(instances) => {
+                        profile.judge_workers = instances as u32;
+                        true
+                    }
+                    // such as `max`, the number of CPUs
+                    None => false,
+                },
+                // a single process runs in cluster mode only to be reloaded
+                "exec_mode" if name == HYDRO_APP => {
+                    profile.restart_mode = match value.as_str() {
+                        Some("cluster") | Some("cluster_mode") => RestartMode::Reload,
+                        _ => RestartMode::Restart,
+                    };
+                    true
+                }
+                "exec_mode" => true,
+                _ => false,
+            };
+            let setting = format!("apps.{}.{} = {}", name, key, value);
+            match imported {
+                true => adoption.imported.push(setting),
+                false => adoption.dropped.push(setting),
+            }
+        }
+        // PM2 starts a single instance unless told otherwise
+        if app.get("instances").is_none() {
+            match name == HYDRO_APP {
+                true => profile.workers = 1,
+                false => profile.judge_workers = 1,
+            }
+        }
+    }
+    Ok(adoption)
+}
+
+/// Applies the ecosystem file to `app`, starting it if it is not running, or replacing its
+/// processes as set by `mode` otherwise.
+pub fn apply(program: &str, ecosystem: &Path, app: &str, mode: RestartMode) -> io::Result<()> {
+    let action = match mode {
+        RestartMode::Reload => "startOrReload",
+        RestartMode::Restart => "startOrRestart",
+    };
+    // reloading keeps the exec mode, so switching it takes a fresh start
+    let ecosystem_mode = fs::read_to_string(ecosystem)
+        .ok()
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+        .and_then(|ecosystem| {
+            let apps = ecosystem["apps"].as_array()?;
+            let entry = apps.iter().find(|entry| entry["name"] == app)?;
+            entry["exec_mode"].as_str().map(ToOwned::to_owned)
+        });
+    if daemon_pid().is_some() {
+        let running = list(program)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|p| p.name == app)
+            .and_then(|p| p.pm2_env["exec_mode"].as_str().map(ToOwned::to_owned));
+        // PM2 reports `cluster_mode` or `fork_mode`
+        if let (Some(running), Some(wanted)) = (running, &ecosystem_mode) {
+            if running.trim_end_matches("_mode") != wanted {
+                run(duct::cmd!(program, "delete", app))?;
+            }
+        }
+    }
+    run(duct::cmd!(program, action, ecosystem, "--only", app))
+}
+
+/// Runs a command of PM2, failing with its error output.
+pub fn run(expr: duct::Expression) -> io::Result<()> {
+    let output = expr.stdout_capture().stderr_capture().unchecked().run()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        debug_output(&output);
+        Err(io::Error::other(format!(
+            "PM2 exited abnormally ({}): {}",
+            output.status,
+            decode_output(&output.stderr).trim()
+        )))
+    }
+}
+
+/// Directory of the PM2 daemon, which keeps its PID file, sockets and logs.
+pub fn home() -> Option<PathBuf> {
+    env::var_os("PM2_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".pm2")))
+}
+
+/// PID of the PM2 daemon, or `None` if it is not running.
+pub fn daemon_pid() -> Option<u32> {
+    let pid = fs::read_to_string(home()?.join("pm2.pid")).ok()?;
+    let pid = pid.trim().parse::<u32>().ok()?;
+    match platform::process_alive(pid) {
+        Some(false) => None,
+        _ => Some(pid),
+    }
+}
+
+/// Lists the processes managed by PM2, or tells why they cannot be listed. Starts the daemon if
+/// it is not running, so check [`daemon_pid`] first.
+pub fn list(program: &str) -> Result<Vec<Process>, String> {
+    let output = duct::cmd!(program, "jlist")
+        .stdout_capture()
+        .stderr_capture()
+        .unchecked()
+        .run()
+        .map_err(|e| format!("{}: {}", program, e))?;
+    // `jlist` may print warnings before the list
+    let stdout = decode_output(&output.stdout);
+    let list = stdout.lines().find(|line| line.starts_with('['));
+    match list.map(serde_json::from_str::<Vec<Process>>) {
+        Some(Ok(processes)) => Ok(processes),
+        _ => Err(format!(
+            "unexpected output of `pm2 jlist`: {}",
+            stdout.trim()
+        )),
+    }
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::config;
+
+/// Held while saving, as the mirrors of several components are probed at the same time.
+static SAVING: Mutex<()> = Mutex::new(());
+
+/// How long a ranking is trusted, after which the mirrors are probed again.
+const TTL: u64 = 24 * 60 * 60;
+
+/// Mirrors of a component ranked by the last probing, e.g. by `h2o2 watch`, so that installs
+/// can skip probing.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Ranking {
+    /// seconds since the Unix epoch when the mirrors were probed
+    pub probed_at: u64,
+
+    /// mirrors that worked, the fastest first
+    pub mirrors: Vec<String>,
+}
+
+pub fn path() -> PathBuf {
+    config::get_cache_path().join("mirrors.json")
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn load_all() -> BTreeMap<String, Ranking> {
+    fs::read_to_string(path())
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// The ranking of the mirror list `key`, e.g. `nodejs`, unless it is stale.
+pub fn load(key: &str) -> Option<Ranking> {
+    load_all()
+        .remove(key)
+        .filter(|ranking| now().saturating_sub(ranking.probed_at) < TTL)
+}
+
+/// Records `mirrors`, the fastest first, as the ranking of the mirror list `key`.
+pub fn save(key: &str, mirrors: Vec<String>) -> io::Result<()> {
+    let _saving = SAVING.lock().unwrap_or_else(|e| e.into_inner());
+    let mut rankings = load_all();
+    rankings.insert(
+        key.to_owned(),
+        Ranking {
+            probed_at: now(),
+            mirrors,
+        },
+    );
+    let path = path();
+    fs::create_dir_all(path.parent().unwrap())?;
+    let text = serde_json::to_string_pretty(&rankings)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    fs::write(path, text)
+}
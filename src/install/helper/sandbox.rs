@@ -1,7 +1,23 @@
-use std::{fs, io, path::Path};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use super::utils;
-use crate::{config, Com};
+use crate::{
+    config::{self, SandboxProfile},
+    net, platform, Com,
+};
+
+/// The latest release of go-judge, which the sandbox is. Drafts and prereleases are never the
+/// latest one.
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/criyle/go-judge/releases/latest";
+
+pub(crate) const HOST: &str = "http://localhost:5050";
+
+/// Timeout of requests to the sandbox, which answers locally.
+const TIMEOUT: Duration = Duration::from_secs(10);
 
 #[cfg(all(windows, target_arch = "x86"))]
 pub(crate) const BIN_INFO: &str = "";
@@ -18,26 +34,52 @@ pub(crate) const BIN_INFO: &str = "arm64";
 #[cfg(all(target_os = "macos", target_arch = "x86_64"))]
 pub(crate) const BIN_INFO: &str = "macOS-amd64";
 
-pub async fn determine_mirror() -> Option<String> {
-    let mirrors = vec!["https://github.com/", "https://download.fastgit.org/"];
+/// Mirrors of GitHub, which the sandbox is released on.
+pub const MIRRORS: utils::MirrorSource = utils::MirrorSource {
+    com: Com::Sandbox,
+    key: "sandbox",
+    builtin: &["https://github.com/", "https://download.fastgit.org/"],
+    testfile: Some("wuxianucw/h2o2/releases/download/dummy/test"),
+};
+
+/// The release of go-judge installed if neither pinned nor found on GitHub.
+pub const VERSION: &str = "1.2.4";
+
+/// Directory of the release `version`, relative to a mirror.
+pub fn release_path(version: &str) -> String {
+    format!("criyle/go-judge/releases/download/v{}/", version)
+}
+
+/// The fastest mirror, which [`release_path`] of a release is relative to.
+pub async fn determine_mirror(opts: &utils::ProbeOptions) -> Result<String, utils::ProbeReport> {
+    utils::determine_mirror(&MIRRORS, opts).await
+}
+
+/// Queries the latest stable release of go-judge on GitHub.
+pub async fn latest_release() -> io::Result<semver::Version> {
+    #[derive(serde::Deserialize)]
+    struct Release {
+        tag_name: String,
+    }
 
-    utils::determine_mirror(
-        Com::Sandbox,
-        mirrors,
-        Some("wuxianucw/h2o2/releases/download/dummy/test"),
-    )
-    .await
-    .map(|s| s + "criyle/go-judge/releases/download/v1.2.4/")
+    let text = net::github_api(LATEST_RELEASE_URL)
+        .send()
+        .await
+        .and_then(|res| res.error_for_status())
+        .map_err(io::Error::other)?
+        .text()
+        .await
+        .map_err(io::Error::other)?;
+    let release = serde_json::from_str::<Release>(&text)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    semver::Version::parse(release.tag_name.trim_start_matches('v'))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
 }
 
 pub fn do_install(path: impl AsRef<Path>) -> io::Result<String> {
     let target_path = config::get_com_path().join("sandbox");
     fs::create_dir_all(&target_path)?;
-    let target_path = target_path.join(if cfg!(windows) {
-        "sandbox.exe"
-    } else {
-        "sandbox"
-    });
+    let target_path = target_path.join(platform::exe("sandbox"));
     fs::copy(&path, &target_path)?;
     #[cfg(unix)]
     {
@@ -50,3 +92,54 @@ pub fn do_install(path: impl AsRef<Path>) -> io::Result<String> {
     }
     Ok(target_path.to_string_lossy().into_owned())
 }
+
+pub fn env_path() -> PathBuf {
+    config::get_com_path().join("sandbox").join("sandbox.env")
+}
+
+/// Writes the environment file read by the sandbox service.
+///
+/// The sandbox reads its options from `ES_*` environment variables.
+pub fn write_env(profile: &SandboxProfile) -> io::Result<PathBuf> {
+    let target_path = env_path();
+    fs::create_dir_all(target_path.parent().unwrap())?;
+    fs::write(&target_path, render_env(profile))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::set_permissions(&target_path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(target_path)
+}
+
+/// The content of the environment file according to the profile.
+pub fn render_env(profile: &SandboxProfile) -> String {
+    let mut content = String::new();
+    if let Some(token) = &profile.auth_token {
+        content.push_str(&format!("ES_AUTH_TOKEN={}\n", token));
+    }
+    if let Some(parallelism) = profile.parallelism {
+        content.push_str(&format!("ES_PARALLELISM={}\n", parallelism));
+    }
+    if let Some(output_limit) = profile.output_limit {
+        content.push_str(&format!("ES_OUTPUT_LIMIT={}\n", output_limit << 20));
+    }
+    content
+}
+
+/// A request to `path` of the HTTP endpoint of the sandbox, authorized with the token of
+/// `profile`.
+pub fn request(
+    profile: &SandboxProfile,
+    method: reqwest::Method,
+    path: &str,
+) -> reqwest::RequestBuilder {
+    let req = reqwest::Client::new()
+        .request(method, format!("{}{}", HOST, path))
+        .timeout(TIMEOUT);
+    match &profile.auth_token {
+        Some(token) => req.bearer_auth(token),
+        None => req,
+    }
+}
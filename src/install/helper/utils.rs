@@ -1,17 +1,30 @@
-use std::time::{Duration, SystemTime};
-use tokio::sync::mpsc;
+use derive_more::Display;
+use std::{
+    error::Error as StdError,
+    fmt,
+    time::{Duration, SystemTime},
+};
+use tokio::{sync::mpsc, time};
 use url::Url;
 
-use crate::Com;
+use super::ranking;
+use crate::{
+    config::{Config, Mirrors},
+    net, profiling, Com,
+};
 
 #[derive(Clone, Default, PartialEq, Eq)]
 struct TestResult {
     pub error: u32,
     pub total: Duration,
+    pub last_error: Option<ProbeError>,
 }
 
+/// Attempts made to probe each mirror.
+pub const ATTEMPTS: u32 = 5;
+
 impl TestResult {
-    const ATTEMPT_TIMES: u32 = 5;
+    const ATTEMPT_TIMES: u32 = ATTEMPTS;
 
     pub fn average(&self) -> Duration {
         self.total / (Self::ATTEMPT_TIMES - self.error)
@@ -52,67 +65,333 @@ impl Ord for TestResult {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct ProbeOptions {
+    /// timeout of each attempt
+    pub attempt_timeout: Duration,
+
+    /// time limit of probing all mirrors, attempts unfinished by then are treated as failures
+    pub budget: Duration,
+
+    /// mirrors configured besides the built-in ones
+    pub mirrors: Mirrors,
+
+    /// whether the ranking left by the last probing may be used instead of probing
+    pub use_ranking: bool,
+}
+
+impl ProbeOptions {
+    pub fn from_config(config: &Config) -> Self {
+        let profile = &config.profile;
+        Self {
+            attempt_timeout: Duration::from_secs(profile.probe_timeout.unwrap_or(10)),
+            budget: Duration::from_secs(profile.probe_budget.unwrap_or(30)),
+            mirrors: config.mirrors.clone(),
+            use_ranking: true,
+        }
+    }
+}
+
+/// Why a probing attempt failed.
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+pub enum ProbeError {
+    #[display(fmt = "DNS resolution failed")]
+    Dns,
+
+    #[display(fmt = "TLS handshake failed")]
+    Tls,
+
+    #[display(fmt = "connection failed")]
+    Connect,
+
+    #[display(fmt = "timed out")]
+    Timeout,
+
+    #[display(fmt = "HTTP {}", _0)]
+    Status(reqwest::StatusCode),
+
+    #[display(fmt = "{}", _0)]
+    Other(String),
+}
+
+impl From<reqwest::Error> for ProbeError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            return Self::Timeout;
+        }
+
+        // reqwest does not expose the failure stage, so look into the source chain
+        let mut messages = Vec::new();
+        let mut source: Option<&dyn StdError> = Some(&e);
+        while let Some(err) = source {
+            messages.push(err.to_string().to_lowercase());
+            source = err.source();
+        }
+        let chain = messages.join(": ");
+        if chain.contains("dns error") || chain.contains("failed to lookup address") {
+            Self::Dns
+        } else if chain.contains("tls") || chain.contains("certificate") || chain.contains("ssl") {
+            Self::Tls
+        } else if e.is_connect() {
+            Self::Connect
+        } else {
+            Self::Other(messages.pop().unwrap_or_default())
+        }
+    }
+}
+
+/// Probing result of a single mirror.
+#[derive(Debug, Clone)]
+pub struct MirrorReport {
+    pub mirror: String,
+    pub failed: u32,
+    pub average: Option<Duration>,
+    pub last_error: Option<ProbeError>,
+}
+
+/// Probing results of all mirrors of a component.
+#[derive(Debug, Clone)]
+pub struct ProbeReport(pub Vec<MirrorReport>);
+
+impl fmt::Display for ProbeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for report in &self.0 {
+            write!(
+                f,
+                "\n  {} -- {}/{} failed",
+                &report.mirror,
+                report.failed,
+                TestResult::ATTEMPT_TIMES
+            )?;
+            if let Some(average) = report.average {
+                write!(f, ", {}ms on average", average.as_millis())?;
+            }
+            if let Some(e) = &report.last_error {
+                write!(f, ", last error: {}", e)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Mirrors of something downloaded, e.g. the releases of Node.js.
+#[derive(Debug)]
+pub struct MirrorSource {
+    pub com: Com,
+    /// names the list in `[mirrors]` of the config, e.g. `nodejs`
+    pub key: &'static str,
+    pub builtin: &'static [&'static str],
+    /// file requested from a mirror to probe it, relative to the mirror
+    pub testfile: Option<&'static str>,
+}
+
+impl MirrorSource {
+    /// The mirrors configured for this source followed by the built-in ones.
+    pub fn mirrors(&self, opts: &ProbeOptions) -> Vec<String> {
+        match opts.mirrors.get(self.key) {
+            Some(list) => list.with_builtin(self.builtin),
+            None => self.builtin.iter().map(|url| url.to_string()).collect(),
+        }
+    }
+}
+
+/// Selects the mirror of `source` to download from: the one pinned in the config, or the
+/// fastest. The ranking left by the last probing is trusted if its best mirror still answers,
+/// otherwise all are probed and ranked again.
 pub async fn determine_mirror(
+    source: &MirrorSource,
+    opts: &ProbeOptions,
+) -> Result<String, ProbeReport> {
+    let MirrorSource {
+        com, key, testfile, ..
+    } = *source;
+    if let Some(pinned) = opts.mirrors.get(key).and_then(|list| list.pinned.as_ref()) {
+        tracing::debug!("[{}] {} is pinned", com, pinned);
+        return Ok(pinned.clone());
+    }
+    let mirrors = source.mirrors(opts);
+    let _span = profiling::span(com.to_string(), "select mirror");
+    if opts.use_ranking {
+        let best = ranking::load(key)
+            .and_then(|ranking| ranking.mirrors.into_iter().find(|m| mirrors.contains(m)));
+        if let Some(best) = best {
+            let answered = match test_url(&best, testfile) {
+                Ok(url) => answers(&url, opts.attempt_timeout).await,
+                Err(_) => false,
+            };
+            if answered {
+                tracing::debug!("[{}] {} is ranked the fastest", com, best);
+                return Ok(best);
+            }
+        }
+    }
+
+    let report = probe(com, &mirrors, testfile, opts).await;
+    let ranked = report
+        .0
+        .iter()
+        .filter(|r| r.average.is_some())
+        .map(|r| r.mirror.clone())
+        .collect::<Vec<_>>();
+    if let Err(e) = ranking::save(key, ranked.clone()) {
+        tracing::debug!("Failed to save the mirror ranking: {}", e);
+    }
+    ranked.into_iter().next().ok_or(report)
+}
+
+/// Mirrors of `source` to switch to when the selected ones fail, in the order of the ranking
+/// followed by those not ranked. None if a mirror is pinned, as it is chosen on purpose.
+pub fn fallbacks(source: &MirrorSource, tried: &[String], opts: &ProbeOptions) -> Vec<String> {
+    if opts
+        .mirrors
+        .get(source.key)
+        .is_some_and(|list| list.pinned.is_some())
+    {
+        return Vec::new();
+    }
+    let mirrors = source.mirrors(opts);
+    let mut ranked = ranking::load(source.key)
+        .map(|ranking| ranking.mirrors)
+        .unwrap_or_default();
+    ranked.retain(|m| mirrors.contains(m));
+    for mirror in mirrors {
+        if !ranked.contains(&mirror) {
+            ranked.push(mirror);
+        }
+    }
+    ranked.retain(|m| !tried.contains(m));
+    ranked
+}
+
+/// The URL requested to probe `mirror`, failing if the mirror, e.g. one edited into the config by
+/// hand, is not a valid URL.
+fn test_url(mirror: &str, testfile: Option<&str>) -> Result<Url, ProbeError> {
+    let url = Url::parse(mirror).and_then(|url| match testfile {
+        Some(file) => url.join(file),
+        None => Ok(url),
+    });
+    url.map_err(|e| ProbeError::Other(format!("invalid URL: {}", e)))
+}
+
+/// Whether `url` answers without an error status within `timeout`, as probing requires.
+async fn answers(url: &Url, timeout: Duration) -> bool {
+    let client = match net::client_builder().timeout(timeout).build() {
+        Ok(client) => client,
+        Err(_) => return false,
+    };
+    match client.get(url.clone()).send().await {
+        Ok(res) => !res.status().is_client_error() && !res.status().is_server_error(),
+        Err(_) => false,
+    }
+}
+
+/// Probes every mirror, returning the results the fastest first and those failed last.
+pub async fn probe(
     com: Com,
-    mirrors: Vec<&str>,
+    mirrors: &[String],
     testfile: Option<&str>,
-) -> Option<String> {
+    opts: &ProbeOptions,
+) -> ProbeReport {
     let (tx, mut rx) = mpsc::channel(16);
+    let mut handles = Vec::new();
 
+    let mut invalid = Vec::new();
     for (i, mirror) in mirrors.iter().enumerate() {
-        let url = match testfile {
-            Some(file) => Url::parse(mirror).unwrap().join(file),
-            None => Url::parse(mirror),
-        }
-        .unwrap();
+        let url = match test_url(mirror, testfile) {
+            Ok(url) => url,
+            Err(e) => {
+                // failed without any attempt
+                tracing::debug!("[{}] {} -- FAILED: {}", com, mirror, &e);
+                invalid.push((i, e));
+                continue;
+            }
+        };
         let tx = tx.clone();
-        tokio::spawn(async move {
+        let attempt_timeout = opts.attempt_timeout;
+        let track = format!("{} mirror {}", com, i);
+        handles.push(tokio::spawn(async move {
+            let client = net::client_builder()
+                .timeout(attempt_timeout)
+                .build()
+                .unwrap();
             for _ in 0..TestResult::ATTEMPT_TIMES {
                 let now = SystemTime::now();
 
-                tx.send((
-                    i,
-                    reqwest::Client::builder()
-                        .timeout(Duration::from_secs(10))
-                        .build()
-                        .unwrap()
-                        .get(url.clone())
-                        .send()
-                        .await
-                        .map_err(|_| ())
-                        .and_then(|_| now.elapsed().map_err(|_| ())),
-                ))
-                .await
-                .expect("mpsc send failed");
+                let span = profiling::span(&track, url.as_str());
+                let res = match client.get(url.clone()).send().await {
+                    Ok(res) if res.status().is_client_error() || res.status().is_server_error() => {
+                        Err(ProbeError::Status(res.status()))
+                    }
+                    Ok(_) => now.elapsed().map_err(|e| ProbeError::Other(e.to_string())),
+                    Err(e) => Err(ProbeError::from(e)),
+                };
+                drop(span);
+                if tx.send((i, res)).await.is_err() {
+                    // the receiver is gone because the budget is exhausted
+                    break;
+                }
             }
-        });
+        }));
     }
 
     // must drop here, otherwise the receiver will block forever
     std::mem::drop(tx);
 
     let mut results = vec![TestResult::default(); mirrors.len()];
+    let mut attempts = vec![0; mirrors.len()];
+    for (i, e) in invalid {
+        results[i].error = TestResult::ATTEMPT_TIMES;
+        results[i].last_error = Some(e);
+        attempts[i] = TestResult::ATTEMPT_TIMES;
+    }
 
-    while let Some((i, res)) = rx.recv().await {
-        let result = &mut results[i];
-        if let Ok(t) = res {
-            result.total += t;
-            log::debug!("[{}] {} -- {}ms", com, mirrors[i], t.as_millis());
-        } else {
-            result.error += 1;
-            log::debug!("[{}] {} -- FAILED", com, mirrors[i]);
+    let collect = async {
+        while let Some((i, res)) = rx.recv().await {
+            attempts[i] += 1;
+            let result = &mut results[i];
+            match res {
+                Ok(t) => {
+                    result.total += t;
+                    tracing::debug!("[{}] {} -- {}ms", com, mirrors[i], t.as_millis());
+                }
+                Err(e) => {
+                    result.error += 1;
+                    tracing::debug!("[{}] {} -- FAILED: {}", com, mirrors[i], &e);
+                    result.last_error = Some(e);
+                }
+            }
         }
-    }
+    };
 
-    results
-        .iter()
-        .enumerate()
-        .min_by(|(_, a), (_, b)| a.cmp(b))
-        .and_then(|(i, r)| {
-            if r.is_failed() {
-                None
-            } else {
-                Some(mirrors[i].to_owned())
+    if time::timeout(opts.budget, collect).await.is_err() {
+        tracing::debug!("[{}] mirror probing exceeded the time budget", com);
+        for handle in &handles {
+            handle.abort();
+        }
+        // attempts unfinished are failures
+        for (result, attempts) in results.iter_mut().zip(&attempts) {
+            if *attempts < TestResult::ATTEMPT_TIMES {
+                result.error += TestResult::ATTEMPT_TIMES - attempts;
+                result.last_error = Some(ProbeError::Timeout);
             }
-        })
+        }
+    }
+
+    let mut ranked = mirrors.iter().zip(results).collect::<Vec<_>>();
+    ranked.sort_by(|(_, a), (_, b)| a.cmp(b));
+    ProbeReport(
+        ranked
+            .into_iter()
+            .map(|(mirror, r)| MirrorReport {
+                mirror: mirror.to_owned(),
+                failed: r.error,
+                average: if r.is_failed() {
+                    None
+                } else {
+                    Some(r.average())
+                },
+                last_error: r.last_error,
+            })
+            .collect(),
+    )
 }
@@ -1,57 +1,99 @@
-use derive_more::{Constructor, Display, IsVariant};
-use std::{path::Path, result::Result as StdResult};
+use backoff::{backoff::Backoff, ExponentialBackoff};
+use derive_more::{Constructor, Display};
+use reqwest::{
+    header::{CONTENT_RANGE, RANGE, RETRY_AFTER},
+    redirect, StatusCode, Url,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    future::Future,
+    io::SeekFrom,
+    path::{Path, PathBuf},
+    result::Result as StdResult,
+    str::FromStr,
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+use tempfile::TempDir;
 use thiserror::Error as ThisError;
 use tokio::{
     fs::File,
-    io::AsyncWriteExt,
-    sync::broadcast::{error::RecvError, Receiver},
+    io::{AsyncSeekExt, AsyncWriteExt},
     time,
 };
 
-use super::helper::*;
+use super::{
+    cache,
+    helper::{
+        bundle::Bundle,
+        utils::{self, MirrorSource, ProbeOptions, ProbeReport},
+        *,
+    },
+    Dependencies, Metrics, Stage,
+};
 pub use crate::config::ComponentInfo;
-use crate::{config::Version, maybe_cmd, utils::sha256_file};
+use crate::{
+    audit,
+    config::{self, Components, Config, HydroChannel, PackageManager, Version},
+    log_i18n, net,
+    platform::{self, Arch},
+    profiling, progress,
+    registry::{self, Descriptor},
+    temp::TempManager,
+    tr,
+    utils::{
+        decode_output, did_you_mean, format_size, sha256_file, sha256_file_async, CappedOutput,
+    },
+};
 
 #[derive(ThisError, Debug, Constructor)]
-#[error("Failed to install {com}: {kind}")]
+#[error("{}", tr!("install.failed", .com, .kind))]
 pub struct Error {
     pub com: Com,
     #[source]
     pub kind: ErrorKind,
 }
 
-#[derive(Debug, Display, ThisError)]
+#[derive(Debug, ThisError)]
 pub enum ErrorKind {
     // TODO: more error kind
-    #[display(fmt = "{}", _0)]
-    RecvError(#[from] RecvError),
-
-    #[display(fmt = "require {}", _0)]
+    #[error("{}", tr!("install.require", .0))]
     DependencyError(Com),
 
-    #[display(fmt = "your platform is not supported yet")]
+    #[error("{}", tr!("install.platform-not-supported"))]
     PlatformNotSupported,
 
-    #[display(fmt = "no available source")]
-    NoAvailableSource,
+    #[error("{}{}", tr!("install.no-available-source"), .0)]
+    NoAvailableSource(ProbeReport),
 
-    #[display(fmt = "{}", _0)]
+    #[error("{0}")]
     IOError(#[from] std::io::Error),
 
-    #[display(fmt = "{}", _0)]
+    #[error("{0}")]
     RequestError(#[from] reqwest::Error),
 
-    #[display(fmt = "{}", _0)]
+    #[error("{0}")]
     RespError(reqwest::StatusCode),
 
-    #[display(fmt = "file checksum mismatch")]
+    /// the server is busy and tells when to retry with `Retry-After`
+    #[error("{0}")]
+    Throttled(reqwest::StatusCode, Duration),
+
+    #[error("{}", tr!("install.redirect-rejected", .0))]
+    RedirectRejected(Url),
+
+    #[error("{}", tr!("install.checksum-mismatch"))]
     ChecksumMismatch,
 
-    #[display(fmt = "{}", _0)]
+    #[error("{}\n{}", tr!("install.command-failed", .0), .1)]
+    CommandFailed(String, String),
+
+    #[error("{0}")]
     Other(String),
 }
 
-#[derive(Debug, Display, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Com {
     #[display(fmt = "Node.js")]
     NodeJS,
@@ -69,345 +111,1326 @@ pub enum Com {
     Hydro,
 }
 
-#[derive(Debug, IsVariant, Clone)]
-pub enum Signal<'a> {
-    Ready(Com, &'a ComponentInfo),
-    Failed(Com),
+impl Com {
+    pub const ALL: [Com; 7] = [
+        Com::NodeJS,
+        Com::MongoDB,
+        Com::MinIO,
+        Com::Sandbox,
+        Com::Yarn,
+        Com::PM2,
+        Com::Hydro,
+    ];
+
+    /// Components required by this one.
+    pub fn dependencies(self) -> &'static [Com] {
+        match self {
+            Com::Yarn | Com::PM2 => &[Com::NodeJS],
+            Com::Hydro => &[Com::NodeJS, Com::Yarn],
+            _ => &[],
+        }
+    }
+
+    /// The name used on the command line.
+    pub fn name(self) -> &'static str {
+        registry::get(self).name
+    }
+
+    /// Components requiring this one, directly or indirectly.
+    pub fn dependents(self) -> Vec<Com> {
+        Self::ALL
+            .iter()
+            .copied()
+            .filter(|com| com.dependencies().contains(&self))
+            .collect()
+    }
 }
 
-macro_rules! ident2com {
-    (nodejs) => {
-        Com::NodeJS
-    };
-    (mongodb) => {
-        Com::MongoDB
-    };
-    (minio) => {
-        Com::MinIO
-    };
-    (sandbox) => {
-        Com::Sandbox
-    };
-    (yarn) => {
-        Com::Yarn
-    };
-    (pm2) => {
-        Com::PM2
-    };
-    (hydro) => {
-        Com::Hydro
-    };
+impl FromStr for Com {
+    type Err = String;
+
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        match registry::find(s) {
+            Some(Descriptor { com: Some(com), .. }) => Ok(*com),
+            Some(desc) => Err(tr!("component.optional", desc.name)),
+            None => match did_you_mean(&s.to_lowercase(), registry::names()) {
+                Some(name) => Err(tr!("component.unknown-suggest", s, name)),
+                None => Err(tr!("component.unknown", s)),
+            },
+        }
+    }
 }
 
-macro_rules! wait_for_components {
-    ($com:expr, $rx:expr, $($dep_com:expr),+ $(,)?) => {{
-        let mut coms = vec![$($dep_com),+];
-        let mut res = vec![$(::either::Left($dep_com)),+];
-        while !coms.is_empty() {
-            match $rx.recv().await.map_err(|e| Error::new($com, ErrorKind::RecvError(e)))? {
-                Signal::Ready(com, info) => {
-                    if let Some(pos) = coms.iter().position(|x| *x == com) {
-                        coms.swap_remove(pos);
-                        let pos = res
-                            .iter()
-                            .position(|x| matches!(*x, ::either::Left(y) if y == com))
-                            .unwrap();
-                        res[pos] = ::either::Right(info);
-                    }
-                }
-                Signal::Failed(com) => {
-                    if let Some(_) = coms.iter().position(|x| *x == com) {
-                        return Err(Error::new($com, ErrorKind::DependencyError(com)));
-                    }
-                }
+pub type Result<T> = StdResult<T, Error>;
+
+/// An artifact provided by the admin instead of downloaded, e.g. copied in with scp.
+#[derive(Debug, Clone)]
+pub struct LocalFile {
+    pub com: Com,
+    pub path: PathBuf,
+    /// SHA-256 it must have, otherwise the built-in one or that in the cached manifest
+    pub checksum: Option<String>,
+}
+
+impl FromStr for LocalFile {
+    type Err = String;
+
+    /// Parses `[component=]path`, where the component is told by the file name if omitted,
+    /// e.g. `node-v14.17.3-linux-x64.tar.gz`.
+    fn from_str(s: &str) -> StdResult<Self, Self::Err> {
+        let (com, path) = match s.split_once('=') {
+            Some((com, path)) => (com.parse()?, PathBuf::from(path)),
+            None => {
+                let path = PathBuf::from(s);
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+                let com = [
+                    ("node-", Com::NodeJS),
+                    ("mongodb-", Com::MongoDB),
+                    ("minio", Com::MinIO),
+                    ("executorserver", Com::Sandbox),
+                    ("sandbox", Com::Sandbox),
+                ]
+                .iter()
+                .find(|(prefix, _)| name.starts_with(prefix))
+                .map(|(_, com)| *com)
+                .ok_or_else(|| tr!("install.file-unknown-component", s))?;
+                (com, path)
             }
+        };
+        if !matches!(com, Com::NodeJS | Com::MongoDB | Com::MinIO | Com::Sandbox) {
+            return Err(tr!("install.file-not-supported", com));
         }
-        res
-        .into_iter()
-        .map(|x| x.right().unwrap())
-        .collect::<Vec<_>>()
-    }};
-    {($com:expr, $rx:expr) @ [$($dep_com:tt),+ $(,)?] => $run:expr} => {{
-        let mut rx = $rx.expect("Receiver cannot be `None`");
-        if let [$($dep_com),+] = *wait_for_components!($com, rx, $(ident2com!($dep_com)),+) {
-            $run($($dep_com),+).await
-        } else {
-            unreachable!()
+        Ok(Self {
+            com,
+            path,
+            checksum: None,
+        })
+    }
+}
+
+/// A list of the artifacts copied to an air-gapped server, e.g.
+///
+/// ```json
+/// { "files": [{ "component": "nodejs", "path": "node-v14.17.3-linux-x64.tar.xz", "sha256": "..." }] }
+/// ```
+///
+/// Paths are relative to the manifest.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct OfflineManifest {
+    pub files: Vec<OfflineFile>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OfflineFile {
+    pub component: String,
+    pub path: String,
+    /// SHA-256 of the file, otherwise the built-in one or that in the cached manifest is used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl OfflineManifest {
+    /// Reads the manifest at `path` into the files it lists.
+    pub fn load(path: &Path) -> StdResult<Vec<LocalFile>, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| tr!("install.manifest-invalid", path.display(), e))?;
+        let manifest = serde_json::from_str::<Self>(&text)
+            .map_err(|e| tr!("install.manifest-invalid", path.display(), e))?;
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        manifest
+            .files
+            .into_iter()
+            .map(|file| {
+                let mut local = LocalFile::from_str(&format!("{}={}", file.component, file.path))?;
+                local.path = dir.join(&local.path);
+                local.checksum = file.sha256.map(|checksum| checksum.to_lowercase());
+                Ok(local)
+            })
+            .collect()
+    }
+}
+
+/// Settings shared by all installers.
+#[derive(Debug)]
+pub struct Options {
+    pub temp: TempManager,
+    pub probe: ProbeOptions,
+    pub metrics: Metrics,
+    pub hydro_channel: HydroChannel,
+    pub package_manager: PackageManager,
+    pub yarn_corepack: bool,
+    /// version of Yarn pinned by corepack, set once Yarn is installed by it
+    pub yarn_pinned: Mutex<Option<String>>,
+    /// artifacts provided instead of downloaded
+    pub files: Vec<LocalFile>,
+    /// LTS line of Node.js whose newest release is installed, e.g. `18`
+    pub nodejs_lts: Option<String>,
+    /// release of Node.js installed instead of the newest LTS one
+    pub nodejs_version: Option<semver::Version>,
+    /// release of the sandbox installed instead of the latest one on GitHub
+    pub sandbox_version: Option<semver::Version>,
+    /// installs from the files provided only, without touching the network
+    pub offline: bool,
+    /// bundle which Yarn, PM2 and Hydro are installed from instead of the registry
+    pub bundle: Option<Bundle>,
+}
+
+impl Options {
+    fn local_file(&self, com: Com) -> Option<&LocalFile> {
+        self.files.iter().find(|file| file.com == com)
+    }
+
+    pub fn from_config(config: &Config) -> std::io::Result<Self> {
+        let profile = &config.profile;
+        Ok(Self {
+            temp: TempManager::new(profile.tmp_dir.as_ref())?,
+            probe: ProbeOptions::from_config(config),
+            metrics: Metrics::default(),
+            hydro_channel: profile.hydro.channel,
+            package_manager: profile.hydro.package_manager,
+            yarn_corepack: profile.yarn.corepack,
+            yarn_pinned: Mutex::default(),
+            files: Vec::new(),
+            nodejs_lts: profile.nodejs.lts.clone(),
+            nodejs_version: pinned_version(Com::NodeJS, profile.nodejs.version.as_deref()),
+            sandbox_version: pinned_version(Com::Sandbox, profile.sandbox.version.as_deref()),
+            offline: false,
+            bundle: None,
+        })
+    }
+}
+
+/// Parses the release of `com` pinned in the profile, e.g. `16.20.2` or `v16.20.2`.
+pub fn pinned_version(com: Com, version: Option<&str>) -> Option<semver::Version> {
+    let version = version?;
+    match semver::Version::parse(version.trim_start_matches('v')) {
+        Ok(version) => Some(version),
+        Err(_) => {
+            log_i18n!(warn, [com], "install.invalid-pin", version);
+            None
         }
-    }};
+    }
 }
 
-pub type Result<T> = StdResult<T, Error>;
+#[tracing::instrument(name = "install", skip(com, deps, opts), fields(com = %com))]
+pub async fn install(com: Com, deps: Dependencies, opts: &Options) -> Result<(Com, ComponentInfo)> {
+    let deps = deps
+        .wait()
+        .await
+        .map_err(|dep| Error::new(com, ErrorKind::DependencyError(dep)))?;
+    // the info of the dependencies is in the order of `Com::dependencies`
+    match (com, deps.as_slice()) {
+        // must await each, because `impl Future<Output = T>` is an opaque type
+        (Com::NodeJS, []) => install_nodejs(opts).await,
+        (Com::MongoDB, []) => install_mongodb(None, opts).await,
+        (Com::MinIO, []) => install_minio(opts).await,
+        (Com::Sandbox, []) => install_sandbox(opts).await,
+        (Com::Yarn, [nodejs]) => install_yarn(opts, nodejs).await,
+        (Com::PM2, [nodejs]) => install_pm2(opts, nodejs).await,
+        (Com::Hydro, [nodejs, yarn]) => install_hydro(opts, nodejs, yarn).await,
+        (com, _) => unreachable!("Dependencies of {} do not match", com),
+    }
+    .map(|ok| (com, ok))
+    .map_err(|e| Error::new(com, e))
+}
 
-pub async fn install(com: Com, rx: Option<Receiver<Signal<'_>>>) -> Result<(Com, ComponentInfo)> {
+/// Installs the `series` release (e.g. `6.0`) of `com` over the current installation, used by
+/// update plans which must go through each release series in turn.
+pub async fn install_series(com: Com, series: &str, opts: &Options) -> Result<ComponentInfo> {
     match com {
-        // must await each, because `impl Future<Output = T>` is an opaque type
-        Com::NodeJS => install_nodejs().await,
-        Com::MongoDB => install_mongodb().await,
-        Com::MinIO => install_minio().await,
-        Com::Sandbox => install_sandbox().await,
-        Com::Yarn => wait_for_components! {
-            (com, rx) @ [nodejs] => install_yarn
-        },
-        Com::PM2 => wait_for_components! {
-            (com, rx) @ [nodejs] => install_pm2
-        },
-        Com::Hydro => wait_for_components! {
-            (com, rx) @ [nodejs, yarn] => install_hydro
-        },
+        Com::MongoDB => install_mongodb(Some(series), opts).await,
+        _ => Err(ErrorKind::Other(format!(
+            "installing a specific release series of {} is not supported",
+            com
+        ))),
+    }
+    .map_err(|e| Error::new(com, e))
+}
+
+/// Installs the release of `com` H2O2 would install now over the current installation, used by
+/// update plans. Yarn and PM2 are installed with the Node.js in `components`, while MongoDB and
+/// Hydro are updated by their own steps.
+pub async fn upgrade(com: Com, components: &Components, opts: &Options) -> Result<ComponentInfo> {
+    match com {
+        Com::NodeJS => install_nodejs(opts).await,
+        Com::MinIO => install_minio(opts).await,
+        Com::Sandbox => install_sandbox(opts).await,
+        Com::Yarn => install_yarn(opts, &components.nodejs).await,
+        Com::PM2 => install_pm2(opts, &components.nodejs).await,
+        Com::MongoDB | Com::Hydro => Err(ErrorKind::Other(format!(
+            "{} is updated by its own steps",
+            com
+        ))),
     }
+    .map_err(|e| Error::new(com, e))
+}
+
+/// Downloads and verifies the artifact of `com` without installing it, then moves it
+/// into the cache dir.
+#[tracing::instrument(name = "download", skip(com, opts), fields(com = %com))]
+pub async fn download(com: Com, opts: &Options) -> Result<(Com, PathBuf)> {
+    match com {
+        Com::NodeJS => fetch_nodejs(opts).await.map(|(artifact, _)| artifact),
+        Com::MongoDB => fetch_mongodb(None, opts)
+            .await
+            .map(|(artifact, _)| artifact),
+        Com::MinIO => fetch_minio(opts).await,
+        Com::Sandbox => fetch_sandbox(opts).await.map(|(artifact, _)| artifact),
+        Com::Yarn | Com::PM2 | Com::Hydro => Err(ErrorKind::Other(
+            "installed by the package manager, cannot be downloaded separately".to_owned(),
+        )),
+    }
+    .and_then(|artifact| {
+        let cache_path = config::get_cache_path();
+        std::fs::create_dir_all(&cache_path).map_err(ErrorKind::IOError)?;
+        let target = cache_path.join(&artifact.filename);
+        std::fs::copy(&artifact.path, &target).map_err(ErrorKind::IOError)?;
+        Ok(target)
+    })
     .map(|ok| (com, ok))
     .map_err(|e| Error::new(com, e))
 }
 
 type InstallResult<T> = StdResult<T, ErrorKind>;
 
-async fn install_nodejs() -> InstallResult<ComponentInfo> {
-    log::info!("开始安装 Node.js... Start to install Node.js...");
+/// The binary of `com` installed into the H2O2 dir, whose checksum is recorded by
+/// [`record_install`] and checked again by `h2o2 verify`.
+pub fn managed_binary(com: Com, info: &ComponentInfo) -> Option<PathBuf> {
+    let path = PathBuf::from(info.path.as_ref()?);
+    let path = match com {
+        // the `bin` dir is recorded on Unix
+        Com::NodeJS if path.is_dir() => path.join(platform::exe("node")),
+        Com::NodeJS | Com::MongoDB | Com::MinIO | Com::Sandbox => path,
+        Com::Yarn | Com::PM2 | Com::Hydro => return None,
+    };
+    Some(path).filter(|path| path.starts_with(config::get_com_path()))
+}
 
-    log::info!("[Node.js] 寻找最快的下载源... Finding the fastest download source...");
-    let dist = nodejs::determine_mirror()
-        .await
-        .ok_or(ErrorKind::NoAvailableSource)?;
-    let (postfix, shasum256) = nodejs::BIN_INFO;
-    let filename = format!("node-v14.17.3{}", postfix);
-    let url = format!("{}v14.17.3/{}", &dist, &filename);
-    log::info!("[Node.js] {}", &url);
+/// Appends the installation to the audit log, which `h2o2 show <component>` reads.
+fn record_install(com: Com, info: &ComponentInfo, artifact: Option<&Artifact>) {
+    let mut details = serde_json::json!({
+        "version": info.version.to_string(),
+        "path": info.path,
+    });
+    if let Some(artifact) = artifact {
+        let sha256 = artifact
+            .sha256
+            .clone()
+            .or_else(|| sha256_file(&artifact.path).ok());
+        details["url"] = artifact.url.clone().into();
+        details["sha256"] = serde_json::json!(sha256);
+    }
+    if let Some(binary) = managed_binary(com, info) {
+        details["binary_sha256"] = serde_json::json!(sha256_file(binary).ok());
+    }
+    let entry = audit::Entry::new("install", Some(com.name().to_owned()), details);
+    if let Err(e) = audit::append(&entry) {
+        tracing::debug!("Failed to write the audit log: {}", e);
+    }
+}
 
-    let dir = tempfile::tempdir().map_err(ErrorKind::IOError)?;
-    let path = dir.path().join(&filename);
-    let mut file = File::create(&path).await.map_err(ErrorKind::IOError)?;
+/// Lines of command output attached to an error.
+const SNIPPET_LINES: usize = 20;
 
-    log::info!("[Node.js] 开始下载... Downloading...");
-    let mut res = reqwest::get(url).await.map_err(ErrorKind::RequestError)?;
-    if !res.status().is_success() {
-        return Err(ErrorKind::RespError(res.status()));
-    }
+fn log_full_output() {
+    log_i18n!(
+        error,
+        "install.full-output-logged",
+        crate::log::get_log_path().display()
+    );
+}
 
-    while let Some(chunk) = res.chunk().await.map_err(ErrorKind::RequestError)? {
-        file.write_all(&chunk).await.map_err(ErrorKind::IOError)?;
+/// A downloaded and verified file, removed together with its temp dir when dropped.
+struct Artifact {
+    _dir: TempDir,
+    filename: String,
+    path: PathBuf,
+    url: String,
+    /// set if the checksum is verified while fetching
+    sha256: Option<String>,
+}
+
+/// Attempts made to download a file, each but the first resuming from the partial file.
+const DOWNLOAD_ATTEMPTS: usize = 4;
+
+/// Longest `Retry-After` waited for, beyond which another mirror is tried instead.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(120);
+
+#[tracing::instrument(name = "stage", skip(com, url, path, opts), fields(stage = %Stage::Download))]
+async fn download_file(com: Com, url: &str, path: &Path, opts: &Options) -> InstallResult<()> {
+    let start = time::Instant::now();
+    let _span = profiling::span(com.to_string(), format!("download {}", url));
+    let mut file = File::create(path).await.map_err(ErrorKind::IOError)?;
+
+    log_i18n!(info, [com], "install.downloading");
+    opts.metrics.begin(com, Stage::Download);
+    let mut backoff = ExponentialBackoff {
+        initial_interval: Duration::from_secs(2),
+        max_interval: Duration::from_secs(30),
+        max_elapsed_time: None,
+        ..Default::default()
+    };
+    let client = download_client(url, opts);
+    let mut partial = Partial::default();
+    for attempt in 1.. {
+        match fetch_into(com, &client, url, &mut file, &mut partial).await {
+            Ok(()) => break,
+            // the URL signed by a CDN may have expired, so the mirror is asked for a new one
+            Err(ErrorKind::RespError(status))
+                if attempt < DOWNLOAD_ATTEMPTS
+                    && status.is_client_error()
+                    && partial.url.is_some() =>
+            {
+                tracing::debug!("[{}] {} from the redirect target, retrying", com, status);
+                partial.url = None;
+            }
+            Err(ErrorKind::Throttled(status, delay))
+                if attempt < DOWNLOAD_ATTEMPTS && delay <= MAX_RETRY_AFTER =>
+            {
+                log_i18n!(
+                    warn,
+                    [com],
+                    "install.download-throttled",
+                    status,
+                    delay.as_secs(),
+                    attempt,
+                    DOWNLOAD_ATTEMPTS
+                );
+                time::sleep(delay).await;
+            }
+            Err(e) if attempt < DOWNLOAD_ATTEMPTS && is_transient(&e) => {
+                log_i18n!(
+                    warn,
+                    [com],
+                    "install.download-interrupted",
+                    e,
+                    format_size(partial.downloaded),
+                    attempt,
+                    DOWNLOAD_ATTEMPTS
+                );
+                if let Some(delay) = backoff.next_backoff() {
+                    time::sleep(delay).await;
+                }
+            }
+            Err(e) => return Err(e),
+        }
     }
+    drop(partial);
 
     file.sync_all().await.map_err(ErrorKind::IOError)?;
-    log::info!("[Node.js] 下载完毕。 Download completed.");
+    log_i18n!(info, [com], "install.downloaded");
+    opts.metrics.record(com, Stage::Download, start.elapsed());
+    if let Ok(meta) = file.metadata().await {
+        opts.metrics.record_size(com, meta.len());
+    }
 
-    if sha256_file(&path).map_err(ErrorKind::IOError)? != shasum256 {
-        log::info!("[Node.js] 文件校验失败！ File checksum mismatch!");
+    Ok(())
+}
+
+/// Downloads `url` to `path` and verifies it against `expected`, reusing the cached download if
+/// there is one. Returns the checksum.
+async fn download_verified(
+    com: Com,
+    url: &str,
+    expected: &str,
+    path: &Path,
+    opts: &Options,
+) -> InstallResult<String> {
+    let filename = path.file_name().unwrap().to_string_lossy();
+    match cache::restore(expected, &filename, path) {
+        Ok(true) => {
+            log_i18n!(info, [com], "install.cache-hit");
+            return Ok(expected.to_owned());
+        }
+        Ok(false) => {}
+        Err(e) => tracing::debug!("Failed to read the download cache: {}", e),
+    }
+
+    download_file(com, url, path, opts).await?;
+    let checksum = opts
+        .metrics
+        .measure_async(com, Stage::Verify, sha256_file_async(path.to_owned()))
+        .await
+        .map_err(ErrorKind::IOError)?;
+    if checksum != expected {
+        log_i18n!(info, [com], "install.checksum-failed");
         return Err(ErrorKind::ChecksumMismatch);
     }
+    if let Err(e) = cache::store(path, &checksum, &filename) {
+        tracing::debug!("Failed to cache the download: {}", e);
+    }
+    Ok(checksum)
+}
+
+/// What is downloaded so far, kept across attempts.
+#[derive(Default)]
+struct Partial {
+    downloaded: u64,
+    bar: Option<progress::Download>,
+    /// where the mirror redirected to, e.g. a CDN, which the rest is asked from
+    url: Option<Url>,
+}
+
+/// Hosts downloads may be redirected to besides the mirrors, such as the CDNs of GitHub.
+const REDIRECT_HOSTS: &[&str] = &[
+    "objects.githubusercontent.com",
+    "release-assets.githubusercontent.com",
+    "fastdl.mongodb.org",
+    "downloads.mongodb.org",
+];
 
-    let path = nodejs::do_install(&path).map_err(ErrorKind::IOError)?;
+/// Redirects followed at most, as many as browsers do.
+const MAX_REDIRECTS: usize = 10;
 
-    Ok(ComponentInfo::new(
-        Version::Valid(semver::Version::parse("14.17.3").unwrap()),
-        Some(path),
-    ))
+#[derive(ThisError, Debug)]
+#[error("redirect to {0} is not allowed")]
+struct RejectedRedirect(Url);
+
+/// A client following redirects only to the mirrors, the hosts in [`REDIRECT_HOSTS`] and those
+/// listed in the cached manifest, so that a mirror cannot hand the download to anywhere else.
+fn download_client(url: &str, opts: &Options) -> reqwest::Client {
+    let mut hosts = [
+        &nodejs::MIRRORS,
+        &minio::MIRRORS,
+        &minio::MC_MIRRORS,
+        &sandbox::MIRRORS,
+    ]
+    .iter()
+    .flat_map(|source| source.mirrors(&opts.probe))
+    .chain(std::iter::once(url.to_owned()))
+    .filter_map(|mirror| Some(Url::parse(&mirror).ok()?.host_str()?.to_owned()))
+    .collect::<Vec<_>>();
+    hosts.extend(REDIRECT_HOSTS.iter().map(|host| host.to_string()));
+    if let Ok(Some(manifest)) = crate::check::Manifest::cached() {
+        hosts.extend(manifest.redirect_hosts);
+    }
+
+    let policy = redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            return attempt.error("too many redirects");
+        }
+        let allowed = attempt.url().host_str().is_some_and(|host| {
+            hosts.iter().any(|allowed| {
+                // subdomains are allowed as well
+                host.strip_suffix(allowed.as_str())
+                    .is_some_and(|sub| sub.is_empty() || sub.ends_with('.'))
+            })
+        });
+        if allowed {
+            attempt.follow()
+        } else {
+            let url = attempt.url().clone();
+            attempt.error(RejectedRedirect(url))
+        }
+    });
+    net::client_builder()
+        .redirect(policy)
+        .build()
+        .expect("Failed to build the HTTP client")
 }
 
-async fn install_mongodb() -> InstallResult<ComponentInfo> {
-    log::info!("开始安装 MongoDB... Start to install MongoDB...");
+/// Downloads `url` into `file`, asking for the rest after what is downloaded if anything is.
+/// Starts over if the server ignores the range.
+async fn fetch_into(
+    com: Com,
+    client: &reqwest::Client,
+    url: &str,
+    file: &mut File,
+    partial: &mut Partial,
+) -> InstallResult<()> {
+    let mut req = client.get(partial.url.as_ref().map_or(url, Url::as_str));
+    if partial.downloaded > 0 {
+        req = req.header(RANGE, format!("bytes={}-", partial.downloaded));
+    }
+    let mut res = req.send().await.map_err(|e| {
+        let rejected = std::error::Error::source(&e)
+            .and_then(|source| source.downcast_ref::<RejectedRedirect>());
+        match rejected {
+            Some(RejectedRedirect(url)) => ErrorKind::RedirectRejected(url.clone()),
+            None => ErrorKind::RequestError(e),
+        }
+    })?;
+    if res.url().as_str() != url && partial.url.as_ref() != Some(res.url()) {
+        log_i18n!(info, [com], "install.redirected", res.url());
+        partial.url = Some(res.url().clone());
+    }
+    let status = res.status();
+    if !status.is_success() {
+        let busy = [
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::SERVICE_UNAVAILABLE,
+        ];
+        return Err(match retry_after(&res) {
+            Some(delay) if busy.contains(&status) => ErrorKind::Throttled(status, delay),
+            _ => ErrorKind::RespError(status),
+        });
+    }
+    let resumed = res.status() == StatusCode::PARTIAL_CONTENT
+        && resumed_from(&res) == Some(partial.downloaded);
+    if partial.downloaded > 0 && !resumed {
+        log_i18n!(info, [com], "install.download-restarted");
+        file.set_len(0).await.map_err(ErrorKind::IOError)?;
+        file.seek(SeekFrom::Start(0))
+            .await
+            .map_err(ErrorKind::IOError)?;
+        partial.downloaded = 0;
+        partial.bar = None;
+    }
 
-    if cfg!(target_arch = "x86") {
-        log::error!("[MongoDB] x86 架构不受支持。 The x86 architecture is not supported.");
-        return Err(ErrorKind::PlatformNotSupported);
+    let downloaded = partial.downloaded;
+    let bar = partial.bar.get_or_insert_with(|| {
+        progress::download(
+            com.name(),
+            com,
+            res.content_length().map(|len| downloaded + len),
+        )
+    });
+    while let Some(chunk) = res.chunk().await.map_err(ErrorKind::RequestError)? {
+        file.write_all(&chunk).await.map_err(ErrorKind::IOError)?;
+        partial.downloaded += chunk.len() as u64;
+        bar.inc(chunk.len() as u64);
     }
+    Ok(())
+}
 
-    time::sleep(time::Duration::from_secs(20)).await;
+/// The first byte of a partial response, read from `Content-Range: bytes <first>-<last>/<size>`.
+fn resumed_from(res: &reqwest::Response) -> Option<u64> {
+    let range = res.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    let (first, _) = range.strip_prefix("bytes ")?.split_once('-')?;
+    first.parse().ok()
+}
 
-    Err(ErrorKind::Other("not yet implemented".to_owned()))
+/// Downloads `file`, relative to a mirror, from the mirror `dist` of `source` with `fetch`, which
+/// is given the URL. If the mirror answers with an error, such as missing the file or being busy
+/// for too long, the next mirror ranked is tried. Returns the URL downloaded and the output.
+async fn fetch_from_mirror<F, Fut, T>(
+    source: &MirrorSource,
+    dist: String,
+    file: &str,
+    opts: &Options,
+    mut fetch: F,
+) -> InstallResult<(String, T)>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = InstallResult<T>>,
+{
+    let mut tried = vec![dist];
+    let mut fallbacks = None;
+    loop {
+        let url = format!("{}{}", tried.last().unwrap(), file);
+        tracing::info!("[{}] {}", source.com, &url);
+        let e = match fetch(url.clone()).await {
+            Ok(output) => return Ok((url, output)),
+            Err(
+                e @ (ErrorKind::RespError(_)
+                | ErrorKind::Throttled(..)
+                | ErrorKind::RedirectRejected(_)),
+            ) => e,
+            Err(e) => return Err(e),
+        };
+        let next = fallbacks
+            .get_or_insert_with(|| utils::fallbacks(source, &tried, &opts.probe).into_iter())
+            .next();
+        match next {
+            Some(next) => {
+                log_i18n!(
+                    warn,
+                    [source.com],
+                    "install.mirror-switched",
+                    tried.last().unwrap(),
+                    e,
+                    &next
+                );
+                tried.push(next);
+            }
+            None => return Err(e),
+        }
+    }
 }
 
-async fn install_minio() -> InstallResult<ComponentInfo> {
-    log::info!("开始安装 MinIO... Start to install MinIO...");
+/// The delay asked by `Retry-After`, in seconds or as an HTTP date.
+fn retry_after(res: &reqwest::Response) -> Option<Duration> {
+    let value = res.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    match value.parse() {
+        Ok(secs) => Some(Duration::from_secs(secs)),
+        Err(_) => httpdate::parse_http_date(value)
+            .ok()
+            .map(|at| at.duration_since(SystemTime::now()).unwrap_or_default()),
+    }
+}
 
-    if cfg!(target_arch = "x86") {
-        log::error!("[MinIO] x86 架构不受支持。 The x86 architecture is not supported.");
-        return Err(ErrorKind::PlatformNotSupported);
+/// Whether retrying may help, as the network or the server may recover. Other client errors,
+/// such as a file missing from the mirror, are permanent.
+fn is_transient(e: &ErrorKind) -> bool {
+    match e {
+        ErrorKind::RequestError(_) => true,
+        ErrorKind::RespError(status) => {
+            status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+        }
+        _ => false,
+    }
+}
+
+/// Verifies the artifact `file` provided and copies it into a temp dir to be installed like a
+/// downloaded one. Its checksum is the one given, that in `listed` (e.g. `SHASUMS256.txt`
+/// next to it), or that in the cached manifest.
+fn use_local_file(
+    file: &LocalFile,
+    listed: Option<&HashMap<String, String>>,
+    opts: &Options,
+) -> InstallResult<Artifact> {
+    let com = file.com;
+    let filename = file
+        .path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    log_i18n!(info, [com], "install.using-file", file.path.display());
+    let expected = match &file.checksum {
+        Some(checksum) => Some(checksum.to_lowercase()),
+        None => listed
+            .and_then(|listed| listed.get(&filename).cloned())
+            .or_else(|| {
+                crate::check::Manifest::cached()
+                    .ok()
+                    .flatten()
+                    .and_then(|manifest| manifest.checksums.get(&filename).cloned())
+            }),
+    };
+    let expected = match expected {
+        Some(expected) => expected,
+        None => {
+            log_i18n!(error, [com], "install.file-no-checksum", &filename);
+            return Err(ErrorKind::ChecksumMismatch);
+        }
+    };
+
+    let checksum = opts
+        .metrics
+        .measure(com, Stage::Verify, || sha256_file(&file.path))
+        .map_err(ErrorKind::IOError)?;
+    if checksum != expected {
+        log_i18n!(info, [com], "install.checksum-failed");
+        return Err(ErrorKind::ChecksumMismatch);
     }
 
-    log::info!("[MinIO] 寻找最快的下载源... Finding the fastest download source...");
-    let dist = minio::determine_mirror()
+    let dir = opts.temp.tempdir().map_err(ErrorKind::IOError)?;
+    let path = dir.path().join(&filename);
+    std::fs::copy(&file.path, &path).map_err(ErrorKind::IOError)?;
+    Ok(Artifact {
+        _dir: dir,
+        url: format!(
+            "file://{}",
+            std::fs::canonicalize(&file.path)
+                .unwrap_or_else(|_| file.path.clone())
+                .display()
+        ),
+        filename,
+        path,
+        sha256: Some(checksum),
+    })
+}
+
+async fn fetch_nodejs(opts: &Options) -> InstallResult<(Artifact, semver::Version)> {
+    if let Some(file) = opts.local_file(Com::NodeJS) {
+        let listed = nodejs::local_shasums(&file.path);
+        let artifact = use_local_file(file, listed.as_ref(), opts)?;
+        // e.g. `node-v14.17.3-linux-x64.tar.gz`
+        let version = artifact
+            .filename
+            .strip_prefix("node-v")
+            .and_then(|rest| rest.split('-').next())
+            .and_then(|version| semver::Version::parse(version).ok())
+            .ok_or_else(|| {
+                ErrorKind::Other(tr!("install.file-unknown-version", &artifact.filename))
+            })?;
+        return Ok((artifact, version));
+    }
+
+    log_i18n!(info, [Com::NodeJS], "install.finding-source");
+    let dist = opts
+        .metrics
+        .measure_async(
+            Com::NodeJS,
+            Stage::Probe,
+            nodejs::determine_mirror(&opts.probe),
+        )
         .await
-        .ok_or(ErrorKind::NoAvailableSource)?;
-    let file = minio::BIN_INFO;
-    let url = format!("{}{}", &dist, file);
+        .map_err(ErrorKind::NoAvailableSource)?;
+    let version = match &opts.nodejs_version {
+        Some(version) => version.to_string(),
+        None => nodejs_lts(&dist, opts.nodejs_lts.as_deref())
+            .await?
+            .to_string(),
+    };
+    let (filename, expected) = nodejs_tarball(&dist, &version).await?;
+
+    let dir = opts.temp.tempdir().map_err(ErrorKind::IOError)?;
+    let path = dir.path().join(&filename);
+    let file = format!("v{}/{}", version, &filename);
+    let (url, checksum) = fetch_from_mirror(&nodejs::MIRRORS, dist, &file, opts, |url| {
+        let (path, expected) = (&path, &expected);
+        async move { download_verified(Com::NodeJS, &url, expected, path, opts).await }
+    })
+    .await?;
+
+    let artifact = Artifact {
+        _dir: dir,
+        filename,
+        path,
+        url,
+        sha256: Some(checksum),
+    };
+    Ok((artifact, semver::Version::parse(&version).unwrap()))
+}
 
-    log::info!("[MinIO] {}", &url);
+/// The newest LTS release of `line` listed by the mirror `dist`, or of the newest line if `None`.
+async fn nodejs_lts(dist: &str, line: Option<&str>) -> InstallResult<semver::Version> {
+    let line_name = line.unwrap_or("*");
+    match nodejs::newest_lts(dist, line).await {
+        Ok(version) => {
+            log_i18n!(
+                info,
+                [Com::NodeJS],
+                "install.nodejs-lts",
+                &version,
+                line_name
+            );
+            Ok(version)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(ErrorKind::Other(tr!(
+            "install.nodejs-lts-not-found",
+            line_name
+        ))),
+        Err(e) => {
+            log_i18n!(error, [Com::NodeJS], "install.nodejs-index-failed", &e);
+            Err(ErrorKind::IOError(e))
+        }
+    }
+}
 
-    let dir = tempfile::tempdir().map_err(ErrorKind::IOError)?;
-    let path = dir.path().join("minio");
-    let mut file = File::create(&path).await.map_err(ErrorKind::IOError)?;
+/// The file of the Node.js `version` to download and its checksum, listed in `SHASUMS256.txt`
+/// of `dist`. The `.tar.xz` is preferred where there is one.
+async fn nodejs_tarball(dist: &str, version: &str) -> InstallResult<(String, String)> {
+    let shasums = nodejs::shasums(dist, version).await.map_err(|e| {
+        log_i18n!(error, [Com::NodeJS], "install.shasums-failed", version, &e);
+        ErrorKind::IOError(e)
+    })?;
+    let tarball = format!("node-v{}{}", version, nodejs::BIN_INFO);
 
-    log::info!("[MinIO] 开始下载... Downloading...");
-    let mut res = reqwest::get(url).await.map_err(ErrorKind::RequestError)?;
-    if !res.status().is_success() {
-        return Err(ErrorKind::RespError(res.status()));
+    let xz = nodejs::xz_postfix().map(|postfix| format!("node-v{}{}", version, postfix));
+    if let Some((xz, checksum)) = xz.and_then(|xz| shasums.get(&xz).map(|c| (xz, c.clone()))) {
+        log_i18n!(info, [Com::NodeJS], "install.smaller-archive", &xz);
+        return Ok((xz, checksum));
     }
+    match shasums.get(&tarball) {
+        Some(checksum) => Ok((tarball, checksum.clone())),
+        None => Err(ErrorKind::Other(tr!(
+            "install.version-not-found",
+            Com::NodeJS,
+            version
+        ))),
+    }
+}
 
-    while let Some(chunk) = res.chunk().await.map_err(ErrorKind::RequestError)? {
-        file.write_all(&chunk).await.map_err(ErrorKind::IOError)?;
+async fn install_nodejs(opts: &Options) -> InstallResult<ComponentInfo> {
+    log_i18n!(info, "install.start", Com::NodeJS);
+
+    let (artifact, version) = fetch_nodejs(opts).await?;
+    let path = opts
+        .metrics
+        .measure(Com::NodeJS, Stage::Install, || {
+            nodejs::do_install(&artifact.path)
+        })
+        .map_err(ErrorKind::IOError)?;
+
+    let info = ComponentInfo::new(Version::Valid(version), Some(path));
+    record_install(Com::NodeJS, &info, Some(&artifact));
+    Ok(info)
+}
+
+/// Fetches the latest release of the `series` (e.g. `6.0`), or of the latest series if `None`.
+async fn fetch_mongodb(
+    series: Option<&str>,
+    opts: &Options,
+) -> InstallResult<(Artifact, semver::Version)> {
+    if platform::current().arch == Arch::X86 {
+        log_i18n!(error, [Com::MongoDB], "install.x86-not-supported");
+        return Err(ErrorKind::PlatformNotSupported);
+    }
+    if let Some(file) = opts.local_file(Com::MongoDB) {
+        let artifact = use_local_file(file, None, opts)?;
+        // e.g. `mongodb-linux-x86_64-ubuntu2204-7.0.12.tgz`
+        let version = artifact
+            .filename
+            .trim_end_matches(".tgz")
+            .trim_end_matches(".zip")
+            .rsplit('-')
+            .next()
+            .and_then(|version| semver::Version::parse(version).ok())
+            .ok_or_else(|| {
+                ErrorKind::Other(tr!("install.file-unknown-version", &artifact.filename))
+            })?;
+        return Ok((artifact, version));
     }
+    let target = match mongodb::download_target() {
+        Some(target) => target,
+        None => {
+            log_i18n!(error, [Com::MongoDB], "install.mongodb-unknown-distro");
+            return Err(ErrorKind::PlatformNotSupported);
+        }
+    };
 
-    file.sync_all().await.map_err(ErrorKind::IOError)?;
-    log::info!("[MinIO] 下载完毕。 Download completed.");
+    log_i18n!(info, [Com::MongoDB], "install.finding-source");
+    let release = opts
+        .metrics
+        .measure_async(
+            Com::MongoDB,
+            Stage::Probe,
+            mongodb::find_release(&target, series),
+        )
+        .await
+        .map_err(ErrorKind::IOError)?;
+    let release = match release {
+        Some(release) => release,
+        None => {
+            log_i18n!(
+                error,
+                [Com::MongoDB],
+                "install.mongodb-no-build",
+                series.unwrap_or(mongodb::RELEASE_SERIES.last().unwrap()),
+                &target,
+                platform::current().arch
+            );
+            return Err(ErrorKind::PlatformNotSupported);
+        }
+    };
+    let url = release.url;
+    tracing::info!("[MongoDB] {}", &url);
 
-    let path = minio::do_install(&path).map_err(ErrorKind::IOError)?;
+    let dir = opts.temp.tempdir().map_err(ErrorKind::IOError)?;
+    let filename = url.rsplit('/').next().unwrap_or("mongodb.tgz").to_owned();
+    let path = dir.path().join(&filename);
+    let checksum = download_verified(Com::MongoDB, &url, &release.sha256, &path, opts).await?;
 
-    Ok(ComponentInfo::new(Version::Installed, Some(path)))
+    let artifact = Artifact {
+        _dir: dir,
+        filename,
+        path,
+        url,
+        sha256: Some(checksum),
+    };
+    Ok((artifact, release.version))
 }
 
-async fn install_sandbox() -> InstallResult<ComponentInfo> {
-    log::info!("开始安装 sandbox... Start to install sandbox...");
+async fn install_mongodb(series: Option<&str>, opts: &Options) -> InstallResult<ComponentInfo> {
+    log_i18n!(info, "install.start", Com::MongoDB);
+
+    let (artifact, version) = fetch_mongodb(series, opts).await?;
+    let path = opts
+        .metrics
+        .measure(Com::MongoDB, Stage::Install, || {
+            mongodb::do_install(&artifact.path)
+        })
+        .map_err(ErrorKind::IOError)?;
 
-    if cfg!(target_arch = "x86") {
-        log::error!("[sandbox] x86 架构不受支持。 The x86 architecture is not supported.");
+    let info = ComponentInfo::new(Version::Valid(version), Some(path));
+    record_install(Com::MongoDB, &info, Some(&artifact));
+    Ok(info)
+}
+
+async fn fetch_minio(opts: &Options) -> InstallResult<Artifact> {
+    if platform::current().arch == Arch::X86 {
+        log_i18n!(error, [Com::MinIO], "install.x86-not-supported");
         return Err(ErrorKind::PlatformNotSupported);
     }
+    if let Some(file) = opts.local_file(Com::MinIO) {
+        return use_local_file(file, None, opts);
+    }
 
-    log::info!("[sandbox] 寻找最快的下载源... Finding the fastest download source...");
-    let dist = sandbox::determine_mirror()
+    log_i18n!(info, [Com::MinIO], "install.finding-source");
+    let dist = opts
+        .metrics
+        .measure_async(
+            Com::MinIO,
+            Stage::Probe,
+            minio::determine_mirror(&opts.probe),
+        )
         .await
-        .ok_or(ErrorKind::NoAvailableSource)?;
-    let postfix = sandbox::BIN_INFO;
-    let url = format!("{}executorserver-{}", &dist, postfix);
+        .map_err(ErrorKind::NoAvailableSource)?;
+    let dir = opts.temp.tempdir().map_err(ErrorKind::IOError)?;
+    let filename = platform::exe("minio");
+    let path = dir.path().join(&filename);
+    let (url, ()) = fetch_from_mirror(&minio::MIRRORS, dist, minio::BIN_INFO, opts, |url| {
+        let path = &path;
+        async move { download_file(Com::MinIO, &url, path, opts).await }
+    })
+    .await?;
+
+    Ok(Artifact {
+        _dir: dir,
+        filename,
+        path,
+        url,
+        sha256: None,
+    })
+}
+
+async fn install_minio(opts: &Options) -> InstallResult<ComponentInfo> {
+    log_i18n!(info, "install.start", Com::MinIO);
 
-    log::info!("[sandbox] {}", &url);
+    let artifact = fetch_minio(opts).await?;
+    let path = opts
+        .metrics
+        .measure(Com::MinIO, Stage::Install, || {
+            minio::do_install(&artifact.path)
+        })
+        .map_err(ErrorKind::IOError)?;
+
+    let info = ComponentInfo::new(Version::Installed, Some(path));
+    record_install(Com::MinIO, &info, Some(&artifact));
+    Ok(info)
+}
 
-    let dir = tempfile::tempdir().map_err(ErrorKind::IOError)?;
-    let path = dir.path().join("sandbox");
-    let mut file = File::create(&path).await.map_err(ErrorKind::IOError)?;
+/// Installs the MinIO client `mc` and returns its path.
+pub async fn install_mc(opts: &Options) -> Result<String> {
+    async {
+        log_i18n!(info, "install.start", "mc");
 
-    log::info!("[sandbox] 开始下载... Downloading...");
-    let mut res = reqwest::get(url).await.map_err(ErrorKind::RequestError)?;
-    if !res.status().is_success() {
-        return Err(ErrorKind::RespError(res.status()));
+        if platform::current().arch == Arch::X86 {
+            log_i18n!(error, [Com::MinIO], "install.x86-not-supported");
+            return Err(ErrorKind::PlatformNotSupported);
+        }
+
+        log_i18n!(info, [Com::MinIO], "install.finding-source");
+        let dist = minio::determine_mc_mirror(&opts.probe)
+            .await
+            .map_err(ErrorKind::NoAvailableSource)?;
+        let dir = opts.temp.tempdir().map_err(ErrorKind::IOError)?;
+        let path = dir.path().join("mc");
+        let file = minio::MC_BIN_INFO;
+        fetch_from_mirror(&minio::MC_MIRRORS, dist, file, opts, |url| {
+            let path = &path;
+            async move { download_file(Com::MinIO, &url, path, opts).await }
+        })
+        .await?;
+
+        minio::do_install_mc(&path).map_err(ErrorKind::IOError)
     }
+    .await
+    .map_err(|e| Error::new(Com::MinIO, e))
+}
 
-    while let Some(chunk) = res.chunk().await.map_err(ErrorKind::RequestError)? {
-        file.write_all(&chunk).await.map_err(ErrorKind::IOError)?;
+/// Downloads the sandbox, returning the release downloaded, which is unknown for a file provided.
+async fn fetch_sandbox(opts: &Options) -> InstallResult<(Artifact, Option<semver::Version>)> {
+    if platform::current().arch == Arch::X86 {
+        log_i18n!(error, [Com::Sandbox], "install.x86-not-supported");
+        return Err(ErrorKind::PlatformNotSupported);
+    }
+    if let Some(file) = opts.local_file(Com::Sandbox) {
+        return use_local_file(file, None, opts).map(|artifact| (artifact, None));
     }
 
-    file.sync_all().await.map_err(ErrorKind::IOError)?;
-    log::info!("[sandbox] 下载完毕。 Download completed.");
+    log_i18n!(info, [Com::Sandbox], "install.finding-source");
+    let dist = opts
+        .metrics
+        .measure_async(
+            Com::Sandbox,
+            Stage::Probe,
+            sandbox::determine_mirror(&opts.probe),
+        )
+        .await
+        .map_err(ErrorKind::NoAvailableSource)?;
+    let version = match &opts.sandbox_version {
+        Some(version) => version.clone(),
+        None => sandbox_release().await,
+    };
+    let filename = format!("executorserver-{}", sandbox::BIN_INFO);
 
-    let path = sandbox::do_install(&path).map_err(ErrorKind::IOError)?;
+    let dir = opts.temp.tempdir().map_err(ErrorKind::IOError)?;
+    let path = dir.path().join(&filename);
+    let file = format!(
+        "{}{}",
+        sandbox::release_path(&version.to_string()),
+        &filename
+    );
+    let (url, ()) = fetch_from_mirror(&sandbox::MIRRORS, dist, &file, opts, |url| {
+        let path = &path;
+        async move { download_file(Com::Sandbox, &url, path, opts).await }
+    })
+    .await?;
 
-    Ok(ComponentInfo::new(Version::Installed, Some(path)))
+    let artifact = Artifact {
+        _dir: dir,
+        filename,
+        path,
+        url,
+        sha256: None,
+    };
+    Ok((artifact, Some(version)))
 }
 
-async fn install_yarn(nodejs: &ComponentInfo) -> InstallResult<ComponentInfo> {
-    log::info!("开始安装 Yarn... Start to install Yarn...");
+/// The latest release of the sandbox on GitHub, or [`sandbox::VERSION`] if GitHub cannot be
+/// queried, e.g. when the rate limit is exceeded.
+async fn sandbox_release() -> semver::Version {
+    match sandbox::latest_release().await {
+        Ok(version) => {
+            log_i18n!(info, [Com::Sandbox], "install.sandbox-latest", &version);
+            version
+        }
+        Err(e) => {
+            log_i18n!(
+                warn,
+                [Com::Sandbox],
+                "install.sandbox-latest-failed",
+                &e,
+                sandbox::VERSION,
+                net::GITHUB_TOKEN_ENV
+            );
+            semver::Version::parse(sandbox::VERSION).unwrap()
+        }
+    }
+}
 
-    duct::cmd!(nodejs.path("npm"), "install", "--global", "yarn")
-        .stdout_capture()
-        .stderr_capture()
-        .unchecked()
-        .run()
-        .map_err(ErrorKind::IOError)
-        .and_then(|output| {
-            if output.status.success() {
-                log::info!(
-                    "[Yarn] 安装已完成，获取安装目录... Installation finished. Getting path..."
-                );
-                let path = String::from_utf8(
-                    duct::cmd!(nodejs.path("npm"), "bin", "--global")
-                        .stdout_capture()
-                        .stderr_null()
-                        .run()
-                        .map_err(ErrorKind::IOError)?
-                        .stdout,
-                )
-                .map_err(|_| ErrorKind::Other("failed to convert output".into()))?;
-                let path = Path::new(&path)
-                    .join(maybe_cmd!("yarn"))
-                    .to_string_lossy()
-                    .into_owned();
-                log::info!("[Yarn] 获取版本... Getting version...");
-                let version = String::from_utf8(
-                    duct::cmd!(&path, "--version")
-                        .stdout_capture()
-                        .stderr_null()
-                        .run()
-                        .map_err(ErrorKind::IOError)?
-                        .stdout,
-                )
-                .map_err(|_| ErrorKind::Other("failed to convert output".into()))?;
-                let version = semver::Version::parse(version.trim())
-                    .map_err(|e| ErrorKind::Other(format!("invalid semver: {}", e)))?;
-                Ok(ComponentInfo::new(Version::Valid(version), Some(path)))
-            } else {
-                log::error!(
-                    "[Yarn] `npm install --global yarn`: {}\nstdout:\n{}\nstderr:\n{}",
-                    &output.status,
-                    String::from_utf8_lossy(&output.stdout),
-                    String::from_utf8_lossy(&output.stderr),
-                );
-                Err(ErrorKind::Other(
-                    "`npm install --global yarn` failed".into(),
-                ))
-            }
+async fn install_sandbox(opts: &Options) -> InstallResult<ComponentInfo> {
+    log_i18n!(info, "install.start", Com::Sandbox);
+
+    let (artifact, version) = fetch_sandbox(opts).await?;
+    let path = opts
+        .metrics
+        .measure(Com::Sandbox, Stage::Install, || {
+            sandbox::do_install(&artifact.path)
         })
+        .map_err(ErrorKind::IOError)?;
+
+    // the version of a file provided is unknown
+    let version = version.map_or(Version::Installed, Version::Valid);
+    let info = ComponentInfo::new(version, Some(path));
+    record_install(Com::Sandbox, &info, Some(&artifact));
+    Ok(info)
 }
 
-async fn install_pm2(nodejs: &ComponentInfo) -> InstallResult<ComponentInfo> {
-    log::info!("开始安装 PM2... Start to install PM2...");
+async fn install_yarn(opts: &Options, nodejs: &ComponentInfo) -> InstallResult<ComponentInfo> {
+    log_i18n!(info, "install.start", Com::Yarn);
+
+    // corepack downloads Yarn itself, which a bundle cannot provide
+    if opts.yarn_corepack && opts.bundle.is_none() {
+        match nodejs.version().and_then(corepack::yarn_version) {
+            Some(version) => return install_yarn_by_corepack(opts, nodejs, version),
+            None => log_i18n!(
+                warn,
+                [Com::Yarn],
+                "install.corepack-unavailable",
+                &nodejs.version
+            ),
+        }
+    }
 
-    duct::cmd!(nodejs.path("npm"), "install", "--global", "pm2")
-        .stdout_capture()
-        .stderr_capture()
-        .unchecked()
-        .run()
+    let start = time::Instant::now();
+    opts.metrics.begin(Com::Yarn, Stage::Install);
+    let res = match &opts.bundle {
+        Some(bundle) => bundle.npm_install(&nodejs.path("npm"), "yarn"),
+        None => {
+            npm::run_with_retry(
+                Com::Yarn,
+                &nodejs.path("npm"),
+                &["install", "--global", "yarn"],
+            )
+            .await
+        }
+    }
+    .map_err(ErrorKind::IOError)
+    .and_then(|output| {
+        if output.status.success() {
+            log_i18n!(info, [Com::Yarn], "install.getting-path");
+            let path = npm::global_bin(&nodejs.path("npm"))
+                .map_err(ErrorKind::IOError)?
+                .join(platform::cmd("yarn"))
+                .to_string_lossy()
+                .into_owned();
+            log_i18n!(info, [Com::Yarn], "install.getting-version");
+            let version = decode_output(
+                &duct::cmd!(&path, "--version")
+                    .stdout_capture()
+                    .stderr_null()
+                    .run()
+                    .map_err(ErrorKind::IOError)?
+                    .stdout,
+            );
+            let version = semver::Version::parse(version.trim())
+                .map_err(|e| ErrorKind::Other(format!("invalid semver: {}", e)))?;
+            let info = ComponentInfo::new(Version::Valid(version), Some(path));
+            record_install(Com::Yarn, &info, None);
+            Ok(info)
+        } else {
+            log_full_output();
+            Err(ErrorKind::CommandFailed(
+                "npm install --global yarn".into(),
+                output.snippet(SNIPPET_LINES),
+            ))
+        }
+    });
+    opts.metrics
+        .record(Com::Yarn, Stage::Install, start.elapsed());
+    res
+}
+
+fn install_yarn_by_corepack(
+    opts: &Options,
+    nodejs: &ComponentInfo,
+    version: &str,
+) -> InstallResult<ComponentInfo> {
+    log_i18n!(info, [Com::Yarn], "install.corepack-pinning", version);
+
+    let start = time::Instant::now();
+    opts.metrics.begin(Com::Yarn, Stage::Install);
+    let res = corepack::enable_yarn(nodejs, version)
         .map_err(ErrorKind::IOError)
-        .and_then(|output| {
-            if output.status.success() {
-                log::info!(
-                    "[PM2] 安装已完成，获取安装目录... Installation finished. Getting path..."
-                );
-                let path = String::from_utf8(
-                    duct::cmd!(nodejs.path("npm"), "bin", "--global")
-                        .stdout_capture()
-                        .stderr_null()
-                        .run()
-                        .map_err(ErrorKind::IOError)?
-                        .stdout,
-                )
-                .map_err(|_| ErrorKind::Other("failed to convert output".into()))?;
-                let path = Path::new(&path)
-                    .join(maybe_cmd!("pm2"))
-                    .to_string_lossy()
-                    .into_owned();
-                log::info!("[PM2] 获取版本... Getting version...");
-                let version = String::from_utf8(
-                    duct::cmd!(&path, "-v", "-s", "--no-daemon")
-                        .stdout_capture()
-                        .stderr_null()
-                        .run()
-                        .map_err(ErrorKind::IOError)?
-                        .stdout,
-                )
-                .map_err(|_| ErrorKind::Other("failed to convert output".into()))?;
-                let version = semver::Version::parse(version.trim())
-                    .map_err(|e| ErrorKind::Other(format!("invalid semver: {}", e)))?;
-                Ok(ComponentInfo::new(Version::Valid(version), Some(path)))
-            } else {
-                log::error!(
-                    "[PM2] `npm install --global pm2`: {}\nstdout:\n{}\nstderr:\n{}",
-                    &output.status,
-                    String::from_utf8_lossy(&output.stdout),
-                    String::from_utf8_lossy(&output.stderr),
-                );
-                Err(ErrorKind::Other("`npm install --global pm2` failed".into()))
-            }
-        })
+        .and_then(|path| {
+            log_i18n!(info, [Com::Yarn], "install.getting-version");
+            let version = decode_output(
+                &duct::cmd!(&path, "--version")
+                    .stdout_capture()
+                    .stderr_null()
+                    .run()
+                    .map_err(ErrorKind::IOError)?
+                    .stdout,
+            );
+            let version = semver::Version::parse(version.trim())
+                .map_err(|e| ErrorKind::Other(format!("invalid semver: {}", e)))?;
+            *opts.yarn_pinned.lock().unwrap() = Some(version.to_string());
+            let info = ComponentInfo::new(Version::Valid(version), Some(path));
+            record_install(Com::Yarn, &info, None);
+            Ok(info)
+        });
+    opts.metrics
+        .record(Com::Yarn, Stage::Install, start.elapsed());
+    res
+}
+
+async fn install_pm2(opts: &Options, nodejs: &ComponentInfo) -> InstallResult<ComponentInfo> {
+    log_i18n!(info, "install.start", Com::PM2);
+
+    let start = time::Instant::now();
+    opts.metrics.begin(Com::PM2, Stage::Install);
+    let res = match &opts.bundle {
+        Some(bundle) => bundle.npm_install(&nodejs.path("npm"), "pm2"),
+        None => {
+            npm::run_with_retry(
+                Com::PM2,
+                &nodejs.path("npm"),
+                &["install", "--global", "pm2"],
+            )
+            .await
+        }
+    }
+    .map_err(ErrorKind::IOError)
+    .and_then(|output| {
+        if output.status.success() {
+            log_i18n!(info, [Com::PM2], "install.getting-path");
+            let path = npm::global_bin(&nodejs.path("npm"))
+                .map_err(ErrorKind::IOError)?
+                .join(platform::cmd("pm2"))
+                .to_string_lossy()
+                .into_owned();
+            log_i18n!(info, [Com::PM2], "install.getting-version");
+            let version = decode_output(
+                &duct::cmd!(&path, "-v", "-s", "--no-daemon")
+                    .stdout_capture()
+                    .stderr_null()
+                    .run()
+                    .map_err(ErrorKind::IOError)?
+                    .stdout,
+            );
+            let version = semver::Version::parse(version.trim())
+                .map_err(|e| ErrorKind::Other(format!("invalid semver: {}", e)))?;
+            let info = ComponentInfo::new(Version::Valid(version), Some(path));
+            record_install(Com::PM2, &info, None);
+            Ok(info)
+        } else {
+            log_full_output();
+            Err(ErrorKind::CommandFailed(
+                "npm install --global pm2".into(),
+                output.snippet(SNIPPET_LINES),
+            ))
+        }
+    });
+    opts.metrics
+        .record(Com::PM2, Stage::Install, start.elapsed());
+    res
 }
 
 async fn install_hydro(
-    _nodejs: &ComponentInfo,
-    _yarn: &ComponentInfo,
+    opts: &Options,
+    nodejs: &ComponentInfo,
+    yarn: &ComponentInfo,
 ) -> InstallResult<ComponentInfo> {
-    log::info!("开始安装 Hydro... Start to install Hydro...");
+    log_i18n!(info, "install.start", Com::Hydro);
 
-    time::sleep(time::Duration::from_secs(5)).await;
+    let start = time::Instant::now();
+    opts.metrics.begin(Com::Hydro, Stage::Install);
+    let res = match &opts.bundle {
+        Some(bundle) => install_hydro_from_bundle(bundle, nodejs, yarn),
+        None => {
+            let pm = jspm::select(opts.package_manager, nodejs, yarn);
+            let args = pm.install_global(&hydro::package_spec(opts.hydro_channel));
+            jspm::run(pm.as_ref(), Com::Hydro, &args, None)
+                .await
+                .map_err(ErrorKind::IOError)
+                .map(|output| (pm, args, output))
+        }
+    }
+    .and_then(|(pm, args, output)| {
+        if output.status.success() {
+            log_i18n!(info, [Com::Hydro], "install.getting-path");
+            // Hydro is recorded by the global dir of the package manager, where its packages are
+            let global_dir = pm.global_dir().map_err(ErrorKind::IOError)?;
+            log_i18n!(info, [Com::Hydro], "install.getting-version");
+            let version =
+                hydro::installed_version(Path::new(&global_dir)).map_err(ErrorKind::IOError)?;
+            let info = ComponentInfo::new(Version::Valid(version), Some(global_dir));
+            record_install(Com::Hydro, &info, None);
+            Ok(info)
+        } else {
+            log_full_output();
+            Err(ErrorKind::CommandFailed(
+                jspm::display(pm.as_ref(), &args),
+                output.snippet(SNIPPET_LINES),
+            ))
+        }
+    });
+    opts.metrics
+        .record(Com::Hydro, Stage::Install, start.elapsed());
+    res
+}
 
-    Err(ErrorKind::Other("not yet implemented".to_owned()))
+/// Installs Hydro with Yarn from the mirror of `bundle`, whatever the package manager
+/// preferred, as the bundle holds the packages in the lockfile of Yarn.
+fn install_hydro_from_bundle(
+    bundle: &Bundle,
+    nodejs: &ComponentInfo,
+    yarn: &ComponentInfo,
+) -> InstallResult<(Box<dyn jspm::JsPm>, Vec<String>, CappedOutput)> {
+    let meta = bundle.meta().map_err(ErrorKind::IOError)?;
+    let pm = jspm::select(PackageManager::Yarn, nodejs, yarn);
+    let global_dir = pm.global_dir().map_err(ErrorKind::IOError)?;
+    if !bundle
+        .seed_global_dir(Path::new(&global_dir))
+        .map_err(ErrorKind::IOError)?
+    {
+        log_i18n!(
+            warn,
+            [Com::Hydro],
+            "install.bundle-lockfile-kept",
+            &global_dir
+        );
+    }
+    let args = pm.install_global(&meta.hydro);
+    let output = bundle
+        .yarn_offline(pm.program(), &args)
+        .map_err(ErrorKind::IOError)?;
+    Ok((pm, args, output))
 }
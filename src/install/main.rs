@@ -1,16 +1,33 @@
 use anyhow::{bail, Context, Result};
 use clap::Clap;
 use futures::{stream::FuturesUnordered, StreamExt};
-use std::path::Path;
-use tokio::{fs, sync::broadcast};
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+use tokio::fs;
 
 use crate::{
     check_version,
-    config::{self, Config, ConfigError},
-    install::{install, Com, ComponentInfo, Signal},
-    maybe_cmd,
+    config::{
+        self, Components, Config, ConfigError, MinIOProfile, MongoDBProfile, Profile,
+        SandboxProfile,
+    },
+    install::{
+        download, install, install_mc, pinned_version, Com, ComponentInfo, LocalFile, Metrics,
+        OfflineManifest, Options, TaskGraph,
+    },
+    log_i18n, net,
+    platform::{self, Os, ServiceManager},
+    progress, telemetry,
+    temp::TempManager,
+    tr,
+    utils::random_token,
 };
 
+use super::helper::{bundle::Bundle, hydro, jspm, minio, mongodb, sandbox, utils::ProbeOptions};
+
 macro_rules! run {
     ($($arg:expr),*) => {
         ::duct::cmd!($($arg),*)
@@ -23,14 +40,14 @@ macro_rules! run {
 macro_rules! expect {
     ($output:expr => valid => semver) => {
         $output.map_err(|_| ()).and_then(|output| {
-            let stdout = String::from_utf8(output.stdout).map_err(|_| ())?;
+            let stdout = $crate::utils::decode_output(&output.stdout);
             let stdout = stdout.lines().next().unwrap_or("").trim();
             ::semver::Version::parse(&stdout).map_err(|_| ())
         })
     };
     ($output:expr => $prefix:expr => semver) => {
         $output.map_err(|_| ()).and_then(|output| {
-            let stdout = String::from_utf8(output.stdout).map_err(|_| ())?;
+            let stdout = $crate::utils::decode_output(&output.stdout);
             let stdout = stdout.lines().next().unwrap_or("").trim();
             if stdout.len() <= $prefix.len() {
                 return Err(());
@@ -46,7 +63,7 @@ macro_rules! expect {
     };
     ($output:expr => starts with $prefix:expr) => {
         $output.map_err(|_| ()).and_then(|output| {
-            let stdout = String::from_utf8(output.stdout).map_err(|_| ())?;
+            let stdout = $crate::utils::decode_output(&output.stdout);
             let stdout = stdout.trim();
             if stdout.starts_with($prefix) {
                 Ok($crate::config::Version::Installed)
@@ -60,209 +77,652 @@ macro_rules! expect {
 #[derive(Clap, Debug)]
 #[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
 pub struct Args {
+    /// 要安装的组件，默认为全部，所依赖的组件会一并安装，如 hydro 需要 nodejs 与 yarn
+    /// Components to install, defaults to all, along with those they depend on, e.g. hydro requires nodejs and yarn
+    components: Vec<Com>,
+
     /// 不加载配置文件
     /// Runs without loading config
     #[clap(long)]
     no_config: bool,
+
+    /// 临时文件目录，默认为系统临时目录
+    /// Directory for temporary files, defaults to the system temp dir
+    #[clap(long)]
+    tmp_dir: Option<String>,
+
+    /// 仅下载并校验组件到缓存目录，不进行安装
+    /// Downloads and verifies components into the cache dir without installing them
+    #[clap(long)]
+    only_download: bool,
+
+    /// 测试下载源时每次请求的超时时间（秒）
+    /// Timeout of each request when probing mirrors, in seconds
+    #[clap(long)]
+    timeout: Option<u64>,
+
+    /// 完成后输出各组件的耗时与文件大小
+    /// Prints the duration of each stage and the artifact size of each component when finished
+    #[clap(long)]
+    metrics: bool,
+
+    /// 使用已下载的文件安装组件，可指定多次，组件名可由文件名推断，如 `node-v14.17.3-linux-x64.tar.gz`
+    /// Installs a component from a file downloaded already, which can be given multiple times. The component is told by the file name if omitted, e.g. `node-v14.17.3-linux-x64.tar.gz`
+    #[clap(
+        long = "file",
+        alias = "from-file",
+        value_name = "[COMPONENT=]PATH",
+        number_of_values = 1
+    )]
+    files: Vec<LocalFile>,
+
+    /// `--file` 所给文件的 SHA-256，用于没有内置校验值的组件
+    /// SHA-256 of the file given by `--file`, for components without a built-in checksum
+    #[clap(long, requires = "files")]
+    checksum: Option<String>,
+
+    /// 离线清单（JSON），列出各组件已下载的文件及其 SHA-256，隐含 `--offline`
+    /// Offline manifest (JSON) listing the files downloaded for each component and their SHA-256, which implies `--offline`
+    #[clap(long, value_name = "FILE")]
+    offline_manifest: Option<PathBuf>,
+
+    /// 不访问网络，仅安装给出了文件的组件，适用于无法联网的服务器
+    /// Installs only the components whose files are given without touching the network, for air-gapped servers
+    #[clap(long)]
+    offline: bool,
+
+    /// 安装指定版本的 Node.js，如 `16.20.2`，校验值从下载源的 SHASUMS256.txt 获取，默认为配置中固定的版本或所配置 LTS 系列的最新版本
+    /// Installs the given release of Node.js, e.g. `16.20.2`, whose checksum is read from SHASUMS256.txt of the mirror, defaults to the one pinned in the config or the newest of the LTS line configured
+    #[clap(long, value_name = "VERSION")]
+    nodejs_version: Option<String>,
+
+    /// 安装指定版本的 sandbox（go-judge），如 `1.2.4`，默认为配置中固定的版本或 GitHub 上的最新版本
+    /// Installs the given release of the sandbox (go-judge), e.g. `1.2.4`, defaults to the one pinned in the config or the latest on GitHub
+    #[clap(long, value_name = "VERSION")]
+    sandbox_version: Option<String>,
+
+    /// bundle made by `h2o2 bundle create`, given by `h2o2 bundle install`
+    #[clap(skip)]
+    bundle: Option<PathBuf>,
+}
+
+impl Args {
+    /// Installs `components` from the bundle in `dir`, for `h2o2 bundle install`.
+    pub fn from_bundle(dir: PathBuf, components: Vec<Com>) -> Self {
+        let mut args = Self::parse_from(["install"]);
+        args.components = components;
+        args.bundle = Some(dir);
+        args
+    }
 }
 
 pub async fn main(args: Args) -> Result<()> {
     // FIXME: support macos
-    if cfg!(target_os = "macos") {
+    if platform::current().os == Os::MacOS {
         bail!("Platform is not supported");
     }
 
+    let current = platform::current();
+    if let Some(container) = current.container {
+        if current.service_manager == ServiceManager::None {
+            log_i18n!(warn, "install.container-detected", container);
+        }
+    }
+
     let mut config = if args.no_config {
-        log::info!("当前模式将不加载配置文件。 Skipped config loading.");
+        log_i18n!(info, "config.skip-loading");
         // always reinstall sandbox
         Config::default()
     } else {
         match config::load_config().await {
             Ok(config) => {
-                log::info!("已成功加载配置。 Config loaded successfully.");
+                log_i18n!(info, "config.loaded");
                 config
             }
-            Err(e) => {
-                match e {
-                    ConfigError::FileNotExist => {
-                        log::info!("配置文件不存在，开始初始化。 Config file does not exist, start initialization.");
-                    }
-                    e => {
-                        log::error!("加载配置失败！准备尝试重新初始化。 Failed to load config! Try to reinitialize.");
-                        log::debug!("{:#?}", e);
-                    }
-                };
+            Err(ConfigError::FileNotExist) => {
+                log_i18n!(info, "config.initializing");
                 Config::default()
             }
+            // installing on defaults would overwrite the profile and secrets when saving
+            Err(e) => return Err(e.into()),
         }
     };
+    // the proxy is set up while loading, and from the environment otherwise
+    net::configure(config.profile.proxy.as_deref());
 
     let com_path = config::get_com_path();
     if !Path::new(&com_path).is_dir() {
         fs::create_dir(&com_path)
             .await
-            .context("创建目录失败！ Failed to create directory!")?;
+            .context(tr!("sys.create-dir-failed"))?;
+    }
+
+    let role = config.profile.role;
+    for &com in &args.components {
+        if !role.runs(com) {
+            log_i18n!(warn, "install.not-in-role", com, role);
+        } else if config.profile.is_external(com) {
+            log_i18n!(warn, "install.external", com);
+        }
+    }
+    let wanted = with_dependencies(&args.components, &config.profile);
+    let added = wanted
+        .iter()
+        .filter(|com| !args.components.is_empty() && !args.components.contains(com))
+        .map(Com::to_string)
+        .collect::<Vec<_>>();
+    if !added.is_empty() {
+        log_i18n!(info, "install.with-dependencies", added.join(", "));
     }
 
+    let mut probe = ProbeOptions::from_config(&config);
+    if let Some(timeout) = args.timeout {
+        probe.attempt_timeout = Duration::from_secs(timeout);
+    }
+    let nodejs_version = match &args.nodejs_version {
+        Some(version) => Some(parse_version(version)?),
+        None => pinned_version(Com::NodeJS, config.profile.nodejs.version.as_deref()),
+    };
+    let sandbox_version = match &args.sandbox_version {
+        Some(version) => Some(parse_version(version)?),
+        None => pinned_version(Com::Sandbox, config.profile.sandbox.version.as_deref()),
+    };
+    let bundle = args.bundle.as_deref().map(Bundle::new);
+    let manifest = match &bundle {
+        Some(bundle) => Some(bundle.manifest()),
+        None => args.offline_manifest,
+    };
+    let opts = Options {
+        temp: TempManager::new(args.tmp_dir.as_ref().or(config.profile.tmp_dir.as_ref()))
+            .context(tr!("sys.temp-dir-failed"))?,
+        probe,
+        metrics: Metrics::default(),
+        hydro_channel: config.profile.hydro.channel,
+        package_manager: config.profile.hydro.package_manager,
+        yarn_corepack: config.profile.yarn.corepack,
+        yarn_pinned: Mutex::default(),
+        files: local_files(args.files, args.checksum, manifest.as_deref())?,
+        nodejs_lts: config.profile.nodejs.lts.clone(),
+        nodejs_version,
+        sandbox_version,
+        offline: args.offline || manifest.is_some(),
+        bundle,
+    };
+    tracing::debug!("Temp dir: {}", opts.temp.base().display());
+
     // find out the components that need installing, and then execute them together
     let com = &mut config.components;
     let mut tasks = Vec::new();
-    let (tx, _) = broadcast::channel(16);
-
-    // Hack: the order is vital, because we must make sure that `tx.subcribe()` is called
-    // before `tx.send()`
+    let graph = TaskGraph::new();
 
     // Hydro
-    if com.hydro.is_installed() {
-        log::info!("Hydro 已安装，不执行任何操作。 Hydro is already installed, skip.");
-        log::info!(
-            "若需要检查更新 Hydro，请运行 `h2o2 check`。 \
-            If you need to check and update Hydro, please run `h2o2 check`."
-        );
-    } else {
-        tasks.push((Com::Hydro, Some(tx.subscribe())));
+    if wanted.contains(&Com::Hydro) {
+        if com.hydro.is_installed() {
+            log_i18n!(info, "install.already-installed", Com::Hydro);
+            log_i18n!(info, "install.check-hydro-update");
+        } else {
+            tasks.push(Com::Hydro);
+        }
     }
 
     // Yarn
-    if com.yarn.is_installed() {
-        log::info!("Yarn 已安装，不执行任何操作。 Yarn is already installed, skip.");
-        let _ = tx.send(Signal::Ready(Com::Yarn, &com.yarn)); // Note: `tx.send()` may fail if there is no receiver
-    } else if let Ok(v) = expect!(
-        run!(maybe_cmd!("yarn"), "-v") => valid
-    ) {
-        log::info!("Yarn 已安装，不执行任何操作。 Yarn is already installed, skip.");
-        com.yarn.path = Some(maybe_cmd!("yarn").to_owned());
-        com.yarn.version = v;
-        let _ = tx.send(Signal::Ready(Com::Yarn, &com.yarn));
-    } else {
-        tasks.push((Com::Yarn, Some(tx.subscribe())));
+    if wanted.contains(&Com::Yarn) {
+        if com.yarn.is_installed() {
+            log_i18n!(info, "install.already-installed", Com::Yarn);
+            graph.ready(Com::Yarn, com.yarn.clone());
+        } else if let Ok(v) = expect!(
+            run!(platform::cmd("yarn"), "-v") => valid
+        ) {
+            log_i18n!(info, "install.already-installed", Com::Yarn);
+            com.yarn.path = Some(platform::cmd("yarn"));
+            com.yarn.version = v;
+            graph.ready(Com::Yarn, com.yarn.clone());
+        } else {
+            tasks.push(Com::Yarn);
+        }
+    }
+
+    if wanted.contains(&Com::Hydro)
+        && !com.hydro.is_installed()
+        && jspm::is_berry(&com.yarn.version)
+    {
+        let pm = jspm::select(opts.package_manager, &com.nodejs, &com.yarn);
+        log_i18n!(warn, "install.yarn-berry", &com.yarn.version, pm.name());
     }
 
     // PM2
-    if com.pm2.is_installed() {
-        log::info!("PM2 已安装，不执行任何操作。 PM2 is already installed, skip.");
-        let _ = tx.send(Signal::Ready(Com::PM2, &com.pm2));
-    } else if let Ok(v) = expect!(
-        run!(maybe_cmd!("pm2"), "-v", "-s", "--no-daemon") => valid
-    ) {
-        log::info!("PM2 已安装，不执行任何操作。 PM2 is already installed, skip.");
-        com.pm2.path = Some(maybe_cmd!("pm2").to_owned());
-        com.pm2.version = v;
-        let _ = tx.send(Signal::Ready(Com::PM2, &com.pm2));
-    } else {
-        tasks.push((Com::PM2, Some(tx.subscribe())));
+    if wanted.contains(&Com::PM2) {
+        if com.pm2.is_installed() {
+            log_i18n!(info, "install.already-installed", Com::PM2);
+            graph.ready(Com::PM2, com.pm2.clone());
+        } else if let Ok(v) = expect!(
+            run!(platform::cmd("pm2"), "-v", "-s", "--no-daemon") => valid
+        ) {
+            log_i18n!(info, "install.already-installed", Com::PM2);
+            com.pm2.path = Some(platform::cmd("pm2"));
+            com.pm2.version = v;
+            graph.ready(Com::PM2, com.pm2.clone());
+        } else {
+            tasks.push(Com::PM2);
+        }
     }
 
     // Node.js
-    if com.nodejs.is_installed() {
-        log::info!("Node.js 已安装，不执行任何操作。 Node.js is already installed, skip.");
-        let version = com
-            .nodejs
-            .version()
-            .expect("Node.js should have a version if installed");
-        check_version!(nodejs, version, warn);
-        log::info!(
-            "若需要 H2O2 安装一个推荐版本的 Node.js，请删除系统中已存在的版本并重新运行 H2O2。 \
-            If you need H2O2 to install a recommended version of Node.js, \
-            please delete the existing version in the system and run H2O2 again."
-        );
-        let _ = tx.send(Signal::Ready(Com::NodeJS, &com.nodejs));
-    } else if let Ok(v) = expect!(
-        run!("node", "-v") => "v" => semver
-    ) {
-        log::info!("Node.js 已安装，不执行任何操作。 Node.js is already installed, skip.");
-        check_version!(nodejs, &v, warn);
-        log::info!(
-            "若需要 H2O2 安装一个推荐版本的 Node.js，请删除系统中已存在的版本并重新运行 H2O2。 \
-            If you need H2O2 to install a recommended version of Node.js, \
-            please delete the existing version in the system and run H2O2 again."
-        );
-        com.nodejs.path = None;
-        com.nodejs.version = config::Version::Valid(v);
-        let _ = tx.send(Signal::Ready(Com::NodeJS, &com.nodejs));
-    } else {
-        tasks.push((Com::NodeJS, None));
+    if wanted.contains(&Com::NodeJS) {
+        if com.nodejs.is_installed() {
+            log_i18n!(info, "install.already-installed", Com::NodeJS);
+            let version = com
+                .nodejs
+                .version()
+                .expect("Node.js should have a version if installed");
+            check_version!(nodejs, version, warn);
+            log_i18n!(info, "install.nodejs-recommended");
+            graph.ready(Com::NodeJS, com.nodejs.clone());
+        } else if let Ok(v) = expect!(
+            run!("node", "-v") => "v" => semver
+        ) {
+            log_i18n!(info, "install.already-installed", Com::NodeJS);
+            check_version!(nodejs, &v, warn);
+            log_i18n!(info, "install.nodejs-recommended");
+            com.nodejs.path = None;
+            com.nodejs.version = config::Version::Valid(v);
+            graph.ready(Com::NodeJS, com.nodejs.clone());
+        } else {
+            tasks.push(Com::NodeJS);
+        }
     }
 
     // MongoDB
-    if com.mongodb.is_installed() {
-        log::info!("MongoDB 已安装，不执行任何操作。 MongoDB is already installed, skip.");
-        let version = com
-            .mongodb
-            .version()
-            .expect("MongoDB should have a version if installed");
-        check_version!(mongodb, version, warn);
-        let _ = tx.send(Signal::Ready(Com::MongoDB, &com.mongodb));
-    } else if let Ok(v) = expect!(
-        run!("mongod", "--version") => "db version v" => semver
-    ) {
-        log::info!("MongoDB 已安装，不执行任何操作。 MongoDB is already installed, skip.");
-        check_version!(mongodb, &v, warn);
-        com.mongodb.path = Some("mongod".to_owned());
-        com.mongodb.version = config::Version::Valid(v);
-        let _ = tx.send(Signal::Ready(Com::MongoDB, &com.mongodb));
-    } else {
-        tasks.push((Com::MongoDB, None));
+    if wanted.contains(&Com::MongoDB) {
+        if com.mongodb.is_installed() {
+            log_i18n!(info, "install.already-installed", Com::MongoDB);
+            let version = com
+                .mongodb
+                .version()
+                .expect("MongoDB should have a version if installed");
+            check_version!(mongodb, version, warn);
+            graph.ready(Com::MongoDB, com.mongodb.clone());
+        } else if let Ok(v) = expect!(
+            run!("mongod", "--version") => "db version v" => semver
+        ) {
+            log_i18n!(info, "install.already-installed", Com::MongoDB);
+            check_version!(mongodb, &v, warn);
+            com.mongodb.path = Some("mongod".to_owned());
+            com.mongodb.version = config::Version::Valid(v);
+            graph.ready(Com::MongoDB, com.mongodb.clone());
+        } else {
+            tasks.push(Com::MongoDB);
+        }
     }
 
     // MinIO
-    if com.minio.is_installed() {
-        log::info!("MinIO 已安装，不执行任何操作。 MinIO is already installed, skip.");
-        let _ = tx.send(Signal::Ready(Com::MinIO, &com.minio));
-    } else if let Ok(v) = expect!(
-        run!("minio", "-v") => starts with "minio version "
-    ) {
-        log::info!("MinIO 已安装，不执行任何操作。 MinIO is already installed, skip.");
-        com.minio.path = Some("minio".to_owned());
-        com.minio.version = v;
-        let _ = tx.send(Signal::Ready(Com::MinIO, &com.minio));
-    } else {
-        tasks.push((Com::MinIO, None));
+    if wanted.contains(&Com::MinIO) {
+        if com.minio.is_installed() {
+            log_i18n!(info, "install.already-installed", Com::MinIO);
+            graph.ready(Com::MinIO, com.minio.clone());
+        } else if let Ok(v) = expect!(
+            run!("minio", "-v") => starts with "minio version "
+        ) {
+            log_i18n!(info, "install.already-installed", Com::MinIO);
+            com.minio.path = Some("minio".to_owned());
+            com.minio.version = v;
+            graph.ready(Com::MinIO, com.minio.clone());
+        } else {
+            tasks.push(Com::MinIO);
+        }
     }
 
     // sandbox
-    if com.sandbox.is_installed() {
-        log::info!("sandbox 已安装，不执行任何操作。 sandbox is already installed, skip.");
-        let _ = tx.send(Signal::Ready(Com::Sandbox, &com.sandbox));
-    } else {
-        tasks.push((Com::Sandbox, None));
+    if wanted.contains(&Com::Sandbox) {
+        if com.sandbox.is_installed() {
+            log_i18n!(info, "install.already-installed", Com::Sandbox);
+            graph.ready(Com::Sandbox, com.sandbox.clone());
+        } else {
+            if platform::current().in_container() {
+                log_i18n!(warn, "install.sandbox-privileged");
+            }
+            tasks.push(Com::Sandbox);
+        }
+    }
+
+    if opts.offline {
+        // the rest would be downloaded, or installed by the package manager from the registry
+        // unless a bundle holds the packages
+        tasks.retain(|com| {
+            let provided = opts.files.iter().any(|file| file.com == *com)
+                || (opts.bundle.is_some() && matches!(com, Com::Yarn | Com::PM2 | Com::Hydro));
+            if !provided {
+                log_i18n!(warn, "install.offline-skipped", com);
+            }
+            provided
+        });
+    }
+
+    if args.only_download {
+        let mut tasks = tasks
+            .into_iter()
+            .filter_map(|com| match com {
+                Com::Yarn | Com::PM2 | Com::Hydro => {
+                    log_i18n!(info, "install.by-package-manager", com);
+                    None
+                }
+                com => Some(download(com, &opts)),
+            })
+            .collect::<FuturesUnordered<_>>();
+
+        let mut failed = false;
+        while let Some(res) = tasks.next().await {
+            match res {
+                Ok((com_id, path)) => {
+                    tracing::info!("OK: {} -> {}", &com_id, path.display());
+                    progress::phase(com_id.name(), "done");
+                }
+                Err(e) => {
+                    log_i18n!(error, "install.download-failed", e.com);
+                    progress::phase(e.com.name(), "failed");
+                    tracing::error!("{}", e);
+                    failed = true;
+                }
+            }
+        }
+
+        report_metrics(&opts.metrics, args.metrics);
+        if failed {
+            bail!(tr!("install.downloads-failed"));
+        }
+        log_i18n!(
+            info,
+            "install.downloads-saved",
+            config::get_cache_path().display()
+        );
+        return Ok(());
     }
 
-    let mut tasks = tasks
-        .into_iter()
-        .map(|(com, rx)| install(com, rx))
+    // those not installed by now never will be, e.g. skipped when offline
+    graph.seal(&tasks);
+    let pending = TaskGraph::order(&tasks);
+    let mut tasks = pending
+        .iter()
+        .map(|&com| install(com, graph.dependencies(com), &opts))
         .collect::<FuturesUnordered<_>>();
 
+    let mut mc = None;
+    let mut failed = Vec::new();
     while let Some(res) = tasks.next().await {
         match res {
             Ok((com_id, com_info)) => {
-                log::info!("OK: {} {}", &com_id, com_info.to_show_format());
-                let info = com.borrow_by_com(com_id);
-                // Hack: *info = com_info;
-                // For each time, we only modify a different part of `com`.
-                // This is obviously safe, but rustc can't understand it.
-                // `Mutex` is also an option, but it is costly.
-                let info_ptr = info as *const ComponentInfo;
-                unsafe {
-                    *(info_ptr as *mut ComponentInfo) = com_info;
+                tracing::info!("OK: {} {}", &com_id, com_info.to_show_format());
+                progress::phase(com_id.name(), "done");
+                if com_id == Com::Sandbox {
+                    if let Err(e) = setup_sandbox_token(&mut config.profile.sandbox) {
+                        log_i18n!(error, "install.sandbox-token-failed");
+                        tracing::error!("{:#}", e);
+                    }
+                }
+                if com_id == Com::MinIO {
+                    match setup_minio(&mut config.profile.minio, &opts).await {
+                        Ok(path) => mc = path,
+                        Err(e) => {
+                            log_i18n!(error, "install.configure-failed", Com::MinIO);
+                            tracing::error!("{:#}", e);
+                        }
+                    }
+                }
+                if com_id == Com::Yarn {
+                    config.profile.yarn.pinned = opts.yarn_pinned.lock().unwrap().take();
+                }
+                if com_id == Com::MongoDB {
+                    if let Err(e) = setup_mongodb(&mut config.profile.mongodb) {
+                        log_i18n!(error, "install.configure-failed", Com::MongoDB);
+                        tracing::error!("{:#}", e);
+                    }
                 }
-                let _ = tx.send(Signal::Ready(com_id, info));
+                graph.ready(com_id, com_info.clone());
+                *com.borrow_mut_by_com(com_id) = com_info;
             }
             Err(e) => {
-                log::error!("安装 {} 失败！", e.com); // English is no need because the error message is already in English
-                log::error!("{}", e);
-                let _ = tx.send(Signal::Failed(e.com));
+                log_i18n!(error, "install.install-failed", e.com); // English is no need because the error message is already in English
+                progress::phase(e.com.name(), "failed");
+                tracing::error!("{}", e);
+                failed.push(e.com);
+                graph.failed(e.com);
             }
         }
     }
+    if let Some(path) = mc {
+        config.components.extras.insert(
+            "mc".to_owned(),
+            ComponentInfo::new(config::Version::Installed, Some(path)),
+        );
+    }
 
-    if cfg!(unix) {
+    report_metrics(&opts.metrics, args.metrics);
+
+    if pending.contains(&Com::Hydro) && !failed.contains(&Com::Hydro) {
+        if let Err(e) = setup_split(&config.profile) {
+            log_i18n!(error, "install.configure-failed", Com::Hydro);
+            tracing::error!("{:#}", e);
+        }
+    }
+
+    // save even if some failed, so that the installed ones are not installed again next time,
+    // unless the config is not loaded, which would be overwritten by the defaults
+    if !args.no_config {
+        config::save_config(&config).await?;
+    }
+    report_summary(&config.components, &wanted, &pending, &failed);
+    if !opts.offline {
+        telemetry::send(
+            &config.profile,
+            &telemetry::InstallReport::new(&pending, &failed),
+        )
+        .await;
+    }
+
+    if failed.len() < pending.len()
+        && !platform::current().is_windows()
+        && !platform::current().in_container()
+    {
         // FIXME: exec $SHELL
-        log::warn!(
-            "请手动执行 `source ~/.profile` 来应用更改。 \
-            Please execute `source ~/.profile` manually to apply changes."
-        );
+        log_i18n!(warn, "install.source-profile");
     }
 
-    todo!();
+    if !failed.is_empty() {
+        let failed = failed.iter().map(Com::to_string).collect::<Vec<_>>();
+        bail!(tr!("install.partially-failed", failed.join(", ")));
+    }
+    Ok(())
+}
+
+/// The components given and those they depend on, or all running on this server according to
+/// `profile` if none is given.
+fn with_dependencies(components: &[Com], profile: &Profile) -> Vec<Com> {
+    if components.is_empty() {
+        return profile.components();
+    }
+    let mut wanted = components.to_vec();
+    let mut i = 0;
+    while i < wanted.len() {
+        for dep in wanted[i].dependencies() {
+            if !wanted.contains(dep) {
+                wanted.push(*dep);
+            }
+        }
+        i += 1;
+    }
+    Com::ALL
+        .iter()
+        .copied()
+        .filter(|com| wanted.contains(com))
+        .collect()
+}
+
+/// Prints the outcome of each component wanted.
+fn report_summary(components: &Components, wanted: &[Com], pending: &[Com], failed: &[Com]) {
+    let mut lines = Vec::new();
+    for com in wanted.iter().copied() {
+        let status = if failed.contains(&com) {
+            tr!("install.summary-failed")
+        } else if pending.contains(&com) {
+            tr!(
+                "install.summary-installed",
+                components.borrow_by_com(com).to_show_format()
+            )
+        } else if components.borrow_by_com(com).is_installed() {
+            tr!("install.summary-skipped")
+        } else {
+            tr!("install.summary-not-installed")
+        };
+        lines.push(format!("  {:<8} {}", com.to_string(), status));
+    }
+    tracing::info!("{}\n{}", tr!("install.summary"), lines.join("\n"));
+}
+
+fn report_metrics(metrics: &Metrics, print: bool) {
+    if metrics.is_empty() {
+        return;
+    }
+    if print {
+        tracing::info!("{}\n{}", tr!("install.metrics"), metrics.summary());
+    }
+    if let Err(e) = metrics.persist() {
+        tracing::debug!("Failed to write metrics to the audit log: {}", e);
+    }
+}
+
+/// Parses a release given by the arguments, e.g. `16.20.2` or `v16.20.2`.
+fn parse_version(version: &str) -> Result<semver::Version> {
+    match semver::Version::parse(version.trim_start_matches('v')) {
+        Ok(version) => Ok(version),
+        Err(_) => bail!(tr!("install.invalid-version", version)),
+    }
+}
+
+/// The files given by `--file`, with the checksum given by `--checksum`, which is ambiguous for
+/// multiple files, and those listed in the offline manifest unless given by `--file` as well.
+fn local_files(
+    mut files: Vec<LocalFile>,
+    checksum: Option<String>,
+    manifest: Option<&Path>,
+) -> Result<Vec<LocalFile>> {
+    if checksum.is_some() && files.len() > 1 {
+        bail!(tr!("install.checksum-ambiguous"));
+    }
+    if let Some(file) = files.first_mut() {
+        file.checksum = checksum;
+    }
+    if let Some(manifest) = manifest {
+        let listed = OfflineManifest::load(manifest).map_err(anyhow::Error::msg)?;
+        for file in listed {
+            if !files.iter().any(|given| given.com == file.com) {
+                files.push(file);
+            }
+        }
+    }
+    for file in &files {
+        if !file.path.is_file() {
+            bail!(tr!("install.file-not-found", file.path.display()));
+        }
+        if files.iter().filter(|other| other.com == file.com).count() > 1 {
+            bail!(tr!("install.file-duplicate", file.com));
+        }
+    }
+    Ok(files)
+}
+
+fn setup_sandbox_token(profile: &mut SandboxProfile) -> Result<()> {
+    let token = profile
+        .auth_token
+        .get_or_insert_with(|| random_token(32))
+        .clone();
+    let env = sandbox::write_env(profile).context("Failed to write sandbox env file")?;
+    tracing::debug!("Sandbox env file: {}", env.display());
+    hydro::update_judge_config(&[("sandbox_host", sandbox::HOST), ("sandbox_token", &token)])
+        .context("Failed to write Hydro judge config")?;
+    Ok(())
+}
+
+/// Points Hydro at MongoDB, MinIO or the sandbox on other servers, when the deployment is split
+/// by `role` or uses external endpoints, which those installed here would do otherwise.
+fn setup_split(profile: &Profile) -> Result<()> {
+    if profile.role.is_web() {
+        if let Some(host) = &profile.sandbox.host {
+            let token = profile.sandbox.auth_token.as_deref().unwrap_or_default();
+            hydro::update_judge_config(&[("sandbox_host", host), ("sandbox_token", token)])
+                .context("Failed to write Hydro judge config")?;
+        }
+    }
+    let external_mongodb = profile.is_external(Com::MongoDB);
+    if profile.role.is_judge() && !external_mongodb && profile.mongodb.is_local_host() {
+        log_i18n!(warn, "install.judge-local-mongodb", &profile.mongodb.host);
+    }
+    if profile.role.is_judge() || external_mongodb {
+        hydro::update_config(
+            "uri",
+            serde_json::Value::String(mongodb::connection_uri(&profile.mongodb)),
+        )
+        .context("Failed to write Hydro config")?;
+    }
+    if profile.is_external(Com::MinIO) {
+        if profile.minio.root_user.is_none() || profile.minio.root_password.is_none() {
+            log_i18n!(warn, "install.external-no-credentials", Com::MinIO);
+        }
+        hydro::update_config("file", minio::hydro_file_config(&profile.minio))
+            .context("Failed to write Hydro config")?;
+    }
+    Ok(())
+}
+
+fn setup_mongodb(profile: &mut MongoDBProfile) -> Result<()> {
+    if !profile.is_local_only() && profile.tls.is_disabled() {
+        log_i18n!(warn, "install.mongodb-insecure-bind", &profile.bind_ip);
+    }
+    mongodb::ensure_cert(profile).context("Failed to generate TLS certificate for MongoDB")?;
+    let path = mongodb::write_config(profile).context("Failed to write mongod.conf")?;
+    tracing::debug!("mongod.conf: {}", path.display());
+    hydro::update_config(
+        "uri",
+        serde_json::Value::String(mongodb::connection_uri(profile)),
+    )
+    .context("Failed to write Hydro config")?;
+    Ok(())
+}
+
+/// Returns the path of mc if it is installed.
+async fn setup_minio(profile: &mut MinIOProfile, opts: &Options) -> Result<Option<String>> {
+    // refuse to silently point MinIO at another directory, leaving the existing files behind
+    if let Ok(Some(applied)) = minio::applied_data_dir() {
+        let has_data = std::fs::read_dir(&applied)
+            .map(|mut entries| entries.next().is_some())
+            .unwrap_or(false);
+        if applied != profile.data_dir() && has_data {
+            bail!(tr!(
+                "minio.data-dir-changed",
+                applied.display(),
+                profile.data_dir().display()
+            ));
+        }
+    }
+    std::fs::create_dir_all(profile.data_dir()).context("Failed to create MinIO data dir")?;
+    if profile.root_user.is_none() || profile.root_password.is_none() {
+        profile.root_user = Some(format!("hydro{}", random_token(4)));
+        profile.root_password = Some(random_token(16));
+    }
+    let env = minio::write_env(profile).context("Failed to write MinIO env file")?;
+    tracing::debug!("MinIO env file: {}", env.display());
+    tracing::debug!("MinIO args: {:?}", minio::server_args(profile));
+    if profile.browser {
+        tracing::info!("MinIO Console: {}", profile.console_url());
+    }
+    if !profile.install_mc {
+        return Ok(None);
+    }
+    if opts.offline {
+        log_i18n!(warn, "install.offline-skipped", "mc");
+        return Ok(None);
+    }
+    let path = install_mc(opts).await?;
+    tracing::info!("OK: mc @ {}", path);
+    minio::set_mc_alias(profile).context("Failed to configure mc alias")?;
+    Ok(Some(path))
 }
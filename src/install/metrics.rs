@@ -0,0 +1,156 @@
+use derive_more::Display;
+use std::{
+    fmt::Write,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::Instrument;
+
+use super::Com;
+use crate::{audit, progress, utils::format_size};
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    #[display(fmt = "probe")]
+    Probe,
+    #[display(fmt = "download")]
+    Download,
+    #[display(fmt = "verify")]
+    Verify,
+    #[display(fmt = "install")]
+    Install,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [Stage::Probe, Stage::Download, Stage::Verify, Stage::Install];
+}
+
+#[derive(Debug, Default, Clone)]
+struct ComMetrics {
+    stages: Vec<(Stage, Duration)>,
+    size: Option<u64>,
+}
+
+impl ComMetrics {
+    fn get(&self, stage: Stage) -> Option<Duration> {
+        self.stages
+            .iter()
+            .filter(|(s, _)| *s == stage)
+            .map(|(_, d)| *d)
+            .reduce(|a, b| a + b)
+    }
+}
+
+/// Timings and artifact sizes collected while installing, shared by all installers.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    inner: Mutex<Vec<(Com, ComMetrics)>>,
+}
+
+impl Metrics {
+    fn with<R>(&self, com: Com, f: impl FnOnce(&mut ComMetrics) -> R) -> R {
+        let mut inner = self.inner.lock().unwrap();
+        let pos = match inner.iter().position(|(c, _)| *c == com) {
+            Some(pos) => pos,
+            None => {
+                inner.push((com, ComMetrics::default()));
+                inner.len() - 1
+            }
+        };
+        f(&mut inner[pos].1)
+    }
+
+    /// Reports that `com` enters `stage`, which [`Metrics::measure`] does by itself.
+    pub fn begin(&self, com: Com, stage: Stage) {
+        progress::phase(com.name(), &stage.to_string());
+    }
+
+    pub fn record(&self, com: Com, stage: Stage, elapsed: Duration) {
+        tracing::debug!("[{}] {}: {:?}", com, stage, elapsed);
+        self.with(com, |m| m.stages.push((stage, elapsed)));
+    }
+
+    pub fn record_size(&self, com: Com, size: u64) {
+        self.with(com, |m| m.size = Some(size));
+    }
+
+    pub fn measure<T>(&self, com: Com, stage: Stage, f: impl FnOnce() -> T) -> T {
+        let _span = tracing::debug_span!("stage", %stage).entered();
+        self.begin(com, stage);
+        let start = Instant::now();
+        let res = f();
+        self.record(com, stage, start.elapsed());
+        res
+    }
+
+    pub async fn measure_async<T>(
+        &self,
+        com: Com,
+        stage: Stage,
+        fut: impl Future<Output = T>,
+    ) -> T {
+        self.begin(com, stage);
+        let start = Instant::now();
+        let res = fut.instrument(tracing::debug_span!("stage", %stage)).await;
+        self.record(com, stage, start.elapsed());
+        res
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().unwrap().is_empty()
+    }
+
+    /// Formats the collected metrics as a table, one component per line.
+    pub fn summary(&self) -> String {
+        let inner = self.inner.lock().unwrap();
+        let mut out = format!(" {:<8}", "");
+        for stage in &Stage::ALL {
+            let _ = write!(out, " {:>9}", stage.to_string());
+        }
+        let _ = writeln!(out, " {:>10}", "size");
+        for (com, m) in inner.iter() {
+            let _ = write!(out, " {:<8}", com.to_string());
+            for stage in &Stage::ALL {
+                let _ = write!(
+                    out,
+                    " {:>9}",
+                    m.get(*stage)
+                        .map_or_else(|| "-".to_owned(), format_duration)
+                );
+            }
+            let _ = writeln!(
+                out,
+                " {:>10}",
+                m.size.map_or_else(|| "-".to_owned(), format_size)
+            );
+        }
+        out
+    }
+
+    /// Appends the metrics of each component to the audit log.
+    pub fn persist(&self) -> std::io::Result<()> {
+        let inner = self.inner.lock().unwrap();
+        for (com, m) in inner.iter() {
+            let mut details = serde_json::Map::new();
+            for stage in &Stage::ALL {
+                if let Some(d) = m.get(*stage) {
+                    details.insert(format!("{}_ms", stage), (d.as_millis() as u64).into());
+                }
+            }
+            if let Some(size) = m.size {
+                details.insert("size".to_owned(), size.into());
+            }
+            audit::append(&audit::Entry::new(
+                "install-metrics",
+                Some(com.to_string()),
+                details.into(),
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+fn format_duration(d: Duration) -> String {
+    format!("{:.1}s", d.as_secs_f64())
+}
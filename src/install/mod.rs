@@ -1,7 +1,12 @@
-mod helper;
+pub mod cache;
+mod graph;
+pub(crate) mod helper;
 #[allow(clippy::module_inception)]
 mod install;
 mod main;
+mod metrics;
 
+pub use graph::{Dependencies, Signal, TaskGraph};
 pub use install::*;
 pub use main::*;
+pub use metrics::{Metrics, Stage};
@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+
+use crate::config::RuntimeInfo;
+
+/// A language setting of Hydro.
+#[derive(Debug, Clone)]
+pub struct Lang {
+    /// key in Hydro's `langs` setting
+    pub key: &'static str,
+    pub name: &'static str,
+    pub display: String,
+    /// name of the runtime in config
+    pub runtime: &'static str,
+    pub code_file: &'static str,
+    pub compile: Option<String>,
+    pub execute: String,
+    pub monaco: &'static str,
+}
+
+/// Builds the language settings for the runtimes available.
+pub fn generate(runtimes: &BTreeMap<String, RuntimeInfo>) -> Vec<Lang> {
+    let mut langs = Vec::new();
+    let mut push = |key,
+                    name: &'static str,
+                    runtime,
+                    code_file,
+                    compile: Option<String>,
+                    execute: String,
+                    monaco| {
+        if let Some(info) = runtimes.get(runtime) {
+            langs.push(Lang {
+                key,
+                name,
+                display: format!("{} ({} {})", name, runtime, &info.version),
+                runtime,
+                code_file,
+                compile: compile.map(|c| c.replace("{path}", &info.path)),
+                execute: execute.replace("{path}", &info.path),
+                monaco,
+            });
+        }
+    };
+
+    push(
+        "c",
+        "C",
+        "gcc",
+        "foo.c",
+        Some("{path} -O2 -Wall -std=c99 -o ${name} foo.c -lm".to_owned()),
+        "${name}".to_owned(),
+        "c",
+    );
+    push(
+        "cc",
+        "C++",
+        "g++",
+        "foo.cc",
+        Some("{path} -O2 -Wall -std=c++14 -o ${name} foo.cc -lm".to_owned()),
+        "${name}".to_owned(),
+        "cpp",
+    );
+    push(
+        "cc.cc17",
+        "C++17",
+        "g++",
+        "foo.cc",
+        Some("{path} -O2 -Wall -std=c++17 -o ${name} foo.cc -lm".to_owned()),
+        "${name}".to_owned(),
+        "cpp",
+    );
+    push(
+        "pas",
+        "Pascal",
+        "fpc",
+        "foo.pas",
+        Some("{path} -O2 -o${name} foo.pas".to_owned()),
+        "${name}".to_owned(),
+        "pascal",
+    );
+    push(
+        "java",
+        "Java",
+        "java",
+        "Main.java",
+        Some("{path} -d /w -encoding utf8 ./Main.java".to_owned()),
+        "/usr/bin/java Main".to_owned(),
+        "java",
+    );
+    push(
+        "py.py2",
+        "Python 2",
+        "python2",
+        "foo.py",
+        Some("{path} -c \"import py_compile; py_compile.compile('/w/foo.py', '/w/foo', doraise=True)\"".to_owned()),
+        "{path} foo".to_owned(),
+        "python",
+    );
+    push(
+        "py.py3",
+        "Python 3",
+        "python3",
+        "foo.py",
+        Some("{path} -c \"import py_compile; py_compile.compile('/w/foo.py', '/w/foo', doraise=True)\"".to_owned()),
+        "{path} foo".to_owned(),
+        "python",
+    );
+    push(
+        "rs",
+        "Rust",
+        "rustc",
+        "foo.rs",
+        Some("{path} -O -o ${name} foo.rs".to_owned()),
+        "${name}".to_owned(),
+        "rust",
+    );
+    push(
+        "go",
+        "Go",
+        "go",
+        "foo.go",
+        Some("{path} build -o ${name} foo.go".to_owned()),
+        "${name}".to_owned(),
+        "go",
+    );
+
+    langs
+}
+
+/// Formats the language settings as the YAML accepted by Hydro's `langs` setting.
+pub fn to_yaml(langs: &[Lang]) -> String {
+    let mut yaml = String::new();
+    for lang in langs {
+        yaml.push_str(&format!("{}:\n", lang.key));
+        yaml.push_str(&format!("  display: {}\n", quote(&lang.display)));
+        yaml.push_str(&format!("  code_file: {}\n", lang.code_file));
+        if let Some(compile) = &lang.compile {
+            yaml.push_str(&format!("  compile: {}\n", quote(compile)));
+        }
+        yaml.push_str(&format!("  execute: {}\n", quote(&lang.execute)));
+        yaml.push_str(&format!("  monaco: {}\n", lang.monaco));
+    }
+    yaml
+}
+
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
@@ -1,9 +1,49 @@
+pub mod adopt;
+pub mod audit;
+pub mod bundle;
+pub mod cert;
 pub mod check;
+pub mod clean;
+pub mod completions;
 pub mod config;
+pub mod crash;
+pub mod db;
 pub mod detect;
+pub mod docs;
+pub mod doctor;
+pub mod env;
+pub mod export;
+pub mod fleet;
+pub mod health;
+pub mod hydro;
+pub mod i18n;
 pub mod install;
+pub mod langs;
+pub mod lock;
 pub mod log;
+pub mod minio;
+pub mod mirror;
+pub mod net;
+pub mod notify;
+pub mod output;
+pub mod platform;
+pub mod profiling;
+pub mod progress;
+pub mod prune;
+pub mod registry;
+pub mod sandbox;
+pub mod scale;
+pub mod self_update;
+pub mod service;
 pub mod show;
+pub mod status;
+pub mod sys;
+pub mod telemetry;
+pub mod temp;
+pub mod uninstall;
+pub mod update;
 pub mod utils;
+pub mod verify;
+pub mod watch;
 
 pub use install::Com;
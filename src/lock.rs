@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process, thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use thiserror::Error;
+use tokio::time;
+
+use crate::{config, log_i18n, platform, tr};
+
+/// How often a waiting process checks the lock again.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A lock whose holder cannot be checked is considered stale after this.
+const STALE_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A lock file which cannot be parsed may be being written, and is only removed after this.
+const PARTIAL_AGE: Duration = Duration::from_secs(10);
+
+/// The process holding the lock, stored in the lock file.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Holder {
+    pub pid: u32,
+    pub operation: String,
+    pub time: u64,
+}
+
+#[derive(Error, Debug)]
+pub enum LockError {
+    #[error("{}", tr!("lock.held", .0.operation, .0.pid))]
+    Held(Holder),
+
+    #[error("{}", tr!("lock.io-failed"))]
+    IOError(#[from] io::Error),
+}
+
+/// Prevents two H2O2 processes from modifying the deployment at the same time, e.g. a
+/// cron-driven update colliding with a manual install.
+///
+/// The lock is released when dropped. Locks left behind by crashed runs are detected and removed.
+#[derive(Debug)]
+pub struct OperationLock {
+    path: PathBuf,
+}
+
+impl OperationLock {
+    /// Acquires the lock for `operation`. If another process holds it, waits for it to be
+    /// released when `wait` is set, or fails otherwise.
+    pub async fn acquire(operation: &str, wait: bool) -> Result<Self, LockError> {
+        let mut waiting = false;
+        loop {
+            match Self::try_acquire(operation)? {
+                Ok(lock) => return Ok(lock),
+                Err(holder) if wait => {
+                    if !waiting {
+                        log_i18n!(info, "lock.waiting", holder.operation, holder.pid);
+                        waiting = true;
+                    }
+                    time::sleep(POLL_INTERVAL).await;
+                }
+                Err(holder) => return Err(LockError::Held(holder)),
+            }
+        }
+    }
+
+    fn try_acquire(operation: &str) -> io::Result<Result<Self, Holder>> {
+        let path = get_lock_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let holder = Holder {
+            pid: process::id(),
+            operation: operation.to_owned(),
+            time: now(),
+        };
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    let text = serde_json::to_string(&holder)
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                    file.write_all(text.as_bytes())?;
+                    return Ok(Ok(Self { path }));
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e),
+            }
+
+            match read_holder(&path) {
+                Ok(holder) if !is_stale(&holder) => return Ok(Err(holder)),
+                Ok(holder) => log_i18n!(warn, "lock.stale-removed", holder.pid),
+                Err(e) => {
+                    let age = fs::metadata(&path)
+                        .and_then(|meta| meta.modified())
+                        .ok()
+                        .and_then(|time| SystemTime::now().duration_since(time).ok());
+                    if !matches!(age, Some(age) if age > PARTIAL_AGE) {
+                        tracing::debug!("Failed to read the lock file: {}", e);
+                        thread::sleep(Duration::from_millis(100));
+                        continue;
+                    }
+                }
+            }
+            match fs::remove_file(&path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Drop for OperationLock {
+    fn drop(&mut self) {
+        // make sure not to remove a lock taken over by another process
+        if matches!(read_holder(&self.path), Ok(holder) if holder.pid == process::id()) {
+            if let Err(e) = fs::remove_file(&self.path) {
+                tracing::debug!("Failed to remove the lock file: {}", e);
+            }
+        }
+    }
+}
+
+pub fn get_lock_path() -> PathBuf {
+    config::get_com_path().join("h2o2.lock")
+}
+
+/// The operation this process holds the lock for, if any.
+pub fn held_operation() -> Option<String> {
+    match read_holder(&get_lock_path()) {
+        Ok(holder) if holder.pid == process::id() => Some(holder.operation),
+        _ => None,
+    }
+}
+
+fn read_holder(path: &Path) -> io::Result<Holder> {
+    let text = fs::read_to_string(path)?;
+    serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn is_stale(holder: &Holder) -> bool {
+    match platform::process_alive(holder.pid) {
+        Some(alive) => !alive,
+        None => now().saturating_sub(holder.time) > STALE_AGE.as_secs(),
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
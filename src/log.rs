@@ -2,7 +2,253 @@ use env_logger::{
     fmt::{Color, Style, StyledValue},
     Builder, Env, Target,
 };
-use log::Level;
+use log::{Level, LevelFilter, Log};
+use once_cell::sync::OnceCell;
+use std::{
+    fmt::{self, Write as _},
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    sync::Mutex,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id},
+    Event, Metadata, Subscriber,
+};
+use tracing_log::{LogTracer, NormalizeEvent};
+use tracing_subscriber::{
+    layer::{Context, Layer},
+    prelude::*,
+    registry::{LookupSpan, Registry},
+};
+
+use crate::{config, progress};
+
+/// The log file is truncated when it grows larger than this.
+const LOG_FILE_LIMIT: u64 = 10 * 1024 * 1024;
+
+/// Logs to stderr as configured by `H2O2_LOG_LEVEL`, and additionally writes every debug
+/// event of H2O2 to the log file.
+///
+/// In the log file, each event is prefixed by the spans it happens in, such as
+/// `install{com=MongoDB}:stage{stage=download}`, and the spans are logged when they start and
+/// finish, so that concurrent installations can be told apart.
+struct Output {
+    stderr: env_logger::Logger,
+}
+
+/// The log file, if it could be opened.
+static LOG_FILE: OnceCell<Mutex<File>> = OnceCell::new();
+
+/// Fields of a span, formatted once it is created.
+struct SpanFields(String);
+
+/// When a span is created.
+struct SpanStart(Instant);
+
+impl<S> Layer<S> for Output
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        if metadata.is_span() {
+            return LOG_FILE.get().is_some() && is_own(metadata.target());
+        }
+        self.stderr.enabled(&to_log_metadata(metadata)) || self.file_enabled(metadata)
+    }
+
+    fn new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let span = match ctx.span(id) {
+            Some(span) => span,
+            None => return,
+        };
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        let mut extensions = span.extensions_mut();
+        extensions.insert(SpanFields(visitor.fields));
+        extensions.insert(SpanStart(Instant::now()));
+        drop(extensions);
+        write_file(Level::Debug, &scope(Some(span)), format_args!("started"));
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let span = match ctx.span(&id) {
+            Some(span) => span,
+            None => return,
+        };
+        let elapsed = span.extensions().get::<SpanStart>().map(|s| s.0.elapsed());
+        if let Some(elapsed) = elapsed {
+            write_file(
+                Level::Debug,
+                &scope(Some(span)),
+                format_args!("finished in {:?}", elapsed),
+            );
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        // records bridged from `log` carry their real metadata in fields
+        let normalized = event.normalized_metadata();
+        let metadata = normalized.as_ref().unwrap_or_else(|| event.metadata());
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        let message = format_args!("{}{}", visitor.message, visitor.fields);
+
+        let log_metadata = to_log_metadata(metadata);
+        if self.stderr.enabled(&log_metadata) {
+            progress::suspend(|| {
+                self.stderr.log(
+                    &log::Record::builder()
+                        .metadata(log_metadata)
+                        .args(message)
+                        .build(),
+                )
+            });
+        }
+        if self.file_enabled(metadata) {
+            let level = to_log_level(metadata.level());
+            write_file(level, &scope(ctx.lookup_current()), message);
+        }
+    }
+}
+
+impl Output {
+    fn file_enabled(&self, metadata: &Metadata<'_>) -> bool {
+        // records of dependencies such as `reqwest` are too noisy
+        LOG_FILE.get().is_some()
+            && *metadata.level() <= tracing::Level::DEBUG
+            && is_own(metadata.target())
+    }
+}
+
+/// Writes a line to the log file. Returns `false` if the log file is not available.
+fn write_file(level: Level, scope: &str, message: fmt::Arguments<'_>) -> bool {
+    let file = match LOG_FILE.get() {
+        Some(file) => file,
+        None => return false,
+    };
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    // a panic while writing must not make the log file unusable for the crash report
+    let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+    writeln!(
+        file,
+        "[{}.{:03}] {:<5} {}{}",
+        time.as_secs(),
+        time.subsec_millis(),
+        level,
+        scope,
+        message
+    )
+    .is_ok()
+}
+
+/// Writes `message` to the log file as an error, prefixed by the spans currently entered.
+/// Returns `false` if the log file is not available.
+pub fn write_error(message: fmt::Arguments<'_>) -> bool {
+    let scope = tracing::dispatcher::get_default(|dispatch| {
+        let registry = dispatch.downcast_ref::<Registry>()?;
+        let id = tracing::Span::current().id()?;
+        Some(scope(registry.span(&id)))
+    });
+    write_file(Level::Error, &scope.unwrap_or_default(), message)
+}
+
+fn is_own(target: &str) -> bool {
+    target.starts_with("h2o2")
+}
+
+/// Formats the span and its parents like `install{com=MongoDB}:stage{stage=download}: `.
+fn scope<S>(span: Option<tracing_subscriber::registry::SpanRef<'_, S>>) -> String
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let mut spans = Vec::new();
+    let mut current = span;
+    while let Some(span) = current {
+        let mut name = span.name().to_owned();
+        if let Some(fields) = span.extensions().get::<SpanFields>() {
+            if !fields.0.is_empty() {
+                let _ = write!(name, "{{{}}}", fields.0.trim_start());
+            }
+        }
+        spans.push(name);
+        current = span.parent();
+    }
+    if spans.is_empty() {
+        return String::new();
+    }
+    spans.reverse();
+    format!("{}: ", spans.join(":"))
+}
+
+/// Collects the message of an event, and the other fields as ` key=value`.
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "message" => {
+                let _ = write!(self.message, "{:?}", value);
+            }
+            name if name.starts_with("log.") => {}
+            name => {
+                let _ = write!(self.fields, " {}={:?}", name, value);
+            }
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else if !field.name().starts_with("log.") {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+}
+
+fn to_log_level(level: &tracing::Level) -> Level {
+    match *level {
+        tracing::Level::TRACE => Level::Trace,
+        tracing::Level::DEBUG => Level::Debug,
+        tracing::Level::INFO => Level::Info,
+        tracing::Level::WARN => Level::Warn,
+        tracing::Level::ERROR => Level::Error,
+    }
+}
+
+fn to_log_metadata<'a>(metadata: &Metadata<'a>) -> log::Metadata<'a> {
+    log::Metadata::builder()
+        .level(to_log_level(metadata.level()))
+        .target(metadata.target())
+        .build()
+}
+
+pub fn get_log_path() -> PathBuf {
+    config::get_com_path().join("h2o2.log")
+}
+
+fn open_log_file() -> io::Result<File> {
+    let path = get_log_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let too_large = matches!(fs::metadata(&path), Ok(meta) if meta.len() > LOG_FILE_LIMIT);
+    let mut options = OpenOptions::new();
+    if too_large {
+        options.write(true).truncate(true);
+    } else {
+        options.append(true);
+    }
+    options.create(true).open(path)
+}
 
 /// Initializes the global logger with the built env logger.
 ///
@@ -28,7 +274,23 @@ pub fn init() {
         writeln!(f, " {} > {}", level, record.args(),)
     });
 
-    builder.target(Target::Stderr).init()
+    let stderr = builder.target(Target::Stderr).build();
+    if let Ok(file) = open_log_file() {
+        let _ = LOG_FILE.set(Mutex::new(file));
+    }
+    let max_level = if LOG_FILE.get().is_some() {
+        stderr.filter().max(LevelFilter::Debug)
+    } else {
+        stderr.filter()
+    };
+
+    // records of `log`, as used by dependencies, are turned into events
+    LogTracer::builder()
+        .with_max_level(max_level)
+        .init()
+        .expect("Failed to initialize the logger");
+    tracing::subscriber::set_global_default(Registry::default().with(Output { stderr }))
+        .expect("Failed to initialize the logger");
 }
 
 fn colored_level<'a>(style: &'a mut Style, level: Level) -> StyledValue<'a, &'static str> {
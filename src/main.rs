@@ -1,5 +1,5 @@
 use anyhow::Result;
-use clap::{AppSettings, Clap};
+use clap::{AppSettings, Clap, IntoApp};
 
 /// H2O2 (a.k.a. hydrogen peroxide): Another powerful tool for Hydro(hydro.js.org)
 #[derive(Clap, Debug)]
@@ -7,6 +7,26 @@ use clap::{AppSettings, Clap};
 #[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
 #[clap(setting = AppSettings::ColoredHelp)]
 struct Args {
+    /// 若有其他 H2O2 进程正在修改部署，等待其完成而不是直接退出
+    /// Waits for other H2O2 processes modifying the deployment to finish instead of exiting
+    #[clap(long, global = true)]
+    wait: bool,
+
+    /// 记录各阶段耗时，以 Chrome trace 格式写入文件
+    /// Records the time of each phase into the file as a Chrome trace
+    #[clap(long, global = true, value_name = "FILE")]
+    profile_startup: Option<std::path::PathBuf>,
+
+    /// 进度的输出方式，`json` 将进度以每行一条 JSON 记录输出到标准输出，供图形界面等程序解析
+    /// How progress is reported, where `json` prints a JSON record per line on stdout for programs such as GUIs to parse
+    #[clap(long, global = true, arg_enum, default_value = "bar")]
+    progress: h2o2::progress::Mode,
+
+    /// show、check、detect 与 verify 的输出格式，`json` 将结果以 JSON 文档输出到标准输出
+    /// Output format of show, check, detect and verify, where `json` prints the result as a JSON document on stdout
+    #[clap(long, global = true, arg_enum, default_value = "text")]
+    format: h2o2::output::Format,
+
     #[clap(subcommand)]
     subcmd: SubCommand,
 }
@@ -16,45 +36,253 @@ enum SubCommand {
     /// 显示配置文件中记录的组件信息
     /// Prints the components recorded in .h2o2config
     #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(visible_alias = "ls")]
     Show(h2o2::show::Args),
 
+    /// 检查各组件的进程是否在运行及其端口是否可连接
+    /// Checks whether the processes of components are running and their ports are open
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Status(h2o2::status::Args),
+
+    /// 持续监控磁盘、内存与 MongoDB 增长，超出阈值时发送通知
+    /// Watches disk, memory and MongoDB growth, sending notifications when thresholds are crossed
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Watch(h2o2::watch::Args),
+
     /// 检查组件状态
     /// Checks for component status
     #[clap(setting = AppSettings::ColoredHelp)]
-    Check,
+    Check(h2o2::check::Args),
+
+    /// 诊断部署环境并给出修复建议
+    /// Diagnoses the deployment environment and suggests fixes
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Doctor(h2o2::doctor::Args),
+
+    /// 校验已安装的二进制文件、生成的配置与服务定义是否被改动
+    /// Verifies that the binaries installed, the configs generated and the service definitions have not drifted
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Verify(h2o2::verify::Args),
 
     /// 安装组件
     /// Installs components
     #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(visible_alias = "i")]
     Install(h2o2::install::Args),
 
+    /// 制作离线包，或从离线包安装组件
+    /// Creates a bundle for air-gapped servers, or installs the components from one
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Bundle(h2o2::bundle::Args),
+
+    /// 卸载组件
+    /// Uninstalls components
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Uninstall(h2o2::uninstall::Args),
+
+    /// 更新组件
+    /// Updates components
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(visible_alias = "up")]
+    Update(h2o2::update::Args),
+
+    /// 按顺序停止服务：先 Hydro，再评测沙箱，最后 MinIO 与 MongoDB
+    /// Stops the services in order: Hydro first, then the sandbox, and MinIO and MongoDB last
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Stop(h2o2::service::StopArgs),
+
+    /// 清空下载缓存
+    /// Purges the download cache
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Clean(h2o2::clean::Args),
+
     /// 探测已安装的组件并更新配置文件
     /// Detects the components installed and updates config
     #[clap(setting = AppSettings::ColoredHelp)]
     Detect(h2o2::detect::Args),
+
+    /// 将并非由 H2O2 写入的配置文件的设置导入配置，并由 H2O2 接管
+    /// Imports the settings of a config file H2O2 did not write into the profile, and takes it over
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Adopt(h2o2::adopt::Args),
+
+    /// 管理配置文件
+    /// Manages the config file
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Config(h2o2::config::Args),
+
+    /// 查看 MinIO 中存储的文件或迁移其数据
+    /// Inspects files stored in MinIO or migrates its data
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Minio(h2o2::minio::Args),
+
+    /// 测试、固定组件的下载源
+    /// Tests and pins the mirrors of components
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Mirror(h2o2::mirror::Args),
+
+    /// 打开 MongoDB shell 或查看数据库统计
+    /// Opens the MongoDB shell or inspects database statistics
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Db(h2o2::db::Args),
+
+    /// 管理反向代理使用的 TLS 证书
+    /// Manages the TLS certificates used by the reverse proxy
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Cert(h2o2::cert::Args),
+
+    /// 清理旧的提交记录与测试数据缓存
+    /// Prunes old submission records and test data caches
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Prune(h2o2::prune::Args),
+
+    /// 调整 Hydro 进程数
+    /// Scales Hydro processes
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Scale(h2o2::scale::Args),
+
+    /// 管理 Hydro 的运行状态
+    /// Manages the running state of Hydro
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Hydro(h2o2::hydro::Args),
+
+    /// 管理评测沙箱
+    /// Manages the sandbox judging programs
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Sandbox(h2o2::sandbox::Args),
+
+    /// 管理多节点评测中评测节点连接 Hydro 所用的令牌
+    /// Manages the tokens judge nodes connect to Hydro with, for judging on multiple nodes
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Fleet(h2o2::fleet::Args),
+
+    /// 检查和调整系统配置
+    /// Checks and tunes the system
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Sys(h2o2::sys::Args),
+
+    /// 以 systemd 服务管理组件
+    /// Manages components as systemd services
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Service(h2o2::service::Args),
+
+    /// 诊断运行环境
+    /// Diagnoses the runtime environment
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Env(h2o2::env::Args),
+
+    /// 导出部署信息
+    /// Exports deployment information
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Export(h2o2::export::Args),
+
+    /// 生成部署文档
+    /// Generates documents of the deployment
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Docs(h2o2::docs::Args),
+
+    /// 生成或安装 shell 补全脚本
+    /// Generates or installs shell completion scripts
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Completions(h2o2::completions::Args),
+
+    /// 将 H2O2 更新到 GitHub 上发布的最新版本
+    /// Updates H2O2 itself to the latest release on GitHub
+    #[clap(setting = AppSettings::ColoredHelp)]
+    SelfUpdate(h2o2::self_update::Args),
+
+    /// 管理匿名安装统计（默认关闭）
+    /// Manages anonymous install statistics, which are off by default
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Telemetry(h2o2::telemetry::Args),
+}
+
+impl SubCommand {
+    /// Name of the operation, if this subcommand modifies the deployment.
+    fn operation(&self) -> Option<&'static str> {
+        match self {
+            SubCommand::Install(_) => Some("install"),
+            SubCommand::Uninstall(_) => Some("uninstall"),
+            SubCommand::Update(_) => Some("update"),
+            SubCommand::Detect(_) => Some("detect"),
+            SubCommand::Adopt(_) => Some("adopt"),
+            SubCommand::Stop(_) => Some("stop"),
+            SubCommand::Clean(_) => Some("clean"),
+            SubCommand::Scale(_) => Some("scale"),
+            SubCommand::Bundle(args) => args.operation(),
+            SubCommand::Hydro(args) => args.operation(),
+            SubCommand::Config(args) => args.operation(),
+            SubCommand::Minio(args) => args.operation(),
+            SubCommand::Mirror(args) => args.operation(),
+            SubCommand::Cert(args) => args.operation(),
+            SubCommand::Db(args) => args.operation(),
+            SubCommand::Prune(args) => args.operation(),
+            SubCommand::Sandbox(args) => args.operation(),
+            SubCommand::Fleet(args) => args.operation(),
+            SubCommand::Sys(args) => args.operation(),
+            SubCommand::Service(args) => args.operation(),
+            SubCommand::Telemetry(args) => args.operation(),
+            SubCommand::SelfUpdate(args) => args.operation(),
+            _ => None,
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    better_panic::Settings::new()
-        .message("Oops, H2O2 panicked (crashed).")
-        .verbosity(better_panic::Verbosity::Medium)
-        .install();
+    h2o2::crash::install_hook();
     h2o2::log::init();
     let args = Args::parse();
+    h2o2::progress::set_mode(args.progress);
+    h2o2::output::set_format(args.format);
+    let _profiler = args.profile_startup.clone().map(h2o2::profiling::start);
+    let _span = h2o2::profiling::span("main", "total");
 
-    if cfg!(target_arch = "x86") {
-        log::warn!(
-            "x86 架构不受支持，Hydro 将无法正常工作，请考虑使用 x86_64。 \
-            The x86 architecture is not supported, Hydro will not work properly, please consider using x86_64."
-        );
+    if h2o2::platform::current().arch == h2o2::platform::Arch::X86 {
+        h2o2::log_i18n!(warn, "platform.x86-warning");
     }
 
+    let _lock = match args.subcmd.operation() {
+        Some(operation) => {
+            let _span = h2o2::profiling::span("main", "acquire lock");
+            Some(h2o2::lock::OperationLock::acquire(operation, args.wait).await?)
+        }
+        None => None,
+    };
+
     match args.subcmd {
-        SubCommand::Show(_) => h2o2::show::main().await?,
-        SubCommand::Check => h2o2::check::main().await?,
+        SubCommand::Show(args) => h2o2::show::main(args).await?,
+        SubCommand::Status(args) => h2o2::status::main(args).await?,
+        SubCommand::Watch(args) => h2o2::watch::main(args).await?,
+        SubCommand::Check(args) => h2o2::check::main(args).await?,
+        SubCommand::Doctor(args) => h2o2::doctor::main(args).await?,
+        SubCommand::Verify(args) => h2o2::verify::main(args).await?,
         SubCommand::Install(args) => h2o2::install::main(args).await?,
+        SubCommand::Bundle(args) => h2o2::bundle::main(args).await?,
+        SubCommand::Uninstall(args) => h2o2::uninstall::main(args).await?,
+        SubCommand::Update(args) => h2o2::update::main(args).await?,
         SubCommand::Detect(args) => h2o2::detect::main(args).await?,
+        SubCommand::Adopt(args) => h2o2::adopt::main(args).await?,
+        SubCommand::Stop(args) => h2o2::service::stop(args).await?,
+        SubCommand::Clean(args) => h2o2::clean::main(args).await?,
+        SubCommand::Config(args) => h2o2::config::main(args).await?,
+        SubCommand::Minio(args) => h2o2::minio::main(args).await?,
+        SubCommand::Mirror(args) => h2o2::mirror::main(args).await?,
+        SubCommand::Db(args) => h2o2::db::main(args).await?,
+        SubCommand::Cert(args) => h2o2::cert::main(args).await?,
+        SubCommand::Prune(args) => h2o2::prune::main(args).await?,
+        SubCommand::Scale(args) => h2o2::scale::main(args).await?,
+        SubCommand::Hydro(args) => h2o2::hydro::main(args).await?,
+        SubCommand::Sandbox(args) => h2o2::sandbox::main(args).await?,
+        SubCommand::Fleet(args) => h2o2::fleet::main(args).await?,
+        SubCommand::Sys(args) => h2o2::sys::main(args).await?,
+        SubCommand::Service(args) => h2o2::service::main(args).await?,
+        SubCommand::Env(args) => h2o2::env::main(args).await?,
+        SubCommand::Export(args) => h2o2::export::main(args).await?,
+        SubCommand::Docs(args) => h2o2::docs::main(args).await?,
+        SubCommand::SelfUpdate(args) => h2o2::self_update::main(args).await?,
+        SubCommand::Telemetry(args) => h2o2::telemetry::main(args).await?,
+        SubCommand::Completions(args) => h2o2::completions::main(args, Args::into_app()).await?,
     }
 
     Ok(())
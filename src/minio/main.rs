@@ -0,0 +1,120 @@
+use anyhow::{bail, Context, Result};
+use clap::{AppSettings, Clap};
+use std::path::Path;
+
+use super::migrate::{self, MigrateArgs};
+use crate::{
+    config::{self, Config},
+    install::{
+        helper::minio::{mc_path, set_mc_alias, MC_ALIAS},
+        install_mc, Options,
+    },
+    log_i18n, tr,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Clap, Debug)]
+enum SubCommand {
+    /// 列出 MinIO 中的文件
+    /// Lists files stored in MinIO
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Ls(PathArgs),
+
+    /// 统计 MinIO 中的文件占用空间
+    /// Summarizes disk usage of files stored in MinIO
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Du(PathArgs),
+
+    /// 使用本地 MinIO 的配置运行 mc
+    /// Runs mc against the local MinIO instance
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(setting = AppSettings::TrailingVarArg)]
+    #[clap(setting = AppSettings::AllowLeadingHyphen)]
+    Mc(McArgs),
+
+    /// 将 MinIO 数据迁移到配置中的新数据目录
+    /// Migrates MinIO data to the new data directory in the profile
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Migrate(MigrateArgs),
+}
+
+impl Args {
+    /// Name of the operation, if the subcommand modifies the deployment.
+    pub fn operation(&self) -> Option<&'static str> {
+        match self.subcmd {
+            SubCommand::Migrate(_) => Some("minio migrate"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clap, Debug)]
+struct PathArgs {
+    /// 桶或对象路径，如 `hydro/problem`
+    /// Bucket or object path, such as `hydro/problem`
+    path: Option<String>,
+}
+
+#[derive(Clap, Debug)]
+struct McArgs {
+    /// 传递给 mc 的参数，其中 `local/` 会被替换为本地 MinIO 的别名
+    /// Arguments passed to mc, `local/` will be replaced with the alias of the local MinIO
+    args: Vec<String>,
+}
+
+pub async fn main(args: Args) -> Result<()> {
+    let config = config::load_config().await?;
+    if !config.components.minio.is_installed() {
+        bail!(tr!("minio.not-installed"));
+    }
+    let subcmd = match args.subcmd {
+        SubCommand::Migrate(args) => return migrate::migrate(&config, args),
+        subcmd => subcmd,
+    };
+    ensure_mc(&config).await?;
+
+    let mc_args = match subcmd {
+        SubCommand::Ls(args) => vec!["ls".to_owned(), "--recursive".to_owned(), target(args.path)],
+        SubCommand::Du(args) => vec!["du".to_owned(), target(args.path)],
+        SubCommand::Mc(args) => args
+            .args
+            .into_iter()
+            .map(|arg| match arg.strip_prefix("local/") {
+                Some(rest) => format!("{}/{}", MC_ALIAS, rest),
+                None => arg,
+            })
+            .collect(),
+        SubCommand::Migrate(_) => unreachable!(),
+    };
+
+    let status = duct::cmd(mc_path(), mc_args)
+        .unchecked()
+        .run()
+        .context("Failed to execute mc")?
+        .status;
+    if !status.success() {
+        bail!(tr!("minio.mc-failed", status));
+    }
+
+    Ok(())
+}
+
+fn target(path: Option<String>) -> String {
+    format!("{}/{}", MC_ALIAS, path.unwrap_or_default())
+}
+
+/// Installs `mc` if it is missing, then refreshes the alias of the local MinIO.
+pub async fn ensure_mc(config: &Config) -> Result<()> {
+    if !Path::new(&mc_path()).is_file() {
+        log_i18n!(info, "minio.mc-not-found");
+        let opts = Options::from_config(config).context(tr!("sys.temp-dir-failed"))?;
+        install_mc(&opts).await?;
+    }
+    set_mc_alias(&config.profile.minio).context("Failed to configure mc alias")
+}
@@ -0,0 +1,238 @@
+use anyhow::{bail, Context, Result};
+use clap::Clap;
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    config::{ComponentInfo, Config, MinIOProfile},
+    install::helper::minio,
+    log_i18n, platform, service, tr,
+    utils::{format_size, summarize_dir},
+    Com,
+};
+
+/// How long to wait for MinIO to exit after being asked to.
+const STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clap, Debug)]
+pub struct MigrateArgs {
+    /// 原数据目录，默认为 MinIO 当前使用的目录
+    /// Old data directory, defaults to the one MinIO is currently set up with
+    #[clap(long)]
+    from: Option<String>,
+
+    /// 跨设备复制后保留原数据
+    /// Keeps the old data after copying across devices
+    #[clap(long)]
+    keep: bool,
+}
+
+/// Moves the MinIO data to the data directory in the profile, so that changing `data_dir` does
+/// not leave the existing files behind. MinIO is stopped meanwhile, through its unit if H2O2
+/// wrote one, and started again with the new directory if it was running.
+pub fn migrate(config: &Config, args: MigrateArgs) -> Result<()> {
+    let info = &config.components.minio;
+    let profile = &config.profile.minio;
+    let from = match args.from {
+        Some(from) => PathBuf::from(from),
+        None => match minio::applied_data_dir().context(tr!("minio.env-read-failed"))? {
+            Some(from) => from,
+            None => bail!(tr!("minio.not-set-up")),
+        },
+    };
+    let to = profile.data_dir();
+    if from == to {
+        log_i18n!(info, "minio.data-dir-unchanged", to.display());
+        return Ok(());
+    }
+    if !from.is_dir() {
+        bail!(tr!("minio.data-dir-missing", from.display()));
+    }
+    if to.starts_with(&from) {
+        bail!(tr!("minio.data-dir-nested"));
+    }
+    if !is_empty_dir(&to)? {
+        bail!(tr!("minio.data-dir-not-empty", to.display()));
+    }
+
+    let unit = service::unit_name(Com::MinIO);
+    let managed = service::unit_path(Com::MinIO).is_file();
+    let was_running = match managed {
+        true if service::active(&unit) => {
+            log_i18n!(info, "service.stopping", Com::MinIO);
+            service::systemctl(Com::MinIO, &["stop", &unit])?;
+            true
+        }
+        true => false,
+        false => stop_minio()?,
+    };
+
+    let (files, size) = summarize_dir(&from).context(tr!("minio.migrate-failed"))?;
+    log_i18n!(info, "minio.moving", from.display(), to.display());
+    let copied = move_dir(&from, &to).context(tr!("minio.migrate-failed"))?;
+    let (new_files, new_size) = summarize_dir(&to).context(tr!("minio.migrate-failed"))?;
+    if (new_files, new_size) != (files, size) {
+        bail!(tr!(
+            "minio.verify-failed",
+            files,
+            format_size(size),
+            new_files,
+            format_size(new_size)
+        ));
+    }
+
+    let env = minio::write_env(profile).context(tr!("minio.env-write-failed"))?;
+    if copied && !args.keep {
+        fs::remove_dir_all(&from).context(tr!("minio.migrate-failed"))?;
+    }
+    log_i18n!(
+        info,
+        "minio.migrated",
+        to.display(),
+        files,
+        format_size(size)
+    );
+    if copied && args.keep {
+        log_i18n!(info, "minio.old-kept", from.display());
+    }
+
+    if managed {
+        // the data dir is given on the command line of the unit
+        let user = service::unit_user(Com::MinIO).unwrap_or_else(|| "root".to_owned());
+        let content = service::render(config, Com::MinIO, &user).expect("Not a service");
+        let path = service::unit_path(Com::MinIO);
+        fs::write(&path, content).with_context(|| tr!("sys.write-failed", path.display()))?;
+        service::systemctl(Com::MinIO, &["daemon-reload"])?;
+        if was_running {
+            service::systemctl(Com::MinIO, &["start", &unit])?;
+            log_i18n!(info, "minio.restarted-unit", &unit);
+        }
+        return Ok(());
+    }
+    if was_running {
+        return start_minio(info, profile);
+    }
+    log_i18n!(info, "minio.start-again", env.display());
+    tracing::info!(
+        "  {} {}",
+        info.path_or("minio"),
+        minio::server_args(profile).join(" ")
+    );
+    Ok(())
+}
+
+/// Asks the running MinIO server to exit and waits for it, making sure that it is not brought
+/// back by a supervisor in the meantime. Returns whether it was running.
+fn stop_minio() -> Result<bool> {
+    let pids = match platform::find_processes("minio") {
+        Some(pids) => pids,
+        None => {
+            log_i18n!(warn, "minio.cannot-check-running");
+            return Ok(false);
+        }
+    };
+    if pids.is_empty() {
+        return Ok(false);
+    }
+
+    for pid in &pids {
+        log_i18n!(info, "minio.stopping", pid);
+        duct::cmd!("kill", pid.to_string())
+            .stderr_null()
+            .unchecked()
+            .run()
+            .with_context(|| tr!("minio.stop-failed", pid))?;
+    }
+    let mut waited = Duration::from_secs(0);
+    while pids
+        .iter()
+        .any(|pid| platform::process_alive(*pid) == Some(true))
+    {
+        if waited >= STOP_TIMEOUT {
+            bail!(tr!("minio.stop-failed", pids[0]));
+        }
+        thread::sleep(Duration::from_millis(500));
+        waited += Duration::from_millis(500);
+    }
+
+    thread::sleep(Duration::from_secs(3));
+    if !matches!(platform::find_processes("minio"), Some(pids) if pids.is_empty()) {
+        bail!(tr!("minio.supervised"));
+    }
+    Ok(true)
+}
+
+/// Starts MinIO in the background with the environment file, as it was running before the
+/// migration, writing its output to [`minio::log_path`].
+fn start_minio(info: &ComponentInfo, profile: &MinIOProfile) -> Result<()> {
+    let log = minio::log_path();
+    let output = File::create(&log).with_context(|| tr!("sys.write-failed", log.display()))?;
+    let mut cmd = Command::new(info.path_or("minio"));
+    cmd.args(minio::server_args(profile))
+        .envs(minio::read_env().context(tr!("minio.env-read-failed"))?)
+        .stdin(Stdio::null())
+        .stdout(output.try_clone()?)
+        .stderr(output);
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+
+        // out of the process group of the terminal, so that closing it does not stop MinIO
+        cmd.process_group(0);
+    }
+    let child = cmd
+        .spawn()
+        .context(tr!("minio.start-failed", log.display()))?;
+    // a wrong setting makes MinIO exit at once
+    thread::sleep(Duration::from_secs(3));
+    if platform::process_alive(child.id()) == Some(false) {
+        bail!(tr!("minio.start-failed", log.display()));
+    }
+    log_i18n!(info, "minio.restarted", child.id(), log.display());
+    Ok(())
+}
+
+fn is_empty_dir(path: &Path) -> io::Result<bool> {
+    match fs::read_dir(path) {
+        Ok(mut entries) => Ok(entries.next().is_none()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(true),
+        Err(e) => Err(e),
+    }
+}
+
+/// Moves `from` to `to`, copying if they are on different devices. Returns whether it copied.
+fn move_dir(from: &Path, to: &Path) -> io::Result<bool> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if to.exists() {
+        fs::remove_dir(to)?;
+    }
+    if fs::rename(from, to).is_ok() {
+        return Ok(false);
+    }
+    log_i18n!(info, "minio.copying");
+    copy_dir(from, to)?;
+    Ok(true)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else if file_type.is_file() {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,4 @@
+mod main;
+mod migrate;
+
+pub use main::*;
@@ -0,0 +1,186 @@
+use anyhow::{bail, Result};
+use clap::{AppSettings, Clap};
+use url::Url;
+
+use crate::{
+    config::{self, mirror_base},
+    install::helper::{
+        minio, nodejs, ranking, sandbox,
+        utils::{self, MirrorSource, ProbeOptions},
+    },
+    log_i18n, tr,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Clap, Debug)]
+enum SubCommand {
+    /// 测试下载源的延迟
+    /// Measures the latency of the mirrors
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Test(TestArgs),
+
+    /// 固定使用某个下载源，安装时不再测速
+    /// Pins a mirror, which installs use without probing
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Set(SetArgs),
+
+    /// 取消固定的下载源
+    /// Unpins the mirror
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Unset(UnsetArgs),
+}
+
+#[derive(Clap, Debug)]
+struct TestArgs {
+    /// 下载源列表（nodejs、minio、mc、sandbox），默认为全部
+    /// Mirror list (nodejs, minio, mc, sandbox), defaults to all
+    list: Option<String>,
+}
+
+#[derive(Clap, Debug)]
+struct SetArgs {
+    /// 下载源列表（nodejs、minio、mc、sandbox）
+    /// Mirror list (nodejs, minio, mc, sandbox)
+    list: String,
+
+    /// 下载源的地址
+    /// URL of the mirror
+    url: String,
+}
+
+#[derive(Clap, Debug)]
+struct UnsetArgs {
+    /// 下载源列表（nodejs、minio、mc、sandbox）
+    /// Mirror list (nodejs, minio, mc, sandbox)
+    list: String,
+}
+
+const SOURCES: &[&MirrorSource] = &[
+    &nodejs::MIRRORS,
+    &minio::MIRRORS,
+    &minio::MC_MIRRORS,
+    &sandbox::MIRRORS,
+];
+
+impl Args {
+    /// Name of the operation, if the subcommand modifies the config.
+    pub fn operation(&self) -> Option<&'static str> {
+        match self.subcmd {
+            SubCommand::Test(_) => None,
+            SubCommand::Set(_) => Some("mirror set"),
+            SubCommand::Unset(_) => Some("mirror unset"),
+        }
+    }
+}
+
+pub async fn main(args: Args) -> Result<()> {
+    let mut config = config::load_config().await?;
+    match args.subcmd {
+        SubCommand::Test(args) => {
+            let sources = match &args.list {
+                Some(list) => vec![find(list)?],
+                None => SOURCES.to_vec(),
+            };
+            let mut opts = ProbeOptions::from_config(&config);
+            opts.use_ranking = false;
+            for source in sources {
+                test(source, &opts).await;
+            }
+            return Ok(());
+        }
+        SubCommand::Set(args) => {
+            find(&args.list)?;
+            if Url::parse(&args.url).is_err() {
+                bail!(tr!("mirror.invalid-url", &args.url));
+            }
+            let url = mirror_base(&args.url);
+            log_i18n!(info, "mirror.pinned", &args.list, &url);
+            config.mirrors.get_mut(&args.list).unwrap().pinned = Some(url);
+        }
+        SubCommand::Unset(args) => {
+            find(&args.list)?;
+            let list = config.mirrors.get_mut(&args.list).unwrap();
+            if list.pinned.take().is_none() {
+                log_i18n!(info, "mirror.not-pinned", &args.list);
+                return Ok(());
+            }
+            log_i18n!(info, "mirror.unpinned", &args.list);
+        }
+    }
+    config::save_config(&config).await?;
+    Ok(())
+}
+
+fn find(list: &str) -> Result<&'static MirrorSource> {
+    match SOURCES.iter().find(|source| source.key == list) {
+        Some(source) => Ok(source),
+        None => bail!(tr!(
+            "mirror.unknown-list",
+            list,
+            SOURCES
+                .iter()
+                .map(|source| source.key)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Probes every mirror of `source` and prints the latency of each, the fastest first.
+async fn test(source: &MirrorSource, opts: &ProbeOptions) {
+    let pinned = opts
+        .mirrors
+        .get(source.key)
+        .and_then(|list| list.pinned.as_ref());
+    println!("{} ({})", source.key, source.com);
+    let mut mirrors = source.mirrors(opts);
+    if let Some(pinned) = pinned.filter(|pinned| !mirrors.contains(pinned)) {
+        mirrors.insert(0, pinned.clone());
+    }
+    let report = utils::probe(source.com, &mirrors, source.testfile, opts).await;
+    for result in &report.0 {
+        let latency = match result.average {
+            Some(average) => format!("{}ms", average.as_millis()),
+            None => "-".to_owned(),
+        };
+        let mut notes = Vec::new();
+        if result.failed > 0 {
+            notes.push(format!("{}/{} failed", result.failed, utils::ATTEMPTS));
+        }
+        if let Some(e) = &result.last_error {
+            notes.push(format!("last error: {}", e));
+        }
+        let mark = if pinned == Some(&result.mirror) {
+            "*"
+        } else {
+            " "
+        };
+        println!(
+            " {}{:<56} {:>8}  {}",
+            mark,
+            result.mirror,
+            latency,
+            notes.join(", ")
+        );
+    }
+    if let Some(pinned) = pinned {
+        log_i18n!(info, "mirror.pinned-note", source.key, pinned);
+    }
+    println!();
+
+    let ranked = report
+        .0
+        .iter()
+        .filter(|r| r.average.is_some())
+        .map(|r| r.mirror.clone())
+        .collect();
+    if let Err(e) = ranking::save(source.key, ranked) {
+        tracing::debug!("Failed to save the mirror ranking: {}", e);
+    }
+}
@@ -0,0 +1,102 @@
+use once_cell::sync::OnceCell;
+use reqwest::{ClientBuilder, Proxy, Url};
+use std::{env, net::IpAddr};
+
+/// Proxy of the remote requests, from `proxy` of the profile or `ALL_PROXY`.
+static PROXY: OnceCell<Option<Url>> = OnceCell::new();
+
+/// Sets up the proxy from `proxy` of the profile, which takes precedence over the environment.
+/// Without it, `HTTP_PROXY` and `HTTPS_PROXY` are read by reqwest itself, then `ALL_PROXY`.
+/// Only the first call takes effect.
+///
+/// Both HTTP and SOCKS5 proxies are supported, e.g. `http://127.0.0.1:7890` or
+/// `socks5h://127.0.0.1:1080`. Child processes such as npm are given HTTP proxies as well.
+pub fn configure(proxy: Option<&str>) {
+    PROXY.get_or_init(|| {
+        let url = resolve(proxy)?;
+        if url.scheme().starts_with("http") {
+            env::set_var("npm_config_proxy", url.as_str());
+            env::set_var("npm_config_https_proxy", url.as_str());
+        }
+        tracing::debug!("Using proxy {}", url);
+        Some(url)
+    });
+}
+
+fn resolve(proxy: Option<&str>) -> Option<Url> {
+    let proxy = match proxy {
+        Some(proxy) => proxy.to_owned(),
+        None if has_env("HTTP_PROXY") || has_env("HTTPS_PROXY") => return None,
+        None => env::var("ALL_PROXY")
+            .or_else(|_| env::var("all_proxy"))
+            .ok()
+            .filter(|proxy| !proxy.is_empty())?,
+    };
+    match Url::parse(&proxy) {
+        Ok(url) if ["http", "https", "socks5", "socks5h"].contains(&url.scheme()) => Some(url),
+        _ => {
+            crate::log_i18n!(warn, "config.invalid-proxy", &proxy);
+            None
+        }
+    }
+}
+
+fn has_env(name: &str) -> bool {
+    [name.to_owned(), name.to_lowercase()]
+        .iter()
+        .any(|name| env::var_os(name).is_some_and(|value| !value.is_empty()))
+}
+
+/// Environment variable of a GitHub token, which raises the rate limit of the GitHub API from 60
+/// requests an hour.
+pub const GITHUB_TOKEN_ENV: &str = "GITHUB_TOKEN";
+
+/// A GET request to the GitHub API, authorized with the token in [`GITHUB_TOKEN_ENV`] if set.
+pub fn github_api(url: &str) -> reqwest::RequestBuilder {
+    let req = client()
+        .get(url)
+        // required by the GitHub API
+        .header(reqwest::header::USER_AGENT, "h2o2");
+    match env::var(GITHUB_TOKEN_ENV) {
+        Ok(token) if !token.is_empty() => req.bearer_auth(token),
+        _ => req,
+    }
+}
+
+/// A builder of clients sending remote requests through the proxy configured.
+pub fn client_builder() -> ClientBuilder {
+    let builder = reqwest::Client::builder();
+    match PROXY.get().and_then(Option::as_ref) {
+        // the services of H2O2 listen locally, which the proxy cannot reach
+        Some(proxy) => {
+            let proxy = proxy.clone();
+            builder.proxy(Proxy::custom(move |url| {
+                if is_local(url) {
+                    None
+                } else {
+                    Some(proxy.clone())
+                }
+            }))
+        }
+        None => builder,
+    }
+}
+
+/// A client sending remote requests through the proxy configured.
+pub fn client() -> reqwest::Client {
+    client_builder()
+        .build()
+        .expect("Failed to build the HTTP client")
+}
+
+fn is_local(url: &Url) -> bool {
+    match url.host_str() {
+        Some("localhost") => true,
+        Some(host) => host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback()),
+        None => true,
+    }
+}
@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use crate::{config::NotifyProfile, log_i18n, net, platform, profiling, utils::decode_output};
+
+/// Sends `message` about `event`, e.g. `cert-expiring`, through the configured hooks.
+///
+/// Failures of the hooks are logged rather than returned, so that they never break the
+/// operation being reported. Returns whether any hook is configured.
+pub async fn send(profile: &NotifyProfile, event: &str, message: &str) -> bool {
+    if let Some(command) = &profile.command {
+        let (shell, flag) = if platform::current().is_windows() {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        let res = duct::cmd!(shell, flag, command)
+            .env("H2O2_EVENT", event)
+            .env("H2O2_MESSAGE", message)
+            .stdout_capture()
+            .stderr_capture()
+            .unchecked()
+            .run();
+        match res {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => log_i18n!(
+                warn,
+                "notify.command-failed",
+                decode_output(&output.stderr).trim()
+            ),
+            Err(e) => log_i18n!(warn, "notify.command-failed", e),
+        }
+    }
+
+    if let Some(webhook) = &profile.webhook {
+        if let Err(e) = post(webhook, event, message).await {
+            log_i18n!(warn, "notify.webhook-failed", e);
+        }
+    }
+
+    profile.command.is_some() || profile.webhook.is_some()
+}
+
+async fn post(url: &str, event: &str, message: &str) -> reqwest::Result<()> {
+    let _span = profiling::span("main", url);
+    let body = serde_json::json!({ "event": event, "message": message }).to_string();
+    net::client_builder()
+        .timeout(Duration::from_secs(10))
+        .build()?
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use clap::ArgEnum;
+use once_cell::sync::OnceCell;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+use crate::{
+    check::advisory::{Finding, Release},
+    config::{ComponentInfo, Components, RuntimeInfo, Version},
+    registry::{self, Descriptor},
+};
+
+/// How commands such as `show`, `check`, `detect` and `verify` print their results.
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// text for humans
+    Text,
+    /// a JSON document on stdout, for scripts
+    Json,
+}
+
+static FORMAT: OnceCell<Format> = OnceCell::new();
+
+/// Sets how results are printed. Only the first call takes effect.
+pub fn set_format(format: Format) {
+    let _ = FORMAT.set(format);
+}
+
+pub fn is_json() -> bool {
+    FORMAT.get() == Some(&Format::Json)
+}
+
+/// Prints `document` as the result of the command.
+pub fn print_json(document: &Value) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(document).expect("JSON values always serialize")
+    );
+}
+
+/// Whether the component is installed as recorded, e.g. `installed` or `missing`.
+fn status(info: &ComponentInfo) -> &'static str {
+    match info.version {
+        Version::Unknown => "missing",
+        Version::Installed | Version::Valid(_) => "installed",
+        Version::Invalid(_) => "invalid",
+    }
+}
+
+pub fn component(desc: &Descriptor, info: &ComponentInfo) -> Value {
+    let version = match &info.version {
+        Version::Valid(v) => Some(v.to_string()),
+        Version::Invalid(text) => Some(text.clone()),
+        Version::Unknown | Version::Installed => None,
+    };
+    json!({
+        "name": desc.name,
+        "display": desc.display,
+        "version": version,
+        "path": info.path,
+        "status": status(info),
+    })
+}
+
+/// The built-in components and the optional ones recorded, in the order `show` prints them.
+pub fn components(com: &Components) -> Value {
+    registry::COMPONENTS
+        .iter()
+        .filter_map(|desc| match desc.com {
+            Some(c) => Some(component(desc, com.borrow_by_com(c))),
+            None => com.extras.get(desc.name).map(|info| component(desc, info)),
+        })
+        .collect()
+}
+
+pub fn runtimes(runtimes: &BTreeMap<String, RuntimeInfo>) -> Value {
+    runtimes
+        .iter()
+        .map(|(name, info)| json!({ "name": name, "version": info.version, "path": info.path }))
+        .collect()
+}
+
+/// Findings of [`crate::check::advisory::evaluate`].
+pub fn findings(findings: &[(String, Release, Finding)]) -> Value {
+    findings
+        .iter()
+        .map(|(name, release, finding)| {
+            json!({
+                "component": name,
+                "release": release.to_string(),
+                "urgent": finding.is_urgent(),
+                "message": finding.message(name, release),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,516 @@
+use derive_more::Display;
+use once_cell::sync::Lazy;
+use std::{
+    env,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::profiling;
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum Os {
+    #[display(fmt = "Linux")]
+    Linux,
+    #[display(fmt = "Windows")]
+    Windows,
+    #[display(fmt = "macOS")]
+    MacOS,
+    #[display(fmt = "other")]
+    Other,
+}
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum Arch {
+    #[display(fmt = "x86")]
+    X86,
+    #[display(fmt = "x86_64")]
+    X86_64,
+    #[display(fmt = "aarch64")]
+    Aarch64,
+    #[display(fmt = "other")]
+    Other,
+}
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    #[display(fmt = "bash")]
+    Bash,
+    #[display(fmt = "zsh")]
+    Zsh,
+    #[display(fmt = "fish")]
+    Fish,
+    #[display(fmt = "sh")]
+    Sh,
+    #[display(fmt = "PowerShell")]
+    PowerShell,
+    #[display(fmt = "cmd")]
+    Cmd,
+    #[display(fmt = "unknown")]
+    Unknown,
+}
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceManager {
+    #[display(fmt = "systemd")]
+    Systemd,
+    #[display(fmt = "OpenRC")]
+    OpenRC,
+    #[display(fmt = "launchd")]
+    Launchd,
+    #[display(fmt = "Windows services")]
+    WindowsService,
+    #[display(fmt = "none")]
+    None,
+}
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum PackageManager {
+    #[display(fmt = "apt")]
+    Apt,
+    #[display(fmt = "dnf")]
+    Dnf,
+    #[display(fmt = "yum")]
+    Yum,
+    #[display(fmt = "pacman")]
+    Pacman,
+    #[display(fmt = "apk")]
+    Apk,
+    #[display(fmt = "zypper")]
+    Zypper,
+    #[display(fmt = "Homebrew")]
+    Brew,
+    #[display(fmt = "winget")]
+    Winget,
+    #[display(fmt = "Chocolatey")]
+    Choco,
+}
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum Container {
+    #[display(fmt = "Docker")]
+    Docker,
+    #[display(fmt = "Podman")]
+    Podman,
+    #[display(fmt = "LXC")]
+    Lxc,
+    #[display(fmt = "Kubernetes")]
+    Kubernetes,
+    #[display(fmt = "unknown container")]
+    Other,
+}
+
+/// The platform H2O2 is running on.
+#[derive(Debug, Clone)]
+pub struct Platform {
+    pub os: Os,
+    pub arch: Arch,
+    pub shell: Shell,
+    pub service_manager: ServiceManager,
+    pub package_manager: Option<PackageManager>,
+    pub wsl: bool,
+    pub container: Option<Container>,
+}
+
+static CURRENT: Lazy<Platform> = Lazy::new(|| {
+    let _span = profiling::span("main", "detect platform");
+    Platform::detect()
+});
+
+/// Returns the platform detected at the first call.
+pub fn current() -> &'static Platform {
+    &CURRENT
+}
+
+/// Shorthand for `current().cmd(name)`.
+pub fn cmd(name: &str) -> String {
+    current().cmd(name)
+}
+
+/// Shorthand for `current().exe(name)`.
+pub fn exe(name: &str) -> String {
+    current().exe(name)
+}
+
+impl Platform {
+    pub fn detect() -> Self {
+        let os = match env::consts::OS {
+            "linux" => Os::Linux,
+            "windows" => Os::Windows,
+            "macos" => Os::MacOS,
+            _ => Os::Other,
+        };
+        let arch = match env::consts::ARCH {
+            "x86" => Arch::X86,
+            "x86_64" => Arch::X86_64,
+            "aarch64" => Arch::Aarch64,
+            _ => Arch::Other,
+        };
+        let shell = match os {
+            Os::Windows if env::var_os("PSModulePath").is_some() => Shell::PowerShell,
+            Os::Windows => Shell::Cmd,
+            _ => env::var("SHELL").map_or(Shell::Unknown, |path| Shell::from_path(&path)),
+        };
+        let container = if os == Os::Linux {
+            detect_container()
+        } else {
+            None
+        };
+        // systemd runs in system containers such as LXC, while services inside other containers
+        // are managed by the container runtime
+        let service_manager = match os {
+            Os::Linux if Path::new("/run/systemd/system").is_dir() => ServiceManager::Systemd,
+            _ if container.is_some() => ServiceManager::None,
+            Os::Linux if Path::new("/run/openrc").is_dir() => ServiceManager::OpenRC,
+            Os::MacOS => ServiceManager::Launchd,
+            Os::Windows => ServiceManager::WindowsService,
+            _ => ServiceManager::None,
+        };
+        let package_manager = PackageManager::candidates(os)
+            .iter()
+            .copied()
+            .find(|pm| find_in_path(pm.executable()).is_some());
+        let wsl = os == Os::Linux
+            && (env::var_os("WSL_DISTRO_NAME").is_some()
+                || fs::read_to_string("/proc/sys/kernel/osrelease")
+                    .map(|release| is_wsl_release(&release))
+                    .unwrap_or(false));
+
+        Self {
+            os,
+            arch,
+            shell,
+            service_manager,
+            package_manager,
+            wsl,
+            container,
+        }
+    }
+
+    pub fn is_windows(&self) -> bool {
+        self.os == Os::Windows
+    }
+
+    pub fn in_container(&self) -> bool {
+        self.container.is_some()
+    }
+
+    /// Name of a Node.js CLI shim, such as `yarn.cmd` on Windows.
+    pub fn cmd(&self, name: &str) -> String {
+        if self.is_windows() {
+            format!("{}.cmd", name)
+        } else {
+            name.to_owned()
+        }
+    }
+
+    /// Name of an executable file, such as `minio.exe` on Windows.
+    pub fn exe(&self, name: &str) -> String {
+        if self.is_windows() {
+            format!("{}.exe", name)
+        } else {
+            name.to_owned()
+        }
+    }
+}
+
+impl Shell {
+    /// Recognizes the shell from its path, such as `/usr/bin/zsh`.
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).file_name().and_then(|name| name.to_str()) {
+            Some("bash") => Shell::Bash,
+            Some("zsh") => Shell::Zsh,
+            Some("fish") => Shell::Fish,
+            Some("sh") | Some("dash") | Some("ash") => Shell::Sh,
+            Some("pwsh") | Some("powershell") => Shell::PowerShell,
+            _ => Shell::Unknown,
+        }
+    }
+}
+
+impl PackageManager {
+    fn candidates(os: Os) -> &'static [PackageManager] {
+        match os {
+            Os::Linux => &[
+                PackageManager::Apt,
+                PackageManager::Dnf,
+                PackageManager::Yum,
+                PackageManager::Pacman,
+                PackageManager::Apk,
+                PackageManager::Zypper,
+            ],
+            Os::MacOS => &[PackageManager::Brew],
+            Os::Windows => &[PackageManager::Winget, PackageManager::Choco],
+            Os::Other => &[],
+        }
+    }
+
+    /// Arguments to install `package` without prompting.
+    pub fn install_args(self, package: &str) -> Vec<&str> {
+        match self {
+            PackageManager::Apt | PackageManager::Dnf | PackageManager::Yum => {
+                vec!["install", "-y", package]
+            }
+            PackageManager::Pacman => vec!["-S", "--noconfirm", package],
+            PackageManager::Apk => vec!["add", package],
+            PackageManager::Zypper => vec!["--non-interactive", "install", package],
+            PackageManager::Brew => vec!["install", package],
+            PackageManager::Winget => vec!["install", "--silent", package],
+            PackageManager::Choco => vec!["install", "-y", package],
+        }
+    }
+
+    pub fn executable(self) -> &'static str {
+        match self {
+            PackageManager::Apt => "apt-get",
+            PackageManager::Dnf => "dnf",
+            PackageManager::Yum => "yum",
+            PackageManager::Pacman => "pacman",
+            PackageManager::Apk => "apk",
+            PackageManager::Zypper => "zypper",
+            PackageManager::Brew => "brew",
+            PackageManager::Winget => "winget",
+            PackageManager::Choco => "choco",
+        }
+    }
+}
+
+impl Container {
+    /// Recognizes the container runtime from the content of `/proc/1/cgroup`.
+    pub fn from_cgroup(cgroup: &str) -> Option<Self> {
+        if cgroup.contains("kubepods") {
+            Some(Container::Kubernetes)
+        } else if cgroup.contains("docker") {
+            Some(Container::Docker)
+        } else if cgroup.contains("libpod") {
+            Some(Container::Podman)
+        } else if cgroup.contains("lxc") {
+            Some(Container::Lxc)
+        } else {
+            None
+        }
+    }
+}
+
+/// Checks the content of `/proc/sys/kernel/osrelease`, which contains `microsoft` under WSL.
+pub fn is_wsl_release(release: &str) -> bool {
+    release.to_lowercase().contains("microsoft")
+}
+
+fn detect_container() -> Option<Container> {
+    if Path::new("/.dockerenv").exists() {
+        return Some(Container::Docker);
+    }
+    if Path::new("/run/.containerenv").exists() {
+        return Some(Container::Podman);
+    }
+    // set by LXC and systemd-nspawn
+    match env::var("container").as_deref() {
+        Ok("lxc") => return Some(Container::Lxc),
+        Ok("docker") => return Some(Container::Docker),
+        Ok("podman") => return Some(Container::Podman),
+        Ok(_) => return Some(Container::Other),
+        Err(_) => {}
+    }
+    fs::read_to_string("/proc/1/cgroup")
+        .ok()
+        .and_then(|cgroup| Container::from_cgroup(&cgroup))
+}
+
+/// Searches `PATH` for an executable like `which`.
+pub fn find_in_path(name: &str) -> Option<PathBuf> {
+    find_in(&env::var_os("PATH")?, name)
+}
+
+/// Searches the directories of `paths`, formatted like `PATH`, for an executable.
+pub fn find_in(paths: &OsStr, name: &str) -> Option<PathBuf> {
+    let names = if current_is_windows() {
+        vec![
+            format!("{}.exe", name),
+            format!("{}.cmd", name),
+            name.to_owned(),
+        ]
+    } else {
+        vec![name.to_owned()]
+    };
+    env::split_paths(paths)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|path| path.is_file())
+}
+
+/// Whether a process with `pid` is running, or `None` if it cannot be determined here.
+pub fn process_alive(pid: u32) -> Option<bool> {
+    if current().os == Os::Linux {
+        Some(Path::new("/proc").join(pid.to_string()).exists())
+    } else {
+        None
+    }
+}
+
+/// Finds the processes named `name`, or `None` if processes cannot be listed here.
+pub fn find_processes(name: &str) -> Option<Vec<u32>> {
+    // processes can only be listed through procfs for now
+    if current().os != Os::Linux {
+        return None;
+    }
+    let entries = match fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(_) => return Some(Vec::new()),
+    };
+    // `comm` is truncated to 15 bytes
+    let comm_name = &name[..name.len().min(15)];
+    let mut pids = entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|pid| {
+            matches!(fs::read_to_string(format!("/proc/{}/comm", pid)),
+                Ok(comm) if comm.trim_end() == comm_name)
+        })
+        .collect::<Vec<_>>();
+    pids.sort_unstable();
+    Some(pids)
+}
+
+/// Memory of the system in bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct Memory {
+    pub total: u64,
+    pub available: u64,
+    pub swap_total: u64,
+}
+
+/// Reads the memory of the system, or `None` if it cannot be determined here.
+pub fn memory() -> Option<Memory> {
+    if current().os != Os::Linux {
+        return None;
+    }
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    // lines look like `MemTotal:        2030432 kB`
+    let field = |name: &str| {
+        meminfo.lines().find_map(|line| {
+            let value = line.strip_prefix(name)?.strip_prefix(':')?;
+            let kib = value
+                .trim()
+                .trim_end_matches("kB")
+                .trim()
+                .parse::<u64>()
+                .ok()?;
+            Some(kib * 1024)
+        })
+    };
+    Some(Memory {
+        total: field("MemTotal")?,
+        available: field("MemAvailable").or_else(|| field("MemFree"))?,
+        swap_total: field("SwapTotal").unwrap_or(0),
+    })
+}
+
+/// Share of the last minute in which some tasks stalled waiting for memory, in percent, or `None`
+/// if the kernel reports no pressure stall information.
+pub fn memory_pressure() -> Option<f64> {
+    if current().os != Os::Linux {
+        return None;
+    }
+    // the first line looks like `some avg10=0.00 avg60=0.00 avg300=0.00 total=0`
+    let pressure = fs::read_to_string("/proc/pressure/memory").ok()?;
+    pressure
+        .lines()
+        .find(|line| line.starts_with("some "))?
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix("avg60="))?
+        .parse()
+        .ok()
+}
+
+/// Space available to unprivileged users on the file system holding `path` in bytes, or `None`
+/// if it cannot be determined here.
+pub fn free_space(path: &Path) -> Option<u64> {
+    if current().is_windows() {
+        return None;
+    }
+    // POSIX output: `Filesystem 1024-blocks Used Available Capacity Mounted on`
+    let output = duct::cmd!("df", "-Pk", path)
+        .stdout_capture()
+        .stderr_null()
+        .run()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let kib = stdout
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(3)?
+        .parse::<u64>()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+/// Controllers of the cgroup file system available to processes, or `None` if no cgroup file
+/// system is mounted. Both cgroup v2 and v1 are recognized.
+pub fn cgroup_controllers() -> Option<Vec<String>> {
+    const ROOT: &str = "/sys/fs/cgroup";
+    if current().os != Os::Linux {
+        return None;
+    }
+    if let Ok(controllers) = fs::read_to_string(Path::new(ROOT).join("cgroup.controllers")) {
+        return Some(
+            controllers
+                .split_whitespace()
+                .map(ToOwned::to_owned)
+                .collect(),
+        );
+    }
+    // v1 mounts a hierarchy for each controller, some of which are combined like `cpu,cpuacct`
+    let mut controllers = fs::read_dir(ROOT)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .flat_map(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .split(',')
+                .map(ToOwned::to_owned)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    controllers.sort();
+    controllers.dedup();
+    Some(controllers)
+}
+
+/// Whether H2O2 is running as root, or `None` if it cannot be determined here.
+pub fn is_root() -> Option<bool> {
+    if current().os != Os::Linux {
+        return None;
+    }
+    // `Uid:` is followed by the real, effective, saved and filesystem UIDs
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let uid = status
+        .lines()
+        .find_map(|line| line.strip_prefix("Uid:"))?
+        .split_whitespace()
+        .nth(1)?;
+    Some(uid == "0")
+}
+
+/// Home dir of `user`, a name or a numeric UID, from `/etc/passwd`, or `None` if not listed there.
+pub fn home_of(user: &str) -> Option<PathBuf> {
+    let passwd = fs::read_to_string("/etc/passwd").ok()?;
+    // name:password:UID:GID:comment:home:shell
+    passwd.lines().find_map(|line| {
+        let fields = line.split(':').collect::<Vec<_>>();
+        match fields.as_slice() {
+            [name, _, uid, _, _, home, ..] if *name == user || *uid == user => {
+                Some(PathBuf::from(home))
+            }
+            _ => None,
+        }
+    })
+}
+
+// `find_in_path` is used during detection, so it cannot rely on `current()`
+fn current_is_windows() -> bool {
+    env::consts::OS == "windows"
+}
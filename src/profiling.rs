@@ -0,0 +1,115 @@
+use once_cell::sync::OnceCell;
+use serde_json::json;
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::log_i18n;
+
+struct Recorder {
+    start: Instant,
+    tracks: Vec<String>,
+    events: Vec<serde_json::Value>,
+}
+
+static RECORDER: OnceCell<Mutex<Recorder>> = OnceCell::new();
+
+/// Writes the trace to the file when dropped.
+pub struct Profiler {
+    path: PathBuf,
+}
+
+/// Starts recording spans, to be written to `path` as a Chrome trace when the returned guard is
+/// dropped. The trace can be opened in `chrome://tracing` or https://ui.perfetto.dev.
+///
+/// Spans are grouped into named tracks, shown as threads, so that concurrent work such as
+/// probing several mirrors does not overlap within a track.
+pub fn start(path: PathBuf) -> Profiler {
+    let _ = RECORDER.set(Mutex::new(Recorder {
+        start: Instant::now(),
+        tracks: Vec::new(),
+        events: Vec::new(),
+    }));
+    Profiler { path }
+}
+
+impl Drop for Profiler {
+    fn drop(&mut self) {
+        match write(&self.path) {
+            Ok(()) => log_i18n!(info, "profiling.written", self.path.display()),
+            Err(e) => log_i18n!(warn, "profiling.write-failed", self.path.display(), e),
+        }
+    }
+}
+
+/// A phase being timed, recorded when dropped. Does nothing if profiling is not enabled.
+#[must_use]
+pub struct Span(Option<(String, String, Instant)>);
+
+/// Starts timing the phase `name` on the track `track`, such as a component name.
+pub fn span(track: impl Into<String>, name: impl Into<String>) -> Span {
+    if RECORDER.get().is_none() {
+        return Span(None);
+    }
+    Span(Some((track.into(), name.into(), Instant::now())))
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let (track, name, begin) = match self.0.take() {
+            Some(span) => span,
+            None => return,
+        };
+        let end = Instant::now();
+        let mut recorder = match RECORDER.get() {
+            Some(recorder) => recorder.lock().unwrap(),
+            None => return,
+        };
+        let tid = match recorder.tracks.iter().position(|t| *t == track) {
+            Some(tid) => tid,
+            None => {
+                recorder.tracks.push(track);
+                recorder.tracks.len() - 1
+            }
+        };
+        let ts = micros(begin.saturating_duration_since(recorder.start));
+        let dur = micros(end.saturating_duration_since(begin));
+        recorder.events.push(json!({
+            "name": name,
+            "ph": "X",
+            "ts": ts,
+            "dur": dur,
+            "pid": 1,
+            "tid": tid,
+        }));
+    }
+}
+
+fn micros(duration: Duration) -> u64 {
+    duration.as_micros() as u64
+}
+
+fn write(path: &PathBuf) -> io::Result<()> {
+    let recorder = match RECORDER.get() {
+        Some(recorder) => recorder.lock().unwrap(),
+        None => return Ok(()),
+    };
+    // name the tracks, then sort them in the order they first appear
+    let mut events = recorder
+        .tracks
+        .iter()
+        .enumerate()
+        .flat_map(|(tid, track)| {
+            vec![
+                json!({ "name": "thread_name", "ph": "M", "pid": 1, "tid": tid, "args": { "name": track } }),
+                json!({ "name": "thread_sort_index", "ph": "M", "pid": 1, "tid": tid, "args": { "sort_index": tid } }),
+            ]
+        })
+        .collect::<Vec<_>>();
+    events.extend(recorder.events.iter().cloned());
+    let trace = json!({ "traceEvents": events, "displayTimeUnit": "ms" });
+    fs::write(path, trace.to_string())
+}
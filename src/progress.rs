@@ -0,0 +1,133 @@
+use clap::ArgEnum;
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressFinish, ProgressStyle};
+use once_cell::sync::{Lazy, OnceCell};
+use serde_json::json;
+use std::{
+    fmt::Display,
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+/// Progress bars of concurrent tasks, drawn to stderr below the logs. They are hidden if stderr
+/// is not a terminal.
+static BARS: Lazy<MultiProgress> = Lazy::new(MultiProgress::new);
+
+/// How progress is reported.
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// progress bars for humans
+    Bar,
+    /// newline-delimited JSON records on stdout, for programs wrapping H2O2
+    Json,
+}
+
+static MODE: OnceCell<Mode> = OnceCell::new();
+
+/// Minimum time between two records of the same download whose length is unknown.
+const RECORD_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Sets how progress is reported. Only the first call takes effect.
+pub fn set_mode(mode: Mode) {
+    if MODE.set(mode).is_ok() && mode == Mode::Json {
+        // the records replace the bars
+        BARS.set_draw_target(ProgressDrawTarget::hidden());
+    }
+}
+
+fn is_json() -> bool {
+    MODE.get() == Some(&Mode::Json)
+}
+
+/// Prints a record of `component` entering `phase`, e.g. `download` or `done`, if progress is
+/// reported as JSON.
+pub fn phase(component: &str, phase: &str) {
+    if is_json() {
+        emit(json!({ "phase": phase, "component": component }));
+    }
+}
+
+fn emit(record: serde_json::Value) {
+    // a record is written at once, so that records of concurrent tasks do not interleave
+    let mut stdout = io::stdout();
+    let _ = writeln!(stdout, "{}", record).and_then(|()| stdout.flush());
+}
+
+/// Progress of a download, drawn as a bar or printed as JSON records.
+pub struct Download {
+    component: String,
+    bar: ProgressBar,
+    len: Option<u64>,
+    /// the percentage, or the time for an unknown length, of the last record
+    last: Option<(u64, Instant)>,
+}
+
+impl Download {
+    /// Adds `delta` bytes downloaded.
+    pub fn inc(&mut self, delta: u64) {
+        self.bar.inc(delta);
+        if !is_json() {
+            return;
+        }
+        let bytes = self.bar.position();
+        let percent = self
+            .len
+            .map(|len| (bytes * 100).checked_div(len).unwrap_or(100));
+        let due = match (self.last, percent) {
+            (None, _) => true,
+            (Some((last, _)), Some(percent)) => percent != last,
+            (Some((_, at)), None) => at.elapsed() >= RECORD_INTERVAL,
+        };
+        if due {
+            self.last = Some((percent.unwrap_or(0), Instant::now()));
+            emit(json!({
+                "phase": "download",
+                "component": self.component,
+                "bytes": bytes,
+                "total": self.len,
+                "percent": percent,
+            }));
+        }
+    }
+}
+
+/// Progress of downloading `len` bytes of `component`, drawn as a bar, or a spinner if the length
+/// is unknown. The bar is removed when dropped.
+pub fn download(component: &str, name: impl Display, len: Option<u64>) -> Download {
+    Download {
+        component: component.to_owned(),
+        bar: download_bar(name, len),
+        len,
+        last: None,
+    }
+}
+
+/// A bar of a download of `len` bytes, or a spinner if the length is unknown. It is removed when
+/// dropped.
+pub fn download_bar(name: impl Display, len: Option<u64>) -> ProgressBar {
+    let bar = match len {
+        Some(len) => ProgressBar::new(len).with_style(
+            ProgressStyle::with_template(
+                "{prefix:>8} [{bar:30}] {bytes}/{total_bytes} {bytes_per_sec} {eta}",
+            )
+            .expect("Incorrect progress template")
+            .progress_chars("=> "),
+        ),
+        None => {
+            let bar = ProgressBar::new_spinner().with_style(
+                ProgressStyle::with_template("{prefix:>8} {spinner} {bytes} {bytes_per_sec}")
+                    .expect("Incorrect progress template"),
+            );
+            bar.enable_steady_tick(Duration::from_millis(100));
+            bar
+        }
+    };
+    BARS.add(
+        bar.with_prefix(name.to_string())
+            .with_finish(ProgressFinish::AndClear),
+    )
+}
+
+/// Runs `f` with the bars hidden, so that the output does not mix with them.
+pub fn suspend<R>(f: impl FnOnce() -> R) -> R {
+    BARS.suspend(f)
+}
@@ -0,0 +1,328 @@
+use anyhow::{bail, Context, Result};
+use clap::Clap;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    config::{self, Config},
+    install::helper::{
+        hydro,
+        minio::{mc_path, MC_ALIAS},
+        mongodb,
+    },
+    log_i18n, minio, tr,
+    utils::{decode_output, format_size, parse_date, summarize_dir},
+    Com,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    /// 删除此日期 (UTC) 之前的提交记录及其在 MinIO 中的提交文件，格式为 YYYY-MM-DD
+    /// Deletes submission records before this date (UTC) and their files in MinIO, as YYYY-MM-DD
+    #[clap(long)]
+    records_before: Option<String>,
+
+    /// 保留通过的提交记录（此时不删除提交文件）
+    /// Keeps accepted records (submission files are kept then)
+    #[clap(long)]
+    keep_accepted: bool,
+
+    /// 清空评测机的测试数据缓存
+    /// Empties the test data cache of the judge
+    #[clap(long)]
+    testdata_cache: bool,
+
+    /// 存放 Hydro 文件的 MinIO 桶
+    /// MinIO bucket storing the files of Hydro
+    #[clap(long, default_value = "hydro")]
+    bucket: String,
+
+    /// 实际执行清理，须先以相同参数预演
+    /// Actually deletes the data, which requires a dry run with the same options first
+    #[clap(long)]
+    execute: bool,
+}
+
+impl Args {
+    /// Name of the operation, if the data is actually deleted.
+    pub fn operation(&self) -> Option<&'static str> {
+        if self.execute {
+            Some("prune")
+        } else {
+            None
+        }
+    }
+}
+
+/// Options of a dry run, which `--execute` must match.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Plan {
+    records_before: Option<String>,
+    keep_accepted: bool,
+    testdata_cache: bool,
+    bucket: String,
+}
+
+/// How long a dry run stays valid for `--execute`.
+const PLAN_TTL: u64 = 24 * 60 * 60;
+
+/// Hydro's `STATUS_ACCEPTED`.
+const STATUS_ACCEPTED: u32 = 1;
+
+/// Prefix Hydro stores submitted files under in its bucket.
+const SUBMISSION_PREFIX: &str = "submission/";
+
+#[derive(Deserialize, Debug)]
+struct RecordsSummary {
+    count: u64,
+    size: u64,
+}
+
+pub async fn main(args: Args) -> Result<()> {
+    let config = config::load_config().await?;
+    let cutoff = match &args.records_before {
+        Some(date) => Some(parse_date(date).with_context(|| tr!("prune.invalid-date", date))?),
+        None => None,
+    };
+    if cutoff.is_none() && !args.testdata_cache {
+        bail!(tr!("prune.nothing"));
+    }
+    let plan = Plan {
+        records_before: args.records_before.clone(),
+        keep_accepted: args.keep_accepted,
+        testdata_cache: args.testdata_cache,
+        bucket: args.bucket.clone(),
+    };
+    if args.execute {
+        check_plan(&plan)?;
+    } else {
+        log_i18n!(info, "prune.dry-run");
+    }
+
+    let mut total = 0;
+    if let Some(cutoff) = cutoff {
+        if !config.components.mongodb.is_installed() {
+            bail!(tr!("check.not-installed", Com::MongoDB));
+        }
+        total += prune_records(&config, cutoff, args.keep_accepted, args.execute)?;
+        if args.keep_accepted {
+            log_i18n!(info, "prune.files-kept");
+        } else if config.components.minio.is_installed() {
+            let date = args.records_before.as_deref().unwrap();
+            total += prune_files(&config, &args.bucket, date, args.execute).await?;
+        }
+    }
+    if args.testdata_cache {
+        total += prune_testdata_cache(args.execute)?;
+    }
+
+    if args.execute {
+        log_i18n!(info, "prune.done", format_size(total));
+        if cutoff.is_some() {
+            log_i18n!(info, "prune.compact-hint");
+        }
+        let _ = fs::remove_file(plan_path());
+    } else {
+        save_plan(&plan)?;
+        log_i18n!(info, "prune.dry-run-done", format_size(total));
+    }
+    Ok(())
+}
+
+/// Counts, or deletes, the records before `cutoff`. Returns the size of them.
+fn prune_records(config: &Config, cutoff: u64, keep_accepted: bool, execute: bool) -> Result<u64> {
+    // ObjectIds start with their creation time, so the first one of the cutoff bounds the range
+    let mut query = format!(
+        "{{ _id: {{ $lt: ObjectId('{:08x}0000000000000000') }}",
+        cutoff
+    );
+    if keep_accepted {
+        query += &format!(", status: {{ $ne: {} }}", STATUS_ACCEPTED);
+    }
+    query += " }";
+
+    // `$bsonSize` requires MongoDB 4.4, estimate by the average size before that
+    let summary_script = format!(
+        r#"
+const n = x => (x && x.toNumber) ? x.toNumber() : Number(x || 0);
+const q = {query};
+const count = db.record.countDocuments(q);
+let size = null;
+try {{
+    const r = db.record.aggregate([{{ $match: q }}, {{ $group: {{ _id: null, size: {{ $sum: {{ $bsonSize: '$$ROOT' }} }} }} }}]).toArray();
+    size = r.length ? n(r[0].size) : 0;
+}} catch (e) {{
+    size = count * n(db.record.stats().avgObjSize);
+}}
+print(JSON.stringify({{ count, size }}));
+"#,
+        query = query
+    );
+    let output = mongodb::eval(
+        &config.components.mongodb,
+        &config.profile.mongodb,
+        &summary_script,
+    )
+    .context(tr!("prune.records-failed"))?;
+    let summary: RecordsSummary = serde_json::from_str(&output)
+        .with_context(|| format!("{}\n{}", tr!("prune.records-failed"), output))?;
+    log_i18n!(
+        info,
+        "prune.records",
+        summary.count,
+        format_size(summary.size)
+    );
+
+    if execute && summary.count > 0 {
+        let delete_script = format!("print(db.record.deleteMany({}).deletedCount)", query);
+        let deleted = mongodb::eval(
+            &config.components.mongodb,
+            &config.profile.mongodb,
+            &delete_script,
+        )
+        .context(tr!("prune.records-failed"))?;
+        log_i18n!(info, "prune.records-deleted", deleted);
+    }
+    Ok(summary.size)
+}
+
+/// Counts, or deletes, the submitted files last modified before `date`. Returns the size of them.
+async fn prune_files(config: &Config, bucket: &str, date: &str, execute: bool) -> Result<u64> {
+    minio::ensure_mc(config).await?;
+    let prefix = format!("{}/{}/{}", MC_ALIAS, bucket, SUBMISSION_PREFIX);
+    let output = duct::cmd!(mc_path(), "ls", "--recursive", "--json", &prefix)
+        .stdout_capture()
+        .stderr_capture()
+        .unchecked()
+        .run()
+        .context(tr!("prune.files-failed"))?;
+    if !output.status.success() {
+        bail!(
+            "{}\n{}",
+            tr!("prune.files-failed"),
+            decode_output(&output.stderr).trim()
+        );
+    }
+
+    let date = normalize_date(date);
+    let mut objects = Vec::new();
+    let mut size = 0;
+    for line in decode_output(&output.stdout).lines() {
+        let entry = match serde_json::from_str::<serde_json::Value>(line) {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+        // `lastModified` is an RFC 3339 time, comparing its date is precise enough here
+        let modified = entry["lastModified"].as_str().unwrap_or_default();
+        if entry["type"] != "file" || modified.get(..10).is_none_or(|day| day >= &date[..]) {
+            continue;
+        }
+        if let Some(key) = entry["key"].as_str() {
+            objects.push(format!("{}{}", prefix, key));
+            size += entry["size"].as_u64().unwrap_or(0);
+        }
+    }
+    log_i18n!(info, "prune.files", objects.len(), format_size(size));
+
+    if execute && !objects.is_empty() {
+        let output = duct::cmd!(mc_path(), "rm", "--force", "--stdin")
+            .stdin_bytes(objects.join("\n"))
+            .stdout_capture()
+            .stderr_capture()
+            .unchecked()
+            .run()
+            .context(tr!("prune.files-failed"))?;
+        if !output.status.success() {
+            bail!(
+                "{}\n{}",
+                tr!("prune.files-failed"),
+                decode_output(&output.stderr).trim()
+            );
+        }
+    }
+    Ok(size)
+}
+
+/// Measures, or empties, the test data cache of the judge. Returns the size of it.
+fn prune_testdata_cache(execute: bool) -> Result<u64> {
+    let dir = testdata_cache_path();
+    let (files, size) = match summarize_dir(&dir) {
+        Ok(summary) => summary,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => (0, 0),
+        Err(e) => return Err(e).context(tr!("prune.cache-failed", dir.display())),
+    };
+    log_i18n!(info, "prune.cache", dir.display(), files, format_size(size));
+    if execute && files > 0 {
+        // the judge downloads the test data again when needed
+        fs::remove_dir_all(&dir).with_context(|| tr!("prune.cache-failed", dir.display()))?;
+    }
+    Ok(size)
+}
+
+/// `cache_dir` in `judge.yaml`, or the default of the judge.
+fn testdata_cache_path() -> PathBuf {
+    let configured = fs::read_to_string(hydro::judge_config_path())
+        .ok()
+        .and_then(|text| {
+            text.lines().find_map(|line| {
+                let value = line.strip_prefix("cache_dir:")?.trim();
+                Some(value.trim_matches(|c| c == '"' || c == '\'').to_owned())
+            })
+        });
+    let home = dirs::home_dir().expect("Failed to get home dir");
+    match configured {
+        Some(dir) => match dir.strip_prefix("~/") {
+            Some(rest) => home.join(rest),
+            None => PathBuf::from(dir),
+        },
+        None => home.join(".cache").join("hydro").join("judge"),
+    }
+}
+
+/// Pads `2023-1-1` into `2023-01-01`, so that dates compare as strings.
+fn normalize_date(date: &str) -> String {
+    let parts = date.trim().split('-').collect::<Vec<_>>();
+    match parts.as_slice() {
+        [year, month, day] => format!("{:0>4}-{:0>2}-{:0>2}", year, month, day),
+        _ => date.to_owned(),
+    }
+}
+
+fn plan_path() -> PathBuf {
+    config::get_cache_path().join("prune-plan.json")
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn save_plan(plan: &Plan) -> Result<()> {
+    let path = plan_path();
+    fs::create_dir_all(path.parent().unwrap())?;
+    let value = serde_json::json!({ "planned_at": now(), "plan": plan });
+    fs::write(path, value.to_string())?;
+    Ok(())
+}
+
+/// Fails unless a dry run with the same options is done recently.
+fn check_plan(plan: &Plan) -> Result<()> {
+    let saved = fs::read_to_string(plan_path())
+        .ok()
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok());
+    let saved = match saved {
+        Some(saved) => saved,
+        None => bail!(tr!("prune.dry-run-required")),
+    };
+    let planned_at = saved["planned_at"].as_u64().unwrap_or(0);
+    match serde_json::from_value::<Plan>(saved["plan"].clone()) {
+        Ok(saved) if saved == *plan && now().saturating_sub(planned_at) <= PLAN_TTL => Ok(()),
+        _ => bail!(tr!("prune.dry-run-required")),
+    }
+}
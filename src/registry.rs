@@ -0,0 +1,129 @@
+use crate::Com;
+
+/// Static description of a component.
+///
+/// Built-in components have a [`Com`] and a field in [`Components`](crate::config::Components),
+/// while optional components are only described here and recorded in `components.extras`.
+#[derive(Debug)]
+pub struct Descriptor {
+    /// Key in `.h2o2config` and the name used on the command line.
+    pub name: &'static str,
+    pub display: &'static str,
+    /// Other names accepted on the command line.
+    pub synonyms: &'static [&'static str],
+    pub com: Option<Com>,
+    /// Executable and arguments printing the version, used to detect optional components.
+    pub probe: Option<(&'static str, &'static [&'static str])>,
+    /// Name of the long-running process, used to report the service status.
+    pub process: Option<&'static str>,
+}
+
+pub static COMPONENTS: &[Descriptor] = &[
+    Descriptor {
+        name: "nodejs",
+        display: "Node.js",
+        synonyms: &["node", "node.js"],
+        com: Some(Com::NodeJS),
+        probe: None,
+        process: None,
+    },
+    Descriptor {
+        name: "mongodb",
+        display: "MongoDB",
+        synonyms: &["mongo", "mongod"],
+        com: Some(Com::MongoDB),
+        probe: None,
+        process: Some("mongod"),
+    },
+    Descriptor {
+        name: "minio",
+        display: "MinIO",
+        synonyms: &[],
+        com: Some(Com::MinIO),
+        probe: None,
+        process: Some("minio"),
+    },
+    Descriptor {
+        name: "sandbox",
+        display: "sandbox",
+        synonyms: &["go-judge", "executorserver"],
+        com: Some(Com::Sandbox),
+        probe: None,
+        process: Some("sandbox"),
+    },
+    Descriptor {
+        name: "yarn",
+        display: "Yarn",
+        synonyms: &[],
+        com: Some(Com::Yarn),
+        probe: None,
+        process: None,
+    },
+    Descriptor {
+        name: "pm2",
+        display: "PM2",
+        synonyms: &[],
+        com: Some(Com::PM2),
+        probe: None,
+        process: None,
+    },
+    Descriptor {
+        name: "hydro",
+        display: "Hydro",
+        synonyms: &["hydrooj"],
+        com: Some(Com::Hydro),
+        probe: None,
+        process: None,
+    },
+    Descriptor {
+        name: "mc",
+        display: "mc",
+        synonyms: &["minio-client"],
+        com: None,
+        probe: Some(("mc", &["--version"])),
+        process: None,
+    },
+    Descriptor {
+        name: "redis",
+        display: "Redis",
+        synonyms: &["redis-server"],
+        com: None,
+        probe: Some(("redis-server", &["--version"])),
+        process: Some("redis-server"),
+    },
+    Descriptor {
+        name: "caddy",
+        display: "Caddy",
+        synonyms: &[],
+        com: None,
+        probe: Some(("caddy", &["version"])),
+        process: Some("caddy"),
+    },
+];
+
+/// Finds a component by its name or synonym, case-insensitively.
+pub fn find(name: &str) -> Option<&'static Descriptor> {
+    let name = name.to_lowercase();
+    COMPONENTS
+        .iter()
+        .find(|desc| desc.name == name || desc.synonyms.contains(&name.as_str()))
+}
+
+/// All names and synonyms, used to suggest a mistyped name.
+pub fn names() -> impl Iterator<Item = &'static str> {
+    COMPONENTS
+        .iter()
+        .flat_map(|desc| std::iter::once(desc.name).chain(desc.synonyms.iter().copied()))
+}
+
+/// The optional components, which are not built into [`Com`].
+pub fn extras() -> impl Iterator<Item = &'static Descriptor> {
+    COMPONENTS.iter().filter(|desc| desc.com.is_none())
+}
+
+pub fn get(com: Com) -> &'static Descriptor {
+    COMPONENTS
+        .iter()
+        .find(|desc| desc.com == Some(com))
+        .expect("every built-in component should have a descriptor")
+}
@@ -0,0 +1,173 @@
+use anyhow::{bail, Context, Result};
+use clap::{AppSettings, Clap};
+use reqwest::Method;
+use std::{env, fs};
+
+use super::test_langs::test_langs;
+use crate::{
+    config::{self, Config},
+    install::helper::sandbox,
+    log_i18n, service, tr, Com,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Clap, Debug)]
+enum SubCommand {
+    /// 删除评测沙箱中缓存的文件，如编译产物
+    /// Deletes the files cached in the sandbox, such as compiled programs
+    #[clap(setting = AppSettings::ColoredHelp)]
+    CleanCache(CleanCacheArgs),
+
+    /// 通过沙箱编译并运行各语言的 Hello World，在学生遇到编译错误前发现缺失的库
+    /// Compiles and runs a hello world in each language through the sandbox, finding missing libraries before students do
+    #[clap(setting = AppSettings::ColoredHelp)]
+    TestLangs(TestLangsArgs),
+}
+
+#[derive(Clap, Debug)]
+struct CleanCacheArgs {
+    /// 写入 systemd 定时器按时清理，格式同 OnCalendar=，如 daily 或 "*-*-* 04:00:00"
+    /// Writes a systemd timer cleaning on schedule, as in OnCalendar=, such as daily or "*-*-* 04:00:00"
+    #[clap(long, value_name = "CALENDAR", conflicts_with = "unschedule")]
+    schedule: Option<String>,
+
+    /// 删除定时清理的 systemd 定时器
+    /// Removes the systemd timer cleaning on schedule
+    #[clap(long)]
+    unschedule: bool,
+}
+
+#[derive(Clap, Debug)]
+struct TestLangsArgs {
+    /// 要测试的语言，如 cc 或 py.py3，默认为 `h2o2 detect --runtimes` 生成的全部语言
+    /// Languages to test, e.g. cc or py.py3, defaults to all generated by `h2o2 detect --runtimes`
+    langs: Vec<String>,
+}
+
+/// Name of the timer cleaning the cache, and of the service it triggers without the suffix.
+const CLEAN_UNIT: &str = "h2o2-sandbox-clean";
+
+impl Args {
+    /// Name of the operation, if the subcommand modifies the sandbox.
+    pub fn operation(&self) -> Option<&'static str> {
+        match self.subcmd {
+            SubCommand::CleanCache(_) => Some("sandbox clean-cache"),
+            SubCommand::TestLangs(_) => None,
+        }
+    }
+}
+
+pub async fn main(args: Args) -> Result<()> {
+    let config = config::load_config().await?;
+    match args.subcmd {
+        // the timer can be removed after the sandbox is
+        SubCommand::CleanCache(args) if args.unschedule => unschedule(),
+        _ if !config.components.sandbox.is_installed() => bail!(tr!("sandbox.not-installed")),
+        SubCommand::CleanCache(args) => match args.schedule {
+            Some(calendar) => schedule(&calendar),
+            None => clean_cache(&config).await,
+        },
+        SubCommand::TestLangs(args) => test_langs(&config, &args.langs).await,
+    }
+}
+
+/// Deletes every file cached through the API of the sandbox. Files are cached for the steps of a
+/// judging task, e.g. from compiling to running, and Hydro deletes them after the task, so those
+/// left are from tasks interrupted.
+async fn clean_cache(config: &Config) -> Result<()> {
+    let profile = &config.profile.sandbox;
+    let res = sandbox::request(profile, Method::GET, "/file")
+        .send()
+        .await
+        .with_context(|| tr!("sandbox.unreachable", sandbox::HOST))?;
+    if !res.status().is_success() {
+        bail!(tr!("sandbox.request-failed", "GET /file", res.status()));
+    }
+    // file IDs mapped to their names
+    let files: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&res.text().await?).context("Unexpected file list of the sandbox")?;
+    if files.is_empty() {
+        log_i18n!(info, "sandbox.cache-empty");
+        return Ok(());
+    }
+
+    let mut deleted = 0;
+    for id in files.keys() {
+        let path = format!("/file/{}", id);
+        match sandbox::request(profile, Method::DELETE, &path)
+            .send()
+            .await
+        {
+            Ok(res) if res.status().is_success() => deleted += 1,
+            // deleted by Hydro in the meantime
+            Ok(res) if res.status() == reqwest::StatusCode::NOT_FOUND => {}
+            Ok(res) => log_i18n!(
+                warn,
+                "sandbox.request-failed",
+                format!("DELETE {}", path),
+                res.status()
+            ),
+            Err(e) => log_i18n!(
+                warn,
+                "sandbox.request-failed",
+                format!("DELETE {}", path),
+                e
+            ),
+        }
+    }
+    log_i18n!(info, "sandbox.cache-cleaned", deleted, files.len());
+    Ok(())
+}
+
+/// Writes and starts the timer running `h2o2 sandbox clean-cache` on `calendar`.
+fn schedule(calendar: &str) -> Result<()> {
+    service::require_systemd()?;
+    let exe = env::current_exe().context("Failed to locate the H2O2 executable")?;
+    let home = dirs::home_dir().expect("Failed to get home dir");
+    let command = [
+        exe.to_string_lossy().into_owned(),
+        "sandbox".to_owned(),
+        "clean-cache".to_owned(),
+    ];
+    let (service_unit, timer_unit) = service::render_timer(
+        "Clean the sandbox cache for Hydro",
+        &command,
+        &home.to_string_lossy(),
+        calendar,
+    );
+    let timer = format!("{}.timer", CLEAN_UNIT);
+    for (name, content) in [
+        (format!("{}.service", CLEAN_UNIT), service_unit),
+        (timer.clone(), timer_unit),
+    ] {
+        let path = service::path_of(&name);
+        fs::write(&path, content).with_context(|| tr!("sys.write-failed", path.display()))?;
+    }
+    service::systemctl(Com::Sandbox, &["daemon-reload"])?;
+    service::systemctl(Com::Sandbox, &["enable", "--now", &timer])?;
+    log_i18n!(info, "sandbox.clean-scheduled", calendar, &timer);
+    Ok(())
+}
+
+fn unschedule() -> Result<()> {
+    service::require_systemd()?;
+    let timer = format!("{}.timer", CLEAN_UNIT);
+    if !service::path_of(&timer).is_file() {
+        log_i18n!(info, "sandbox.clean-not-scheduled");
+        return Ok(());
+    }
+    service::systemctl(Com::Sandbox, &["disable", "--now", &timer])?;
+    for name in [timer, format!("{}.service", CLEAN_UNIT)] {
+        let path = service::path_of(&name);
+        fs::remove_file(&path).with_context(|| tr!("service.failed", Com::Sandbox))?;
+    }
+    service::systemctl(Com::Sandbox, &["daemon-reload"])?;
+    log_i18n!(info, "sandbox.clean-unscheduled");
+    Ok(())
+}
@@ -0,0 +1,4 @@
+mod main;
+mod test_langs;
+
+pub use main::*;
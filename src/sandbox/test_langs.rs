@@ -0,0 +1,230 @@
+use anyhow::{bail, Context, Result};
+use reqwest::Method;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use crate::{
+    config::{Config, SandboxProfile},
+    install::helper::sandbox,
+    langs::{self, Lang},
+    log_i18n, tr,
+};
+
+/// Printed by every hello world program.
+const EXPECTED: &str = "Hello, World!";
+
+/// Environment of the programs, as Hydro gives them.
+const ENV: &[&str] = &["PATH=/usr/local/bin:/usr/bin:/bin", "HOME=/w"];
+
+/// Time a compiler may take, as javac and rustc are slow to start.
+const COMPILE_TIME: Duration = Duration::from_secs(30);
+
+/// Time a hello world may take to run.
+const RUN_TIME: Duration = Duration::from_secs(5);
+
+const MEMORY: u64 = 512 << 20;
+
+/// Processes a compiler may start, as the Go and Java toolchains run many threads.
+const PROCESSES: u32 = 128;
+
+/// Bytes of output kept of each program.
+const OUTPUT: u64 = 64 << 10;
+
+/// Lines of compiler errors shown on failure.
+const ERROR_LINES: usize = 5;
+
+/// Compiles and runs a hello world in each language set for the runtimes detected, or those
+/// whose keys are given, through the sandbox as Hydro does. Fails if any of them does not print
+/// [`EXPECTED`].
+pub async fn test_langs(config: &Config, keys: &[String]) -> Result<()> {
+    let langs = langs::generate(&config.runtimes)
+        .into_iter()
+        .filter(|lang| keys.is_empty() || keys.iter().any(|key| key == lang.key))
+        .collect::<Vec<_>>();
+    if let Some(key) = keys
+        .iter()
+        .find(|key| !langs.iter().any(|lang| lang.key == key.as_str()))
+    {
+        bail!(tr!("sandbox.lang-unknown", key));
+    }
+    if langs.is_empty() {
+        bail!(tr!("sandbox.no-langs"));
+    }
+
+    let profile = &config.profile.sandbox;
+    let mut failed = Vec::new();
+    for lang in &langs {
+        let source = match hello_world(lang) {
+            Some(source) => source,
+            None => {
+                log_i18n!(warn, "sandbox.lang-skipped", lang.key, &lang.display);
+                continue;
+            }
+        };
+        match test_lang(profile, lang, source).await? {
+            None => log_i18n!(info, "sandbox.lang-passed", lang.key, &lang.display),
+            Some(problem) => {
+                log_i18n!(
+                    error,
+                    "sandbox.lang-failed",
+                    lang.key,
+                    &lang.display,
+                    problem
+                );
+                failed.push(lang.key);
+            }
+        }
+    }
+    if !failed.is_empty() {
+        bail!(tr!("sandbox.langs-failed", failed.len(), langs.len()));
+    }
+    log_i18n!(info, "sandbox.langs-passed", langs.len());
+    Ok(())
+}
+
+/// Runs `source`, the hello world of `lang`, returning the problem found. Fails only if the sandbox does
+/// not answer.
+async fn test_lang(profile: &SandboxProfile, lang: &Lang, source: &str) -> Result<Option<String>> {
+    let target = target(lang);
+    let mut cached = None;
+    if let Some(compile) = &lang.compile {
+        let cmd = json!({
+            "copyIn": { lang.code_file: { "content": source } },
+            "copyOutCached": [target],
+        });
+        let result = run(profile, compile, cmd, COMPILE_TIME).await?;
+        if result["status"] != "Accepted" {
+            return Ok(Some(problem("compile", &result)));
+        }
+        cached = result["fileIds"][target].as_str().map(ToOwned::to_owned);
+        if cached.is_none() {
+            return Ok(Some(tr!("sandbox.lang-no-output", target)));
+        }
+    }
+
+    let copy_in = match &cached {
+        Some(id) => json!({ target: { "fileId": id } }),
+        None => json!({ lang.code_file: { "content": source } }),
+    };
+    let result = run(
+        profile,
+        &lang.execute,
+        json!({ "copyIn": copy_in }),
+        RUN_TIME,
+    )
+    .await;
+    if let Some(id) = &cached {
+        // left in the cache otherwise, until `h2o2 sandbox clean-cache`
+        let _ = sandbox::request(profile, Method::DELETE, &format!("/file/{}", id))
+            .send()
+            .await;
+    }
+    let result = result?;
+    if result["status"] != "Accepted" {
+        return Ok(Some(problem("run", &result)));
+    }
+    let stdout = result["files"]["stdout"]
+        .as_str()
+        .unwrap_or_default()
+        .trim();
+    if stdout != EXPECTED {
+        return Ok(Some(tr!(
+            "sandbox.lang-wrong-output",
+            format!("{:?}", stdout)
+        )));
+    }
+    Ok(None)
+}
+
+/// Runs `command` of a language setting in the sandbox with the files of `cmd`, returning the
+/// result of the sandbox.
+async fn run(
+    profile: &SandboxProfile,
+    command: &str,
+    mut cmd: Value,
+    time: Duration,
+) -> Result<Value> {
+    let command = command.replace("${name}", "/w/foo");
+    cmd["args"] = json!(["/bin/sh", "-c", command]);
+    cmd["env"] = json!(ENV);
+    cmd["files"] = json!([
+        { "content": "" },
+        { "name": "stdout", "max": OUTPUT },
+        { "name": "stderr", "max": OUTPUT },
+    ]);
+    cmd["cpuLimit"] = json!(time.as_nanos() as u64);
+    cmd["clockLimit"] = json!(time.as_nanos() as u64 * 2);
+    cmd["memoryLimit"] = json!(MEMORY);
+    cmd["procLimit"] = json!(PROCESSES);
+
+    let res = sandbox::request(profile, Method::POST, "/run")
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(json!({ "cmd": [cmd] }).to_string())
+        // the request waits for the program to exit
+        .timeout(time * 3)
+        .send()
+        .await
+        .with_context(|| tr!("sandbox.unreachable", sandbox::HOST))?;
+    if !res.status().is_success() {
+        bail!(tr!("sandbox.request-failed", "POST /run", res.status()));
+    }
+    let text = res.text().await?;
+    let results: Vec<Value> =
+        serde_json::from_str(&text).context("Unexpected result of the sandbox")?;
+    results
+        .into_iter()
+        .next()
+        .context("Unexpected result of the sandbox")
+}
+
+/// Describes a failed step from the status, the error of the sandbox and the last lines of
+/// stderr, e.g. a missing library reported by the linker.
+fn problem(step: &str, result: &Value) -> String {
+    let mut problem = format!(
+        "{} {} (exit {})",
+        step,
+        result["status"].as_str().unwrap_or("unknown"),
+        result["exitStatus"]
+    );
+    if let Some(error) = result["error"].as_str().filter(|e| !e.is_empty()) {
+        problem.push_str(&format!(": {}", error));
+    }
+    let stderr = result["files"]["stderr"].as_str().unwrap_or_default();
+    let lines = stderr.trim_end().lines().collect::<Vec<_>>();
+    for line in &lines[lines.len().saturating_sub(ERROR_LINES)..] {
+        problem.push_str("\n    ");
+        problem.push_str(line);
+    }
+    problem
+}
+
+/// The file the compiler writes, which is run.
+fn target(lang: &Lang) -> &'static str {
+    match lang.key {
+        "java" => "Main.class",
+        _ => "foo",
+    }
+}
+
+/// A program printing [`EXPECTED`] in `lang`, or `None` if there is none for it yet.
+fn hello_world(lang: &Lang) -> Option<&'static str> {
+    let source = match lang.key {
+        "c" => "#include <stdio.h>\nint main(void) { puts(\"Hello, World!\"); return 0; }\n",
+        "cc" | "cc.cc17" => {
+            "#include <iostream>\nint main() { std::cout << \"Hello, World!\" << std::endl; }\n"
+        }
+        "pas" => "begin\n  writeln('Hello, World!');\nend.\n",
+        "java" => {
+            "public class Main {\n  public static void main(String[] args) {\n    \
+            System.out.println(\"Hello, World!\");\n  }\n}\n"
+        }
+        // the same for Python 2 and 3
+        "py.py2" | "py.py3" => "print(\"Hello, World!\")\n",
+        "rs" => "fn main() {\n    println!(\"Hello, World!\");\n}\n",
+        "go" => {
+            "package main\n\nimport \"fmt\"\n\nfunc main() {\n\tfmt.Println(\"Hello, World!\")\n}\n"
+        }
+        _ => return None,
+    };
+    Some(source)
+}
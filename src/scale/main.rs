@@ -0,0 +1,79 @@
+use anyhow::{bail, Context, Result};
+use clap::{ArgEnum, Clap};
+
+use crate::{
+    config, health,
+    install::helper::pm2::{self, HYDRO_APP, JUDGE_APP},
+    log_i18n, tr, Com,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    /// 要调整的进程
+    /// Processes to scale
+    #[clap(arg_enum)]
+    target: Target,
+
+    /// 进程数
+    /// Number of processes
+    count: u32,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy)]
+enum Target {
+    Hydro,
+    Judge,
+}
+
+pub async fn main(args: Args) -> Result<()> {
+    let mut config = config::load_config().await?;
+    let com = &config.components;
+    if !com.hydro.is_installed() || !com.pm2.is_installed() {
+        bail!(tr!("scale.not-installed"));
+    }
+    if args.count == 0 && matches!(args.target, Target::Hydro) {
+        bail!(tr!("scale.zero-processes"));
+    }
+
+    let profile = &mut config.profile.hydro;
+    let (app, previous) = match args.target {
+        Target::Hydro => (
+            HYDRO_APP,
+            std::mem::replace(&mut profile.workers, args.count),
+        ),
+        Target::Judge => (
+            JUDGE_APP,
+            std::mem::replace(&mut profile.judge_workers, args.count),
+        ),
+    };
+
+    let ecosystem = pm2::write_ecosystem(
+        &config.components.hydro,
+        &config.profile.hydro,
+        config.profile.kill_after(),
+    )
+    .context("Failed to write PM2 ecosystem file")?;
+    let pm2 = config.components.pm2.path_or("pm2");
+    // `pm2 scale` only works on running processes in cluster mode, so (re)start from
+    // the ecosystem file when the exec mode changes
+    let applied = if args.count == 0 {
+        pm2::run(duct::cmd!(pm2, "delete", app))
+    } else if previous > 1 && args.count > 1 {
+        pm2::run(duct::cmd!(pm2, "scale", app, args.count.to_string()))
+    } else {
+        pm2::apply(pm2, &ecosystem, app, config.profile.hydro.restart_mode)
+    };
+    applied.context("Failed to execute PM2")?;
+
+    config::save_config(&config).await?;
+    if args.count > 0 {
+        let service = match args.target {
+            Target::Hydro => health::Service::of(&config, Com::Hydro).expect("Hydro is a service"),
+            Target::Judge => health::Service::judge(),
+        };
+        health::wait_healthy(&config, &service).await?;
+    }
+    log_i18n!(info, "scale.scaled", app, args.count);
+    Ok(())
+}
@@ -0,0 +1,206 @@
+use anyhow::{bail, Context, Result};
+use clap::Clap;
+use semver::Version;
+use serde::Deserialize;
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+use tempfile::NamedTempFile;
+
+use crate::{log_i18n, net, progress, tr, utils};
+
+/// Latest stable release of H2O2 on GitHub.
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/wuxianucw/h2o2/releases/latest";
+
+/// Asset listing the SHA-256 of the others, in the format of `sha256sum`.
+const CHECKSUMS: &str = "SHA256SUMS";
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    /// 仅检查是否有新版本，不下载
+    /// Checks for a newer version only, without downloading it
+    #[clap(long)]
+    check: bool,
+
+    /// 即使已是最新版本也重新下载并替换
+    /// Downloads and replaces the executable even if it is up to date
+    #[clap(long, conflicts_with = "check")]
+    force: bool,
+}
+
+impl Args {
+    /// Name of the operation, if the executable is replaced.
+    pub fn operation(&self) -> Option<&'static str> {
+        match self.check {
+            true => None,
+            false => Some("self-update"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Release {
+    fn asset(&self, name: &str) -> Option<&Asset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+}
+
+/// Replaces the running executable with the binary of the latest release for this platform,
+/// verified against the checksums published with it.
+pub async fn main(args: Args) -> Result<()> {
+    let exe = env::current_exe().context(tr!("self-update.no-exe"))?;
+    remove_old(&exe);
+
+    let release = latest_release().await?;
+    let current = Version::parse(env!("CARGO_PKG_VERSION")).expect("Invalid package version");
+    let latest = Version::parse(release.tag_name.trim_start_matches('v'))
+        .with_context(|| tr!("self-update.bad-tag", &release.tag_name))?;
+    if latest <= current && !args.force {
+        log_i18n!(info, "self-update.up-to-date", current);
+        return Ok(());
+    }
+    log_i18n!(info, "self-update.available", latest, current);
+    if args.check {
+        return Ok(());
+    }
+
+    let name = asset_name();
+    let asset = release
+        .asset(&name)
+        .with_context(|| tr!("self-update.no-asset", &name, &release.tag_name))?;
+    let checksums = release
+        .asset(CHECKSUMS)
+        .with_context(|| tr!("self-update.no-checksums", &release.tag_name))?;
+    let checksums = fetch_text(&checksums.browser_download_url).await?;
+    let expected = checksum_of(&checksums, &name)
+        .with_context(|| tr!("self-update.no-checksums", &release.tag_name))?;
+
+    // in the same directory, so that it is renamed over the executable rather than copied
+    let dir = exe.parent().context(tr!("self-update.no-exe"))?;
+    let file = tempfile::Builder::new()
+        .prefix(".h2o2-update")
+        .tempfile_in(dir)
+        .with_context(|| tr!("sys.write-failed", dir.display()))?;
+    let file = download(&asset.browser_download_url, &name, file).await?;
+    let actual = utils::sha256_file(file.path())?;
+    if actual != expected {
+        bail!(tr!(
+            "self-update.checksum-mismatch",
+            &name,
+            expected,
+            actual
+        ));
+    }
+    replace(&exe, file)?;
+    log_i18n!(info, "self-update.updated", current, latest);
+    Ok(())
+}
+
+async fn latest_release() -> Result<Release> {
+    let text = fetch_text(LATEST_RELEASE_URL).await?;
+    serde_json::from_str(&text).context(tr!("self-update.bad-release"))
+}
+
+async fn fetch_text(url: &str) -> Result<String> {
+    let res = net::github_api(url)
+        .send()
+        .await
+        .with_context(|| tr!("self-update.request-failed", url))?;
+    if !res.status().is_success() {
+        bail!(tr!("self-update.request-failed", url));
+    }
+    Ok(res.text().await?)
+}
+
+/// Downloads `url` into `file`.
+async fn download(url: &str, name: &str, mut file: NamedTempFile) -> Result<NamedTempFile> {
+    let mut res = net::github_api(url)
+        .send()
+        .await
+        .with_context(|| tr!("self-update.request-failed", url))?;
+    if !res.status().is_success() {
+        bail!(tr!("self-update.request-failed", url));
+    }
+    let mut bar = progress::download("h2o2", name, res.content_length());
+    while let Some(chunk) = res.chunk().await? {
+        file.write_all(&chunk)?;
+        bar.inc(chunk.len() as u64);
+    }
+    file.flush()?;
+    Ok(file)
+}
+
+/// Name of the asset built for this platform, e.g. `h2o2-x86_64-linux` or
+/// `h2o2-x86_64-windows.exe`.
+fn asset_name() -> String {
+    format!(
+        "h2o2-{}-{}{}",
+        env::consts::ARCH,
+        env::consts::OS,
+        env::consts::EXE_SUFFIX
+    )
+}
+
+/// The checksum of `name` in `text`, lines of `<sha256>  <name>`, where the name is preceded by
+/// `*` in binary mode.
+fn checksum_of(text: &str, name: &str) -> Option<String> {
+    text.lines().find_map(|line| {
+        let (sum, file) = line.trim().split_once(char::is_whitespace)?;
+        let file = file.trim_start();
+        (file.strip_prefix('*').unwrap_or(file) == name).then(|| sum.to_lowercase())
+    })
+}
+
+/// Where the replaced executable is moved to on Windows, which refuses to remove or overwrite a
+/// running one but allows renaming it.
+fn old_path(exe: &Path) -> PathBuf {
+    let mut old = OsString::from(exe.as_os_str());
+    old.push(".old");
+    PathBuf::from(old)
+}
+
+/// Removes the executable replaced by the last update on Windows, which could not remove it while
+/// it was running.
+fn remove_old(exe: &Path) {
+    let _ = fs::remove_file(old_path(exe));
+}
+
+/// Moves `file` over `exe`.
+fn replace(exe: &Path, file: NamedTempFile) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(file.path(), fs::Permissions::from_mode(0o755))?;
+    }
+    // Windows refuses to overwrite a running executable, but allows renaming it
+    #[cfg(windows)]
+    let old = old_path(exe);
+    #[cfg(windows)]
+    fs::rename(exe, &old).with_context(|| tr!("sys.write-failed", exe.display()))?;
+    let persisted = file.persist(exe);
+    #[cfg(windows)]
+    if persisted.is_err() {
+        // put the running executable back, so that H2O2 still works
+        let _ = fs::rename(&old, exe);
+    }
+    persisted
+        .map_err(|e| e.error)
+        .with_context(|| tr!("sys.write-failed", exe.display()))?;
+    Ok(())
+}
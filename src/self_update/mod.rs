@@ -0,0 +1,3 @@
+mod main;
+
+pub use main::*;
@@ -0,0 +1,267 @@
+use anyhow::{bail, Context, Result};
+use clap::{AppSettings, Clap};
+use std::fs;
+
+use super::unit::{self, SERVICES};
+use crate::{
+    config::{self, Config},
+    health,
+    install::helper::{minio, mongodb, pm2, sandbox},
+    log_i18n,
+    platform::{self, Os, ServiceManager},
+    registry, tr,
+    utils::decode_output,
+    Com,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Clap, Debug)]
+enum SubCommand {
+    /// 为组件写入 systemd 单元并设为开机启动
+    /// Writes systemd units for components and enables them on boot
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Install(InstallArgs),
+
+    /// 停止并删除组件的 systemd 单元
+    /// Stops components and removes their systemd units
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Remove(RemoveArgs),
+
+    /// 等待 Hydro 依赖的服务就绪，供 Hydro 的单元在启动前运行
+    /// Waits for the services Hydro depends on to become healthy, run by the unit of Hydro before starting it
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Wait,
+}
+
+#[derive(Clap, Debug)]
+struct InstallArgs {
+    /// 要管理的组件（MongoDB、MinIO、sandbox、Hydro），默认为全部已安装的
+    /// Components to manage (MongoDB, MinIO, sandbox, Hydro), defaults to all installed ones
+    components: Vec<Com>,
+
+    /// 运行 MongoDB、MinIO 与 Hydro 的用户，默认为 H2O2 目录的所有者
+    /// User running MongoDB, MinIO and Hydro, defaults to the owner of the H2O2 dir
+    #[clap(long)]
+    user: Option<String>,
+
+    /// 仅设为开机启动，不立即启动
+    /// Only enables them on boot without starting them now
+    #[clap(long)]
+    no_start: bool,
+}
+
+#[derive(Clap, Debug)]
+struct RemoveArgs {
+    /// 要移除的组件，默认为全部
+    /// Components to remove, defaults to all
+    components: Vec<Com>,
+}
+
+impl Args {
+    /// Name of the operation, if the subcommand modifies the system.
+    pub fn operation(&self) -> Option<&'static str> {
+        match &self.subcmd {
+            SubCommand::Install(_) => Some("service install"),
+            SubCommand::Remove(_) => Some("service remove"),
+            SubCommand::Wait => None,
+        }
+    }
+}
+
+pub async fn main(args: Args) -> Result<()> {
+    // waiting is run by systemd as the user running Hydro, who is not necessarily root
+    if !matches!(args.subcmd, SubCommand::Wait) {
+        require_systemd()?;
+    }
+    let config = config::load_config().await?;
+    match args.subcmd {
+        SubCommand::Install(args) => install(args, &config).await,
+        SubCommand::Remove(args) => remove(args),
+        SubCommand::Wait => wait(&config).await,
+    }
+}
+
+/// Fails unless units can be managed here, i.e. as root on Linux with systemd.
+pub fn require_systemd() -> Result<()> {
+    let current = platform::current();
+    if current.os != Os::Linux {
+        bail!(tr!("sys.linux-only"));
+    }
+    if current.service_manager != ServiceManager::Systemd {
+        bail!(tr!("service.systemd-required", current.service_manager));
+    }
+    if platform::is_root() == Some(false) {
+        bail!(tr!("sys.root-required"));
+    }
+    Ok(())
+}
+
+async fn install(args: InstallArgs, config: &Config) -> Result<()> {
+    let role = config.profile.role;
+    let targets = targets(&args.components)?
+        .into_iter()
+        .filter(|&com| {
+            // those given are written anyway, e.g. for a server of two roles
+            if args.components.is_empty() && !role.runs(com) {
+                log_i18n!(info, "service.not-in-role", com, role);
+                return false;
+            }
+            if args.components.is_empty() && config.profile.is_external(com) {
+                log_i18n!(info, "service.external", com);
+                return false;
+            }
+            let installed = config.components.borrow_by_com(com).is_installed();
+            if !installed {
+                log_i18n!(info, "service.not-installed", com);
+            }
+            installed
+        })
+        .collect::<Vec<_>>();
+    let user = args.user.unwrap_or_else(default_user);
+
+    for com in targets {
+        let name = unit::unit_name(com);
+        // the files referred to by the unit, kept in sync with the profile
+        let written = match com {
+            Com::MongoDB => mongodb::write_config(&config.profile.mongodb),
+            Com::MinIO => minio::write_env(&config.profile.minio),
+            Com::Hydro => pm2::write_ecosystem(
+                &config.components.hydro,
+                &config.profile.hydro,
+                config.profile.kill_after(),
+            ),
+            _ => sandbox::write_env(&config.profile.sandbox),
+        };
+        written.with_context(|| tr!("service.failed", com))?;
+        let content = unit::render(config, com, &user).expect("Not a service");
+        let path = unit::unit_path(com);
+        fs::write(&path, content).with_context(|| tr!("sys.write-failed", path.display()))?;
+        systemctl(com, &["daemon-reload"])?;
+
+        // starting it alongside a copy started by hand would fail on the ports
+        let unmanaged = match com {
+            Com::Hydro => pm2::daemon_pid().map(|pid| vec![pid]),
+            _ => {
+                let process = registry::get(com).process.unwrap_or_default();
+                platform::find_processes(process).filter(|pids| !pids.is_empty())
+            }
+        };
+        let start = !args.no_start && !active(&name);
+        match unmanaged {
+            Some(pids) if start => {
+                systemctl(com, &["enable", &name])?;
+                let pids = pids.iter().map(u32::to_string).collect::<Vec<_>>();
+                log_i18n!(
+                    warn,
+                    "service.running-unmanaged",
+                    com,
+                    pids.join(", "),
+                    &name
+                );
+                continue;
+            }
+            _ if start => {
+                systemctl(com, &["enable", "--now", &name])?;
+                if let Some(mut service) = health::Service::of(config, com) {
+                    service
+                        .logs
+                        .get_or_insert_with(|| health::Logs::Journal(name.clone()));
+                    health::wait_healthy(config, &service).await?;
+                }
+            }
+            _ => systemctl(com, &["enable", &name])?,
+        }
+        log_i18n!(info, "service.installed", com, path.display());
+    }
+    Ok(())
+}
+
+fn remove(args: RemoveArgs) -> Result<()> {
+    for com in targets(&args.components)? {
+        let path = unit::unit_path(com);
+        if !path.is_file() {
+            log_i18n!(info, "service.not-found", com);
+            continue;
+        }
+        systemctl(com, &["disable", "--now", &unit::unit_name(com)])?;
+        fs::remove_file(&path).with_context(|| tr!("service.failed", com))?;
+        systemctl(com, &["daemon-reload"])?;
+        log_i18n!(info, "service.removed", com, path.display());
+    }
+    Ok(())
+}
+
+/// Waits for every service Hydro depends on to become healthy.
+async fn wait(config: &Config) -> Result<()> {
+    for com in unit::dependencies(config, Com::Hydro) {
+        if let Some(mut service) = health::Service::of(config, com) {
+            service.logs = Some(health::Logs::Journal(unit::unit_name(com)));
+            health::wait_healthy(config, &service).await?;
+        }
+    }
+    Ok(())
+}
+
+/// The components given, or all services if none is.
+fn targets(components: &[Com]) -> Result<Vec<Com>> {
+    if let Some(com) = components.iter().find(|com| !SERVICES.contains(com)) {
+        bail!(tr!("service.not-service", com));
+    }
+    Ok(SERVICES
+        .iter()
+        .copied()
+        .filter(|com| components.is_empty() || components.contains(com))
+        .collect())
+}
+
+/// The owner of the H2O2 dir, which the binaries and data are in.
+fn default_user() -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        if let Ok(metadata) = fs::metadata(config::get_com_path()) {
+            // systemd accepts numeric IDs as well
+            return match metadata.uid() {
+                0 => "root".to_owned(),
+                uid => uid.to_string(),
+            };
+        }
+    }
+    "root".to_owned()
+}
+
+/// Whether systemd reports the unit named `unit` as active.
+pub fn active(unit: &str) -> bool {
+    duct::cmd!("systemctl", "is-active", "--quiet", unit)
+        .stdout_null()
+        .stderr_null()
+        .unchecked()
+        .run()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Runs `systemctl` with `args`, failing with its error output on behalf of `com`.
+pub fn systemctl(com: Com, args: &[&str]) -> Result<()> {
+    let output = duct::cmd("systemctl", args)
+        .stdout_capture()
+        .stderr_capture()
+        .unchecked()
+        .run()
+        .with_context(|| tr!("service.failed", com))?;
+    if !output.status.success() {
+        bail!(
+            "{}\n{}",
+            tr!("service.failed", com),
+            decode_output(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,7 @@
+mod main;
+mod stop;
+mod unit;
+
+pub use main::*;
+pub use stop::*;
+pub use unit::*;
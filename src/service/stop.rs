@@ -0,0 +1,207 @@
+use anyhow::{bail, Context, Result};
+use clap::Clap;
+use std::time::Duration;
+use tokio::time::{self, Instant};
+
+use super::{main::active, systemctl, unit};
+use crate::{
+    config::{self, Config},
+    install::{
+        helper::{mongodb, pm2},
+        managed_binary,
+    },
+    log_i18n, platform, registry, tr, Com,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct StopArgs {
+    /// 要停止的组件（Hydro、sandbox、MinIO、MongoDB），`all` 表示全部
+    /// Components to stop (Hydro, sandbox, MinIO, MongoDB), or `all` for all of them
+    #[clap(required = true)]
+    targets: Vec<String>,
+
+    /// 等待每个服务退出的秒数，超时后强制结束，默认为 profile.stop_timeout；由 PM2 运行的 Hydro 始终使用后者
+    /// Seconds to wait for each service to exit before killing it, defaults to profile.stop_timeout, which Hydro run by PM2 always uses
+    #[clap(long)]
+    timeout: Option<u64>,
+}
+
+/// Order the services are stopped in: Hydro first so that no request or judging task is left
+/// half done, then the sandbox it judges with, and the storage last.
+pub const STOP_ORDER: [Com; 4] = [Com::Hydro, Com::Sandbox, Com::MinIO, Com::MongoDB];
+
+const INTERVAL: Duration = Duration::from_millis(500);
+
+/// Stops the services given in [`STOP_ORDER`], each asked to exit and killed after the timeout.
+pub async fn stop(args: StopArgs) -> Result<()> {
+    let config = config::load_config().await?;
+    let targets = if args.targets.iter().any(|target| target == "all") {
+        STOP_ORDER.to_vec()
+    } else {
+        let mut given = Vec::new();
+        for target in &args.targets {
+            let com = target.parse::<Com>().map_err(anyhow::Error::msg)?;
+            if !STOP_ORDER.contains(&com) {
+                bail!(tr!("service.cannot-stop", com));
+            }
+            given.push(com);
+        }
+        STOP_ORDER
+            .iter()
+            .copied()
+            .filter(|com| given.contains(com))
+            .collect()
+    };
+    let timeout = match args.timeout {
+        Some(secs) => Duration::from_secs(secs),
+        None => config.profile.kill_after(),
+    };
+
+    for com in targets {
+        if !config.components.borrow_by_com(com).is_installed() {
+            continue;
+        }
+        if stop_unit(com, timeout).await? {
+            continue;
+        }
+        match com {
+            Com::Hydro => stop_hydro(&config, timeout)?,
+            _ => stop_process(&config, com, timeout).await?,
+        }
+    }
+    Ok(())
+}
+
+/// Stops the Hydro processes through PM2, unless systemd runs PM2. PM2 kills them after the
+/// `kill_timeout` they were started with, written into the ecosystem file from the profile, so a
+/// changed profile takes effect once they restart, and a `timeout` given differently never does.
+fn stop_hydro(config: &Config, timeout: Duration) -> Result<()> {
+    if pm2::daemon_pid().is_none() {
+        log_i18n!(info, "service.not-running", Com::Hydro);
+        return Ok(());
+    }
+    let cmd = platform::cmd("pm2");
+    let program = config.components.pm2.path_or(&cmd);
+    let processes = pm2::list(program).map_err(anyhow::Error::msg)?;
+    let mut stopped = false;
+    for app in [pm2::HYDRO_APP, pm2::JUDGE_APP] {
+        let online = processes
+            .iter()
+            .filter(|p| p.name == app && p.status() == "online")
+            .collect::<Vec<_>>();
+        // PM2 kills after 1.6 seconds by default
+        let kill_timeout = online
+            .iter()
+            .map(|p| p.pm2_env["kill_timeout"].as_u64().unwrap_or(1600))
+            .max();
+        if let Some(kill_timeout) = kill_timeout.filter(|&ms| ms != timeout.as_millis() as u64) {
+            log_i18n!(
+                warn,
+                "service.kill-timeout",
+                app,
+                kill_timeout as f64 / 1000.0,
+                timeout.as_secs()
+            );
+        }
+        if !online.is_empty() {
+            log_i18n!(info, "service.stopping", app);
+            pm2::run(duct::cmd!(program, "stop", app))
+                .with_context(|| tr!("service.failed", Com::Hydro))?;
+            stopped = true;
+        }
+    }
+    match stopped {
+        true => log_i18n!(info, "service.stopped", Com::Hydro),
+        false => log_i18n!(info, "service.not-running", Com::Hydro),
+    }
+    Ok(())
+}
+
+/// Asks systemd to stop `com` and kills it once `timeout` passes. Returns whether systemd
+/// manages `com` at all.
+async fn stop_unit(com: Com, timeout: Duration) -> Result<bool> {
+    let name = unit::unit_name(com);
+    if !unit::unit_path(com).is_file() || !active(&name) {
+        return Ok(false);
+    }
+    log_i18n!(info, "service.stopping", com);
+    systemctl(com, &["stop", "--no-block", &name])?;
+    if !wait_until(timeout, || !active(&name)).await {
+        log_i18n!(warn, "service.killed", com, timeout.as_secs());
+        systemctl(com, &["kill", "--signal=SIGKILL", &name])?;
+        // the kill counts as a failure, and would be restarted otherwise
+        systemctl(com, &["stop", &name])?;
+    }
+    log_i18n!(info, "service.stopped", com);
+    Ok(true)
+}
+
+/// Asks `com` to exit by itself, and kills it once `timeout` passes.
+async fn stop_process(config: &Config, com: Com, timeout: Duration) -> Result<()> {
+    let process = registry::get(com).process.unwrap_or_default();
+    let pids = match platform::find_processes(process) {
+        Some(pids) => pids,
+        None => bail!(tr!("service.stop-unsupported", com)),
+    };
+    if pids.is_empty() {
+        log_i18n!(info, "service.not-running", com);
+        return Ok(());
+    }
+    log_i18n!(info, "service.stopping", com);
+    let shut_down = com == Com::MongoDB && shutdown_mongod(config);
+    if !shut_down {
+        signal(&pids, "-TERM");
+    }
+    let exited = || {
+        pids.iter()
+            .all(|pid| platform::process_alive(*pid) != Some(true))
+    };
+    if !wait_until(timeout, exited).await {
+        log_i18n!(warn, "service.killed", com, timeout.as_secs());
+        signal(&pids, "-KILL");
+    }
+    log_i18n!(info, "service.stopped", com);
+    Ok(())
+}
+
+/// Shuts MongoDB down by `mongod --shutdown`, which flushes the data files before exiting.
+/// Returns whether it is done, which needs the binary and config H2O2 installed.
+fn shutdown_mongod(config: &Config) -> bool {
+    let mongod = match managed_binary(Com::MongoDB, &config.components.mongodb) {
+        Some(mongod) => mongod,
+        None => return false,
+    };
+    if !mongodb::config_path().is_file() {
+        return false;
+    }
+    duct::cmd!(mongod, "--config", mongodb::config_path(), "--shutdown")
+        .stdout_null()
+        .stderr_null()
+        .unchecked()
+        .run()
+        .is_ok_and(|output| output.status.success())
+}
+
+fn signal(pids: &[u32], signal: &str) {
+    for pid in pids {
+        let _ = duct::cmd!("kill", signal, pid.to_string())
+            .stderr_null()
+            .unchecked()
+            .run();
+    }
+}
+
+/// Waits up to `timeout` for `done` to hold, and returns whether it does.
+async fn wait_until(timeout: Duration, done: impl Fn() -> bool) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if done() {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        time::sleep(INTERVAL).await;
+    }
+}
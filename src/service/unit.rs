@@ -0,0 +1,233 @@
+use std::{env, path::PathBuf};
+
+use crate::{
+    config::Config,
+    install::helper::{minio, mongodb, pm2, sandbox},
+    platform, Com,
+};
+
+/// Directory of the units written by the admin, which take precedence over those of packages.
+const UNIT_DIR: &str = "/etc/systemd/system";
+
+/// Components run as services, in the order they start on boot.
+pub const SERVICES: &[Com] = &[Com::MongoDB, Com::MinIO, Com::Sandbox, Com::Hydro];
+
+/// `PATH` given by systemd to services, which the `bin` dir of Node.js is put before.
+const DEFAULT_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+/// Name of the unit running `com`, e.g. `h2o2-mongodb.service`.
+pub fn unit_name(com: Com) -> String {
+    format!("h2o2-{}.service", com.name())
+}
+
+pub fn unit_path(com: Com) -> PathBuf {
+    path_of(&unit_name(com))
+}
+
+/// The account in `User=` of the unit written for `com`, or `None` if there is no such unit or it
+/// runs as root.
+pub fn unit_user(com: Com) -> Option<String> {
+    let unit = std::fs::read_to_string(unit_path(com)).ok()?;
+    unit.lines()
+        .find_map(|line| line.strip_prefix("User="))
+        .map(|user| user.trim().to_owned())
+}
+
+/// Path of the unit file named `name`, e.g. `h2o2-sandbox-clean.timer`.
+pub fn path_of(name: &str) -> PathBuf {
+    PathBuf::from(UNIT_DIR).join(name)
+}
+
+/// Timers written by H2O2, e.g. `h2o2-sandbox-clean.timer`, sorted by name.
+pub fn timer_paths() -> Vec<PathBuf> {
+    let mut timers = std::fs::read_dir(UNIT_DIR)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("h2o2-") && name.ends_with(".timer"))
+        })
+        .collect::<Vec<_>>();
+    timers.sort();
+    timers
+}
+
+/// The services `com` needs on this server, i.e. those Hydro stores data in and judges with,
+/// which are started before it on boot.
+pub fn dependencies(config: &Config, com: Com) -> Vec<Com> {
+    match com {
+        Com::Hydro => [Com::MongoDB, Com::MinIO, Com::Sandbox]
+            .iter()
+            .copied()
+            .filter(|&dep| {
+                config.profile.runs(dep) && config.components.borrow_by_com(dep).is_installed()
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Renders the unit running `com` as `user`, or `None` if `com` is not a service. The sandbox
+/// always runs as root, as it creates namespaces and cgroups for the programs judged.
+///
+/// Hydro runs by the PM2 daemon, with the `HOME` of `user` so that PM2 may write its files. It
+/// requires the services it depends on whose units are written, and waits for them all to become
+/// healthy rather than merely started, as a rebooted machine starts them all at once.
+pub fn render(config: &Config, com: Com, user: &str) -> Option<String> {
+    let components = &config.components;
+    let (description, service) = match com {
+        Com::MongoDB => (
+            "MongoDB for Hydro",
+            format!(
+                "User={}\n\
+                ExecStart={} --config {}\n\
+                LimitNOFILE=64000\n",
+                user,
+                quote(components.mongodb.path_or("mongod")),
+                quote(&mongodb::config_path().to_string_lossy()),
+            ),
+        ),
+        Com::MinIO => (
+            "MinIO for Hydro",
+            format!(
+                "User={}\n\
+                EnvironmentFile={}\n\
+                ExecStart={} {}\n",
+                user,
+                minio::env_path().display(),
+                quote(components.minio.path_or("minio")),
+                minio::server_args(&config.profile.minio)
+                    .iter()
+                    .map(|arg| quote(arg))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+        ),
+        Com::Sandbox => (
+            "Sandbox judging programs for Hydro",
+            format!(
+                "EnvironmentFile={}\n\
+                ExecStart={}\n\
+                Delegate=yes\n",
+                sandbox::env_path().display(),
+                quote(components.sandbox.path_or("sandbox")),
+            ),
+        ),
+        Com::Hydro => {
+            let home = platform::home_of(user)
+                .or_else(dirs::home_dir)
+                .expect("Failed to get home dir");
+            let pm2_home = env::var_os("PM2_HOME")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| home.join(".pm2"));
+            let exe = env::current_exe().unwrap_or_else(|_| PathBuf::from("h2o2"));
+            let pm2 = components.pm2.path_or("pm2");
+            let ecosystem = pm2::ecosystem_path();
+            let path = match &components.nodejs.path {
+                Some(bin) => format!("{}:{}", bin, DEFAULT_PATH),
+                None => DEFAULT_PATH.to_owned(),
+            };
+            (
+                "Hydro",
+                format!(
+                    "Type=forking\n\
+                    User={}\n\
+                    Environment={} {} {}\n\
+                    PIDFile={}\n\
+                    ExecStartPre={} service wait\n\
+                    ExecStart={} start {}\n\
+                    ExecStop={} kill\n",
+                    user,
+                    quote(&format!("HOME={}", home.display())),
+                    quote(&format!("PM2_HOME={}", pm2_home.display())),
+                    quote(&format!("PATH={}", path)),
+                    pm2_home.join("pm2.pid").display(),
+                    quote(&exe.to_string_lossy()),
+                    quote(pm2),
+                    quote(&ecosystem.to_string_lossy()),
+                    quote(pm2),
+                ),
+            )
+        }
+        _ => return None,
+    };
+    // those started by hand are waited for only, as systemd refuses units requiring missing ones
+    let units = dependencies(config, com)
+        .into_iter()
+        .filter(|&dep| unit_path(dep).is_file())
+        .map(unit_name)
+        .collect::<Vec<_>>();
+    let mut after = "network-online.target h2o2-sysfs.service".to_owned();
+    let mut requires = String::new();
+    if !units.is_empty() {
+        after = format!("{} {}", after, units.join(" "));
+        requires = format!("Requires={}\n", units.join(" "));
+    }
+    Some(format!(
+        "# Written by H2O2 (`h2o2 service install`)\n\
+        [Unit]\n\
+        Description={}\n\
+        Wants=network-online.target\n\
+        After={}\n\
+        {}\n\
+        [Service]\n\
+        {}\
+        Restart=on-failure\n\
+        RestartSec=3\n\n\
+        [Install]\n\
+        WantedBy=multi-user.target\n",
+        description, after, requires, service
+    ))
+}
+
+/// Renders a oneshot service running `command` with `home` as `HOME`, so that it finds the same
+/// config, and the timer triggering it on `calendar`, as in `OnCalendar=`. Returns the service
+/// and the timer in order.
+pub fn render_timer(
+    description: &str,
+    command: &[String],
+    home: &str,
+    calendar: &str,
+) -> (String, String) {
+    let service = format!(
+        "# Written by H2O2\n\
+        [Unit]\n\
+        Description={}\n\n\
+        [Service]\n\
+        Type=oneshot\n\
+        Environment={}\n\
+        ExecStart={}\n",
+        description,
+        quote(&format!("HOME={}", home)),
+        command
+            .iter()
+            .map(|arg| quote(arg))
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+    let timer = format!(
+        "# Written by H2O2\n\
+        [Unit]\n\
+        Description={}\n\n\
+        [Timer]\n\
+        OnCalendar={}\n\
+        Persistent=true\n\n\
+        [Install]\n\
+        WantedBy=timers.target\n",
+        description, calendar
+    );
+    (service, timer)
+}
+
+/// Quotes a word of a command line of systemd, which expands `%` specifiers and `$` variables.
+fn quote(word: &str) -> String {
+    let escaped = word.replace('%', "%%").replace('$', "$$");
+    if escaped.is_empty() || escaped.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\')
+    {
+        format!("\"{}\"", escaped.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        escaped
+    }
+}
@@ -0,0 +1,115 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+use crate::{
+    audit,
+    config::{ComponentInfo, Config},
+    install::helper::{hydro, minio, mongodb, pm2, sandbox},
+    platform,
+    registry::Descriptor,
+    utils::format_timestamp,
+    Com,
+};
+
+/// Prints everything known about a single component.
+pub fn show_component(config: &Config, desc: &Descriptor) -> Result<()> {
+    let default = ComponentInfo::default();
+    let info = config.components.get(desc.name).unwrap_or(&default);
+    let last_install = audit::read()?
+        .into_iter()
+        .rev()
+        .find(|entry| entry.action == "install" && entry.component.as_deref() == Some(desc.name));
+
+    println!(" {}", desc.display);
+    println!("   version:    {}", info.version);
+    if let Some(path) = &info.path {
+        println!("   path:       {}", path);
+    }
+    if let Some(entry) = &last_install {
+        println!("   installed:  {}", format_timestamp(entry.time));
+        if let Some(url) = entry.details["url"].as_str() {
+            println!("   source:     {}", url);
+        }
+        if let Some(sha256) = entry.details["sha256"].as_str() {
+            println!("   sha256:     {}", sha256);
+        }
+    }
+    if let Some(status) = service_status(config, desc) {
+        println!("   service:    {}", status);
+    }
+
+    let files = owned_files(config, desc);
+    if !files.is_empty() {
+        println!("   files:");
+        for file in files {
+            let missing = if file.exists() { "" } else { " (missing)" };
+            println!("     {}{}", file.display(), missing);
+        }
+    }
+    Ok(())
+}
+
+/// Config files written by H2O2 for the component.
+fn owned_files(config: &Config, desc: &Descriptor) -> Vec<PathBuf> {
+    match desc.com {
+        Some(Com::MongoDB) => {
+            let mut files = vec![mongodb::config_path()];
+            files.extend(
+                config
+                    .profile
+                    .mongodb
+                    .tls_cert_file
+                    .iter()
+                    .map(PathBuf::from),
+            );
+            files
+        }
+        Some(Com::MinIO) => vec![minio::env_path()],
+        Some(Com::Sandbox) => vec![sandbox::env_path()],
+        Some(Com::Hydro) => vec![
+            hydro::config_path(),
+            hydro::judge_config_path(),
+            pm2::ecosystem_path(),
+        ],
+        _ if desc.name == "mc" => dirs::home_dir()
+            .map(|home| home.join(".mc").join("config.json"))
+            .into_iter()
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn service_status(config: &Config, desc: &Descriptor) -> Option<String> {
+    if desc.com == Some(Com::Hydro) {
+        return pm2_status(config);
+    }
+    let pids = platform::find_processes(desc.process?)?;
+    Some(if pids.is_empty() {
+        "not running".to_owned()
+    } else {
+        format!(
+            "running (pid {})",
+            pids.iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    })
+}
+
+/// Status of the Hydro processes reported by PM2.
+fn pm2_status(config: &Config) -> Option<String> {
+    let pm2 = platform::cmd("pm2");
+    let pm2 = config.components.pm2.path_or(&pm2);
+    let status = pm2::list(pm2)
+        .ok()?
+        .iter()
+        .filter(|app| app.name == pm2::HYDRO_APP || app.name == pm2::JUDGE_APP)
+        .map(|app| format!("{}: {}", app.name, app.status()))
+        .collect::<Vec<_>>();
+    Some(if status.is_empty() {
+        "not running".to_owned()
+    } else {
+        status.join(", ")
+    })
+}
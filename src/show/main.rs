@@ -1,19 +1,99 @@
-use crate::config;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use clap::Clap;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+use super::detail::show_component;
+use crate::{
+    cert,
+    check::advisory,
+    config::{self, RuntimeInfo},
+    install::helper::caddy,
+    output, registry, tr,
+    utils::{did_you_mean, format_timestamp, redact},
+};
 
 #[derive(Clap, Debug)]
 #[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
-pub struct Args {}
+pub struct Args {
+    /// 显示单个组件的详细信息
+    /// Prints the details of a single component
+    component: Option<String>,
+}
 
-pub async fn main() -> Result<()> {
+pub async fn main(args: Args) -> Result<()> {
     let config = config::load_config().await?;
+    if let Some(name) = &args.component {
+        let desc = registry::find(name).ok_or_else(|| {
+            match did_you_mean(&name.to_lowercase(), registry::names()) {
+                Some(suggestion) => anyhow!(tr!("component.unknown-suggest", name, suggestion)),
+                None => anyhow!(tr!("component.unknown", name)),
+            }
+        })?;
+        if output::is_json() {
+            let default = config::ComponentInfo::default();
+            let info = config.components.get(desc.name).unwrap_or(&default);
+            output::print_json(&output::component(desc, info));
+            return Ok(());
+        }
+        return show_component(&config, desc);
+    }
+    if output::is_json() {
+        output::print_json(&json!({
+            "components": output::components(&config.components),
+            "runtimes": output::runtimes(&config.runtimes),
+            "findings": output::findings(&advisory::evaluate(&config, &advisory::known())),
+        }));
+        return Ok(());
+    }
     println!("H2O2 show");
     println!();
     println!("目前 H2O2 配置文件中记录的组件状况如下：");
     println!("Components recorded in .h2o2config:");
     println!();
     show_components(&config.components);
+    if !config.runtimes.is_empty() {
+        println!();
+        show_runtimes(&config.runtimes);
+    }
+    let findings = advisory::evaluate(&config, &advisory::known());
+    if !findings.is_empty() {
+        println!();
+        for (name, release, finding) in &findings {
+            let mark = if finding.is_urgent() { "!" } else { "*" };
+            println!(" {} {}", mark, finding.message(name, release));
+        }
+    }
+    let profile = &config.profile;
+    if config.components.minio.is_installed() && profile.minio.browser {
+        println!();
+        println!(" MinIO console: {}", profile.minio.console_url());
+    }
+    if profile.hydro.channel.is_beta() {
+        println!();
+        println!(" Hydro channel: {}", profile.hydro.channel);
+    }
+    if let Some(pinned) = &profile.yarn.pinned {
+        println!();
+        println!(" Yarn pinned by corepack: {}", pinned);
+    }
+    if let (Some(_), Some(domain)) = (config.components.extras.get("caddy"), &profile.caddy.domain)
+    {
+        println!();
+        match caddy::served_cert(domain) {
+            Ok(cert) => println!(
+                " certificate: {}, expires {} ({} days left)",
+                domain,
+                format_timestamp(cert.not_after),
+                cert::days_left(&cert)
+            ),
+            Err(_) => println!(" certificate: {}, unavailable", domain),
+        }
+    }
+    if let Some(token) = &profile.sandbox.auth_token {
+        println!();
+        println!(" sandbox auth token: {}", redact(token));
+    }
     println!();
     println!("如果配置文件中记录的组件状况与实际情况不一致，请手动运行 `h2o2 detect` 来重新同步组件状况。");
     println!("If the components recorded is inconsistent with the actual situation, please run `h2o2 detect` to resync components.");
@@ -28,4 +108,15 @@ pub fn show_components(com: &config::Components) {
     println!(" Yarn    {}", com.yarn.to_show_format());
     println!(" PM2     {}", com.pm2.to_show_format());
     println!(" Hydro   {}", com.hydro.to_show_format());
+    for desc in registry::extras() {
+        if let Some(info) = com.extras.get(desc.name) {
+            println!(" {:<7} {}", desc.display, info.to_show_format());
+        }
+    }
+}
+
+pub fn show_runtimes(runtimes: &BTreeMap<String, RuntimeInfo>) {
+    for (name, info) in runtimes {
+        println!(" {:<7} {} @ {}", name, &info.version, &info.path);
+    }
 }
@@ -1,3 +1,4 @@
+mod detail;
 mod main;
 
 pub use main::*;
@@ -0,0 +1,209 @@
+use anyhow::{bail, Result};
+use clap::Clap;
+use std::time::Duration;
+use tokio::{net::TcpStream, time::timeout};
+
+use crate::{
+    config::{self, Config},
+    install::helper::{pm2, sandbox},
+    log_i18n, platform,
+    registry::{self, Descriptor},
+    tr, Com,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {}
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Runtime state of a service.
+struct Service {
+    name: &'static str,
+    /// `None` if processes cannot be listed here, in which case the ports tell the state
+    pids: Option<Vec<u32>>,
+    /// addresses it should listen on, and whether they accept connections
+    ports: Vec<(String, bool)>,
+}
+
+impl Service {
+    fn is_up(&self) -> bool {
+        match &self.pids {
+            Some(pids) => !pids.is_empty(),
+            None => !self.ports.is_empty() && self.ports.iter().all(|(_, open)| *open),
+        }
+    }
+}
+
+pub async fn main(_args: Args) -> Result<()> {
+    let config = config::load_config().await?;
+
+    let mut services = Vec::new();
+    for desc in registry::COMPONENTS {
+        let installed = config
+            .components
+            .get(desc.name)
+            .is_some_and(|info| info.is_installed());
+        if !installed {
+            continue;
+        }
+        if desc.com == Some(Com::PM2) {
+            services.push(Service {
+                name: desc.display,
+                pids: Some(pm2::daemon_pid().into_iter().collect()),
+                ports: Vec::new(),
+            });
+        } else if desc.com == Some(Com::Hydro) {
+            services.extend(hydro_services(&config).await);
+        } else if let Some(process) = desc.process {
+            services.push(Service {
+                name: desc.display,
+                pids: platform::find_processes(process),
+                ports: check_ports(addresses(&config, desc)).await,
+            });
+        }
+    }
+    // external endpoints run elsewhere, so only their ports tell the state
+    for com in [Com::MongoDB, Com::MinIO] {
+        if !config.profile.is_external(com) {
+            continue;
+        }
+        let addresses = match com {
+            Com::MongoDB => config.profile.mongodb.addresses(),
+            _ => config.profile.minio.api_address().into_iter().collect(),
+        };
+        if !addresses.is_empty() {
+            services.push(Service {
+                name: registry::get(com).display,
+                pids: None,
+                ports: check_ports(addresses).await,
+            });
+        }
+    }
+
+    println!("H2O2 status");
+    println!();
+    for service in &services {
+        let state = if service.is_up() { "up" } else { "down" };
+        let pids = match &service.pids {
+            Some(pids) if !pids.is_empty() => format!(
+                "pid {}",
+                pids.iter()
+                    .map(u32::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            _ => String::new(),
+        };
+        let ports = service
+            .ports
+            .iter()
+            .map(|(addr, open)| format!("{} ({})", addr, if *open { "open" } else { "closed" }))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!(" {:<10} {:<5} {:<20} {}", service.name, state, pids, ports);
+    }
+    println!();
+    let minio = &config.profile.minio;
+    if config.components.minio.is_installed()
+        && minio.browser
+        && !config.profile.is_external(Com::MinIO)
+    {
+        println!(" MinIO console: {}", minio.console_url());
+        println!();
+    }
+
+    let mut down = Vec::new();
+    for service in &services {
+        if !service.is_up() {
+            down.push(service.name);
+            continue;
+        }
+        for (addr, _) in service.ports.iter().filter(|(_, open)| !open) {
+            log_i18n!(warn, "status.port-closed", service.name, addr);
+        }
+    }
+    if !down.is_empty() {
+        bail!(tr!("status.some-down", down.join(", ")));
+    }
+    log_i18n!(info, "status.all-up");
+    Ok(())
+}
+
+/// The Hydro server and standalone judge processes managed by PM2.
+async fn hydro_services(config: &Config) -> Vec<Service> {
+    let mut apps = vec![(pm2::HYDRO_APP, "Hydro")];
+    if config.profile.hydro.judge_workers > 0 {
+        apps.push((pm2::JUDGE_APP, "HydroJudge"));
+    }
+    // querying PM2 starts its daemon if it is not running
+    let processes = match pm2::daemon_pid() {
+        Some(_) => {
+            let cmd = platform::cmd("pm2");
+            match pm2::list(config.components.pm2.path_or(&cmd)) {
+                Ok(processes) => processes,
+                Err(reason) => {
+                    log_i18n!(warn, "status.pm2-unavailable", reason);
+                    Vec::new()
+                }
+            }
+        }
+        None => Vec::new(),
+    };
+
+    let mut services = Vec::new();
+    for (app, name) in apps {
+        let pids = processes
+            .iter()
+            .filter(|p| p.name == app && p.status() == "online")
+            .filter_map(|p| p.pid.filter(|pid| *pid != 0))
+            .collect();
+        let ports = if app == pm2::HYDRO_APP {
+            check_ports(vec![config::to_local_address(
+                &config.profile.caddy.upstream,
+            )])
+            .await
+        } else {
+            Vec::new()
+        };
+        services.push(Service {
+            name,
+            pids: Some(pids),
+            ports,
+        });
+    }
+    services
+}
+
+/// Addresses the service of `desc` listens on, reachable locally.
+fn addresses(config: &Config, desc: &Descriptor) -> Vec<String> {
+    let profile = &config.profile;
+    match desc.com {
+        Some(Com::MongoDB) => vec![format!("{}:{}", profile.mongodb.host, profile.mongodb.port)],
+        Some(Com::MinIO) => {
+            let mut addresses = vec![config::to_local_address(&profile.minio.address)];
+            if profile.minio.browser {
+                addresses.push(config::to_local_address(&profile.minio.console_address));
+            }
+            addresses
+        }
+        Some(Com::Sandbox) => vec![sandbox::HOST.trim_start_matches("http://").to_owned()],
+        _ if desc.name == "redis" => vec!["127.0.0.1:6379".to_owned()],
+        _ if desc.name == "caddy" && profile.caddy.domain.is_some() => {
+            vec!["127.0.0.1:80".to_owned(), "127.0.0.1:443".to_owned()]
+        }
+        _ => Vec::new(),
+    }
+}
+
+async fn check_ports(addresses: Vec<String>) -> Vec<(String, bool)> {
+    let mut ports = Vec::new();
+    for address in addresses {
+        let open = matches!(
+            timeout(CONNECT_TIMEOUT, TcpStream::connect(address.as_str())).await,
+            Ok(Ok(_))
+        );
+        ports.push((address, open));
+    }
+    ports
+}
@@ -0,0 +1,103 @@
+use anyhow::{bail, Context, Result};
+use clap::{AppSettings, Clap};
+
+use super::{
+    swap::{self, AddSwapArgs},
+    timesync,
+    tuning::{self, TuneArgs},
+};
+use crate::{
+    cert,
+    config::{self, Config},
+    log_i18n, tr,
+    utils::decode_output,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Clap, Debug)]
+enum SubCommand {
+    /// 检查系统配置是否适合运行 Hydro
+    /// Checks whether the system is set up well for running Hydro
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Check,
+
+    /// 创建并启用 swap 文件
+    /// Creates and enables a swap file
+    #[clap(setting = AppSettings::ColoredHelp)]
+    AddSwap(AddSwapArgs),
+
+    /// 安装并启用时间同步服务
+    /// Installs and enables a time sync service
+    #[clap(setting = AppSettings::ColoredHelp)]
+    EnableTimesync,
+
+    /// 查看或应用推荐的内核参数与资源限制
+    /// Shows or applies the recommended kernel parameters and resource limits
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Tune(TuneArgs),
+}
+
+impl Args {
+    /// Name of the operation, if the subcommand modifies the system.
+    pub fn operation(&self) -> Option<&'static str> {
+        match &self.subcmd {
+            SubCommand::Check => None,
+            SubCommand::AddSwap(_) => Some("sys add-swap"),
+            SubCommand::EnableTimesync => Some("sys enable-timesync"),
+            SubCommand::Tune(args) if args.apply => Some("sys tune"),
+            SubCommand::Tune(_) => None,
+        }
+    }
+}
+
+pub async fn main(args: Args) -> Result<()> {
+    // the checks work without H2O2 set up, but can be more accurate with it
+    let config = config::load_config().await.ok();
+    match args.subcmd {
+        SubCommand::Check => {
+            if check(config.as_ref()).await {
+                log_i18n!(info, "sys.check-passed");
+            }
+            Ok(())
+        }
+        SubCommand::AddSwap(args) => swap::add_swap(args),
+        SubCommand::EnableTimesync => timesync::enable_timesync(),
+        SubCommand::Tune(args) => tuning::tune(args, config.as_ref()),
+    }
+}
+
+/// Runs every system check, logging the problems found. Returns whether all passed.
+pub async fn check(config: Option<&Config>) -> bool {
+    // run every check, even if an earlier one fails
+    let domain = config.and_then(|config| config.profile.caddy.domain.as_deref());
+    let checks = [
+        swap::check(),
+        timesync::check().await,
+        tuning::check(config),
+        match domain {
+            Some(domain) => cert::preflight(domain).await,
+            None => true,
+        },
+    ];
+    checks.iter().all(|ok| *ok)
+}
+
+/// Runs a command of the system, failing with the message of `failed` and its error output.
+pub(super) fn run(expr: duct::Expression, failed: &str) -> Result<()> {
+    let output = expr
+        .stdout_capture()
+        .stderr_capture()
+        .unchecked()
+        .run()
+        .with_context(|| tr!(failed))?;
+    if !output.status.success() {
+        bail!("{}\n{}", tr!(failed), decode_output(&output.stderr).trim());
+    }
+    Ok(())
+}
@@ -0,0 +1,6 @@
+mod main;
+mod swap;
+mod timesync;
+mod tuning;
+
+pub use main::*;
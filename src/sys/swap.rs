@@ -0,0 +1,153 @@
+use anyhow::{bail, Context, Result};
+use clap::Clap;
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use super::main::run;
+use crate::{
+    log_i18n,
+    platform::{self, Os},
+    tr,
+    utils::{format_size, parse_size},
+};
+
+/// Systems with less memory than this often fail to build the Hydro UI without swap.
+const LOW_MEMORY: u64 = 2 << 30;
+
+/// Swap considered enough for such systems.
+const ENOUGH_SWAP: u64 = 1 << 30;
+
+#[derive(Clap, Debug)]
+pub struct AddSwapArgs {
+    /// swap 文件大小，如 `2G`、`512M`
+    /// Size of the swap file, e.g. `2G`, `512M`
+    #[clap(long, default_value = "2G")]
+    size: String,
+
+    /// swap 文件路径
+    /// Path of the swap file
+    #[clap(long, default_value = "/swapfile")]
+    path: String,
+
+    /// 同时写入 /etc/fstab，以便开机时自动启用
+    /// Also adds it to /etc/fstab, so that it is enabled on boot
+    #[clap(long)]
+    fstab: bool,
+}
+
+/// Warns if the memory is low and there is little swap. Returns whether it is fine.
+pub fn check() -> bool {
+    let memory = match platform::memory() {
+        Some(memory) => memory,
+        None => return true,
+    };
+    if memory.total < LOW_MEMORY && memory.swap_total < ENOUGH_SWAP {
+        log_i18n!(
+            warn,
+            "sys.low-memory",
+            format_size(memory.total),
+            format_size(memory.swap_total)
+        );
+        return false;
+    }
+    true
+}
+
+pub fn add_swap(args: AddSwapArgs) -> Result<()> {
+    if platform::current().os != Os::Linux {
+        bail!(tr!("sys.linux-only"));
+    }
+    if platform::current().in_container() {
+        bail!(tr!("sys.swap-in-container"));
+    }
+    if platform::is_root() == Some(false) {
+        bail!(tr!("sys.root-required"));
+    }
+    let size = match parse_size(&args.size) {
+        Some(size) if size > 0 => size,
+        _ => bail!(tr!("sys.invalid-size", &args.size)),
+    };
+    let path = Path::new(&args.path);
+    if path.exists() {
+        bail!(tr!("sys.swap-exists", path.display()));
+    }
+
+    log_i18n!(info, "sys.swap-creating", path.display(), format_size(size));
+    if let Err(e) = create_swap(path, size) {
+        let _ = fs::remove_file(path);
+        return Err(e);
+    }
+    if args.fstab {
+        add_to_fstab(path).context(tr!("sys.fstab-failed"))?;
+    }
+
+    let swap = platform::memory()
+        .map(|memory| memory.swap_total)
+        .unwrap_or(size);
+    log_i18n!(info, "sys.swap-enabled", path.display(), format_size(swap));
+    if !args.fstab {
+        log_i18n!(info, "sys.swap-not-persistent");
+    }
+    Ok(())
+}
+
+fn create_swap(path: &Path, size: u64) -> Result<()> {
+    // `fallocate` is fast, but swap files on some filesystems, e.g. btrfs before Linux 5.0, must
+    // be written out in full
+    let allocated = duct::cmd!("fallocate", "-l", size.to_string(), path)
+        .stdout_null()
+        .stderr_null()
+        .unchecked()
+        .run()
+        .map(|output| output.status.success())
+        .unwrap_or(false);
+    if allocated {
+        match enable_swap(path) {
+            Ok(()) => return Ok(()),
+            Err(e) => tracing::debug!("Failed to enable the swap file from fallocate: {:#}", e),
+        }
+        let _ = fs::remove_file(path);
+    }
+    run(
+        duct::cmd!(
+            "dd",
+            "if=/dev/zero",
+            format!("of={}", path.display()),
+            "bs=1M",
+            format!("count={}", (size + (1 << 20) - 1) >> 20)
+        ),
+        "sys.swap-failed",
+    )?;
+    enable_swap(path)
+}
+
+fn enable_swap(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .context(tr!("sys.swap-failed"))?;
+    }
+    run(duct::cmd!("mkswap", path), "sys.swap-failed")?;
+    run(duct::cmd!("swapon", path), "sys.swap-failed")
+}
+
+fn add_to_fstab(path: &Path) -> std::io::Result<()> {
+    const FSTAB: &str = "/etc/fstab";
+    let content = fs::read_to_string(FSTAB)?;
+    let exists = content
+        .lines()
+        .any(|line| line.split_whitespace().next() == Some(&*path.to_string_lossy()));
+    if exists {
+        return Ok(());
+    }
+    let mut file = OpenOptions::new().append(true).open(FSTAB)?;
+    if !content.is_empty() && !content.ends_with('\n') {
+        writeln!(file)?;
+    }
+    writeln!(file, "{} none swap sw 0 0", path.display())
+}
@@ -0,0 +1,186 @@
+use anyhow::{bail, Result};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use super::main::run;
+use crate::{
+    log_i18n, net,
+    platform::{self, Os, ServiceManager},
+    profiling, tr,
+    utils::parse_http_date,
+};
+
+/// Time sync services, by the name of both their systemd units and processes.
+const SERVICES: &[&str] = &[
+    "chronyd",
+    "chrony",
+    "ntpd",
+    "ntp",
+    "openntpd",
+    "systemd-timesyncd",
+];
+
+/// Sites whose `Date` header is used as the reference time.
+const TIME_SOURCES: &[&str] = &["https://www.cloudflare.com/", "https://www.baidu.com/"];
+
+/// Drift tolerated, given that `Date` only has a precision of one second.
+const MAX_DRIFT: f64 = 3.0;
+
+/// Warns if the clock drifts or no time sync service is running. Returns whether it is fine.
+pub async fn check() -> bool {
+    let mut ok = true;
+    match active_service() {
+        Some(Some(service)) => tracing::debug!("Time sync service: {}", service),
+        Some(None) => {
+            log_i18n!(warn, "sys.timesync-inactive");
+            ok = false;
+        }
+        None => {}
+    }
+    match drift().await {
+        Some(drift) if drift.abs() > MAX_DRIFT => {
+            log_i18n!(warn, "sys.clock-drift", format!("{:+.1}", drift));
+            ok = false;
+        }
+        Some(drift) => tracing::debug!("Clock drift: {:+.1}s", drift),
+        None => log_i18n!(warn, "sys.clock-drift-unknown"),
+    }
+    ok
+}
+
+pub fn enable_timesync() -> Result<()> {
+    let platform = platform::current();
+    if platform.os != Os::Linux {
+        bail!(tr!("sys.linux-only"));
+    }
+    if platform.in_container() {
+        bail!(tr!("sys.timesync-in-container"));
+    }
+    if platform::is_root() == Some(false) {
+        bail!(tr!("sys.root-required"));
+    }
+    if let Some(Some(service)) = active_service() {
+        log_i18n!(info, "sys.timesync-active", service);
+        return Ok(());
+    }
+
+    match platform.service_manager {
+        ServiceManager::Systemd if unit_exists("systemd-timesyncd") => {
+            run(
+                duct::cmd!("timedatectl", "set-ntp", "true"),
+                "sys.timesync-failed",
+            )?;
+        }
+        ServiceManager::Systemd => {
+            install_chrony()?;
+            // the unit is `chronyd` on RHEL-like distributions, `chrony` on Debian-like ones
+            let unit = if unit_exists("chronyd") {
+                "chronyd"
+            } else {
+                "chrony"
+            };
+            run(
+                duct::cmd!("systemctl", "enable", "--now", unit),
+                "sys.timesync-failed",
+            )?;
+        }
+        ServiceManager::OpenRC => {
+            install_chrony()?;
+            run(
+                duct::cmd!("rc-update", "add", "chronyd", "default"),
+                "sys.timesync-failed",
+            )?;
+            run(
+                duct::cmd!("rc-service", "chronyd", "start"),
+                "sys.timesync-failed",
+            )?;
+        }
+        _ => bail!(tr!("sys.no-service-manager")),
+    }
+
+    match active_service() {
+        Some(Some(service)) => log_i18n!(info, "sys.timesync-enabled", service),
+        _ => bail!(tr!("sys.timesync-failed")),
+    }
+    Ok(())
+}
+
+/// The running time sync service, or `None` if it cannot be determined here.
+fn active_service() -> Option<Option<&'static str>> {
+    if platform::current().service_manager == ServiceManager::Systemd {
+        return Some(SERVICES.iter().copied().find(|service| {
+            duct::cmd!("systemctl", "is-active", "--quiet", service)
+                .stdout_null()
+                .stderr_null()
+                .unchecked()
+                .run()
+                .map(|output| output.status.success())
+                .unwrap_or(false)
+        }));
+    }
+    let mut found = None;
+    for service in SERVICES {
+        if !platform::find_processes(service)?.is_empty() {
+            found = Some(*service);
+            break;
+        }
+    }
+    Some(found)
+}
+
+fn unit_exists(unit: &str) -> bool {
+    duct::cmd!("systemctl", "cat", format!("{}.service", unit))
+        .stdout_null()
+        .stderr_null()
+        .unchecked()
+        .run()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+fn install_chrony() -> Result<()> {
+    let pm = match platform::current().package_manager {
+        Some(pm) => pm,
+        None => bail!(tr!("sys.no-package-manager", "chrony")),
+    };
+    log_i18n!(info, "sys.package-installing", "chrony", pm);
+    run(
+        duct::cmd(pm.executable(), pm.install_args("chrony")),
+        "sys.package-install-failed",
+    )
+}
+
+/// Seconds the local clock is ahead of the first reachable time source.
+async fn drift() -> Option<f64> {
+    let client = net::client_builder()
+        .timeout(Duration::from_secs(5))
+        .build()
+        .ok()?;
+    for url in TIME_SOURCES {
+        let start = Instant::now();
+        let span = profiling::span("main", *url);
+        let res = match client.head(*url).send().await {
+            Ok(res) => res,
+            Err(e) => {
+                tracing::debug!("Failed to fetch {}: {}", url, e);
+                continue;
+            }
+        };
+        let rtt = start.elapsed();
+        drop(span);
+        let local = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+        let remote = match res
+            .headers()
+            .get(reqwest::header::DATE)
+            .and_then(|date| date.to_str().ok())
+            .and_then(parse_http_date)
+        {
+            Some(remote) => remote,
+            None => continue,
+        };
+        // the server stamps the response about halfway through the round trip, and truncates
+        // to a whole second
+        let remote = remote as f64 + 0.5 + rtt.as_secs_f64() / 2.0;
+        return Some(local.as_secs_f64() - remote);
+    }
+    None
+}
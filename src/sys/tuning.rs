@@ -0,0 +1,466 @@
+use anyhow::{bail, Context, Result};
+use clap::Clap;
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::main::run;
+use crate::{
+    config::Config,
+    install::helper::mongodb,
+    log_i18n,
+    platform::{self, Os, ServiceManager},
+    tr,
+};
+
+/// Kernel parameters recommended by the MongoDB production notes and for running judges:
+/// (key, recommended value, whether the value is an upper bound).
+const SYSCTLS: &[(&str, u64, bool)] = &[
+    ("vm.max_map_count", 262144, false),
+    ("kernel.pid_max", 64000, false),
+    ("net.core.somaxconn", 4096, false),
+    ("vm.swappiness", 1, true),
+];
+
+/// Resource limits recommended by the MongoDB production notes:
+/// (item in `limits.conf`, row in `/proc/self/limits`, recommended value).
+const LIMITS: &[(&str, &str, u64)] = &[
+    ("nofile", "Max open files", 64000),
+    ("nproc", "Max processes", 64000),
+];
+
+const THP_PATH: &str = "/sys/kernel/mm/transparent_hugepage";
+
+/// Readahead recommended for the device holding the MongoDB data, in KiB.
+const READAHEAD_KB: u64 = 16;
+
+const SYSCTL_DROP_IN: &str = "/etc/sysctl.d/60-h2o2.conf";
+const LIMITS_DROP_IN: &str = "/etc/security/limits.d/60-h2o2.conf";
+
+/// Applies the sysfs settings on boot, before MongoDB starts.
+const SYSFS_UNIT: &str = "h2o2-sysfs.service";
+const SYSFS_UNIT_PATH: &str = "/etc/systemd/system/h2o2-sysfs.service";
+
+/// The tuned profile applying the sysfs settings, used instead of the unit if tuned is active,
+/// as tuned would override them.
+const TUNED_PROFILE: &str = "h2o2";
+const TUNED_PROFILE_PATH: &str = "/etc/tuned/h2o2/tuned.conf";
+
+#[derive(Clap, Debug)]
+pub struct TuneArgs {
+    /// 写入配置以应用推荐值
+    /// Applies the recommended values by writing drop-in files
+    #[clap(long)]
+    pub apply: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Kind {
+    Sysctl(&'static str),
+    /// an item in `limits.conf` with the current hard limit, as the soft one is shown
+    Limit(&'static str, Option<u64>),
+    /// a file under `/sys`, named for display
+    Sysfs(String, PathBuf),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    AtLeast,
+    AtMost,
+    Exactly,
+}
+
+/// A tunable setting with its current and recommended values.
+struct Setting {
+    kind: Kind,
+    current: Option<String>,
+    recommended: String,
+    bound: Bound,
+}
+
+impl Setting {
+    fn numeric(kind: Kind, current: Option<u64>, recommended: u64, bound: Bound) -> Self {
+        Self {
+            kind,
+            current: current.map(format_number),
+            recommended: recommended.to_string(),
+            bound,
+        }
+    }
+
+    fn name(&self) -> String {
+        match &self.kind {
+            Kind::Sysctl(key) => key.to_string(),
+            Kind::Limit(item, _) => format!("ulimit {}", item),
+            Kind::Sysfs(name, _) => name.clone(),
+        }
+    }
+
+    fn is_ok(&self) -> bool {
+        let current = match &self.current {
+            Some(current) => current,
+            None => return true,
+        };
+        match (
+            self.bound,
+            parse_number(current),
+            parse_number(&self.recommended),
+        ) {
+            (Bound::AtLeast, Some(current), Some(recommended)) => current >= recommended,
+            (Bound::AtMost, Some(current), Some(recommended)) => current <= recommended,
+            _ => *current == self.recommended,
+        }
+    }
+
+    /// The value to set, never making a setting worse than it is.
+    fn target(&self) -> String {
+        if self.is_ok() {
+            if let Some(current) = &self.current {
+                return current.clone();
+            }
+        }
+        self.recommended.clone()
+    }
+}
+
+/// Warns about the settings differing from the recommended values. Returns whether all are fine.
+pub fn check(config: Option<&Config>) -> bool {
+    let bad = settings(config)
+        .into_iter()
+        .filter(|setting| !setting.is_ok())
+        .collect::<Vec<_>>();
+    for setting in &bad {
+        log_i18n!(
+            warn,
+            "sys.tuning-not-recommended",
+            setting.name(),
+            setting.current.as_deref().unwrap_or("?"),
+            &setting.recommended
+        );
+    }
+    if !bad.is_empty() {
+        log_i18n!(warn, "sys.tuning-suggest");
+    }
+    bad.is_empty()
+}
+
+pub fn tune(args: TuneArgs, config: Option<&Config>) -> Result<()> {
+    if platform::current().os != Os::Linux {
+        bail!(tr!("sys.linux-only"));
+    }
+    let settings = settings(config);
+    print_settings(&settings);
+    if !args.apply {
+        if settings.iter().any(|setting| !setting.is_ok()) {
+            log_i18n!(info, "sys.tuning-apply-hint");
+        }
+        return Ok(());
+    }
+
+    if platform::current().in_container() {
+        bail!(tr!("sys.tuning-in-container"));
+    }
+    if platform::is_root() == Some(false) {
+        bail!(tr!("sys.root-required"));
+    }
+
+    let mut sysctl = String::from("# Written by H2O2 (`h2o2 sys tune --apply`)\n");
+    let mut limits = sysctl.clone();
+    let mut sysfs = Vec::new();
+    for setting in &settings {
+        match &setting.kind {
+            Kind::Sysctl(key) => writeln!(sysctl, "{} = {}", key, setting.target()).unwrap(),
+            Kind::Limit(item, hard) => {
+                let soft = setting.target();
+                // the hard limit may already be above the soft one recommended
+                let hard = match (hard, parse_number(&soft)) {
+                    (Some(hard), Some(target)) => format_number((*hard).max(target)),
+                    _ => soft.clone(),
+                };
+                // `*` does not cover root
+                for domain in ["*", "root"].iter() {
+                    writeln!(limits, "{} soft {} {}", domain, item, soft).unwrap();
+                    writeln!(limits, "{} hard {} {}", domain, item, hard).unwrap();
+                }
+            }
+            Kind::Sysfs(_, path) => sysfs.push((path.clone(), setting.target())),
+        }
+    }
+    write_file(SYSCTL_DROP_IN, &sysctl)?;
+    run(
+        duct::cmd!("sysctl", "-p", SYSCTL_DROP_IN),
+        "sys.tuning-failed",
+    )?;
+    write_file(LIMITS_DROP_IN, &limits)?;
+    if !sysfs.is_empty() {
+        apply_sysfs(&sysfs)?;
+    }
+    log_i18n!(info, "sys.tuning-applied", SYSCTL_DROP_IN, LIMITS_DROP_IN);
+
+    // some settings may be rejected or overridden, e.g. by tuned
+    let remaining = self::settings(config)
+        .into_iter()
+        .filter(|setting| !setting.is_ok() && !matches!(setting.kind, Kind::Limit(..)))
+        .collect::<Vec<_>>();
+    if !remaining.is_empty() {
+        print_settings(&remaining);
+        bail!(tr!("sys.tuning-not-effective"));
+    }
+    Ok(())
+}
+
+fn print_settings(settings: &[Setting]) {
+    let width = settings
+        .iter()
+        .map(|setting| setting.name().len())
+        .max()
+        .unwrap_or(0)
+        .max("setting".len());
+    tracing::info!(
+        "  {:<width$}  {:>10}  {:>11}",
+        "setting",
+        "current",
+        "recommended",
+        width = width
+    );
+    for setting in settings {
+        tracing::info!(
+            "{} {:<width$}  {:>10}  {:>11}",
+            if setting.is_ok() { " " } else { "!" },
+            setting.name(),
+            setting.current.as_deref().unwrap_or("?"),
+            &setting.recommended,
+            width = width
+        );
+    }
+}
+
+/// Applies the sysfs settings now and on every boot, through tuned if it is active, or a
+/// systemd unit otherwise.
+fn apply_sysfs(sysfs: &[(PathBuf, String)]) -> Result<()> {
+    if tuned_active() {
+        let mut profile = String::from("# Written by H2O2 (`h2o2 sys tune --apply`)\n");
+        if let Some(current) = tuned_profile() {
+            writeln!(profile, "[main]\ninclude={}\n", current).unwrap();
+        }
+        profile.push_str("[sysfs]\n");
+        for (path, value) in sysfs {
+            writeln!(profile, "{}={}", path.display(), value).unwrap();
+        }
+        write_file(TUNED_PROFILE_PATH, &profile)?;
+        return run(
+            duct::cmd!("tuned-adm", "profile", TUNED_PROFILE),
+            "sys.tuning-failed",
+        );
+    }
+
+    for (path, value) in sysfs {
+        fs::write(path, value).with_context(|| tr!("sys.write-failed", path.display()))?;
+    }
+    if platform::current().service_manager != ServiceManager::Systemd {
+        log_i18n!(warn, "sys.tuning-not-persistent");
+        return Ok(());
+    }
+    let commands = sysfs
+        .iter()
+        .map(|(path, value)| format!("echo {} > {}", value, path.display()))
+        .collect::<Vec<_>>()
+        .join(" && ");
+    let unit = format!(
+        "# Written by H2O2 (`h2o2 sys tune --apply`)\n\
+        [Unit]\n\
+        Description=Kernel settings recommended for MongoDB\n\
+        DefaultDependencies=no\n\
+        After=sysinit.target local-fs.target\n\
+        Before=basic.target mongod.service\n\n\
+        [Service]\n\
+        Type=oneshot\n\
+        ExecStart=/bin/sh -c '{}'\n\n\
+        [Install]\n\
+        WantedBy=basic.target\n",
+        commands
+    );
+    write_file(SYSFS_UNIT_PATH, &unit)?;
+    run(
+        duct::cmd!("systemctl", "daemon-reload"),
+        "sys.tuning-failed",
+    )?;
+    run(
+        duct::cmd!("systemctl", "enable", SYSFS_UNIT),
+        "sys.tuning-failed",
+    )
+}
+
+fn tuned_active() -> bool {
+    platform::current().service_manager == ServiceManager::Systemd
+        && duct::cmd!("systemctl", "is-active", "--quiet", "tuned")
+            .stdout_null()
+            .stderr_null()
+            .unchecked()
+            .run()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+}
+
+/// The active tuned profile other than ours, which ours is based on.
+fn tuned_profile() -> Option<String> {
+    let output = duct::cmd!("tuned-adm", "active")
+        .stdout_capture()
+        .stderr_null()
+        .unchecked()
+        .run()
+        .ok()?;
+    // `Current active profile: virtual-guest`
+    let profile = String::from_utf8_lossy(&output.stdout)
+        .split(':')
+        .nth(1)?
+        .trim()
+        .to_owned();
+    Some(profile).filter(|profile| !profile.is_empty() && profile != TUNED_PROFILE)
+}
+
+fn settings(config: Option<&Config>) -> Vec<Setting> {
+    if platform::current().os != Os::Linux {
+        return Vec::new();
+    }
+    let mut settings = SYSCTLS
+        .iter()
+        .map(|(key, recommended, upper_bound)| {
+            let bound = if *upper_bound {
+                Bound::AtMost
+            } else {
+                Bound::AtLeast
+            };
+            Setting::numeric(Kind::Sysctl(key), read_sysctl(key), *recommended, bound)
+        })
+        .collect::<Vec<_>>();
+
+    let limits = fs::read_to_string("/proc/self/limits").unwrap_or_default();
+    settings.extend(LIMITS.iter().map(|(item, row, recommended)| {
+        let (soft, hard) = read_limit(&limits, row).unzip();
+        Setting::numeric(
+            Kind::Limit(item, hard.flatten()),
+            soft.flatten(),
+            *recommended,
+            Bound::AtLeast,
+        )
+    }));
+
+    if Path::new(THP_PATH).is_dir() {
+        // MongoDB 8.0 uses a new TCMalloc which works better with THP, unlike earlier versions
+        let thp_preferred = config
+            .and_then(|config| config.components.mongodb.version())
+            .is_some_and(|version| version.major >= 8);
+        let (enabled, defrag) = if thp_preferred {
+            ("always", "defer+madvise")
+        } else {
+            ("never", "never")
+        };
+        for (name, recommended) in [("enabled", enabled), ("defrag", defrag)].iter() {
+            let path = Path::new(THP_PATH).join(name);
+            settings.push(Setting {
+                current: read_selected(&path),
+                kind: Kind::Sysfs(format!("THP {}", name), path),
+                recommended: recommended.to_string(),
+                bound: Bound::Exactly,
+            });
+        }
+    }
+
+    if let Some((device, path)) = readahead_path(&mongodb::data_path()) {
+        let current = fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| text.trim().parse().ok());
+        settings.push(Setting::numeric(
+            Kind::Sysfs(format!("readahead {} (KiB)", device), path),
+            current,
+            READAHEAD_KB,
+            Bound::AtMost,
+        ));
+    }
+    settings
+}
+
+fn read_sysctl(key: &str) -> Option<u64> {
+    let path = Path::new("/proc/sys").join(key.replace('.', "/"));
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Reads the soft and hard limits from `/proc/self/limits`, whose rows look like
+/// `Max open files            1024                 1048576              files`.
+fn read_limit(limits: &str, row: &str) -> Option<(Option<u64>, Option<u64>)> {
+    let mut values = limits
+        .lines()
+        .find_map(|line| line.strip_prefix(row))?
+        .split_whitespace();
+    let soft = values.next().and_then(parse_number);
+    let hard = values.next().and_then(parse_number);
+    Some((soft, hard))
+}
+
+/// Reads the selected option from a sysfs file like `always madvise [never]`.
+fn read_selected(path: &Path) -> Option<String> {
+    let text = fs::read_to_string(path).ok()?;
+    let start = text.find('[')? + 1;
+    let end = start + text[start..].find(']')?;
+    Some(text[start..end].to_owned())
+}
+
+/// Finds the device holding `path` (or its closest existing ancestor) and the sysfs file of its
+/// readahead.
+fn readahead_path(path: &Path) -> Option<(String, PathBuf)> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let meta = path.ancestors().find_map(|path| fs::metadata(path).ok())?;
+        let dev = meta.dev();
+        let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+        let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+        // virtual filesystems such as overlayfs have no block device
+        if major == 0 {
+            return None;
+        }
+        let block = fs::canonicalize(format!("/sys/dev/block/{}:{}", major, minor)).ok()?;
+        // a partition has no queue of its own, but its disk does
+        let disk = if block.join("partition").exists() {
+            block.parent()?.to_owned()
+        } else {
+            block
+        };
+        let name = disk.file_name()?.to_string_lossy().into_owned();
+        let readahead = Path::new("/sys/block")
+            .join(&name)
+            .join("queue")
+            .join("read_ahead_kb");
+        if readahead.is_file() {
+            return Some((name, readahead));
+        }
+    }
+    let _ = path;
+    None
+}
+
+fn parse_number(value: &str) -> Option<u64> {
+    match value {
+        "unlimited" => Some(u64::MAX),
+        value => value.parse().ok(),
+    }
+}
+
+fn format_number(value: u64) -> String {
+    match value {
+        u64::MAX => "unlimited".to_owned(),
+        value => value.to_string(),
+    }
+}
+
+fn write_file(path: &str, content: &str) -> Result<()> {
+    let path = Path::new(path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| tr!("sys.write-failed", path.display()))?;
+    }
+    fs::write(path, content).with_context(|| tr!("sys.write-failed", path.display()))
+}
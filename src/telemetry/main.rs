@@ -0,0 +1,82 @@
+use anyhow::{bail, Result};
+use clap::{AppSettings, Clap};
+
+use super::InstallReport;
+use crate::{config, log_i18n, tr, Com};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Clap, Debug)]
+enum SubCommand {
+    /// 显示是否发送匿名统计，以及发送的内容
+    /// Shows whether anonymous statistics are sent, and what is sent
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Status,
+
+    /// 同意在安装后发送匿名的成功/失败统计与平台信息
+    /// Opts in to sending anonymous success/failure counts and platform info after installs
+    #[clap(setting = AppSettings::ColoredHelp)]
+    On(OnArgs),
+
+    /// 停止发送匿名统计
+    /// Stops sending anonymous statistics
+    #[clap(setting = AppSettings::ColoredHelp)]
+    Off,
+}
+
+#[derive(Clap, Debug)]
+struct OnArgs {
+    /// 接收统计的 URL，以 JSON 格式 POST
+    /// URL the statistics are POSTed to as JSON
+    #[clap(long)]
+    endpoint: Option<String>,
+}
+
+impl Args {
+    /// Name of the operation, if the subcommand modifies the config.
+    pub fn operation(&self) -> Option<&'static str> {
+        match self.subcmd {
+            SubCommand::Status => None,
+            SubCommand::On(_) | SubCommand::Off => Some("telemetry"),
+        }
+    }
+}
+
+pub async fn main(args: Args) -> Result<()> {
+    let mut config = config::load_config().await?;
+    let profile = &mut config.profile;
+    match args.subcmd {
+        SubCommand::Status => {
+            match (&profile.telemetry, &profile.telemetry_endpoint) {
+                (true, Some(endpoint)) => log_i18n!(info, "telemetry.enabled", endpoint),
+                _ => log_i18n!(info, "telemetry.disabled"),
+            }
+            let sample = InstallReport::new(&[Com::NodeJS, Com::MongoDB], &[Com::MongoDB]);
+            log_i18n!(info, "telemetry.sample");
+            println!("{}", serde_json::to_string_pretty(&sample)?);
+            return Ok(());
+        }
+        SubCommand::On(args) => {
+            if let Some(endpoint) = args.endpoint {
+                profile.telemetry_endpoint = Some(endpoint);
+            }
+            let endpoint = match &profile.telemetry_endpoint {
+                Some(endpoint) => endpoint.clone(),
+                None => bail!(tr!("telemetry.no-endpoint")),
+            };
+            profile.telemetry = true;
+            log_i18n!(info, "telemetry.enabled", endpoint);
+        }
+        SubCommand::Off => {
+            profile.telemetry = false;
+            log_i18n!(info, "telemetry.disabled");
+        }
+    }
+    config::save_config(&config).await?;
+    Ok(())
+}
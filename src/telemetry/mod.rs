@@ -0,0 +1,5 @@
+mod main;
+mod report;
+
+pub use main::*;
+pub use report::*;
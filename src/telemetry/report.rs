@@ -0,0 +1,67 @@
+use serde::Serialize;
+use std::{collections::BTreeMap, time::Duration};
+
+use crate::{config::Profile, net, platform, profiling, Com};
+
+/// Anonymous statistics of an installation, sent only if the admin opts in.
+///
+/// Nothing identifying the host or the admin, such as paths, addresses, the domain or tokens, is
+/// included, and the same report is shown by `h2o2 telemetry status`.
+#[derive(Serialize, Debug)]
+pub struct InstallReport {
+    pub h2o2: &'static str,
+    pub os: String,
+    pub arch: String,
+    pub package_manager: Option<String>,
+    pub service_manager: String,
+    pub container: Option<String>,
+    pub wsl: bool,
+    /// components attempted, and whether each is installed
+    pub components: BTreeMap<&'static str, bool>,
+}
+
+impl InstallReport {
+    pub fn new(attempted: &[Com], failed: &[Com]) -> Self {
+        let current = platform::current();
+        Self {
+            h2o2: env!("CARGO_PKG_VERSION"),
+            os: current.os.to_string(),
+            arch: current.arch.to_string(),
+            package_manager: current.package_manager.map(|pm| pm.to_string()),
+            service_manager: current.service_manager.to_string(),
+            container: current.container.map(|c| c.to_string()),
+            wsl: current.wsl,
+            components: attempted
+                .iter()
+                .map(|com| (com.name(), !failed.contains(com)))
+                .collect(),
+        }
+    }
+}
+
+/// Sends `report` if telemetry is enabled in `profile`. Failures are only logged in the log
+/// file, as the statistics are never worth bothering the admin.
+pub async fn send(profile: &Profile, report: &InstallReport) {
+    let endpoint = match (&profile.telemetry, &profile.telemetry_endpoint) {
+        (true, Some(endpoint)) => endpoint,
+        _ => return,
+    };
+    if let Err(e) = post(endpoint, report).await {
+        tracing::debug!("Failed to send the statistics: {}", e);
+    }
+}
+
+async fn post(url: &str, report: &InstallReport) -> reqwest::Result<()> {
+    let _span = profiling::span("main", url);
+    let body = serde_json::to_string(report).expect("The report is serializable");
+    net::client_builder()
+        .timeout(Duration::from_secs(5))
+        .build()?
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
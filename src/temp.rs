@@ -0,0 +1,93 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, SystemTime},
+};
+use tempfile::TempDir;
+
+use crate::platform;
+
+const PREFIX: &str = ".h2o2-";
+
+/// Temp dirs whose owner cannot be determined are considered stale after this.
+const STALE_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Creates every temp dir used by H2O2 under a single base directory.
+///
+/// Each temp dir is named `.h2o2-<pid>-<random>` and is removed when the returned
+/// [`TempDir`] is dropped. Dirs left behind by crashed runs are removed by [`TempManager::new`].
+#[derive(Debug)]
+pub struct TempManager {
+    base: PathBuf,
+}
+
+impl TempManager {
+    /// Creates a manager using `base`, or the system temp dir if `None`.
+    pub fn new(base: Option<impl AsRef<Path>>) -> io::Result<Self> {
+        let base = match base {
+            Some(base) => base.as_ref().to_path_buf(),
+            None => std::env::temp_dir(),
+        };
+        fs::create_dir_all(&base)?;
+
+        let manager = Self { base };
+        manager.remove_stale();
+        Ok(manager)
+    }
+
+    pub fn base(&self) -> &Path {
+        &self.base
+    }
+
+    pub fn tempdir(&self) -> io::Result<TempDir> {
+        tempfile::Builder::new()
+            .prefix(&format!("{}{}-", PREFIX, process::id()))
+            .tempdir_in(&self.base)
+    }
+
+    fn remove_stale(&self) {
+        let entries = match fs::read_dir(&self.base) {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::debug!("Failed to read temp dir {}: {}", self.base.display(), e);
+                return;
+            }
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if !name.starts_with(PREFIX) || !path.is_dir() || !is_stale(&name, &path) {
+                continue;
+            }
+            match fs::remove_dir_all(&path) {
+                Ok(_) => tracing::debug!("Removed stale temp dir {}", path.display()),
+                Err(e) => {
+                    tracing::debug!("Failed to remove stale temp dir {}: {}", path.display(), e)
+                }
+            }
+        }
+    }
+}
+
+fn is_stale(name: &str, path: &Path) -> bool {
+    // name: .h2o2-{pid}-{random}
+    let pid = name[PREFIX.len()..]
+        .split('-')
+        .next()
+        .and_then(|pid| pid.parse::<u32>().ok());
+
+    match pid.map(|pid| (pid, platform::process_alive(pid))) {
+        Some((pid, _)) if pid == process::id() => false,
+        Some((_, Some(alive))) => !alive,
+        _ => {
+            let age = fs::metadata(path)
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|time| SystemTime::now().duration_since(time).ok());
+            matches!(age, Some(age) if age > STALE_AGE)
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use anyhow::{bail, Context, Result};
+use clap::Clap;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    audit,
+    config::{self, ComponentInfo, Config},
+    install::helper::{corepack, hydro, jspm, minio, mongodb, nodejs, pm2, sandbox},
+    log_i18n, platform, tr,
+    utils::decode_output,
+    Com,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    /// 要卸载的组件
+    /// Components to uninstall
+    #[clap(required = true)]
+    components: Vec<Com>,
+
+    /// 同时删除数据（MongoDB 数据库、MinIO 中的文件与 Hydro 配置），不可恢复
+    /// Removes the data as well (MongoDB databases, files in MinIO and Hydro config), which cannot be undone
+    #[clap(long)]
+    purge: bool,
+}
+
+/// Files kept in the MongoDB dir unless purging.
+const MONGODB_DATA: &[&str] = &["data", "tls", "mongod.conf"];
+
+pub async fn main(args: Args) -> Result<()> {
+    let mut config = config::load_config().await?;
+
+    // dependents first, as removing Hydro requires Yarn
+    let mut targets = Com::ALL
+        .iter()
+        .rev()
+        .copied()
+        .filter(|com| args.components.contains(com))
+        .collect::<Vec<_>>();
+    targets.retain(|&com| {
+        let installed = config.components.borrow_by_com(com).is_installed();
+        if !installed {
+            log_i18n!(info, "uninstall.not-installed", com);
+        }
+        installed
+    });
+    for &com in &targets {
+        let left = com
+            .dependents()
+            .into_iter()
+            .filter(|dep| {
+                !targets.contains(dep) && config.components.borrow_by_com(*dep).is_installed()
+            })
+            .map(|dep| dep.to_string())
+            .collect::<Vec<_>>();
+        if !left.is_empty() {
+            bail!(tr!("uninstall.required-by", com, left.join(", ")));
+        }
+        if let Some(process) = process_name(com) {
+            if let Some(pids) = platform::find_processes(process).filter(|p| !p.is_empty()) {
+                let pids = pids.iter().map(u32::to_string).collect::<Vec<_>>();
+                bail!(tr!("uninstall.running", com, pids.join(", ")));
+            }
+        }
+    }
+
+    for com in targets {
+        let removed =
+            uninstall(com, &config, args.purge).with_context(|| tr!("uninstall.failed", com))?;
+        let removed = match removed {
+            Some(removed) => removed,
+            None => continue,
+        };
+        *config.components.borrow_mut_by_com(com) = ComponentInfo::default();
+        if com == Com::MinIO {
+            config.components.extras.remove("mc");
+        }
+        if com == Com::Yarn {
+            config.profile.yarn.pinned = None;
+        }
+        // save after each, so that a later failure does not lose the records
+        config::save_config(&config).await?;
+
+        let details = serde_json::json!({ "purge": args.purge, "removed": removed });
+        let entry = audit::Entry::new("uninstall", Some(com.name().to_owned()), details);
+        if let Err(e) = audit::append(&entry) {
+            tracing::debug!("Failed to write the audit log: {}", e);
+        }
+        log_i18n!(info, "uninstall.done", com);
+    }
+    Ok(())
+}
+
+/// Name of the process of `com` which must be stopped before removing it.
+fn process_name(com: Com) -> Option<&'static str> {
+    match com {
+        Com::MongoDB => Some("mongod"),
+        Com::MinIO => Some("minio"),
+        Com::Sandbox => Some("sandbox"),
+        _ => None,
+    }
+}
+
+/// Removes the files of `com`, and its data if `purge`. Returns the paths removed, or `None` if
+/// it is not installed by H2O2 and left untouched.
+fn uninstall(com: Com, config: &Config, purge: bool) -> Result<Option<Vec<PathBuf>>> {
+    let info = config.components.borrow_by_com(com);
+    let path = info.path.as_deref().unwrap_or_default();
+    let managed = match com {
+        // recorded as the global dir of the package manager
+        Com::Hydro => info.path.is_some(),
+        // installed by npm, recorded by the absolute path of the shim
+        Com::Yarn if config.profile.yarn.pinned.is_some() => true,
+        Com::Yarn | Com::PM2 => Path::new(path).is_absolute(),
+        _ => is_managed(path),
+    };
+    if !managed {
+        log_i18n!(warn, "uninstall.not-managed", com, path);
+        return Ok(None);
+    }
+
+    let com_path = config::get_com_path();
+    let mut paths = Vec::new();
+    let mut data = Vec::new();
+    match com {
+        Com::NodeJS => {
+            paths.push(com_path.join("nodejs"));
+            if nodejs::remove_from_profile(path)? {
+                log_i18n!(info, "uninstall.profile-updated", path);
+            }
+        }
+        Com::MongoDB => {
+            let entries = match fs::read_dir(mongodb::get_mongodb_path()) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Some(Vec::new())),
+                Err(e) => return Err(e.into()),
+            };
+            for entry in entries {
+                let entry = entry?;
+                if MONGODB_DATA.iter().any(|name| entry.file_name() == **name) {
+                    data.push(entry.path());
+                } else {
+                    paths.push(entry.path());
+                }
+            }
+        }
+        Com::MinIO => {
+            paths.push(PathBuf::from(path));
+            paths.push(minio::mc_path());
+            paths.push(minio::env_path());
+            data.push(config.profile.minio.data_dir());
+        }
+        Com::Sandbox => paths.push(sandbox::env_path().parent().unwrap().to_owned()),
+        Com::Yarn if config.profile.yarn.pinned.is_some() => {
+            corepack::disable_yarn(&config.components.nodejs)
+                .with_context(|| tr!("uninstall.failed", com))?;
+        }
+        Com::Yarn | Com::PM2 => {
+            let package = if com == Com::Yarn { "yarn" } else { "pm2" };
+            let npm = config.components.nodejs.path("npm");
+            run(com, duct::cmd!(npm, "uninstall", "--global", package))?;
+        }
+        Com::Hydro => {
+            let pm = jspm::from_config(config);
+            run(
+                com,
+                duct::cmd(pm.program(), pm.remove_global(hydro::PACKAGE)),
+            )?;
+            paths.push(pm2::ecosystem_path());
+            data.push(hydro::get_hydro_path());
+        }
+    }
+
+    if purge {
+        paths.append(&mut data);
+    } else {
+        for dir in data.iter().filter(|dir| dir.exists()) {
+            log_i18n!(info, "uninstall.data-kept", com, dir.display());
+        }
+    }
+    let mut removed = Vec::new();
+    for path in paths {
+        if remove(&path).with_context(|| tr!("uninstall.remove-failed", path.display()))? {
+            log_i18n!(info, "uninstall.removed", path.display());
+            removed.push(path);
+        }
+    }
+    // remove the dir of MongoDB or MinIO if nothing is left
+    if purge && matches!(com, Com::MongoDB | Com::MinIO) {
+        let dir = match com {
+            Com::MongoDB => mongodb::get_mongodb_path(),
+            _ => minio::env_path().parent().unwrap().to_owned(),
+        };
+        let _ = fs::remove_dir(dir);
+    }
+    Ok(Some(removed))
+}
+
+/// Whether `path` is under the directory H2O2 installs components into.
+fn is_managed(path: &str) -> bool {
+    !path.is_empty() && Path::new(path).starts_with(config::get_com_path())
+}
+
+/// Removes a file or a directory. Returns `false` if it does not exist.
+fn remove(path: &Path) -> io::Result<bool> {
+    let res = match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(e) => Err(e),
+    };
+    match res {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn run(com: Com, expr: duct::Expression) -> Result<()> {
+    let output = expr
+        .stdout_capture()
+        .stderr_capture()
+        .unchecked()
+        .run()
+        .with_context(|| tr!("uninstall.failed", com))?;
+    if !output.status.success() {
+        bail!(
+            "{}\n{}",
+            tr!("uninstall.failed", com),
+            decode_output(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
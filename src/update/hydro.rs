@@ -0,0 +1,199 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    collections::VecDeque,
+    env,
+    ffi::OsString,
+    io::{BufRead, BufReader},
+    path::Path,
+    process::ExitStatus,
+};
+
+use super::Step;
+use crate::{
+    config::{ComponentInfo, Config, RestartMode, Version},
+    health,
+    install::helper::{hydro, jspm, pm2},
+    log_i18n, tr,
+    utils::decode_output,
+    Com,
+};
+
+/// Lines of output kept to explain a failure.
+const TAIL_LINES: usize = 20;
+
+/// Output of Node.js or the build tools when running out of memory.
+const OOM_PATTERNS: &[&str] = &[
+    "JavaScript heap out of memory",
+    "Reached heap limit",
+    "Cannot allocate memory",
+    "ENOMEM",
+    "SIGKILL",
+];
+
+/// Plans the update of Hydro to the `to` dist-tag or version, defaulting to the configured
+/// channel. The UI is rebuilt before restarting, as the new version may ship a different one.
+pub fn plan(config: &Config, to: Option<&str>) -> Result<Vec<Step>> {
+    if config.components.hydro.path.is_none() {
+        bail!(tr!("update.version-unknown", Com::Hydro));
+    }
+    let to = to.unwrap_or_else(|| config.profile.hydro.channel.dist_tag());
+    let mut steps = vec![Step::Install {
+        component: Com::Hydro.name().to_owned(),
+        series: to.to_owned(),
+    }];
+    steps.extend(rebuild_plan(config));
+    Ok(steps)
+}
+
+/// Plans rebuilding the UI alone, e.g. after adding addons. Hydro is restarted through PM2 if
+/// it is installed, or by the admin otherwise.
+pub fn rebuild_plan(config: &Config) -> Vec<Step> {
+    vec![Step::BuildUi, restart_step(config)]
+}
+
+/// Restarts Hydro through PM2 if it is installed, or asks the admin to otherwise.
+pub fn restart_step(config: &Config) -> Step {
+    if config.components.pm2.is_installed() {
+        Step::ReloadHydro {
+            mode: config.profile.hydro.restart_mode,
+        }
+    } else {
+        Step::Restart {
+            component: Com::Hydro.name().to_owned(),
+        }
+    }
+}
+
+/// Applies the ecosystem file to the Hydro processes, reloading or restarting them with `mode`,
+/// then waits for them to become healthy.
+pub async fn reload(config: &Config, mode: RestartMode) -> Result<()> {
+    let ecosystem = pm2::write_ecosystem(
+        &config.components.hydro,
+        &config.profile.hydro,
+        config.profile.kill_after(),
+    )
+    .context(tr!("update.reload-failed"))?;
+    let program = config.components.pm2.path_or("pm2");
+    let mut apps = vec![(
+        pm2::HYDRO_APP,
+        health::Service::of(config, Com::Hydro).expect("Hydro is a service"),
+    )];
+    if config.profile.hydro.judge_workers > 0 {
+        apps.push((pm2::JUDGE_APP, health::Service::judge()));
+    }
+    for (app, _) in &apps {
+        log_i18n!(info, "update.reloading", app, mode);
+        pm2::apply(program, &ecosystem, app, mode).context(tr!("update.reload-failed"))?;
+    }
+    for (_, service) in &apps {
+        health::wait_healthy(config, service).await?;
+    }
+    Ok(())
+}
+
+/// Upgrades the global package of Hydro to `to`, from `registry` if it is given.
+pub async fn install(config: &Config, to: &str, registry: Option<&str>) -> Result<ComponentInfo> {
+    global_dir(config)?;
+    let spec = format!("{}@{}", hydro::PACKAGE, to);
+    let pm = jspm::from_config(config);
+    let output = jspm::run(
+        pm.as_ref(),
+        Com::Hydro,
+        &pm.upgrade_global(hydro::PACKAGE, to),
+        registry,
+    )
+    .await
+    .with_context(|| tr!("update.hydro-install-failed", &spec))?;
+    if !output.status.success() {
+        bail!(
+            "{}\n{}",
+            tr!("update.hydro-install-failed", &spec),
+            output.snippet(TAIL_LINES)
+        );
+    }
+
+    // the global dir moves if the package manager is switched in the profile
+    let global_dir = pm
+        .global_dir()
+        .with_context(|| tr!("update.hydro-install-failed", &spec))?;
+    let version = hydro::installed_version(Path::new(&global_dir))
+        .with_context(|| tr!("update.hydro-install-failed", &spec))?;
+    log_i18n!(info, [Com::Hydro], "update.hydro-installed", &version);
+    Ok(ComponentInfo::new(
+        Version::Valid(version),
+        Some(global_dir),
+    ))
+}
+
+/// Builds the UI with the managed Node.js, streaming the output of the build.
+pub fn build_ui(config: &Config) -> Result<()> {
+    let ui_dir = hydro::ui_dir(Path::new(global_dir(config)?));
+    let script = hydro::ui_build_script(&ui_dir)
+        .with_context(|| tr!("update.ui-not-found", ui_dir.display()))?;
+
+    log_i18n!(info, [Com::Hydro], "update.ui-building");
+    let pm = jspm::from_config(config);
+    let mut expr = duct::cmd(pm.program(), pm.run_script(script))
+        .dir(&ui_dir)
+        .stderr_to_stdout()
+        .unchecked();
+    if let Some(path) = node_path(&config.components.nodejs) {
+        expr = expr.env("PATH", path);
+    }
+    let reader = expr.reader().context(tr!("update.ui-build-failed"))?;
+
+    let mut tail = VecDeque::with_capacity(TAIL_LINES);
+    for line in BufReader::new(&reader).split(b'\n') {
+        let line = decode_output(&line.context(tr!("update.ui-build-failed"))?);
+        let line = line.trim_end();
+        tracing::info!("[{}] {}", Com::Hydro, line);
+        if tail.len() == TAIL_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(line.to_owned());
+    }
+    let status = reader
+        .try_wait()
+        .context(tr!("update.ui-build-failed"))?
+        .expect("the output is read to the end")
+        .status;
+
+    if status.success() {
+        log_i18n!(info, [Com::Hydro], "update.ui-built");
+        return Ok(());
+    }
+    if is_out_of_memory(&tail, status) {
+        log_i18n!(error, [Com::Hydro], "update.ui-build-oom");
+    }
+    bail!(tr!("update.ui-build-failed"))
+}
+
+fn global_dir(config: &Config) -> Result<&str> {
+    match config.components.hydro.path.as_deref() {
+        Some(path) => Ok(path),
+        None => bail!(tr!("update.version-unknown", Com::Hydro)),
+    }
+}
+
+/// `PATH` with the managed Node.js in front, so that build scripts run with it.
+fn node_path(nodejs: &ComponentInfo) -> Option<OsString> {
+    let dir = nodejs.path.as_deref()?;
+    let paths = env::var_os("PATH").unwrap_or_default();
+    env::join_paths(std::iter::once(dir.into()).chain(env::split_paths(&paths))).ok()
+}
+
+fn is_out_of_memory(tail: &VecDeque<String>, status: ExitStatus) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        // the OOM killer sends SIGKILL
+        if status.signal() == Some(9) {
+            return true;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = status;
+    tail.iter()
+        .any(|line| OOM_PATTERNS.iter().any(|pattern| line.contains(pattern)))
+}
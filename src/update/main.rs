@@ -0,0 +1,280 @@
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Clap;
+use std::str::FromStr;
+use url::Url;
+
+use super::{hydro, mongodb, Plan, Step};
+use crate::{
+    config::{self, Config},
+    health,
+    hydro::maintenance,
+    install::{
+        self,
+        helper::{nodejs, npm},
+    },
+    log_i18n, tr, Com,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    /// 要更新的组件，默认为全部已安装的组件
+    /// Components to update, defaults to all installed
+    components: Vec<Com>,
+
+    /// 目标版本系列，如 `6.0`，默认为最新；仅可用于单个组件
+    /// Target release series, e.g. `6.0`, defaults to the latest; for a single component only
+    #[clap(long)]
+    to: Option<String>,
+
+    /// 安装 Hydro 使用的 npm 源，如 `npmmirror` 或其地址，默认为已配置的源
+    /// npm registry Hydro is installed from, e.g. `npmmirror` or its URL, defaults to the configured one
+    #[clap(long)]
+    registry: Option<String>,
+
+    /// 仅重新构建 Hydro 前端，如添加插件后
+    /// Only rebuilds the Hydro UI, e.g. after adding addons
+    #[clap(long, conflicts_with_all = &["components", "to"])]
+    build_ui: bool,
+
+    /// 继续执行中断的更新计划
+    /// Resumes the interrupted update plan
+    #[clap(long = "continue")]
+    resume: bool,
+
+    /// 放弃中断的更新计划
+    /// Discards the interrupted update plan
+    #[clap(long, conflicts_with = "resume")]
+    abort: bool,
+
+    /// 更新期间不开启维护模式
+    /// Keeps maintenance mode off during the update
+    #[clap(long)]
+    no_maintenance: bool,
+}
+
+pub async fn main(args: Args) -> Result<()> {
+    if args.abort {
+        let pending = Plan::load().context(tr!("update.plan-io-failed"))?;
+        if pending.is_some_and(|plan| plan.maintenance) {
+            let mut config = config::load_config().await?;
+            maintenance::set(&mut config, false).await?;
+        }
+        Plan::remove().context(tr!("update.plan-io-failed"))?;
+        log_i18n!(info, "update.plan-aborted");
+        return Ok(());
+    }
+
+    let mut config = config::load_config().await?;
+    let pending = Plan::load().context(tr!("update.plan-io-failed"))?;
+    let mut plan = match (pending, args.resume) {
+        (Some(plan), true) => plan,
+        (None, true) => bail!(tr!("update.no-plan")),
+        (Some(plan), false) if !plan.is_finished() => bail!(tr!("update.plan-pending")),
+        (_, false) if args.build_ui => {
+            let plan = Plan::new(hydro::rebuild_plan(&config));
+            plan.save().context(tr!("update.plan-io-failed"))?;
+            plan
+        }
+        (_, false) => {
+            if args.to.is_some() && args.components.len() != 1 {
+                bail!(tr!("update.to-single"));
+            }
+            let registry = args.registry.as_deref().map(registry_url).transpose()?;
+            let mut plan = Plan::new(plan_components(&config, &args).await?);
+            if plan.steps.is_empty() {
+                log_i18n!(info, "update.nothing");
+                return Ok(());
+            }
+            plan.registry = registry;
+            plan.save().context(tr!("update.plan-io-failed"))?;
+            plan
+        }
+    };
+
+    log_i18n!(info, "update.plan");
+    for (i, step) in plan.steps.iter().enumerate() {
+        let mark = if i < plan.next { "x" } else { " " };
+        tracing::info!("  [{}] {}", mark, step);
+    }
+
+    // let visitors know rather than showing them errors, unless the admin has turned it on
+    let maintain = !args.no_maintenance
+        && plan.next == 0
+        && plan.takes_hydro_down()
+        && !config.profile.caddy.maintenance
+        && maintenance::caddy_program(&config).is_some();
+    if maintain {
+        maintenance::set(&mut config, true).await?;
+        plan.maintenance = true;
+        plan.save().context(tr!("update.plan-io-failed"))?;
+    }
+
+    run(&mut plan, &mut config).await
+}
+
+/// Plans the update of the components given, or of all installed, in the order of their
+/// dependencies.
+async fn plan_components(config: &Config, args: &Args) -> Result<Vec<Step>> {
+    let components = Com::ALL
+        .iter()
+        .copied()
+        .filter(|com| match args.components.is_empty() {
+            true => config.components.borrow_by_com(*com).is_installed(),
+            false => args.components.contains(com),
+        })
+        .collect::<Vec<_>>();
+    let mut steps = Vec::new();
+    for &com in &components {
+        if !config.components.borrow_by_com(com).is_installed() {
+            bail!(tr!("check.not-installed", com));
+        }
+        steps.extend(make_steps(config, com, args.to.as_deref()).await?);
+    }
+    // Hydro runs on Node.js, so it is restarted unless it is being updated anyway
+    let hydro = &config.components.hydro;
+    if components.contains(&Com::NodeJS)
+        && !components.contains(&Com::Hydro)
+        && hydro.is_installed()
+    {
+        steps.push(hydro::restart_step(config));
+    }
+    Ok(steps)
+}
+
+async fn make_steps(config: &Config, com: Com, to: Option<&str>) -> Result<Vec<Step>> {
+    let component = com.name().to_owned();
+    match com {
+        Com::MongoDB => mongodb::plan(config, to),
+        Com::Hydro => hydro::plan(config, to),
+        Com::NodeJS => {
+            let pinned = install::pinned_version(com, config.profile.nodejs.version.as_deref());
+            // a release pinned is installed even if older, e.g. after rolling back
+            let (target, exact) = match pinned {
+                Some(pinned) => (pinned, true),
+                None => {
+                    let latest = nodejs::latest_lts(config.profile.nodejs.lts.as_deref())
+                        .await
+                        .map_err(|e| anyhow!(tr!("install.nodejs-index-failed", e)))?;
+                    (latest, false)
+                }
+            };
+            let current = |version: &semver::Version| match exact {
+                true => *version == target,
+                false => *version >= target,
+            };
+            match config.components.nodejs.version() {
+                Some(version) if current(version) => {
+                    log_i18n!(info, "update.already-latest", com, version);
+                    Ok(Vec::new())
+                }
+                _ => Ok(vec![Step::Upgrade { component }]),
+            }
+        }
+        Com::Yarn | Com::PM2 => Ok(vec![Step::Upgrade { component }]),
+        // the running binaries are replaced, which take effect once restarted
+        Com::MinIO | Com::Sandbox => Ok(vec![
+            Step::Upgrade {
+                component: component.clone(),
+            },
+            Step::Restart { component },
+        ]),
+    }
+}
+
+/// The URL of the npm registry `name`, which is either `npmjs`, `npmmirror` or a URL.
+fn registry_url(name: &str) -> Result<String> {
+    match name {
+        "npmjs" => Ok(npm::NPMJS_REGISTRY.to_owned()),
+        "npmmirror" => Ok(npm::NPMMIRROR_REGISTRY.to_owned()),
+        url => match Url::parse(url) {
+            Ok(url) if url.scheme().starts_with("http") => Ok(url.to_string()),
+            _ => bail!(tr!("update.invalid-registry", name)),
+        },
+    }
+}
+
+/// Runs the plan from its first unfinished step, saving progress after each step.
+async fn run(plan: &mut Plan, config: &mut Config) -> Result<()> {
+    // resuming after the admin restarts a component, which must work before going on
+    if let Some(Step::Restart { component }) = plan.next.checked_sub(1).map(|i| &plan.steps[i]) {
+        let service = Com::from_str(component)
+            .ok()
+            .and_then(|com| health::Service::of(config, com));
+        if let Some(service) = service {
+            health::wait_healthy(config, &service).await?;
+        }
+    }
+
+    let total = plan.steps.len();
+    while !plan.is_finished() {
+        let step = plan.steps[plan.next].clone();
+        tracing::info!("[{}/{}] {}", plan.next + 1, total, step);
+        match &step {
+            Step::Restart { component } => {
+                plan.next += 1;
+                plan.save().context(tr!("update.plan-io-failed"))?;
+                log_i18n!(warn, "update.restart-required", component);
+                return Ok(());
+            }
+            step => {
+                if let Err(e) = run_step(step, config, plan.registry.as_deref()).await {
+                    log_i18n!(error, "update.step-failed");
+                    if plan.maintenance {
+                        log_i18n!(warn, "update.maintenance-kept");
+                    }
+                    return Err(e);
+                }
+                plan.next += 1;
+                plan.save().context(tr!("update.plan-io-failed"))?;
+            }
+        }
+    }
+    if plan.maintenance {
+        maintenance::set(config, false).await?;
+    }
+    Plan::remove().context(tr!("update.plan-io-failed"))?;
+    log_i18n!(info, "update.finished");
+    Ok(())
+}
+
+async fn run_step(step: &Step, config: &mut Config, registry: Option<&str>) -> Result<()> {
+    match step {
+        Step::Install { component, series } => {
+            let com = Com::from_str(component).map_err(anyhow::Error::msg)?;
+            let opts = install::Options::from_config(config).context(tr!("sys.temp-dir-failed"))?;
+            let info = match com {
+                Com::Hydro => hydro::install(config, series, registry).await?,
+                Com::MongoDB => {
+                    mongodb::ensure_installable(config, series)?;
+                    install::install_series(com, series, &opts).await?
+                }
+                _ => install::install_series(com, series, &opts).await?,
+            };
+            *config.components.borrow_mut_by_com(com) = info;
+            config::save_config(config).await?;
+        }
+        Step::Upgrade { component } => {
+            let com = Com::from_str(component).map_err(anyhow::Error::msg)?;
+            let opts = install::Options::from_config(config).context(tr!("sys.temp-dir-failed"))?;
+            let info = install::upgrade(com, &config.components, &opts).await?;
+            log_i18n!(info, "update.upgraded", com, &info.version);
+            *config.components.borrow_mut_by_com(com) = info;
+            if let Some(pinned) = opts.yarn_pinned.lock().unwrap().take() {
+                config.profile.yarn.pinned = Some(pinned);
+            }
+            config::save_config(config).await?;
+        }
+        Step::BuildUi => hydro::build_ui(config)?,
+        Step::ReloadHydro { mode } => hydro::reload(config, *mode).await?,
+        Step::CheckHealth { component } => match Com::from_str(component) {
+            Ok(Com::MongoDB) => mongodb::check_health(config)?,
+            _ => bail!(tr!("update.not-supported", component)),
+        },
+        Step::SetFeatureCompatibility { series } => {
+            mongodb::set_feature_compatibility(config, series)?
+        }
+        Step::Restart { .. } => unreachable!(),
+    }
+    Ok(())
+}
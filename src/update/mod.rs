@@ -0,0 +1,7 @@
+mod hydro;
+mod main;
+mod mongodb;
+mod plan;
+
+pub use main::*;
+pub use plan::*;
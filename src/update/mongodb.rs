@@ -0,0 +1,101 @@
+use anyhow::{bail, Context, Result};
+
+use super::Step;
+use crate::{
+    config::{Config, Version},
+    install::helper::mongodb,
+    log_i18n, tr, Com,
+};
+
+/// Plans the upgrade to the `to` series, going through every release series in between. Nothing
+/// is planned if it is already on the series.
+///
+/// The featureCompatibilityVersion must match the running series before the binaries of the
+/// next series are installed, so it is raised after each restart.
+pub fn plan(config: &Config, to: Option<&str>) -> Result<Vec<Step>> {
+    let installed = match &config.components.mongodb.version {
+        Version::Valid(version) => version,
+        _ => bail!(tr!("update.version-unknown", Com::MongoDB)),
+    };
+    let to = to.unwrap_or_else(|| mongodb::RELEASE_SERIES.last().unwrap());
+    let path = match mongodb::upgrade_path(installed, to) {
+        Some(path) if !path.is_empty() => path,
+        Some(_) => {
+            log_i18n!(info, "update.up-to-date", Com::MongoDB, to);
+            return Ok(Vec::new());
+        }
+        None => bail!(tr!(
+            "update.invalid-target",
+            mongodb::series_of(installed),
+            to,
+            mongodb::RELEASE_SERIES.join(", ")
+        )),
+    };
+
+    let component = Com::MongoDB.name().to_owned();
+    let mut steps = Vec::new();
+    let mut current = mongodb::series_of(installed);
+    for series in path {
+        steps.push(Step::CheckHealth {
+            component: component.clone(),
+        });
+        steps.push(Step::SetFeatureCompatibility { series: current });
+        steps.push(Step::Install {
+            component: component.clone(),
+            series: series.to_string(),
+        });
+        steps.push(Step::Restart {
+            component: component.clone(),
+        });
+        current = series.to_string();
+    }
+    steps.push(Step::CheckHealth {
+        component: component.clone(),
+    });
+    steps.push(Step::SetFeatureCompatibility { series: current });
+    Ok(steps)
+}
+
+pub fn check_health(config: &Config) -> Result<()> {
+    let problem = mongodb::check_health(&config.components.mongodb, &config.profile.mongodb)
+        .context(tr!("update.mongodb-unreachable"))?;
+    if let Some(problem) = problem {
+        bail!(tr!("update.mongodb-unhealthy", problem));
+    }
+    Ok(())
+}
+
+pub fn set_feature_compatibility(config: &Config, series: &str) -> Result<()> {
+    let (com, profile) = (&config.components.mongodb, &config.profile.mongodb);
+    let current = mongodb::feature_compatibility_version(com, profile)
+        .context(tr!("update.mongodb-unreachable"))?;
+    if current == series {
+        tracing::debug!("featureCompatibilityVersion is already {}", series);
+        return Ok(());
+    }
+    mongodb::set_feature_compatibility_version(com, profile, series)
+        .context(tr!("update.fcv-failed", series))
+}
+
+/// Refuses to install the `series` unless it directly follows the running series, and the
+/// featureCompatibilityVersion has been raised to the running series.
+pub fn ensure_installable(config: &Config, series: &str) -> Result<()> {
+    let (com, profile) = (&config.components.mongodb, &config.profile.mongodb);
+    let installed = match com.version() {
+        Some(version) => mongodb::series_of(version),
+        None => bail!(tr!("update.version-unknown", Com::MongoDB)),
+    };
+    let next = mongodb::RELEASE_SERIES
+        .iter()
+        .position(|s| *s == installed)
+        .and_then(|i| mongodb::RELEASE_SERIES.get(i + 1));
+    if next != Some(&series) {
+        bail!(tr!("update.series-skipped", installed, series));
+    }
+    let fcv = mongodb::feature_compatibility_version(com, profile)
+        .context(tr!("update.mongodb-unreachable"))?;
+    if fcv != installed {
+        bail!(tr!("update.fcv-mismatch", fcv, installed));
+    }
+    Ok(())
+}
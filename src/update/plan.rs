@@ -0,0 +1,127 @@
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::config::{self, RestartMode};
+
+/// A step of an update plan.
+#[derive(Serialize, Deserialize, Debug, Display, Clone, PartialEq, Eq)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum Step {
+    /// installs the `series` release of `component`
+    #[display(fmt = "install {} {}", component, series)]
+    Install { component: String, series: String },
+
+    /// installs the release of `component` H2O2 installs now over the current one
+    #[display(fmt = "upgrade {}", component)]
+    Upgrade { component: String },
+
+    /// waits for the admin to restart `component` (or reboot), then `h2o2 update --continue`
+    #[display(fmt = "restart {}", component)]
+    Restart { component: String },
+
+    /// restarts the Hydro processes through PM2 as set by `restart_mode` of the profile
+    #[display(fmt = "{} Hydro through PM2", mode)]
+    ReloadHydro { mode: RestartMode },
+
+    /// verifies that `component` is up and healthy
+    #[display(fmt = "check the health of {}", component)]
+    CheckHealth { component: String },
+
+    /// builds the Hydro UI, which is required after Hydro or its addons are updated
+    #[display(fmt = "build the Hydro UI")]
+    BuildUi,
+
+    /// sets the featureCompatibilityVersion of MongoDB to the `series`
+    #[display(fmt = "set featureCompatibilityVersion to {}", series)]
+    SetFeatureCompatibility { series: String },
+}
+
+/// A multi-step update persisted to disk, so that it can be resumed after a reboot or a failure.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Plan {
+    pub created: u64,
+    pub steps: Vec<Step>,
+    /// index of the first unfinished step
+    pub next: usize,
+    /// whether maintenance mode is turned on by the plan, to be turned off when it finishes
+    #[serde(default)]
+    pub maintenance: bool,
+    /// npm registry Hydro is installed from instead of the configured one
+    #[serde(default)]
+    pub registry: Option<String>,
+}
+
+impl Plan {
+    pub fn new(steps: Vec<Step>) -> Self {
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            created,
+            steps,
+            next: 0,
+            maintenance: false,
+            registry: None,
+        }
+    }
+
+    /// Loads the unfinished plan, if any.
+    pub fn load() -> io::Result<Option<Self>> {
+        let text = match fs::read_to_string(get_plan_path()) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        serde_json::from_str(&text)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Saves the plan, replacing the file atomically so that a crash never leaves it truncated.
+    pub fn save(&self) -> io::Result<()> {
+        let path = get_plan_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, text)?;
+        fs::rename(&tmp, &path)
+    }
+
+    pub fn remove() -> io::Result<()> {
+        match fs::remove_file(get_plan_path()) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.steps.len()
+    }
+
+    /// Whether Hydro is unavailable during the plan, which is not the case if it only rebuilds
+    /// the UI, as PM2 may reload Hydro without downtime.
+    pub fn takes_hydro_down(&self) -> bool {
+        self.steps.iter().any(|step| {
+            matches!(
+                step,
+                Step::Install { .. }
+                    | Step::Upgrade { .. }
+                    | Step::Restart { .. }
+                    | Step::SetFeatureCompatibility { .. }
+            )
+        })
+    }
+}
+
+pub fn get_plan_path() -> PathBuf {
+    config::get_com_path().join("update-plan.json")
+}
@@ -1,16 +1,143 @@
 use data_encoding::HEXLOWER;
-use ring::digest::{Context, Digest, SHA256};
+use ring::{
+    digest::{Context, Digest, SHA256},
+    rand::{SecureRandom, SystemRandom},
+};
 use std::{
-    fs::File,
-    io::{self, BufReader, Read},
-    path::Path,
-    process::Output,
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::{ExitStatus, Output},
+    sync::mpsc,
+    thread,
 };
 
+/// Only the first and the last this many bytes of a command output are logged.
+const CAPTURE_HEAD: usize = 8 * 1024;
+const CAPTURE_TAIL: usize = 8 * 1024;
+
+/// Decodes the output of a command, which is not necessarily UTF-8.
+///
+/// Console programs on Windows write in the ANSI code page, such as GBK on Chinese Windows.
+/// Invalid sequences are replaced rather than treated as errors.
+pub fn decode_output(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => s.to_owned(),
+        Err(_) => {
+            if crate::platform::current().is_windows() {
+                if let Some(s) =
+                    encoding_rs::GB18030.decode_without_bom_handling_and_without_replacement(bytes)
+                {
+                    return s.into_owned();
+                }
+            }
+            String::from_utf8_lossy(bytes).into_owned()
+        }
+    }
+}
+
 pub fn debug_output(output: &Output) {
-    log::debug!("{}", &output.status);
-    log::debug!("stdout:\n{}", String::from_utf8_lossy(&output.stdout));
-    log::debug!("stderr:\n{}", String::from_utf8_lossy(&output.stderr));
+    tracing::debug!("{}", &output.status);
+    tracing::debug!("stdout:\n{}", truncate_output(&output.stdout));
+    tracing::debug!("stderr:\n{}", truncate_output(&output.stderr));
+}
+
+/// Keeps the first and the last few KiB of a command output.
+pub fn truncate_output(bytes: &[u8]) -> String {
+    if bytes.len() <= CAPTURE_HEAD + CAPTURE_TAIL {
+        return decode_output(bytes);
+    }
+    format!(
+        "{}\n... ({} bytes truncated) ...\n{}",
+        decode_output(&bytes[..CAPTURE_HEAD]),
+        bytes.len() - CAPTURE_HEAD - CAPTURE_TAIL,
+        decode_output(&bytes[bytes.len() - CAPTURE_TAIL..]),
+    )
+}
+
+/// Output of a child process with stderr merged into stdout, of which only the first and
+/// the last few KiB are kept.
+#[derive(Debug)]
+pub struct CappedOutput {
+    pub status: ExitStatus,
+    head: Vec<u8>,
+    tail: VecDeque<u8>,
+    truncated: usize,
+}
+
+impl CappedOutput {
+    pub fn text(&self) -> String {
+        let tail = self.tail.iter().copied().collect::<Vec<_>>();
+        if self.truncated == 0 {
+            format!("{}{}", decode_output(&self.head), decode_output(&tail))
+        } else {
+            format!(
+                "{}\n... ({} bytes truncated) ...\n{}",
+                decode_output(&self.head),
+                self.truncated,
+                decode_output(&tail)
+            )
+        }
+    }
+
+    /// The last `lines` non-empty lines, which usually explain a failure.
+    pub fn snippet(&self, lines: usize) -> String {
+        let text = self.text();
+        let mut snippet = text
+            .lines()
+            .rev()
+            .filter(|line| !line.trim().is_empty())
+            .take(lines)
+            .collect::<Vec<_>>();
+        snippet.reverse();
+        snippet.join("\n")
+    }
+}
+
+/// Runs `expr` and streams its output, so that a verbose command never takes up much memory.
+///
+/// A non-zero exit status is not an error, check `status` instead.
+pub fn run_capped(expr: &duct::Expression) -> io::Result<CappedOutput> {
+    let mut reader = expr.stderr_to_stdout().unchecked().reader()?;
+    let mut head = Vec::with_capacity(CAPTURE_HEAD);
+    let mut tail = VecDeque::with_capacity(CAPTURE_TAIL);
+    let mut truncated = 0;
+    let mut buf = [0; 4096];
+    loop {
+        let count = reader.read(&mut buf)?;
+        if count == 0 {
+            break;
+        }
+        let mut chunk = &buf[..count];
+        if head.len() < CAPTURE_HEAD {
+            let n = chunk.len().min(CAPTURE_HEAD - head.len());
+            head.extend_from_slice(&chunk[..n]);
+            chunk = &chunk[n..];
+        }
+        for &byte in chunk {
+            if tail.len() == CAPTURE_TAIL {
+                tail.pop_front();
+                truncated += 1;
+            }
+            tail.push_back(byte);
+        }
+    }
+    let status = reader
+        .try_wait()?
+        .map(|output| output.status)
+        .ok_or_else(|| io::Error::other("child process has not exited"))?;
+    Ok(CappedOutput {
+        status,
+        head,
+        tail,
+        truncated,
+    })
+}
+
+pub fn debug_capped(output: &CappedOutput) {
+    tracing::debug!("{}", &output.status);
+    tracing::debug!("output:\n{}", output.text());
 }
 
 #[macro_export]
@@ -31,11 +158,11 @@ macro_rules! check_version {
     (nodejs, $version:expr, warn) => {{
         let req = $crate::version_req!(nodejs);
         if !req.matches($version) {
-            ::log::warn!(
-                "Hydro 需要 `Node.js {}`，当前版本可能无法正常工作。 \
-                Hydro requires `Node.js {}`, the current version may not work properly.",
-                &req,
-                &req,
+            $crate::log_i18n!(
+                warn,
+                "detect.version-unsupported",
+                $crate::Com::NodeJS,
+                &req
             );
             false
         } else {
@@ -45,11 +172,11 @@ macro_rules! check_version {
     (mongodb, $version:expr, warn) => {{
         let req = $crate::version_req!(mongodb);
         if !req.matches($version) {
-            ::log::warn!(
-                "Hydro 需要 `MongoDB {}`，当前版本可能无法正常工作。 \
-                Hydro requires `MongoDB {}`, the current version may not work properly.",
-                &req,
-                &req,
+            $crate::log_i18n!(
+                warn,
+                "detect.version-unsupported",
+                $crate::Com::MongoDB,
+                &req
             );
             false
         } else {
@@ -58,35 +185,204 @@ macro_rules! check_version {
     }};
 }
 
-#[macro_export]
-macro_rules! maybe_cmd {
-    ($cmd:expr) => {
-        if cfg!(windows) {
-            concat!($cmd, ".cmd")
-        } else {
-            $cmd
+/// Size of each chunk read while hashing.
+const HASH_CHUNK: usize = 1 << 20;
+
+/// Hashes `reader`, reading the next chunk on another thread while the current one is hashed,
+/// so that the disk and the CPU work at the same time.
+///
+/// SHA-256 itself cannot be split across threads, as each block depends on the previous one.
+fn sha256_digest<R: Read + Send>(mut reader: R) -> io::Result<Digest> {
+    let (tx, rx) = mpsc::sync_channel::<Vec<u8>>(2);
+    // chunks hashed are handed back to be read into again
+    let (recycle_tx, recycle_rx) = mpsc::channel::<Vec<u8>>();
+    thread::scope(|s| {
+        let reading = s.spawn(move || -> io::Result<()> {
+            loop {
+                let mut chunk = recycle_rx
+                    .try_recv()
+                    .unwrap_or_else(|_| Vec::with_capacity(HASH_CHUNK));
+                chunk.resize(HASH_CHUNK, 0);
+                let count = reader.read(&mut chunk)?;
+                if count == 0 {
+                    return Ok(());
+                }
+                chunk.truncate(count);
+                if tx.send(chunk).is_err() {
+                    return Ok(());
+                }
+            }
+        });
+
+        let mut context = Context::new(&SHA256);
+        for chunk in rx {
+            context.update(&chunk);
+            let _ = recycle_tx.send(chunk);
         }
+        reading.join().expect("the reading thread panicked")?;
+        Ok(context.finish())
+    })
+}
+
+pub fn sha256_file(file: impl AsRef<Path>) -> io::Result<String> {
+    let input = File::open(file)?;
+    let digest = sha256_digest(input)?;
+    Ok(HEXLOWER.encode(digest.as_ref()))
+}
+
+/// [`sha256_file`] on the blocking pool, so that the artifacts of several components are hashed
+/// on their own threads rather than holding up the runtime.
+pub async fn sha256_file_async(file: PathBuf) -> io::Result<String> {
+    tokio::task::spawn_blocking(move || sha256_file(file))
+        .await
+        .map_err(io::Error::other)?
+}
+
+/// Generates a random hex string from `len` random bytes.
+pub fn random_token(len: usize) -> String {
+    let mut buf = vec![0; len];
+    SystemRandom::new()
+        .fill(&mut buf)
+        .expect("Failed to generate random bytes");
+    HEXLOWER.encode(&buf)
+}
+
+/// Finds the candidate most similar to a mistyped `input`, the same way clap suggests
+/// subcommands.
+pub fn did_you_mean<'a>(
+    input: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    candidates
+        .into_iter()
+        .map(|candidate| (strsim::jaro_winkler(input, candidate), candidate))
+        .filter(|(confidence, _)| *confidence > 0.8)
+        .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+        .map(|(_, candidate)| candidate)
+}
+
+/// Formats a size in bytes with a binary unit, e.g. `1.5 MiB`.
+pub fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = size as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", size, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Parses a size like `2G`, `512M` or `1.5GiB` into bytes. Units are binary, and a bare number
+/// is in bytes.
+pub fn parse_size(size: &str) -> Option<u64> {
+    let size = size.trim();
+    let split = size
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+    let number = number.parse::<f64>().ok()?;
+    let unit = unit.trim().to_ascii_uppercase();
+    let shift = match unit.trim_end_matches("IB").trim_end_matches('B') {
+        "" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        "T" => 40,
+        _ => return None,
     };
+    Some((number * (1u64 << shift) as f64) as u64)
 }
 
-fn sha256_digest<R: Read>(mut reader: R) -> io::Result<Digest> {
-    let mut context = Context::new(&SHA256);
-    let mut buffer = [0; 1024];
+/// Formats seconds since the Unix epoch as `YYYY-MM-DD HH:MM:SS UTC`.
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+    // civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
 
-    loop {
-        let count = reader.read(&mut buffer)?;
-        if count == 0 {
-            break;
-        }
-        context.update(&buffer[..count]);
+/// Parses an HTTP date like `Sun, 06 Nov 1994 08:49:37 GMT` into seconds since the Unix epoch.
+pub fn parse_http_date(date: &str) -> Option<u64> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let mut parts = date.split_whitespace().skip(1);
+    let day = parts.next()?.parse::<i64>().ok()?;
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|m| *m == month)? as i64 + 1;
+    let year = parts.next()?.parse::<i64>().ok()?;
+    let mut time = parts.next()?.split(':').map(|n| n.parse::<u64>().ok());
+    let (hour, min, sec) = (time.next()??, time.next()??, time.next()??);
+    Some(days_from_civil(year, month, day)? * 86400 + hour * 3600 + min * 60 + sec)
+}
+
+/// Parses a date like `2023-01-01` into seconds since the Unix epoch at its midnight in UTC.
+pub fn parse_date(date: &str) -> Option<u64> {
+    let mut parts = date.trim().split('-').map(|n| n.parse::<i64>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day)? * 86400)
+}
+
+/// Days since the Unix epoch, the inverse of the algorithm in `format_timestamp`.
+fn days_from_civil(year: i64, month: i64, day: i64) -> Option<u64> {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    if days < 0 {
+        return None;
     }
+    Some(days as u64)
+}
 
-    Ok(context.finish())
+/// Counts the files under `dir` and their total size.
+pub fn summarize_dir(dir: &Path) -> io::Result<(u64, u64)> {
+    let mut files = 0;
+    let mut size = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            let (sub_files, sub_size) = summarize_dir(&entry.path())?;
+            files += sub_files;
+            size += sub_size;
+        } else if file_type.is_file() {
+            files += 1;
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok((files, size))
 }
 
-pub fn sha256_file(file: impl AsRef<Path>) -> io::Result<String> {
-    let input = File::open(file)?;
-    let reader = BufReader::new(input);
-    let digest = sha256_digest(reader)?;
-    Ok(HEXLOWER.encode(digest.as_ref()))
+/// Hides all but the first 4 characters of a secret.
+pub fn redact(secret: &str) -> String {
+    format!("{}****", secret.chars().take(4).collect::<String>())
 }
@@ -0,0 +1,238 @@
+use anyhow::{bail, Result};
+use clap::Clap;
+use serde_json::json;
+use std::{fs, path::Path};
+
+use crate::{
+    audit,
+    config::{self, Config},
+    install::{
+        helper::{minio, mongodb, sandbox},
+        managed_binary,
+    },
+    log_i18n, output, service, tr,
+    utils::sha256_file_async,
+    Com,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {}
+
+/// Components whose binaries are installed into the H2O2 dir.
+const BINARIES: [Com; 4] = [Com::NodeJS, Com::MongoDB, Com::MinIO, Com::Sandbox];
+
+/// What is found about a file H2O2 manages.
+struct Finding {
+    /// `binary`, `config` or `service`
+    kind: &'static str,
+    target: String,
+    /// `ok`, `drifted`, `missing` or `unrecorded`
+    status: &'static str,
+    detail: Option<String>,
+}
+
+impl Finding {
+    fn new(kind: &'static str, target: &Path, status: &'static str) -> Self {
+        Self {
+            kind,
+            target: target.display().to_string(),
+            status,
+            detail: None,
+        }
+    }
+
+    fn detail(mut self, detail: String) -> Self {
+        self.detail = Some(detail);
+        self
+    }
+
+    fn is_problem(&self) -> bool {
+        matches!(self.status, "drifted" | "missing")
+    }
+}
+
+pub async fn main(_args: Args) -> Result<()> {
+    // loading checks the config against its schema
+    let config = config::load_config().await?;
+
+    let mut findings = Vec::new();
+    for com in BINARIES {
+        let info = config.components.borrow_by_com(com);
+        if !info.is_installed() {
+            continue;
+        }
+        if let Some(binary) = managed_binary(com, info) {
+            findings.push(verify_binary(com, &binary).await);
+        }
+    }
+    verify_configs(&config, &mut findings);
+    for &com in service::SERVICES {
+        if let Some(finding) = verify_unit(&config, com) {
+            findings.push(finding);
+        }
+    }
+
+    let problems = findings.iter().filter(|f| f.is_problem()).count();
+    if output::is_json() {
+        output::print_json(&json!({
+            "findings": findings
+                .iter()
+                .map(|f| json!({
+                    "kind": f.kind,
+                    "target": f.target,
+                    "status": f.status,
+                    "detail": f.detail,
+                }))
+                .collect::<Vec<_>>(),
+            "problems": problems,
+        }));
+    } else {
+        for finding in &findings {
+            let status = match finding.status {
+                "ok" => tr!("verify.status-ok"),
+                "drifted" => tr!("verify.status-drifted"),
+                "missing" => tr!("verify.status-missing"),
+                _ => tr!("verify.status-unrecorded"),
+            };
+            println!(" {:<8} {:<12} {}", finding.kind, status, finding.target);
+            if let Some(detail) = &finding.detail {
+                println!(" {:<8} {:<12} {}", "", "", detail);
+            }
+        }
+    }
+
+    if problems > 0 {
+        bail!(tr!("verify.problems-found", problems));
+    }
+    log_i18n!(info, "verify.passed");
+    Ok(())
+}
+
+/// Checks the binary of `com` against the checksum recorded when it was installed.
+async fn verify_binary(com: Com, binary: &Path) -> Finding {
+    if !binary.is_file() {
+        return Finding::new("binary", binary, "missing");
+    }
+    let recorded = match recorded_checksum(com) {
+        Some(recorded) => recorded,
+        None => return Finding::new("binary", binary, "unrecorded"),
+    };
+    match sha256_file_async(binary.to_owned()).await {
+        Ok(checksum) if checksum == recorded => Finding::new("binary", binary, "ok"),
+        Ok(checksum) => Finding::new("binary", binary, "drifted").detail(tr!(
+            "verify.checksum-differs",
+            checksum,
+            recorded
+        )),
+        Err(e) => Finding::new("binary", binary, "missing").detail(e.to_string()),
+    }
+}
+
+/// The checksum of the binary recorded by the latest installation of `com` in the audit log.
+/// MinIO and the sandbox are single binaries, so the checksum of the download is that of the
+/// binary as well.
+fn recorded_checksum(com: Com) -> Option<String> {
+    let entries = audit::read().ok()?;
+    let entry = entries.iter().rev().find(|entry| {
+        entry.action == "install" && entry.component.as_deref() == Some(com.name())
+    })?;
+    let details = &entry.details;
+    let checksum = match com {
+        Com::MinIO | Com::Sandbox => details["binary_sha256"]
+            .as_str()
+            .or_else(|| details["sha256"].as_str()),
+        _ => details["binary_sha256"].as_str(),
+    };
+    checksum.map(str::to_owned)
+}
+
+/// Compares the config files written for the components with what the profile generates now.
+/// Files not written yet, e.g. before `h2o2 service install`, are skipped.
+fn verify_configs(config: &Config, findings: &mut Vec<Finding>) {
+    let profile = &config.profile;
+    let mut expected = Vec::new();
+    if config.components.mongodb.is_installed() {
+        expected.push((
+            mongodb::config_path(),
+            mongodb::render_config(&profile.mongodb),
+        ));
+    }
+    if config.components.minio.is_installed() {
+        expected.push((minio::env_path(), Ok(minio::render_env(&profile.minio))));
+    }
+    if config.components.sandbox.is_installed() {
+        expected.push((
+            sandbox::env_path(),
+            Ok(sandbox::render_env(&profile.sandbox)),
+        ));
+    }
+    for (path, content) in expected {
+        let actual = match fs::read_to_string(&path) {
+            Ok(actual) => actual,
+            Err(_) => continue,
+        };
+        let finding = match content {
+            Ok(content) if content == actual => Finding::new("config", &path, "ok"),
+            Ok(_) => Finding::new("config", &path, "drifted").detail(tr!("verify.config-differs")),
+            Err(e) => Finding::new("config", &path, "drifted").detail(e.to_string()),
+        };
+        findings.push(finding);
+    }
+}
+
+/// Checks that the unit running `com` refers to existing files and matches what
+/// `h2o2 service install` writes now.
+fn verify_unit(config: &Config, com: Com) -> Option<Finding> {
+    let path = service::unit_path(com);
+    let content = fs::read_to_string(&path).ok()?;
+    let mut user = "root";
+    let mut referred = Vec::new();
+    for line in content.lines() {
+        if let Some(value) = line.strip_prefix("User=") {
+            user = value.trim();
+        } else if let Some(value) = line.strip_prefix("EnvironmentFile=") {
+            referred.push(value.trim().trim_start_matches('-').to_owned());
+        } else if let Some(value) = line.strip_prefix("ExecStart=") {
+            referred.extend(
+                words(value)
+                    .into_iter()
+                    .filter(|word| word.starts_with('/')),
+            );
+        }
+    }
+    if let Some(missing) = referred.iter().find(|file| !Path::new(file).exists()) {
+        return Some(
+            Finding::new("service", &path, "missing").detail(tr!("verify.unit-missing", missing)),
+        );
+    }
+    let finding = match service::render(config, com, user) {
+        Some(expected) if expected == content => Finding::new("service", &path, "ok"),
+        _ => Finding::new("service", &path, "drifted").detail(tr!("verify.unit-differs")),
+    };
+    Some(finding)
+}
+
+/// Splits a command line of systemd into words, undoing the quoting of the units written.
+fn words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    let mut chars = line.trim().chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' if quoted => word.extend(chars.next()),
+            c if c.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
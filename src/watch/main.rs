@@ -0,0 +1,263 @@
+use anyhow::{bail, Result};
+use clap::Clap;
+use std::{
+    collections::{HashSet, VecDeque},
+    time::Duration,
+};
+use tokio::time::{self, Instant};
+
+use crate::{
+    config::{self, Config},
+    install::helper::{minio, mongodb, nodejs, sandbox, utils::ProbeOptions},
+    log_i18n, notify, platform, tr,
+    utils::{format_size, parse_size, summarize_dir},
+    Com,
+};
+
+#[derive(Clap, Debug)]
+#[clap(version = "0.1.0", author = "wuxianucw <i@ucw.moe>")]
+pub struct Args {
+    /// 检查间隔（秒）
+    /// Seconds between checks
+    #[clap(long, default_value = "60")]
+    interval: u64,
+
+    /// 磁盘剩余空间低于此值时告警，如 `2G`
+    /// Alerts when the free disk space is below this, e.g. `2G`
+    #[clap(long, default_value = "2G")]
+    min_disk: String,
+
+    /// 可用内存低于总量的此百分比时告警
+    /// Alerts when the available memory is below this percentage of the total
+    #[clap(long, default_value = "10")]
+    min_memory: u64,
+
+    /// 最近一分钟内因等待内存而停顿的时间超过此百分比时告警（需要内核支持 PSI）
+    /// Alerts when tasks stalled waiting for memory for more than this percentage of the last minute (requires PSI of the kernel)
+    #[clap(long, default_value = "20")]
+    max_memory_pressure: f64,
+
+    /// MongoDB 数据目录一小时内增长超过此值时告警，如 `1G`
+    /// Alerts when the MongoDB data dir grows more than this in an hour, e.g. `1G`
+    #[clap(long, default_value = "1G")]
+    max_db_growth: String,
+
+    /// 重新测试下载源并保存排名的间隔（小时），以便安装时直接使用最快的下载源，0 表示不测试
+    /// Hours between probing the mirrors again and saving the ranking, so that installs use the fastest one right away, or 0 not to probe
+    #[clap(long, default_value = "6")]
+    mirror_interval: u64,
+
+    /// 只检查一次并对发现的问题发送通知，适合在 cron 中运行
+    /// Checks once and notifies of the problems found, for use in cron
+    #[clap(long)]
+    once: bool,
+}
+
+/// Period the growth of MongoDB is measured over.
+const GROWTH_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Thresholds of the alerts, parsed from the arguments.
+struct Thresholds {
+    min_disk: u64,
+    min_memory: u64,
+    max_memory_pressure: f64,
+    max_db_growth: u64,
+}
+
+/// A threshold crossed.
+struct Alert {
+    /// identifies the alert across checks, e.g. `disk-low:/home/hydro/.h2o2`
+    key: String,
+    event: &'static str,
+    message: String,
+}
+
+/// Sizes of the MongoDB data dir measured, the oldest first.
+type Samples = VecDeque<(Instant, u64)>;
+
+pub async fn main(args: Args) -> Result<()> {
+    let config = config::load_config().await?;
+    let size = |size: &str| match parse_size(size) {
+        Some(size) => Ok(size),
+        None => bail!(tr!("sys.invalid-size", size)),
+    };
+    let thresholds = Thresholds {
+        min_disk: size(&args.min_disk)?,
+        min_memory: args.min_memory,
+        max_memory_pressure: args.max_memory_pressure,
+        max_db_growth: size(&args.max_db_growth)?,
+    };
+    let notify = &config.profile.notify;
+    if notify.command.is_none() && notify.webhook.is_none() {
+        log_i18n!(warn, "watch.no-hooks");
+    }
+
+    let mut samples = Samples::new();
+    if args.once {
+        for alert in check(&config, &thresholds, &mut samples) {
+            fire(&config, &alert).await;
+        }
+        return Ok(());
+    }
+
+    log_i18n!(info, "watch.started", args.interval);
+    let mut interval = time::interval(Duration::from_secs(args.interval.max(1)));
+    // alerts are sent when a threshold is crossed, rather than on every check
+    let mut firing = HashSet::new();
+    let mirror_interval = Duration::from_secs(args.mirror_interval * 60 * 60);
+    let mut mirrors_probed: Option<Instant> = None;
+    loop {
+        interval.tick().await;
+        let due = mirrors_probed.is_none_or(|at| at.elapsed() >= mirror_interval);
+        if args.mirror_interval > 0 && due {
+            refresh_mirrors(&config).await;
+            mirrors_probed = Some(Instant::now());
+        }
+        let alerts = check(&config, &thresholds, &mut samples);
+        for alert in &alerts {
+            if firing.insert(alert.key.clone()) {
+                fire(&config, alert).await;
+            }
+        }
+        let resolved = firing
+            .iter()
+            .filter(|key| !alerts.iter().any(|alert| &alert.key == *key))
+            .cloned()
+            .collect::<Vec<_>>();
+        for key in resolved {
+            firing.remove(&key);
+            let message = tr!("watch.resolved", &key);
+            log_i18n!(info, "watch.resolved", &key);
+            notify::send(notify, "resolved", &message).await;
+        }
+    }
+}
+
+/// Probes the mirrors of every downloaded component again, so that the next install or update
+/// starts with the ranking rather than probing.
+async fn refresh_mirrors(config: &Config) {
+    let mut opts = ProbeOptions::from_config(config);
+    opts.use_ranking = false;
+    let (nodejs, minio, mc, sandbox) = tokio::join!(
+        nodejs::determine_mirror(&opts),
+        minio::determine_mirror(&opts),
+        minio::determine_mc_mirror(&opts),
+        sandbox::determine_mirror(&opts),
+    );
+    let mut reachable = true;
+    for (name, res) in [
+        ("Node.js", nodejs),
+        ("MinIO", minio),
+        ("mc", mc),
+        ("sandbox", sandbox),
+    ] {
+        if let Err(report) = res {
+            reachable = false;
+            log_i18n!(warn, "watch.mirrors-unreachable", name, report);
+        }
+    }
+    if reachable {
+        log_i18n!(info, "watch.mirrors-ranked");
+    }
+}
+
+async fn fire(config: &Config, alert: &Alert) {
+    tracing::warn!("{}", alert.message);
+    notify::send(&config.profile.notify, alert.event, &alert.message).await;
+}
+
+/// Runs every check, returning the thresholds crossed.
+fn check(config: &Config, thresholds: &Thresholds, samples: &mut Samples) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+    check_disk(config, thresholds, &mut alerts);
+    check_memory(thresholds, &mut alerts);
+    if config.components.mongodb.is_installed() {
+        check_db_growth(thresholds, samples, &mut alerts);
+    }
+    alerts
+}
+
+/// Checks the file systems holding H2O2 and the data of the components.
+fn check_disk(config: &Config, thresholds: &Thresholds, alerts: &mut Vec<Alert>) {
+    let mut dirs = vec![config::get_com_path()];
+    if config.components.mongodb.is_installed() {
+        dirs.push(mongodb::data_path());
+    }
+    if config.components.minio.is_installed() {
+        dirs.push(config.profile.minio.data_dir());
+    }
+    // report each file system once, as told by the free space
+    let mut reported = Vec::new();
+    for dir in dirs.iter().filter(|dir| dir.exists()) {
+        match platform::free_space(dir) {
+            Some(free) if free < thresholds.min_disk && !reported.contains(&free) => {
+                reported.push(free);
+                alerts.push(Alert {
+                    key: format!("disk-low:{}", dir.display()),
+                    event: "disk-low",
+                    message: tr!("watch.disk-low", dir.display(), format_size(free)),
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+fn check_memory(thresholds: &Thresholds, alerts: &mut Vec<Alert>) {
+    if let Some(memory) = platform::memory() {
+        if memory.available * 100 < memory.total * thresholds.min_memory {
+            alerts.push(Alert {
+                key: "memory-low".to_owned(),
+                event: "memory-low",
+                message: tr!(
+                    "watch.memory-low",
+                    format_size(memory.available),
+                    format_size(memory.total)
+                ),
+            });
+        }
+    }
+    match platform::memory_pressure() {
+        Some(pressure) if pressure > thresholds.max_memory_pressure => alerts.push(Alert {
+            key: "memory-pressure".to_owned(),
+            event: "memory-pressure",
+            message: tr!("watch.memory-pressure", pressure),
+        }),
+        _ => {}
+    }
+}
+
+/// Checks how much the MongoDB data dir grows over [`GROWTH_WINDOW`], which tells runaway
+/// collections such as logs or submissions flooded.
+fn check_db_growth(thresholds: &Thresholds, samples: &mut Samples, alerts: &mut Vec<Alert>) {
+    let path = mongodb::data_path();
+    let size = match summarize_dir(&path) {
+        Ok((_, size)) => size,
+        Err(e) => {
+            tracing::debug!("Failed to measure {}: {}", path.display(), e);
+            return;
+        }
+    };
+    let now = Instant::now();
+    while samples.len() > 1 && now.duration_since(samples[1].0) >= GROWTH_WINDOW {
+        samples.pop_front();
+    }
+    samples.push_back((now, size));
+
+    let (since, oldest) = samples[0];
+    let growth = size.saturating_sub(oldest);
+    if growth > thresholds.max_db_growth {
+        let minutes = now.duration_since(since).as_secs() / 60;
+        alerts.push(Alert {
+            key: "db-growth".to_owned(),
+            event: "db-growth",
+            message: tr!(
+                "watch.db-growth",
+                Com::MongoDB,
+                format_size(growth),
+                minutes,
+                format_size(size)
+            ),
+        });
+    }
+}